@@ -9,10 +9,18 @@
 //! demonstrates initialization boilerplates
 
 extern crate redirect;
+#[cfg(windows)]
 extern crate winit;
 
+#[cfg(windows)]
 use redirect::descriptor::DescriptorHeap;
 
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("this example needs Direct3D 12, which is only available on windows");
+}
+
+#[cfg(windows)]
 fn main() {
     // initialize factory
     let mut factory = redirect::factory::Factory::new().unwrap();
@@ -60,7 +68,7 @@ fn main() {
     ).expect("command queue creation failed.");
 
     // initialize `winit` events loop
-    let events_loop = winit::EventsLoop::new();
+    let mut events_loop = winit::EventsLoop::new();
     // initialize `winit` window, get `hwnd` from it
     let window = winit::Window::new(&events_loop).expect(
         "window initialization failed"
@@ -116,9 +124,8 @@ fn main() {
         let mut interruptted = false;
         events_loop.poll_events(|event| {
             match event {
-                winit::Event::WindowEvent { event: winit::WindowEvent::Closed, ..} => {
+                winit::Event::WindowEvent { event: winit::WindowEvent::CloseRequested, ..} => {
                     println!("window closed; stopping..");
-                    events_loop.interrupt();
                     interruptted = true;
                 },
                 _ => ()
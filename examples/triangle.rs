@@ -9,19 +9,34 @@
 //! demonstrates how to draw a triangle on the screen
 
 extern crate redirect;
+#[cfg(windows)]
 extern crate winit;
 
+#[cfg(windows)]
 use redirect::descriptor::DescriptorHeap;
+#[cfg(windows)]
 use redirect::command::GraphicsCommandList;
+#[cfg(windows)]
 use redirect::resource::{Buffer, CpuWriteBuffer, Texture, DsableTex2D};
+#[cfg(windows)]
+use redirect::pipeline::ia::Vertex as _;
 
+/// the vertex shader input, see `VSMain` in `triangle.hlsl`
+#[cfg(windows)]
 #[repr(C)]
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, redirect::pipeline::ia::Vertex)]
 struct Vertex {
+    #[vertex(semantic = "POS")]
     position: [f32; 3],
     color: [f32; 4],
 }
 
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("this example needs Direct3D 12, which is only available on windows");
+}
+
+#[cfg(windows)]
 fn main() {
     // initialize factory
     let mut factory = redirect::factory::Factory::new().unwrap();
@@ -37,14 +52,14 @@ fn main() {
     ).expect("command queue creation failed.");
 
     // initialize `winit` events loop
-    let events_loop = winit::EventsLoop::new();
+    let mut events_loop = winit::EventsLoop::new();
     // initialize `winit` window, get `hwnd` from it
     let window = winit::Window::new(&events_loop).expect(
         "window initialization failed"
     );
-    let (width, height) = window.get_inner_size().expect(
+    let (width, height): (u32, u32) = window.get_inner_size().expect(
         "can get window size"
-    );
+    ).to_physical(window.get_hidpi_factor()).into();
     let hwnd = <winit::Window as winit::os::windows::WindowExt>::get_hwnd(&window);
     let hwnd = unsafe { ::std::mem::transmute(hwnd)};
 
@@ -97,14 +112,8 @@ fn main() {
     vertex_buffer.write_slice(&triangle, None).expect("Writing failed!");
 
     // create vertex buffer view
-    let vbv = vertex_buffer.create_vbv(
-        std::mem::size_of::<Vertex>() as u32 * triangle.len() as u32,
-        std::mem::size_of::<Vertex>() as u32
-    );
-
-    // create input layout description for the vertex
-    let pos_cstr = std::ffi::CString::new("POS").unwrap();
-    let color_cstr = std::ffi::CString::new("COLOR").unwrap();
+    let stride = triangle[0].get_stride();
+    let vbv = vertex_buffer.create_vbv(stride * triangle.len() as u32, stride);
 
     // create view port and scissor rects
     let viewport = redirect::format::Viewport::new(
@@ -131,12 +140,8 @@ fn main() {
     let mut psod = redirect::pipeline::GraphicsPipelineStateBuilder::new(&rootsig);
     psod.vs = Some(vs);
     psod.ps = Some(ps);
-    psod.input_layout.elements.push(redirect::pipeline::ia::InputElementDesc::new(
-        &pos_cstr, redirect::format::DXGI_FORMAT_R32G32B32_FLOAT
-    ));
-    psod.input_layout.elements.push(redirect::pipeline::ia::InputElementDesc::new(
-        &color_cstr, redirect::format::DXGI_FORMAT_R32G32B32A32_FLOAT
-    ));
+    // the input layout derived from the vertex
+    psod.input_layout = triangle[0].get_input_layout();
     psod.depth_stencil_state.depth = true.into();
     psod.rtv_formats[0] = backbuffers[0].get_desc().format;
     let pso = psod.build(&mut device).expect("PSO creation failed");
@@ -168,9 +173,8 @@ fn main() {
         let mut interruptted = false;
         events_loop.poll_events(|event| {
             match event {
-                winit::Event::WindowEvent { event: winit::WindowEvent::Closed, ..} => {
+                winit::Event::WindowEvent { event: winit::WindowEvent::CloseRequested, ..} => {
                     println!("window closed; stopping..");
-                    events_loop.interrupt();
                     interruptted = true;
                 },
                 _ => ()
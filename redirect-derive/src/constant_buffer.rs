@@ -0,0 +1,196 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `#[derive(ConstantBuffer)]` and `#[constant_buffer]`
+
+use proc_macro2::{Span, TokenStream};
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Generics, Ident, ItemStruct};
+use syn::spanned::Spanned;
+
+pub fn expand_derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => return Err(Error::new(input.ident.span(), "`ConstantBuffer` can only be derived for structs")),
+    };
+    check_struct(&input.ident, &input.generics, fields)?;
+    if !has_repr_c(&input.attrs)? {
+        return Err(Error::new(input.ident.span(), "`ConstantBuffer` requires `#[repr(C)]`"));
+    }
+
+    let ident = &input.ident;
+    let mut size_steps = Vec::new();
+    let mut checks = Vec::new();
+    for field in fields {
+        if is_padding(&field.attrs)? { continue; }
+        let member = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let packed = quote_spanned!(ty.span()=> <#ty as ::redirect::resource::HlslPacked>);
+        let name = member.to_string();
+        let layout_message = format!(
+            "`{}::{}`: the rust layout of this field's type doesn't match HLSL, \
+             e.g. an array of vectors with less than 4 components, or of structs \
+             whose size isn't a multiple of 16 bytes", ident, name
+        );
+        let offset_message = format!(
+            "`{}::{}` is not at its HLSL packing offset. values can't straddle a \
+             16 byte register, structs and arrays start at a register, \
+             add or remove padding before this field", ident, name
+        );
+
+        size_steps.push(quote!{
+            let offset = ::redirect::resource::cbuffer::hlsl_offset(
+                offset, #packed::HLSL_SIZE, #packed::REGISTER_ALIGNED
+            ) + #packed::HLSL_SIZE;
+        });
+        // span the checks to the field, so errors point at it
+        checks.push(quote_spanned!{field.span()=>
+            assert!(#packed::LAYOUT_MATCHES, #layout_message);
+            let offset = ::redirect::resource::cbuffer::hlsl_offset(
+                offset, #packed::HLSL_SIZE, #packed::REGISTER_ALIGNED
+            );
+            assert!(::std::mem::offset_of!(#ident, #member) == offset, #offset_message);
+            let offset = offset + #packed::HLSL_SIZE;
+        });
+    }
+
+    Ok(quote!{
+        unsafe impl ::redirect::resource::HlslPacked for #ident {
+            // structs take whole registers, the next value starts at a new one
+            const HLSL_SIZE: usize = {
+                let offset = 0usize;
+                #(#size_steps)*
+                ::redirect::resource::cbuffer::align_up(
+                    offset, ::redirect::resource::cbuffer::CB_REGISTER_SIZE
+                )
+            };
+            const REGISTER_ALIGNED: bool = true;
+            const LAYOUT_MATCHES: bool = true;
+        }
+
+        unsafe impl ::redirect::resource::ConstantBuffer for #ident { }
+
+        #[allow(clippy::let_and_return, unused_variables)]
+        const _: () = {
+            let offset = 0usize;
+            #(#checks)*
+        };
+    })
+}
+
+pub fn expand_attribute(mut item: ItemStruct) -> syn::Result<TokenStream> {
+    check_struct(&item.ident, &item.generics, &item.fields)?;
+    let ident = item.ident.clone();
+
+    let fields = match item.fields {
+        Fields::Named(ref mut fields) => ::std::mem::take(&mut fields.named),
+        _ => unreachable!(),
+    };
+    let mut padded = Vec::new();
+    let mut consts = Vec::new();
+    let mut prev_end = quote!(0usize);
+    let mut tail_vis = None;
+    for field in fields {
+        if is_padding(&field.attrs)? {
+            padded.push(field);
+            continue;
+        }
+        let member = field.ident.clone().unwrap();
+        let ty = &field.ty;
+        let packed = quote_spanned!(ty.span()=> <#ty as ::redirect::resource::HlslPacked>);
+        let start = Ident::new(&format!("__CBUFFER_{}_START_{}", ident, member), Span::call_site());
+        let end = Ident::new(&format!("__CBUFFER_{}_END_{}", ident, member), Span::call_site());
+        consts.push(quote!{
+            #[doc(hidden)]
+            #[allow(non_upper_case_globals)]
+            const #start: usize = ::redirect::resource::cbuffer::hlsl_offset(
+                #prev_end, #packed::HLSL_SIZE, #packed::REGISTER_ALIGNED
+            );
+            #[doc(hidden)]
+            #[allow(non_upper_case_globals)]
+            const #end: usize = #start + #packed::HLSL_SIZE;
+        });
+
+        // the first field always starts at 0
+        if !padded.is_empty() {
+            let pad_ident = Ident::new(&format!("_pad_{}", member), member.span());
+            let vis = &field.vis;
+            padded.push(syn::parse_quote!{
+                #[cbuffer(padding)]
+                #vis #pad_ident: [u8; #start - #prev_end]
+            });
+        }
+        tail_vis = Some(field.vis.clone());
+        padded.push(field);
+        prev_end = quote!(#end);
+    }
+    // round the struct up to a whole register, so that it can be an
+    // array element, or followed by other values in an enclosing struct
+    if let Some(vis) = tail_vis {
+        padded.push(syn::parse_quote!{
+            #[cbuffer(padding)]
+            #vis _pad: [u8; ::redirect::resource::cbuffer::align_up(
+                #prev_end, ::redirect::resource::cbuffer::CB_REGISTER_SIZE
+            ) - #prev_end]
+        });
+    }
+    if let Fields::Named(ref mut fields) = item.fields {
+        fields.named = padded.into_iter().collect();
+    }
+
+    let repr = if has_repr_c(&item.attrs)? { quote!() } else { quote!(#[repr(C)]) };
+    Ok(quote!{
+        #(#consts)*
+
+        #[derive(::redirect::resource::ConstantBuffer)]
+        #repr
+        #item
+    })
+}
+
+/// reject structs the layout checks can't handle
+fn check_struct(ident: &Ident, generics: &Generics, fields: &Fields) -> syn::Result<()> {
+    if !generics.params.is_empty() {
+        return Err(Error::new(generics.span(), "`ConstantBuffer` can't be used with generic structs"));
+    }
+    match *fields {
+        Fields::Named(_) => (),
+        _ => return Err(Error::new(ident.span(), "`ConstantBuffer` requires named fields")),
+    }
+    Ok(())
+}
+
+fn has_repr_c(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut ret = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") { ret = true; }
+            // skip arguments, e.g. of `align(16)`
+            if meta.input.peek(::syn::token::Paren) {
+                let _content;
+                ::syn::parenthesized!(_content in meta.input);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(ret)
+}
+
+fn is_padding(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut ret = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("cbuffer")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("padding") {
+                ret = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown `cbuffer` attribute"))
+            }
+        })?;
+    }
+    Ok(ret)
+}
@@ -0,0 +1,190 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `#[derive(HlslStruct)]`
+
+use proc_macro2::TokenStream;
+use syn::{Attribute, Data, DeriveInput, Error, LitStr, Type};
+use syn::spanned::Spanned;
+use vertex::{array_len, parse_flag};
+
+/// field level attributes
+#[derive(Default)]
+struct FieldAttrs {
+    name: Option<LitStr>,
+    ty: Option<LitStr>,
+    semantic: Option<LitStr>,
+    row_major: bool,
+    column_major: bool,
+    skip: bool,
+}
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => return Err(Error::new(input.ident.span(), "`HlslStruct` can only be derived for structs")),
+    };
+    let name = match parse_struct_attrs(&input.attrs)? {
+        Some(name) => name,
+        None => LitStr::new(&input.ident.to_string(), input.ident.span()),
+    };
+
+    let mut hlsl_fields = Vec::new();
+    let mut dependencies = Vec::new();
+    for field in fields {
+        let attrs = parse_field_attrs(&field.attrs)?;
+        if attrs.skip { continue; }
+        let field_name = match (attrs.name, field.ident.as_ref()) {
+            (Some(name), _) => name,
+            (None, Some(ident)) => {
+                let name = ident.to_string();
+                LitStr::new(name.trim_start_matches("r#"), ident.span())
+            },
+            (None, None) => return Err(Error::new(
+                field.span(), "tuple struct fields need a `#[hlsl(name = \"...\")]`"
+            )),
+        };
+
+        let ty = &field.ty;
+        let matrix = match (attrs.row_major, attrs.column_major) {
+            (true, true) => return Err(Error::new(
+                field.span(), "`row_major` and `column_major` are exclusive"
+            )),
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            (false, false) => None,
+        };
+        let mut hlsl_field = match (attrs.ty, matrix) {
+            (Some(hlsl_ty), None) => quote!(::redirect::shader::hlsl::HlslField::new(#field_name, #hlsl_ty)),
+            (Some(hlsl_ty), Some(_)) => return Err(Error::new(
+                hlsl_ty.span(), "`ty` can't be combined with `row_major` or `column_major`"
+            )),
+            (None, Some(row_major)) => {
+                let hlsl_ty = matrix_type(ty, row_major)?;
+                quote!(::redirect::shader::hlsl::HlslField::new(#field_name, #hlsl_ty))
+            },
+            (None, None) => {
+                dependencies.push(quote_spanned!{ty.span()=>
+                    <#ty as ::redirect::shader::hlsl::HlslType>::declare_dependencies(module);
+                });
+                quote_spanned!{ty.span()=>
+                    ::redirect::shader::hlsl::HlslField::of::<#ty, _>(#field_name)
+                }
+            },
+        };
+        if let Some(semantic) = attrs.semantic {
+            hlsl_field = quote!(#hlsl_field.with_semantic(#semantic));
+        }
+        hlsl_fields.push(hlsl_field);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote!{
+        impl #impl_generics ::redirect::shader::hlsl::HlslStruct for #ident #ty_generics #where_clause {
+            fn hlsl_name() -> ::std::borrow::Cow<'static, str> {
+                ::std::borrow::Cow::Borrowed(#name)
+            }
+
+            fn hlsl_fields() -> Vec<::redirect::shader::hlsl::HlslField> {
+                vec![#(#hlsl_fields),*]
+            }
+
+            #[allow(unused_variables)]
+            fn declare_dependencies(module: &mut ::redirect::shader::hlsl::HlslModule) {
+                #(#dependencies)*
+            }
+        }
+    })
+}
+
+/// the HLSL matrix type of an array of vectors, e.g. `row_major float4x3`
+/// for `[[f32; 3]; 4]`. with `row_major`, each vector is a row, otherwise a
+/// column.
+fn matrix_type(ty: &Type, row_major: bool) -> syn::Result<String> {
+    let invalid = || Error::new(
+        ty.span(), "a matrix field must be an array of 1 to 4 vectors of 1 to 4 `f32`, `u32`, `i32` or `Bool`, e.g. `[[f32; 4]; 4]`"
+    );
+    let (vector, len) = match *ty {
+        Type::Array(ref array) => (&*array.elem, array_len(&array.len)?),
+        _ => return Err(invalid()),
+    };
+    let (scalar, width) = match *vector {
+        Type::Array(ref array) => (&*array.elem, array_len(&array.len)?),
+        _ => return Err(invalid()),
+    };
+    let scalar = match *scalar {
+        Type::Path(ref path) if path.qself.is_none() => {
+            path.path.segments.last().map(|segment| segment.ident.to_string())
+        },
+        _ => None,
+    };
+    let scalar = match scalar.as_ref().map(|scalar| scalar.as_str()) {
+        Some("f32") => "float",
+        Some("u32") => "uint",
+        Some("i32") => "int",
+        Some("Bool") => "bool",
+        _ => return Err(invalid()),
+    };
+    if !(1..=4).contains(&len) || !(1..=4).contains(&width) {
+        return Err(invalid());
+    }
+    Ok(if row_major {
+        format!("row_major {}{}x{}", scalar, len, width)
+    } else {
+        format!("column_major {}{}x{}", scalar, width, len)
+    })
+}
+
+fn parse_struct_attrs(attrs: &[Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut ret = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("hlsl")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                ret = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unknown `hlsl` attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(ret)
+}
+
+fn parse_field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut ret = FieldAttrs::default();
+    for attr in attrs {
+        if attr.path().is_ident("hlsl") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    ret.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("ty") {
+                    ret.ty = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("semantic") {
+                    ret.semantic = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("row_major") {
+                    ret.row_major = parse_flag(&meta)?;
+                } else if meta.path.is_ident("column_major") {
+                    ret.column_major = parse_flag(&meta)?;
+                } else if meta.path.is_ident("skip") {
+                    ret.skip = parse_flag(&meta)?;
+                } else {
+                    return Err(meta.error("unknown `hlsl` attribute"));
+                }
+                Ok(())
+            })?;
+        } else if attr.path().is_ident("cbuffer") {
+            // padding of constant buffers has no HLSL counterpart
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("padding") { ret.skip = true; }
+                Ok(())
+            })?;
+        }
+    }
+    Ok(ret)
+}
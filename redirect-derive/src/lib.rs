@@ -0,0 +1,152 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! derive macros for `redirect`, re-exported by `redirect` with the
+//! `derive` feature enabled.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate syn;
+#[macro_use]
+extern crate quote;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenTree;
+use syn::DeriveInput;
+use syn::spanned::Spanned;
+
+mod constant_buffer;
+mod hlsl;
+mod vertex;
+
+/// derive `redirect::pipeline::ia::Vertex` for a struct, one input element
+/// per field. with `#[vertex(hlsl)]`, also derive
+/// `redirect::shader::hlsl::HlslStruct` declaring the matching vertex shader
+/// input struct.
+///
+/// field types map to formats as follows, where `N` is 1 to 4:
+///
+/// - `f32`, `[f32; N]`: `R32..._FLOAT`
+/// - `u32`, `i32`, `[u32; N]`, `[i32; N]`: `R32..._UINT`, `R32..._SINT`
+/// - `u16`, `i16`, `u8`, `i8` and arrays of 1, 2 or 4 of them: `..._UINT`
+///   or `..._SINT`, `..._UNORM` or `..._SNORM` with `#[vertex(normalized)]`
+/// - `f16` from any path, e.g. `half::f16`, and arrays of 1, 2 or 4 of it:
+///   `R16..._FLOAT`
+///
+/// field attributes:
+///
+/// - `semantic = "TEXCOORD"`: semantic name, the upper cased field name by default
+/// - `index = 1`: semantic index, 0 by default
+/// - `normalized`: use a normalized format for integer fields
+/// - `format = "R10G10B10A2_UNORM"`: use the given format, ignoring the field type
+/// - `hlsl_type = "float4"`: the HLSL field type with `#[vertex(hlsl)]`,
+///   inferred from the format by default
+/// - `skip`: don't generate an element for the field, e.g. for padding
+///
+/// struct attributes:
+///
+/// - `slot = 1`: the input slot, 0 by default
+/// - `per_instance`: classify elements as per instance data
+/// - `step_rate = 1`: instance data step rate, 1 by default for per instance data
+/// - `hlsl`: also derive `HlslStruct`, named after the struct
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    vertex::expand(&input)
+        .unwrap_or_else(compile_error)
+        .into()
+}
+
+/// derive `redirect::shader::hlsl::HlslStruct` for a struct, declaring an
+/// HLSL struct or constant buffer with the same fields.
+///
+/// field types must implement `redirect::shader::hlsl::HlslType`. fields
+/// marked `#[cbuffer(padding)]` are skipped.
+///
+/// field attributes:
+///
+/// - `name = "..."`: the HLSL field name, the field name by default
+/// - `ty = "..."`: the HLSL type, ignoring the field type
+/// - `semantic = "..."`: the semantic, none by default
+/// - `row_major`, `column_major`: declare an array of vectors as a matrix
+///   of rows or columns. the field must be an array of 1 to 4 vectors of 1
+///   to 4 `f32`, `u32`, `i32` or `Bool`, written out, e.g. `[[f32; 3]; 4]`
+/// - `skip`: leave the field out
+///
+/// struct attributes:
+///
+/// - `name = "..."`: the HLSL struct name, the struct name by default
+#[proc_macro_derive(HlslStruct, attributes(hlsl, cbuffer))]
+pub fn derive_hlsl_struct(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    hlsl::expand(&input)
+        .unwrap_or_else(compile_error)
+        .into()
+}
+
+/// derive `redirect::resource::ConstantBuffer` for a `#[repr(C)]` struct
+/// with named fields, checking at compile time that every field is where
+/// HLSL constant buffer packing expects it.
+///
+/// field types must implement `redirect::resource::HlslPacked`: `f32`, `u32`,
+/// `i32`, `Bool`, vectors of 1 to 4 of them, arrays of vectors, which include
+/// matrices, other constant buffer structs and arrays of them.
+///
+/// a field that breaks the packing rules fails compilation with an error
+/// naming it. fields marked `#[cbuffer(padding)]` are skipped.
+#[proc_macro_derive(ConstantBuffer, attributes(cbuffer))]
+pub fn derive_constant_buffer(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    constant_buffer::expand_derive(&input)
+        .unwrap_or_else(compile_error)
+        .into()
+}
+
+/// insert the padding HLSL constant buffer packing requires into a struct
+/// with named fields, then derive `ConstantBuffer` for it.
+///
+/// a `_pad_<field>` field of type `[u8; N]` is inserted before each field
+/// but the first, and a `_pad` field after the last one, rounding the
+/// struct up to a multiple of 16 bytes so that it can be an array element.
+/// `N` is zero if no padding is needed. these can be initialized with
+/// `Default::default()`. adds `#[repr(C)]` if missing. should be placed
+/// before other derives.
+#[proc_macro_attribute]
+pub fn constant_buffer(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+        return compile_error(syn::Error::new(attr.span(), "`constant_buffer` takes no arguments"))
+            .into();
+    }
+    let item = syn::parse_macro_input!(item as syn::ItemStruct);
+    constant_buffer::expand_attribute(item)
+        .unwrap_or_else(compile_error)
+        .into()
+}
+
+/// `syn::Error::to_compile_error` invokes `::core::compile_error!`, which
+/// 2015 edition crates can't resolve without `extern crate core`, hiding the
+/// message. the unqualified macro is in the prelude of every edition.
+fn compile_error(err: syn::Error) -> proc_macro2::TokenStream {
+    let tokens: Vec<_> = err.to_compile_error().into_iter().collect();
+    let mut ret = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let is_core_path = match tokens[i..] {
+            [TokenTree::Punct(ref a), TokenTree::Punct(ref b), TokenTree::Ident(ref core),
+             TokenTree::Punct(ref c), TokenTree::Punct(ref d), TokenTree::Ident(ref name), ..] =>
+                [a, b, c, d].iter().all(|punct| punct.as_char() == ':') &&
+                core == "core" && name == "compile_error",
+            _ => false,
+        };
+        if is_core_path { i += 5; }
+        ret.push(tokens[i].clone());
+        i += 1;
+    }
+    ret.into_iter().collect()
+}
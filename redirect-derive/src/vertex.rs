@@ -0,0 +1,286 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `#[derive(Vertex)]`
+
+use proc_macro2::TokenStream;
+use syn::{Attribute, Data, DeriveInput, Error, Expr, ExprLit, Ident, Index, Lit, LitBool, LitByteStr, LitInt, LitStr, Type};
+use syn::spanned::Spanned;
+
+/// struct level attributes
+#[derive(Default)]
+struct SlotAttrs {
+    slot: u32,
+    per_instance: bool,
+    step_rate: Option<u32>,
+    hlsl: bool,
+}
+
+/// field level attributes
+#[derive(Default)]
+struct FieldAttrs {
+    semantic: Option<LitStr>,
+    index: u32,
+    normalized: bool,
+    format: Option<LitStr>,
+    hlsl_type: Option<LitStr>,
+    skip: bool,
+}
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => return Err(Error::new(input.ident.span(), "`Vertex` can only be derived for structs")),
+    };
+    let slot_attrs = parse_slot_attrs(&input.attrs)?;
+    let slot = slot_attrs.slot;
+    let (class, step_rate) = match (slot_attrs.per_instance, slot_attrs.step_rate) {
+        (true, step_rate) => (quote!(PER_INSTANCE), step_rate.unwrap_or(1)),
+        (false, None) => (quote!(PER_VERTEX), 0),
+        (false, Some(_)) => return Err(Error::new(
+            input.ident.span(), "`step_rate` requires `per_instance`"
+        )),
+    };
+
+    let mut elements = Vec::new();
+    let mut hlsl_fields = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = parse_field_attrs(&field.attrs)?;
+        if attrs.skip { continue; }
+
+        let member = match field.ident {
+            Some(ref ident) => quote!(#ident),
+            None => {
+                let index = Index::from(i);
+                quote!(#index)
+            },
+        };
+
+        let (semantic, span) = match (attrs.semantic, field.ident.as_ref()) {
+            (Some(semantic), _) => (semantic.value(), semantic.span()),
+            (None, Some(ident)) => {
+                let name = ident.to_string();
+                (name.trim_start_matches("r#").to_uppercase(), ident.span())
+            },
+            (None, None) => return Err(Error::new(
+                field.span(), "tuple struct fields need a `#[vertex(semantic = \"...\")]`"
+            )),
+        };
+        if semantic.is_empty() || semantic.contains('\0') {
+            return Err(Error::new(span, "invalid semantic name"));
+        }
+        let index = attrs.index;
+        let hlsl_name = match field.ident {
+            Some(ref ident) => ident.to_string().trim_start_matches("r#").to_owned(),
+            None => format!("field{}", i),
+        };
+        let hlsl_semantic = if index == 0 {
+            semantic.clone()
+        } else {
+            format!("{}{}", semantic, index)
+        };
+        let semantic = LitByteStr::new(format!("{}\0", semantic).as_bytes(), span);
+
+        let (format_name, format_span) = match attrs.format {
+            Some(format) => {
+                if attrs.normalized {
+                    return Err(Error::new(format.span(), "`normalized` can't be combined with `format`"));
+                }
+                (format.value().trim_start_matches("DXGI_FORMAT_").to_owned(), format.span())
+            },
+            None => (infer_format(&field.ty, attrs.normalized)?, field.ty.span()),
+        };
+        let format = Ident::new(&format!("DXGI_FORMAT_{}", format_name), format_span);
+        if slot_attrs.hlsl {
+            let hlsl_type = match attrs.hlsl_type {
+                Some(hlsl_type) => hlsl_type.value(),
+                None => hlsl_type_of_format(&format_name).ok_or_else(|| Error::new(
+                    format_span, "can't infer the HLSL type of this format, specify a `#[vertex(hlsl_type = \"...\")]`"
+                ))?,
+            };
+            hlsl_fields.push(quote!{
+                ::redirect::shader::hlsl::HlslField::new(#hlsl_name, #hlsl_type)
+                    .with_semantic(#hlsl_semantic)
+            });
+        }
+
+        elements.push(quote!{
+            {
+                let semantic_name = unsafe {
+                    ::std::ffi::CStr::from_bytes_with_nul_unchecked(#semantic)
+                };
+                let mut element = ::redirect::pipeline::ia::InputElementDesc::new(
+                    semantic_name, ::redirect::format::#format
+                );
+                element.semantic_index = #index;
+                element.input_slot = #slot;
+                element.aligned_byte_offset = ::std::mem::offset_of!(Self, #member) as u32;
+                element.input_slot_class = ::redirect::pipeline::ia::InputClassification::#class;
+                element.instance_data_step_rate = #step_rate;
+                layout.elements.push(element);
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    let name = ident.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let hlsl_struct = if slot_attrs.hlsl {
+        quote!{
+            impl #impl_generics ::redirect::shader::hlsl::HlslStruct for #ident #ty_generics #where_clause {
+                fn hlsl_name() -> ::std::borrow::Cow<'static, str> {
+                    ::std::borrow::Cow::Borrowed(#name)
+                }
+
+                fn hlsl_fields() -> Vec<::redirect::shader::hlsl::HlslField> {
+                    vec![#(#hlsl_fields),*]
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+    Ok(quote!{
+        impl #impl_generics ::redirect::pipeline::ia::Vertex for #ident #ty_generics #where_clause {
+            fn get_input_layout(&self) -> ::redirect::pipeline::ia::InputLayoutBuilder {
+                let mut layout = ::redirect::pipeline::ia::InputLayoutBuilder::new();
+                #(#elements)*
+                layout
+            }
+        }
+
+        #hlsl_struct
+    })
+}
+
+/// the HLSL type an input element of a format is read as, e.g. `float4`
+/// for `R8G8B8A8_UNORM`
+fn hlsl_type_of_format(format: &str) -> Option<String> {
+    let split = format.find('_')?;
+    let (channels, kind) = (&format[..split], &format[split + 1..]);
+    let count = channels.chars().filter(|c| "RGBA".contains(*c)).count();
+    let scalar = match kind {
+        "FLOAT" | "UNORM" | "SNORM" | "UNORM_SRGB" => "float",
+        "UINT" => "uint",
+        "SINT" => "int",
+        _ => return None,
+    };
+    match count {
+        1 => Some(scalar.to_owned()),
+        2..=4 => Some(format!("{}{}", scalar, count)),
+        _ => None,
+    }
+}
+
+/// infer the name of a format without the `DXGI_FORMAT_` prefix from a field type
+fn infer_format(ty: &Type, normalized: bool) -> syn::Result<String> {
+    let (scalar, count) = match *ty {
+        Type::Array(ref array) => (&*array.elem, array_len(&array.len)?),
+        _ => (ty, 1),
+    };
+    let name = match *scalar {
+        Type::Path(ref path) if path.qself.is_none() => {
+            path.path.segments.last().map(|segment| segment.ident.to_string())
+        },
+        _ => None,
+    };
+    let unsupported = || Error::new(
+        ty.span(), "unsupported vertex field type, specify a `#[vertex(format = \"...\")]`"
+    );
+    let name = name.ok_or_else(unsupported)?;
+
+    let (bits, kind) = match (name.as_str(), normalized) {
+        ("f32", false) => (32, "FLOAT"),
+        ("u32", false) => (32, "UINT"),
+        ("i32", false) => (32, "SINT"),
+        ("f16", false) => (16, "FLOAT"),
+        ("u16", false) => (16, "UINT"),
+        ("u16", true) => (16, "UNORM"),
+        ("i16", false) => (16, "SINT"),
+        ("i16", true) => (16, "SNORM"),
+        ("u8", false) => (8, "UINT"),
+        ("u8", true) => (8, "UNORM"),
+        ("i8", false) => (8, "SINT"),
+        ("i8", true) => (8, "SNORM"),
+        ("f32", true) | ("u32", true) | ("i32", true) | ("f16", true) => return Err(Error::new(
+            ty.span(), "`normalized` only applies to 8 and 16 bit integers"
+        )),
+        _ => return Err(unsupported()),
+    };
+    if !(1..=4).contains(&count) || (count == 3 && bits < 32) {
+        return Err(Error::new(
+            ty.span(), format!("there's no format with {} {} bit components", count, bits)
+        ));
+    }
+    let channels: String = ["R", "G", "B", "A"][..count].iter()
+        .map(|channel| format!("{}{}", channel, bits))
+        .collect();
+    Ok(format!("{}_{}", channels, kind))
+}
+
+pub fn array_len(len: &Expr) -> syn::Result<usize> {
+    match *len {
+        Expr::Lit(ExprLit{lit: Lit::Int(ref int), ..}) => int.base10_parse(),
+        _ => Err(Error::new(len.span(), "array length must be an integer literal")),
+    }
+}
+
+fn parse_slot_attrs(attrs: &[Attribute]) -> syn::Result<SlotAttrs> {
+    let mut ret = SlotAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("slot") {
+                ret.slot = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+            } else if meta.path.is_ident("per_instance") {
+                ret.per_instance = parse_flag(&meta)?;
+            } else if meta.path.is_ident("step_rate") {
+                ret.step_rate = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.path.is_ident("hlsl") {
+                ret.hlsl = parse_flag(&meta)?;
+            } else {
+                return Err(meta.error("unknown `vertex` attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(ret)
+}
+
+fn parse_field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut ret = FieldAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("semantic") {
+                ret.semantic = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("index") {
+                ret.index = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+            } else if meta.path.is_ident("normalized") {
+                ret.normalized = parse_flag(&meta)?;
+            } else if meta.path.is_ident("format") {
+                ret.format = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("hlsl_type") {
+                ret.hlsl_type = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("skip") {
+                ret.skip = parse_flag(&meta)?;
+            } else {
+                return Err(meta.error("unknown `vertex` attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(ret)
+}
+
+/// parse a flag given either as `flag` or `flag = true`
+pub fn parse_flag(meta: &::syn::meta::ParseNestedMeta) -> syn::Result<bool> {
+    if meta.input.peek(::syn::Token![=]) {
+        Ok(meta.value()?.parse::<LitBool>()?.value)
+    } else {
+        Ok(true)
+    }
+}
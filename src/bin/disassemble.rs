@@ -0,0 +1,51 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! disassembles compiled shaders, e.g. `.cso` files, like `fxc /dumpbin`
+//!
+//! usage: `cargo run --bin disassemble -- <shader>...`
+//!
+//! only uses `redirect::dxbc`, so it builds and runs on any host.
+
+extern crate redirect;
+
+use redirect::dxbc::DxbcContainer;
+use std::{env, fs, process};
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: disassemble <shader>...");
+        process::exit(2);
+    }
+
+    let mut failed = false;
+    for path in &paths {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                failed = true;
+                continue;
+            },
+        };
+        let listing = DxbcContainer::from_bytes(&bytes)
+            .and_then(|container| container.disassemble());
+        match listing {
+            Ok(listing) => {
+                if paths.len() > 1 { println!("// {}", path); }
+                print!("{}", listing);
+            },
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                failed = true;
+            },
+        }
+    }
+    if failed { process::exit(1); }
+}
@@ -9,14 +9,17 @@
 //! common command list methods implementation boilerplate
 
 use super::*;
+use error::InvalidEnumValue;
+use std::convert::TryFrom;
 
 pub trait CommandList {
     //  get raw ID3D12CommandList pointer
     fn as_raw_ptr(&mut self) -> &mut ComPtr<ID3D12GraphicsCommandList>;
 
     /// get type of this command list
-    fn get_type(&mut self) -> CommandListType {
-        unsafe { ::std::mem::transmute(self.as_raw_ptr().GetType()) }
+    fn get_type(&mut self) -> Result<CommandListType, InvalidEnumValue> {
+        let ty = unsafe { self.as_raw_ptr().GetType() };
+        CommandListType::try_from(ty.0)
     }
 }
 
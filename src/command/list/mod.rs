@@ -8,35 +8,46 @@
 
 //! command lists
 
+#[cfg(windows)]
 use comptr::ComPtr;
+#[cfg(windows)]
 use winapi::{ID3D12GraphicsCommandList, ID3D12CommandAllocator};
+#[cfg(windows)]
 use error::WinError;
+#[cfg(windows)]
 use descriptor::heap::*;
+#[cfg(windows)]
 use pipeline::{PipelineState, GraphicsPipelineState, ComputePipelineState};
+#[cfg(windows)]
 use resource::{RawResource, ResourceBarriersBuilder, TextureCopyLocation};
 
+#[cfg(windows)]
 mod common;
+#[cfg(windows)]
 pub use self::common::*;
 
+#[cfg(windows)]
 mod direct;
+#[cfg(windows)]
 pub use self::direct::*;
 
+#[cfg(windows)]
 mod bundle;
+#[cfg(windows)]
 pub use self::bundle::*;
 
-bitflags!{
+d3d_enum!{
     /// type of a `CommandList`
-    #[repr(C)]
-    pub struct CommandListType: u32 {
+    pub enum CommandListType {
         /// direct list doesn't inherit any GPU state.
-        const DIRECT = 0;
+        DIRECT = 0,
         /// bundle inherits all GPU state (except PSO and primitive topology)
         /// from the direct list, where it must be opearated on
-        const BUNDLE = 1;
+        BUNDLE = 1,
         /// computing command list
-        const COMPUTE = 2;
+        COMPUTE = 2,
         /// copying(drawing) command list
-        const COPY = 3;
+        COPY = 3,
     }
 }
 
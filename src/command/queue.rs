@@ -6,20 +6,31 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+#[cfg(windows)]
 use comptr::ComPtr;
+#[cfg(windows)]
 use winapi::ID3D12CommandQueue;
+#[cfg(windows)]
 use error::WinError;
 use super::list::*;
+#[cfg(windows)]
 use smallvec::SmallVec;
+#[cfg(windows)]
 use fence::Fence;
+#[cfg(windows)]
+use error::InvalidEnumValue;
+#[cfg(windows)]
+use std::convert::TryFrom;
 
 /// A GPU command queue, providing methods for command submission,
 /// execution synchronization, etc.
+#[cfg(windows)]
 #[derive(Debug, Clone)]
 pub struct CommandQueue {
     pub(crate) ptr: ComPtr<ID3D12CommandQueue>,
 }
 
+#[cfg(windows)]
 impl CommandQueue {
     // TODO: add method for `CopyTileMapping, UpdateTileMappings`, block on resources
 
@@ -88,13 +99,16 @@ impl CommandQueue {
 
     // TODO: add method for PIX events?
 
-    /// get description of this queue
+    /// get description of this queue.
+    ///
+    /// panics if the runtime reports a value unknown to this crate.
     pub fn get_desc(&mut self) -> CommandQueueDesc {
-        unsafe{
+        let desc = unsafe{
             let mut ret = ::std::mem::uninitialized();
             self.ptr.GetDesc(&mut ret);
-            ::std::mem::transmute(ret)
-        }
+            ret
+        };
+        CommandQueueDesc::try_from(desc).expect("unknown command queue description")
     }
 }
 
@@ -112,10 +126,31 @@ pub struct CommandQueueDesc {
     pub node_mask: u32,
 }
 
+#[cfg(windows)]
 impl From<CommandQueueDesc> for ::winapi::D3D12_COMMAND_QUEUE_DESC {
     #[inline]
     fn from(desc: CommandQueueDesc) -> Self {
-        unsafe { ::std::mem::transmute(desc) }
+        ::winapi::D3D12_COMMAND_QUEUE_DESC{
+            Type: ::winapi::D3D12_COMMAND_LIST_TYPE(desc.list_type as u32),
+            Priority: desc.priority as i32,
+            Flags: ::winapi::D3D12_COMMAND_QUEUE_FLAGS(desc.flags.bits()),
+            NodeMask: desc.node_mask,
+        }
+    }
+}
+
+#[cfg(windows)]
+impl TryFrom<::winapi::D3D12_COMMAND_QUEUE_DESC> for CommandQueueDesc {
+    type Error = InvalidEnumValue;
+
+    #[inline]
+    fn try_from(desc: ::winapi::D3D12_COMMAND_QUEUE_DESC) -> Result<Self, InvalidEnumValue> {
+        Ok(CommandQueueDesc{
+            list_type: CommandListType::try_from(desc.Type.0)?,
+            priority: CommandQueuePriority::try_from(desc.Priority as u32)?,
+            flags: CommandQueueFlags::from_bits_truncate(desc.Flags.0),
+            node_mask: desc.NodeMask,
+        })
     }
 }
 
@@ -131,14 +166,12 @@ impl Default for CommandQueueDesc {
     }
 }
 
-// TODO: double check types maybe?
-bitflags!{
+d3d_enum!{
     /// priority levels for a command queue
-    #[repr(C)]
-    pub struct CommandQueuePriority: u32 {
-        const NORMAL = 0;
-        const HIGH = 100;
-        const REALTIME = 1000;
+    pub enum CommandQueuePriority {
+        NORMAL = 0,
+        HIGH = 100,
+        REALTIME = 10000,
     }
 }
 
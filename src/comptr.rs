@@ -9,9 +9,11 @@
 //! RAII COM-pointer wrapper
 
 use ::winapi::IUnknown;
+#[cfg(test)]
+use ::winapi::IUnknownVtbl;
 use ::std::ops::{Deref, DerefMut};
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Debug)]
 pub struct ComPtr<T> {
     ptr: *mut T,
 }
@@ -44,6 +46,34 @@ impl<T> ComPtr<T> {
     }
 }
 
+#[cfg(test)]
+impl<T> ComPtr<T> {
+    /// a pointer to a static object implementing only `IUnknown`, with
+    /// reference counting doing nothing, standing in for runtime objects in
+    /// tests. calling any other method is undefined behavior.
+    pub(crate) fn inert() -> ComPtr<T> {
+        unsafe extern "system" fn query_interface(
+            _: *mut IUnknown, _: ::winapi::REFIID, _: *mut *mut ::winapi::c_void
+        ) -> ::winapi::HRESULT {
+            ::winapi::E_NOINTERFACE
+        }
+        unsafe extern "system" fn add_ref(_: *mut IUnknown) -> ::winapi::ULONG { 1 }
+        unsafe extern "system" fn release(_: *mut IUnknown) -> ::winapi::ULONG { 1 }
+
+        struct Inert(IUnknown);
+        // the object is never written to
+        unsafe impl Sync for Inert {}
+
+        static VTBL: IUnknownVtbl = IUnknownVtbl{
+            QueryInterface: query_interface,
+            AddRef: add_ref,
+            Release: release,
+        };
+        static INERT: Inert = Inert(IUnknown{lpVtbl: &VTBL});
+        ComPtr{ptr: &INERT.0 as *const IUnknown as *mut T}
+    }
+}
+
 // impl<T> AsRef<*mut T> for ComPtr<T> {
 //     #[inline]
 //     fn as_ref(&self) -> &*mut T {
@@ -79,6 +109,23 @@ impl<T> Clone for ComPtr<T> {
     }
 }
 
+// pointers are compared by identity, regardless of `T`
+impl<T> PartialEq for ComPtr<T> {
+    #[inline]
+    fn eq(&self, other: &ComPtr<T>) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
+impl<T> Eq for ComPtr<T> {}
+
+impl<T> ::std::hash::Hash for ComPtr<T> {
+    #[inline]
+    fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+        self.ptr.hash(state)
+    }
+}
+
 impl<T> Drop for ComPtr<T> {
     #[inline]
     fn drop(&mut self) {
@@ -13,6 +13,7 @@ use resource::GpuVAddress;
 /// describes constant buffer view
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CbvDesc {
     /// the gpu virtual address of the viewed virtual buffer
     pub buffer_location: GpuVAddress,
@@ -12,12 +12,15 @@ use format::DxgiFormat;
 
 /// describes a depth stencil view
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DsvDesc {
+    #[cfg_attr(feature = "serde", serde(with = "::format::serde_format"))]
     pub format: DxgiFormat,
     pub flags: DsvFlags,
     pub dimension: DsvDimension,
 }
 
+#[cfg(windows)]
 impl DsvDesc {
     #[inline]
     pub(crate) fn into_cstruct(self) -> DsvDescBindHelper {
@@ -26,6 +29,7 @@ impl DsvDesc {
 }
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DsvDimension {
     Tex1D(DsvTex1DDesc),
     Tex1DArray(DsvTex1DArrayDesc),
@@ -37,6 +41,7 @@ pub enum DsvDimension {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DsvTex1DDesc {
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -44,6 +49,7 @@ pub struct DsvTex1DDesc {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DsvTex1DArrayDesc{
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -55,6 +61,7 @@ pub struct DsvTex1DArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DsvTex2DDesc{
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -62,6 +69,7 @@ pub struct DsvTex2DDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DsvTex2DArrayDesc{
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -73,6 +81,7 @@ pub struct DsvTex2DArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DsvTex2DMsArrayDesc{
     /// first array slice to use
     pub first_slice: u32,
@@ -91,6 +100,8 @@ bitflags!{
     }
 }
 
+impl_bitflags_serde!(DsvFlags, u32);
+
 impl Default for DsvFlags {
     #[inline]
     fn default() -> Self {
@@ -99,6 +110,7 @@ impl Default for DsvFlags {
 }
 
 /// helper struct for ffi, not intended for application user
+#[cfg(windows)]
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct DsvDescBindHelper {
@@ -108,6 +120,7 @@ pub(crate) struct DsvDescBindHelper {
     a: [u32; 3],
 }
 
+#[cfg(windows)]
 impl From<DsvDesc> for DsvDescBindHelper{
     #[inline]
     fn from(desc: DsvDesc) -> DsvDescBindHelper {
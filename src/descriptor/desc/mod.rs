@@ -28,6 +28,7 @@ pub use self::uav::*;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Shader4ComponentMapping{inner: u32}
 
 impl Shader4ComponentMapping {
@@ -38,12 +39,14 @@ impl Shader4ComponentMapping {
         b: ShaderComponentMapping, 
         a: ShaderComponentMapping)
      -> Self {
+        const SHIFT: u32 = 3; // D3D12_SHADER_COMPONENT_MAPPING_SHIFT
         Shader4ComponentMapping{
-            // TODO: double check
-            inner: r.bits() + 
-             g.bits()<< ::winapi::D3D12_SHADER_COMPONENT_MAPPING_SHIFT + 
-             b.bits()<< ::winapi::D3D12_SHADER_COMPONENT_MAPPING_SHIFT*2 + 
-             a.bits()<< ::winapi::D3D12_SHADER_COMPONENT_MAPPING_SHIFT*3
+            // the extra bit at `SHIFT*4` is always set, as in `D3D12_ENCODE_SHADER_4_COMPONENT_MAPPING`
+            inner: r as u32 |
+             (g as u32) << SHIFT |
+             (b as u32) << (SHIFT*2) |
+             (a as u32) << (SHIFT*3) |
+             1 << (SHIFT*4)
         }
     }
 }
@@ -60,21 +63,20 @@ impl Default for Shader4ComponentMapping {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// specifies how memory gets routed by a srv
-    #[repr(C)]
-    pub struct ShaderComponentMapping: u32 {
+    pub enum ShaderComponentMapping {
         /// indicates return component 0, i.e. R in RGBA
-        const FROM_MEMORY_COMPONENT_0 = 0;
+        FROM_MEMORY_COMPONENT_0 = 0,
         /// indicates return component 1, i.e. G in RGBA
-        const FROM_MEMORY_COMPONENT_1 = 1;
+        FROM_MEMORY_COMPONENT_1 = 1,
         /// indicates return component 2, i.e. B in RGBA
-        const FROM_MEMORY_COMPONENT_2 = 2;
+        FROM_MEMORY_COMPONENT_2 = 2,
         /// indicates return component 3, i.e. A in RGBA
-        const FROM_MEMORY_COMPONENT_3 = 3;
+        FROM_MEMORY_COMPONENT_3 = 3,
         /// indicates forcing the resulting value to 0
-        const FORCE_VALUE_0 = 4;
+        FORCE_VALUE_0 = 4,
         /// indicates forcing the resulting value to 0x1 or 1.0f
-        const FORCE_VALUE_1 = 5;
+        FORCE_VALUE_1 = 5,
     }
 }
@@ -12,11 +12,14 @@ use format::DxgiFormat;
 
 /// describes a render target view
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RtvDesc {
+    #[cfg_attr(feature = "serde", serde(with = "::format::serde_format"))]
     pub format: DxgiFormat,
     pub dimension: RtvDimension,
 }
 
+#[cfg(windows)]
 impl RtvDesc {
     #[inline]
     pub(crate) fn into_cstruct(self) -> RtvDescBindHelper {
@@ -25,6 +28,7 @@ impl RtvDesc {
 }
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RtvDimension {
     Buffer(RtvBufferDesc),
     Tex1D(RtvTex1DDesc),
@@ -38,6 +42,7 @@ pub enum RtvDimension {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RtvBufferDesc {
     /// first element to be accessed by the view
     pub offset: u64,
@@ -47,6 +52,7 @@ pub struct RtvBufferDesc {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RtvTex1DDesc {
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -54,6 +60,7 @@ pub struct RtvTex1DDesc {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RtvTex1DArrayDesc{
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -65,6 +72,7 @@ pub struct RtvTex1DArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RtvTex2DDesc{
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -74,6 +82,7 @@ pub struct RtvTex2DDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RtvTex2DArrayDesc{
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -87,6 +96,7 @@ pub struct RtvTex2DArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RtvTex2DMsArrayDesc{
     /// first array slice to use
     pub first_slice: u32,
@@ -96,6 +106,7 @@ pub struct RtvTex2DMsArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RtvTex3DDesc{
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -106,6 +117,7 @@ pub struct RtvTex3DDesc{
 }
 
 /// helper struct for ffi, not intended for application user
+#[cfg(windows)]
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct RtvDescBindHelper {
@@ -114,6 +126,7 @@ pub(crate) struct RtvDescBindHelper {
     a: [u32; 4],
 }
 
+#[cfg(windows)]
 impl From<RtvDesc> for RtvDescBindHelper{
     #[inline]
     fn from(desc: RtvDesc) -> RtvDescBindHelper {
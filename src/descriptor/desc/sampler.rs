@@ -13,6 +13,7 @@ pub type ComparisonFunc = ::pipeline::ComparisonFunc;
 /// describes a sampler
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SamplerDesc {
     /// filtering method
     pub filter: Filter,
@@ -13,12 +13,15 @@ use super::Shader4ComponentMapping;
 
 /// describes a shader resource view
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvDesc {
+    #[cfg_attr(feature = "serde", serde(with = "::format::serde_format"))]
     pub format: DxgiFormat,
     pub dimension: SrvDimension,
     pub component_mapping: Shader4ComponentMapping,
 }
 
+#[cfg(windows)]
 impl SrvDesc {
     #[inline]
     pub fn into_cstruct(self) -> SrvDescBindHelper {
@@ -27,6 +30,7 @@ impl SrvDesc {
 }
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SrvDimension {
     Unknown,
     Buffer(SrvBufferDesc),
@@ -43,6 +47,7 @@ pub enum SrvDimension {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvBufferDesc {
     /// first element to be accessed by the view
     pub offset: u64,
@@ -56,6 +61,7 @@ pub struct SrvBufferDesc {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvTex1DDesc {
     /// index of the most detailed mipmap to use
     pub most_detailed_mip: u32,
@@ -67,6 +73,7 @@ pub struct SrvTex1DDesc {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvTex1DArrayDesc{
     /// index of the most detailed mipmap to use
     pub most_detailed_mip: u32,
@@ -82,6 +89,7 @@ pub struct SrvTex1DArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvTex2DDesc{
     /// index of the most detailed mipmap to use
     pub most_detailed_mip: u32,
@@ -95,6 +103,7 @@ pub struct SrvTex2DDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvTex2DArrayDesc{
     /// index of the most detailed mipmap to use
     pub most_detailed_mip: u32,
@@ -112,6 +121,7 @@ pub struct SrvTex2DArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvTex2DMsArrayDesc{
     /// first array slice to use
     pub first_slice: u32,
@@ -121,6 +131,7 @@ pub struct SrvTex2DMsArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvTex3DDesc{
     /// index of the most detailed mipmap to use
     pub most_detailed_mip: u32,
@@ -132,6 +143,7 @@ pub struct SrvTex3DDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvTexCubeDesc{
     /// index of the most detailed mipmap to use
     pub most_detailed_mip: u32,
@@ -143,6 +155,7 @@ pub struct SrvTexCubeDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SrvTexCubeArrayDesc{
     /// index of the most detailed mipmap to use
     pub most_detailed_mip: u32,
@@ -158,6 +171,7 @@ pub struct SrvTexCubeArrayDesc{
 
 /// helper struct for ffi, not intended for application user
 /// TODO: remove from public interface
+#[cfg(windows)]
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct SrvDescBindHelper {
@@ -167,6 +181,7 @@ pub struct SrvDescBindHelper {
     a: [u32; 6],
 }
 
+#[cfg(windows)]
 impl From<SrvDesc> for SrvDescBindHelper{
     #[inline]
     fn from(desc: SrvDesc) -> SrvDescBindHelper {
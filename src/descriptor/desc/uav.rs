@@ -12,11 +12,14 @@ use format::DxgiFormat;
 
 /// describes an unordered access view
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UavDesc {
+    #[cfg_attr(feature = "serde", serde(with = "::format::serde_format"))]
     pub format: DxgiFormat,
     pub dimension: UavDimension,
 }
 
+#[cfg(windows)]
 impl UavDesc {
     #[inline]
     pub fn into_cstruct(self) -> UavDescBindHelper {
@@ -25,6 +28,7 @@ impl UavDesc {
 }
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UavDimension {
     Unknown,
     Buffer(UavBufferDesc),
@@ -37,6 +41,7 @@ pub enum UavDimension {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UavBufferDesc {
     /// first element to be accessed by the view
     pub offset: u64,
@@ -53,6 +58,7 @@ pub struct UavBufferDesc {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UavTex1DDesc {
     /// mipmap slice to use
     pub mip_slice: u32,
@@ -60,6 +66,7 @@ pub struct UavTex1DDesc {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UavTex1DArrayDesc{
     /// mipmap slice to use
     pub mip_slice: u32,
@@ -71,6 +78,7 @@ pub struct UavTex1DArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UavTex2DDesc{
     /// mipmap slice to use
     pub mip_slice: u32,
@@ -80,6 +88,7 @@ pub struct UavTex2DDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UavTex2DArrayDesc{
     /// mipmap slice to use
     pub mip_slice: u32,
@@ -93,6 +102,7 @@ pub struct UavTex2DArrayDesc{
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UavTex3DDesc{
     /// index of the mipmap slice to use
     pub mip_slice: u32,
@@ -104,6 +114,7 @@ pub struct UavTex3DDesc{
 
 /// helper struct for ffi, not intended for application user
 /// TODO: remove from public interface
+#[cfg(windows)]
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct UavDescBindHelper {
@@ -113,6 +124,7 @@ pub struct UavDescBindHelper {
     a: UavBufferDesc,
 }
 
+#[cfg(windows)]
 impl From<UavDesc> for UavDescBindHelper{
     #[inline]
     fn from(desc: UavDesc) -> UavDescBindHelper {
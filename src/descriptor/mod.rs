@@ -8,7 +8,9 @@
 
 //! a relatively small block of data that fully describles an object to the GPU.
 
+#[cfg(windows)]
 pub mod heap;
+#[cfg(windows)]
 pub use self::heap::*;
 
 pub mod desc;
@@ -15,7 +15,7 @@ use std::os::raw::c_void;
 use factory::Adapter;
 use command::{CommandQueue, CommandQueueDesc, DirectCommandAllocator, BundleCommandAllocator, DirectCommandListRecording, BundleRecording, DirectCommandList, Bundle};
 use resource::*;
-use pipeline::rootsig::{RootSig, RootSigDescBlob};
+use pipeline::rootsig::{RootSig, RootSigDescBlob, RootSigVersion};
 use pipeline::{PipelineState, GraphicsPipelineState, ComputePipelineState};
 use fence::{Fence, FenceFlags};
 use descriptor::{CsuHeapSv, CsuHeapNsv, RtvHeap, DsvHeap, SamplerHeapSv, SamplerHeapNsv};
@@ -26,6 +26,10 @@ pub struct Device {
     pub(crate) ptr: ComPtr<ID3D12Device>,
 }
 
+// the device is free-threaded
+unsafe impl Send for Device {}
+unsafe impl Sync for Device {}
+
 impl Device {
     /// attempt to create a device from the given adapter and feature level.
     /// `None` means the default adapter would be used.
@@ -51,25 +55,52 @@ impl Device {
         }
     }
 
+    /// get the locally unique id of the adapter this device is created on
+    #[inline]
+    pub fn get_adapter_luid(&mut self) -> ::winapi::LUID {
+        unsafe {
+            let mut ret = ::std::mem::zeroed();
+            self.ptr.GetAdapterLuid(&mut ret);
+            ret
+        }
+    }
+
+    /// get the highest root signature version supported, 1.0 on runtimes
+    /// predating the query
+    pub fn get_root_sig_version(&mut self) -> RootSigVersion {
+        // `D3D12_FEATURE_ROOT_SIGNATURE`, missing from the bindings, whose
+        // data is the `D3D_ROOT_SIGNATURE_VERSION` to query, replaced by
+        // the highest supported one
+        const FEATURE_ROOT_SIGNATURE: u32 = 12;
+        let mut version = RootSigVersion::V1_1 as u32;
+        let hr = unsafe {
+            self.ptr.CheckFeatureSupport(
+                ::winapi::D3D12_FEATURE(FEATURE_ROOT_SIGNATURE),
+                &mut version as *mut u32 as *mut _, 4
+            )
+        };
+        if !::winapi::SUCCEEDED(hr) { return RootSigVersion::V1_0; }
+        ::std::convert::TryFrom::try_from(version).unwrap_or(RootSigVersion::V1_0)
+    }
+
     /// attempts to create a root signature from a description blob
     #[inline]
     pub fn create_root_sig(
         &mut self, node_mask: u32, desc_blob: &RootSigDescBlob
     ) -> Result<RootSig, WinError> {
+        let bytes = desc_blob.as_bytes();
         unsafe {
-            let pblob = desc_blob.ptr.as_mut_ptr();
-            let length = (*pblob).GetBufferSize();
-            let pblob = (*pblob).GetBufferPointer();
-
             let mut ret = ::std::mem::uninitialized();
             let hr = self.ptr.CreateRootSignature(
-                node_mask, pblob, length,
+                node_mask, bytes.as_ptr() as *const _, bytes.len() as _,
                 & ::dxguid::IID_ID3D12RootSignature,
                 &mut ret as *mut *mut _ as *mut *mut _
             );
             
             WinError::from_hresult_or_ok(hr, || RootSig{
-                ptr: ComPtr::new(ret)
+                ptr: ComPtr::new(ret),
+                desc: bytes.into(),
+                node_mask,
             })
         }
     }
@@ -78,10 +109,11 @@ impl Device {
     pub fn create_command_queue(
         &mut self, desc: &CommandQueueDesc
     ) -> Result<CommandQueue, WinError> {
+        let desc: ::winapi::D3D12_COMMAND_QUEUE_DESC = (*desc).into();
         unsafe {
             let mut ret = ::std::mem::uninitialized();
             let hr = self.ptr.CreateCommandQueue(
-                desc as *const _ as *const ::winapi::D3D12_COMMAND_QUEUE_DESC,
+                &desc,
                 & ::dxguid::IID_ID3D12CommandQueue,
                 &mut ret as *mut *mut _ as *mut *mut c_void
             );
@@ -292,14 +324,13 @@ impl Device {
     // TODO: add methods for resource tiling
 }
 
-bitflags! {
+d3d_enum!{
     /// set of features targeted by a device
-    #[repr(C)]
-    pub struct FeatureLevel: u32 {
-        const L_11_0 = 0xb000;
-        const L_11_1 = 0xb100;
-        const L_12_0 = 0xc000;
-        const L_12_1 = 0xc100;
+    pub enum FeatureLevel {
+        L_11_0 = 0xb000,
+        L_11_1 = 0xb100,
+        L_12_0 = 0xc000,
+        L_12_1 = 0xc100,
     }
 }
 
@@ -312,7 +343,7 @@ impl Default for FeatureLevel {
 impl From<FeatureLevel> for ::winapi::D3D_FEATURE_LEVEL {
     #[inline]
     fn from(level: FeatureLevel) -> Self {
-        ::winapi::D3D_FEATURE_LEVEL(level.bits())
+        ::winapi::D3D_FEATURE_LEVEL(level as u32)
     }
 }
 
@@ -0,0 +1,959 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! a disassembler of shader model 4.0 to 5.1 programs, the `SHDR` and
+//! `SHEX` parts, printing listings in the syntax of `fxc /dumpbin`.
+
+use super::{DxbcContainer, DxbcError, ShaderVersion, Signature, SystemValue, RegisterComponentType};
+use super::{ResourceBinding, ShaderInputType, ShaderInputDimension, ResourceReturnType};
+use super::{u32_at, bytes_at};
+use std::fmt;
+
+/// disassemble the content of a `SHDR` or `SHEX` part, one instruction
+/// per line after the shader version
+pub fn disassemble(program: &[u8]) -> Result<String, DxbcError> {
+    let version = ShaderVersion::from_token(u32_at(program, 0)?)?;
+    let length = u32_at(program, 4)? as usize;
+    bytes_at(program, 0, length.checked_mul(4).ok_or(DxbcError::Truncated)?)?;
+
+    let mut disassembler = Disassembler{
+        program,
+        sm5_1: version.major == 5 && version.minor >= 1,
+        indent: 0,
+        listing: format!("{}\n", version),
+    };
+    let mut offset = 2;
+    while offset < length {
+        offset = disassembler.instruction(offset, length)?;
+    }
+    Ok(disassembler.listing)
+}
+
+impl<'a> DxbcContainer<'a> {
+    /// disassemble the `SHEX` or `SHDR` program, preceded by the resource
+    /// bindings and signatures as comments, like `fxc /dumpbin`. constant
+    /// buffer layouts are left out.
+    pub fn disassemble(&self) -> Result<String, DxbcError> {
+        let program = self.any_part(&[b"SHEX", b"SHDR"])?;
+        let version = ShaderVersion::from_token(u32_at(program, 0)?)?;
+        let mut listing = String::from("//\n");
+        if let Ok(defs) = self.resource_defs() {
+            listing.push_str(&format!("// Generated by {}\n//\n", defs.creator));
+            if !defs.bindings.is_empty() {
+                let sm5_1 = version.major == 5 && version.minor >= 1;
+                listing.push_str(&binding_table(&defs.bindings, sm5_1));
+            }
+        }
+        let signatures = [
+            ("Input", self.input_signature(), false),
+            ("Output", self.output_signature(), true),
+            ("Patch Constant", self.patch_constant_signature(), true),
+        ];
+        for &(title, ref signature, output) in &signatures {
+            if let Ok(ref signature) = *signature {
+                listing.push_str(&signature_table(title, signature, output));
+            }
+        }
+        listing.push_str(&disassemble(program)?);
+        if let Ok(statistics) = self.statistics() {
+            listing.push_str(&format!(
+                "// Approximately {} instruction slots used\n", statistics.instruction_count
+            ));
+        }
+        Ok(listing)
+    }
+}
+
+/// the `Resource Bindings` comment table, with the range ids since shader
+/// model 5.1
+fn binding_table(bindings: &[ResourceBinding], sm5_1: bool) -> String {
+    let mut ret = String::from("//\n// Resource Bindings:\n//\n");
+    if sm5_1 {
+        ret.push_str("// Name                                 Type  Format         Dim      ID      HLSL Bind  Count\n");
+        ret.push_str("// ------------------------------ ---------- ------- ----------- ------- -------------- ------\n");
+    } else {
+        ret.push_str("// Name                                 Type  Format         Dim      HLSL Bind  Count\n");
+        ret.push_str("// ------------------------------ ---------- ------- ----------- -------------- ------\n");
+    }
+    for binding in bindings {
+        let uav = matches!(binding.input_type,
+            ShaderInputType::UAV_RWTYPED | ShaderInputType::UAV_RWSTRUCTURED |
+            ShaderInputType::UAV_RWBYTEADDRESS | ShaderInputType::UAV_APPEND_STRUCTURED |
+            ShaderInputType::UAV_CONSUME_STRUCTURED |
+            ShaderInputType::UAV_RWSTRUCTURED_WITH_COUNTER |
+            ShaderInputType::UAV_FEEDBACKTEXTURE
+        );
+        let (ty, prefix) = match binding.input_type {
+            ShaderInputType::CBUFFER => ("cbuffer", "cb"),
+            ShaderInputType::TBUFFER => ("tbuffer", "t"),
+            ShaderInputType::SAMPLER => ("sampler", "s"),
+            _ if uav => ("UAV", "u"),
+            _ => ("texture", "t"),
+        };
+        let format = match binding.input_type {
+            ShaderInputType::STRUCTURED | ShaderInputType::UAV_RWSTRUCTURED |
+            ShaderInputType::UAV_APPEND_STRUCTURED | ShaderInputType::UAV_CONSUME_STRUCTURED |
+            ShaderInputType::UAV_RWSTRUCTURED_WITH_COUNTER => "struct".to_owned(),
+            ShaderInputType::BYTEADDRESS | ShaderInputType::UAV_RWBYTEADDRESS => "byte".to_owned(),
+            _ => match binding.return_type {
+                Some(return_type) => {
+                    let components = ((binding.flags.bits() >> 2) & 0x3) + 1;
+                    let name = match return_type {
+                        ResourceReturnType::UNORM => "unorm",
+                        ResourceReturnType::SNORM => "snorm",
+                        ResourceReturnType::SINT => "sint",
+                        ResourceReturnType::UINT => "uint",
+                        ResourceReturnType::FLOAT => "float",
+                        ResourceReturnType::MIXED => "mixed",
+                        ResourceReturnType::DOUBLE => "double",
+                        ResourceReturnType::CONTINUED => "cont",
+                    };
+                    if components == 1 { name.to_owned() } else { format!("{}{}", name, components) }
+                },
+                None => "NA".to_owned(),
+            },
+        };
+        let dimension = match binding.dimension {
+            ShaderInputDimension::UNKNOWN => "NA",
+            ShaderInputDimension::BUFFER => "buf",
+            ShaderInputDimension::TEXTURE1D => "1d",
+            ShaderInputDimension::TEXTURE1DARRAY => "1darray",
+            ShaderInputDimension::TEXTURE2D => "2d",
+            ShaderInputDimension::TEXTURE2DARRAY => "2darray",
+            ShaderInputDimension::TEXTURE2DMS => "2dMS",
+            ShaderInputDimension::TEXTURE2DMSARRAY => "2darrayMS",
+            ShaderInputDimension::TEXTURE3D => "3d",
+            ShaderInputDimension::TEXTURECUBE => "cube",
+            ShaderInputDimension::TEXTURECUBEARRAY => "cubearray",
+            ShaderInputDimension::BUFFEREX if uav => "r/w",
+            ShaderInputDimension::BUFFEREX => "r/o",
+        };
+        let bind = if binding.register_space == 0 {
+            format!("{}{}", prefix, binding.bind_point)
+        } else {
+            format!("{}{},space{}", prefix, binding.bind_point, binding.register_space)
+        };
+        let count = match binding.bind_count {
+            0 => "unbounded".to_owned(),
+            count => count.to_string(),
+        };
+        let id = if sm5_1 {
+            format!(" {:>7}", format!("{}{}", prefix.to_uppercase(), binding.id))
+        } else {
+            String::new()
+        };
+        // `fxc` ends the rows with a space
+        ret.push_str(&format!(
+            "// {:<30} {:>10} {:>7} {:>11}{} {:>14} {:>6} \n",
+            binding.name, ty, format, dimension, id, bind, count
+        ));
+    }
+    ret.push_str("//\n//\n");
+    ret
+}
+
+/// a signature comment table. empty signatures, e.g. of compute shaders,
+/// get a `no Input` row and no closing line.
+fn signature_table(title: &str, signature: &Signature, output: bool) -> String {
+    let mut ret = format!("//\n// {} signature:\n//\n", title);
+    ret.push_str("// Name                 Index   Mask Register SysValue  Format   Used\n");
+    ret.push_str("// -------------------- ----- ------ -------- -------- ------- ------\n");
+    if signature.elements.is_empty() {
+        ret.push_str(&format!("// no {}\n", title));
+        return ret;
+    }
+    for element in &signature.elements {
+        let system_value = match element.system_value {
+            SystemValue::UNDEFINED => "NONE",
+            SystemValue::POSITION => "POS",
+            SystemValue::CLIP_DISTANCE => "CLIPDST",
+            SystemValue::CULL_DISTANCE => "CULLDST",
+            SystemValue::RENDER_TARGET_ARRAY_INDEX => "RTINDEX",
+            SystemValue::VIEWPORT_ARRAY_INDEX => "VPINDEX",
+            SystemValue::VERTEX_ID => "VERTID",
+            SystemValue::PRIMITIVE_ID => "PRIMID",
+            SystemValue::INSTANCE_ID => "INSTID",
+            SystemValue::IS_FRONT_FACE => "FFACE",
+            SystemValue::SAMPLE_INDEX => "SAMPLE",
+            SystemValue::FINAL_QUAD_EDGE_TESSFACTOR => "QUADEDGE",
+            SystemValue::FINAL_QUAD_INSIDE_TESSFACTOR => "QUADINT",
+            SystemValue::FINAL_TRI_EDGE_TESSFACTOR => "TRIEDGE",
+            SystemValue::FINAL_TRI_INSIDE_TESSFACTOR => "TRIINT",
+            SystemValue::FINAL_LINE_DETAIL_TESSFACTOR => "LINEDET",
+            SystemValue::FINAL_LINE_DENSITY_TESSFACTOR => "LINEDEN",
+            SystemValue::BARYCENTRICS => "BARYCEN",
+            SystemValue::SHADINGRATE => "SHDINGRATE",
+            SystemValue::CULLPRIMITIVE => "CULLPRIM",
+            SystemValue::TARGET => "TARGET",
+            SystemValue::DEPTH => "DEPTH",
+            SystemValue::COVERAGE => "COVERAGE",
+            SystemValue::DEPTH_GREATER_EQUAL => "DEPTHGE",
+            SystemValue::DEPTH_LESS_EQUAL => "DEPTHLE",
+            SystemValue::STENCIL_REF => "STENCILREF",
+            SystemValue::INNER_COVERAGE => "INNERCOV",
+        };
+        let format = match element.component_type {
+            RegisterComponentType::UNKNOWN => "unknown",
+            RegisterComponentType::UINT32 => "uint",
+            RegisterComponentType::SINT32 => "int",
+            RegisterComponentType::FLOAT32 => "float",
+            RegisterComponentType::UINT16 => "uint16",
+            RegisterComponentType::SINT16 => "int16",
+            RegisterComponentType::FLOAT16 => "float16",
+            RegisterComponentType::UINT64 => "uint64",
+            RegisterComponentType::SINT64 => "int64",
+            RegisterComponentType::FLOAT64 => "double",
+        };
+        // outputs store the components never written instead
+        let used = if output { element.mask & !element.rw_mask } else { element.rw_mask };
+        let register = if element.register == !0 {
+            "N/A".to_owned()
+        } else {
+            element.register.to_string()
+        };
+        ret.push_str(&format!(
+            "// {:<20} {:>5}   {} {:>8} {:>8} {:>7}   {}\n",
+            element.semantic_name, element.semantic_index, MaskColumn(element.mask),
+            register, system_value, format, MaskColumn(used)
+        ));
+    }
+    ret.push_str("//\n");
+    ret
+}
+
+/// a component mask with each component in its own column, e.g. `x zw`
+struct MaskColumn(u8);
+
+impl fmt::Display for MaskColumn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for (i, c) in "xyzw".chars().enumerate() {
+            write!(f, "{}", if self.0 & (1 << i) != 0 { c } else { ' ' })?;
+        }
+        Ok(())
+    }
+}
+
+const OPCODE_CUSTOMDATA: u32 = 53;
+const OPERAND_IMMEDIATE32: u32 = 4;
+const OPERAND_IMMEDIATE64: u32 = 5;
+
+struct Disassembler<'a> {
+    program: &'a [u8],
+    /// shader model 5.1 declarations carry register ranges and spaces
+    sm5_1: bool,
+    indent: usize,
+    listing: String,
+}
+
+impl<'a> Disassembler<'a> {
+    /// disassemble the instruction at the DWORD `offset`, returning the
+    /// offset of the next one
+    fn instruction(&mut self, offset: usize, length: usize) -> Result<usize, DxbcError> {
+        let token = u32_at(self.program, offset * 4)?;
+        let opcode = token & 0x7ff;
+        // custom data blocks give their length in the following DWORD
+        let (size, first_operand) = if opcode == OPCODE_CUSTOMDATA {
+            (u32_at(self.program, offset * 4 + 4)? as usize, offset + 2)
+        } else {
+            (((token >> 24) & 0x7f) as usize, offset + 1)
+        };
+        let end = offset + size;
+        if size < first_operand - offset || end > length {
+            return Err(DxbcError::InvalidInstruction(offset));
+        }
+        let mut tokens = Tokens{program: self.program, offset, position: first_operand, end};
+
+        let text = if opcode == OPCODE_CUSTOMDATA {
+            custom_data(token, &mut tokens)?
+        } else {
+            self.decode(token, &mut tokens)?
+        };
+
+        match OPCODE_NAMES.get(opcode as usize).cloned() {
+            Some("else") | Some("endif") | Some("endloop") | Some("endswitch") =>
+                self.indent = self.indent.saturating_sub(1),
+            _ => (),
+        }
+        for _ in 0..self.indent {
+            self.listing.push_str("  ");
+        }
+        self.listing.push_str(&text);
+        self.listing.push('\n');
+        match OPCODE_NAMES.get(opcode as usize).cloned() {
+            Some("if") | Some("else") | Some("loop") | Some("switch") => self.indent += 1,
+            _ => (),
+        }
+        Ok(end)
+    }
+
+    /// the text of an instruction other than custom data
+    fn decode(&self, token: u32, tokens: &mut Tokens) -> Result<String, DxbcError> {
+        let opcode = token & 0x7ff;
+        let name = match OPCODE_NAMES.get(opcode as usize).cloned() {
+            Some(name) if !name.is_empty() => name,
+            _ => return Ok(format!("// unknown opcode {}", opcode)),
+        };
+
+        // extended opcode tokens: immediate texel offsets, the resource
+        // dimension and the resource return type
+        let mut suffix = String::new();
+        let mut extended = token >> 31 != 0;
+        while extended {
+            let ext = tokens.next()?;
+            match ext & 0x3f {
+                1 => {
+                    let offset = |shift: u32| ((ext >> shift) << 28) as i32 >> 28;
+                    suffix = format!("_aoffimmi({},{},{}){}", offset(9), offset(13), offset(17), suffix);
+                },
+                2 => {
+                    let dimension = name_of(RESOURCE_DIMENSIONS, (ext >> 6) & 0x1f);
+                    let stride = (ext >> 11) & 0xfff;
+                    if (ext >> 6) & 0x1f == 12 {
+                        suffix.push_str(&format!("_indexable({}, stride={})", dimension, stride));
+                    } else {
+                        suffix.push_str(&format!("_indexable({})", dimension));
+                    }
+                },
+                3 => suffix.push_str(&format!("({})", ReturnTypes(ext >> 6))),
+                _ => (),
+            }
+            extended = ext >> 31 != 0;
+        }
+
+        let controls = (token >> 11) & 0x1fff;
+        let mut text = String::from(name);
+        match opcode {
+            // declarations
+            88 => {
+                text.push('_');
+                text.push_str(&name_of(RESOURCE_DIMENSIONS, controls & 0x1f));
+                let samples = (controls >> 5) & 0x7f;
+                if samples != 0 { text.push_str(&format!("({})", samples)); }
+                let operand = Operand::parse(tokens)?;
+                text.push_str(&format!(" ({}) {}", ReturnTypes(tokens.next()?), self.range(&operand)));
+                return self.space(text, tokens);
+            },
+            89 => {
+                let operand = Operand::parse(tokens)?;
+                let access = if controls & 1 != 0 { "dynamicIndexed" } else { "immediateIndexed" };
+                if self.sm5_1 {
+                    text.push_str(&format!(" {}[{}], {}", self.range(&operand), tokens.next()?, access));
+                    return self.space(text, tokens);
+                }
+                return Ok(format!("{} {}, {}", text, operand, access));
+            },
+            90 => {
+                let operand = Operand::parse(tokens)?;
+                let mode = name_of(&["mode_default", "mode_comparison", "mode_mono"], controls & 0xf);
+                text.push_str(&format!(" {}, {}", self.range(&operand), mode));
+                return self.space(text, tokens);
+            },
+            91 => return Ok(format!("{} {} {}", text, Operand::parse(tokens)?, tokens.next()?)),
+            92 => return Ok(format!("{} {}", text, name_of(TOPOLOGIES, controls & 0x7f))),
+            93 => {
+                let primitive = controls & 0x3f;
+                return Ok(match primitive {
+                    8..=39 => format!("{} patch{}", text, primitive - 7),
+                    _ => format!("{} {}", text, name_of(PRIMITIVES, primitive)),
+                });
+            },
+            94 | 104 | 153 | 154 | 206 => return Ok(format!("{} {}", text, tokens.next()?)),
+            95 | 101 => return Ok(format!("{} {}", text, Operand::parse(tokens)?)),
+            96 | 97 | 102 | 103 => {
+                let operand = Operand::parse(tokens)?;
+                return Ok(format!("{} {}, {}", text, operand, name_of(SYSTEM_NAMES, tokens.next()?)));
+            },
+            98..=100 => {
+                let interpolation = controls & 0xf;
+                if interpolation != 0 {
+                    text.push(' ');
+                    text.push_str(&name_of(INTERPOLATIONS, interpolation));
+                }
+                text.push_str(&format!(" {}", Operand::parse(tokens)?));
+                if opcode != 98 {
+                    text.push_str(&format!(", {}", name_of(SYSTEM_NAMES, tokens.next()?)));
+                }
+                return Ok(text);
+            },
+            105 => {
+                let register = tokens.next()?;
+                let count = tokens.next()?;
+                return Ok(format!("{} x{}[{}], {}", text, register, count, tokens.next()?));
+            },
+            106 => {
+                let flags = GLOBAL_FLAGS.iter().enumerate()
+                    .filter(|&(i, _)| controls & (1 << i) != 0)
+                    .map(|(_, &name)| name)
+                    .collect::<Vec<_>>();
+                return Ok(format!("{} {}", text, flags.join(" | ")));
+            },
+            143 => return Ok(format!("{} {}", text, Operand::parse(tokens)?)),
+            144 => return Ok(format!("{} fb{}", text, tokens.next()?)),
+            145 => {
+                let table = tokens.next()?;
+                let count = tokens.next()?;
+                let bodies = (0..count)
+                    .map(|_| tokens.next().map(|body| format!("fb{}", body)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(format!("{} ft{} = {{{}}}", text, table, bodies.join(", ")));
+            },
+            146 => {
+                if controls & 1 != 0 { text.push_str("_dynamicindexed"); }
+                let interface = tokens.next()?;
+                let table_length = tokens.next()?;
+                let counts = tokens.next()?;
+                let tables = (0..counts & 0xffff)
+                    .map(|_| tokens.next().map(|table| format!("ft{}", table)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(format!(
+                    "{} fp{}[{}][{}] = {{{}}}",
+                    text, interface, counts >> 16, table_length, tables.join(", ")
+                ));
+            },
+            147 | 148 => return Ok(format!("{} {}", text, controls & 0x3f)),
+            149 => return Ok(format!("{} {}", text, name_of(&["", "domain_isoline", "domain_tri", "domain_quad"], controls & 0x3))),
+            150 => return Ok(format!("{} {}", text, name_of(PARTITIONINGS, controls & 0x7))),
+            151 => return Ok(format!("{} {}", text, name_of(OUTPUT_PRIMITIVES, controls & 0x7))),
+            152 => return Ok(format!("{} l({})", text, Immediate32(tokens.next()?))),
+            155 => {
+                let x = tokens.next()?;
+                let y = tokens.next()?;
+                return Ok(format!("{} {}, {}, {}", text, x, y, tokens.next()?));
+            },
+            156..=158 => {
+                if opcode == 156 {
+                    text.push('_');
+                    text.push_str(&name_of(RESOURCE_DIMENSIONS, controls & 0x1f));
+                }
+                if controls & 0x20 != 0 { text.push_str("_glc"); }
+                if controls & 0x40 != 0 { text.push_str("_rov"); }
+                if opcode == 158 && controls & 0x1000 != 0 { text.push_str("_opc"); }
+                let operand = Operand::parse(tokens)?;
+                match opcode {
+                    156 => text.push_str(&format!(" ({}) {}", ReturnTypes(tokens.next()?), self.range(&operand))),
+                    157 => text.push_str(&format!(" {}", self.range(&operand))),
+                    _ => text.push_str(&format!(" {}, {}", self.range(&operand), tokens.next()?)),
+                }
+                return self.space(text, tokens);
+            },
+            159 => return Ok(format!("{} {}, {}", text, Operand::parse(tokens)?, tokens.next()?)),
+            160 => {
+                let operand = Operand::parse(tokens)?;
+                let stride = tokens.next()?;
+                return Ok(format!("{} {}, {}, {}", text, operand, stride, tokens.next()?));
+            },
+            161 => {
+                text.push_str(&format!(" {}", self.range(&Operand::parse(tokens)?)));
+                return self.space(text, tokens);
+            },
+            162 => {
+                let operand = Operand::parse(tokens)?;
+                text.push_str(&format!(" {}, {}", self.range(&operand), tokens.next()?));
+                return self.space(text, tokens);
+            },
+            // interface calls name the call site before the interface
+            120 => {
+                let call_site = tokens.next()?;
+                text.push_str(&format!(" {}, {}", Operand::parse(tokens)?, call_site));
+                return Ok(text);
+            },
+            _ => (),
+        }
+
+        // instructions
+        match opcode {
+            // sync
+            190 => {
+                let flags = [(0x8, "_uglobal"), (0x4, "_ugroup"), (0x2, "_g"), (0x1, "_t")];
+                for &(bit, flag) in &flags {
+                    if controls & bit != 0 { text.push_str(flag); }
+                }
+            },
+            // breakc, callc, continuec, discard, if and retc
+            3 | 5 | 8 | 13 | 31 | 63 => {
+                text.push_str(if controls & 0x80 != 0 { "_nz" } else { "_z" });
+            },
+            _ => if controls & 0x4 != 0 { text.push_str("_sat"); },
+        }
+        text.push_str(&suffix);
+        match opcode {
+            61 => text.push_str(name_of(&["", "_rcpFloat", "_uint"], controls & 0x3).as_str()),
+            111 if controls & 0x1 != 0 => text.push_str("_uint"),
+            _ => (),
+        }
+        let precise = (controls >> 8) & 0xf;
+        if precise != 0 && opcode != 190 {
+            text.push_str(&format!(" [precise({})]", Mask(precise as u8)));
+        }
+
+        let mut operands = Vec::new();
+        while !tokens.is_empty() {
+            operands.push(Operand::parse(tokens)?.to_string());
+        }
+        if !operands.is_empty() {
+            text.push(' ');
+            text.push_str(&operands.join(", "));
+        } else {
+            // `fxc` ends operand-less instructions with a space, e.g. `ret `
+            text.push(' ');
+        }
+        Ok(text)
+    }
+
+    /// a declared register, with its range since shader model 5.1
+    fn range(&self, operand: &Operand) -> String {
+        if !self.sm5_1 || operand.indices.len() < 3 { return operand.to_string(); }
+        let bound = |index: &Index| match index.immediate {
+            Some(0xffffffff) => "*".to_owned(),
+            _ => index.to_string(),
+        };
+        format!(
+            "{}{}[{}:{}]",
+            operand.prefix().to_uppercase(), operand.indices[0],
+            bound(&operand.indices[1]), bound(&operand.indices[2])
+        )
+    }
+
+    /// append the register space ending shader model 5.1 resource declarations
+    fn space(&self, mut text: String, tokens: &mut Tokens) -> Result<String, DxbcError> {
+        if self.sm5_1 {
+            text.push_str(&format!(", space={}", tokens.next()?));
+        }
+        Ok(text)
+    }
+}
+
+/// the text of a custom data block, e.g. an immediate constant buffer
+fn custom_data(token: u32, tokens: &mut Tokens) -> Result<String, DxbcError> {
+    let class = token >> 11;
+    if class != 3 {
+        return Ok(format!("// custom data class {}, {} DWORDs", class, tokens.end - tokens.offset));
+    }
+    let mut text = String::from("dcl_immediateConstantBuffer {");
+    let mut first = true;
+    while !tokens.is_empty() {
+        let mut values = Vec::with_capacity(4);
+        for _ in 0..4 {
+            values.push(Immediate32(tokens.next()?).to_string());
+        }
+        text.push_str(if first { " { " } else { ",\n                              { " });
+        text.push_str(&values.join(", "));
+        text.push('}');
+        first = false;
+    }
+    text.push_str(" }");
+    Ok(text)
+}
+
+/// the DWORDs of an instruction
+struct Tokens<'a> {
+    program: &'a [u8],
+    /// the DWORD offset of the instruction
+    offset: usize,
+    position: usize,
+    end: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn next(&mut self) -> Result<u32, DxbcError> {
+        if self.position >= self.end {
+            return Err(DxbcError::InvalidInstruction(self.offset));
+        }
+        self.position += 1;
+        u32_at(self.program, (self.position - 1) * 4)
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.position >= self.end
+    }
+}
+
+/// the components an operand refers to
+#[derive(Copy, Clone, Debug)]
+enum Components {
+    /// scalar operands and registers without components
+    None,
+    Mask(u8),
+    Swizzle([u8; 4]),
+    Select(u8),
+}
+
+/// an index into a register file, an immediate, a register, or their sum
+#[derive(Clone, Debug)]
+struct Index {
+    immediate: Option<u64>,
+    relative: Option<Box<Operand>>,
+}
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match (self.relative.as_ref(), self.immediate) {
+            (Some(relative), Some(immediate)) => write!(f, "{} + {}", relative, immediate),
+            (Some(relative), None) => write!(f, "{}", relative),
+            (None, Some(immediate)) => write!(f, "{}", immediate),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// an instruction operand
+#[derive(Clone, Debug)]
+struct Operand {
+    ty: u32,
+    components: Components,
+    indices: Vec<Index>,
+    /// the values of immediate operands
+    immediate: Vec<u32>,
+    /// none, neg, abs or absneg
+    modifier: u32,
+    min_precision: u32,
+    non_uniform: bool,
+}
+
+impl Operand {
+    fn parse(tokens: &mut Tokens) -> Result<Operand, DxbcError> {
+        let token = tokens.next()?;
+        let ty = (token >> 12) & 0xff;
+        let component_count = match token & 0x3 {
+            0 => 0,
+            1 => 1,
+            2 => 4,
+            _ => return Err(DxbcError::InvalidInstruction(tokens.offset)),
+        };
+        let components = if component_count == 4 {
+            match (token >> 2) & 0x3 {
+                0 => Components::Mask(((token >> 4) & 0xf) as u8),
+                1 => {
+                    let select = |i: u32| ((token >> (4 + i * 2)) & 0x3) as u8;
+                    Components::Swizzle([select(0), select(1), select(2), select(3)])
+                },
+                2 => Components::Select(((token >> 4) & 0x3) as u8),
+                _ => return Err(DxbcError::InvalidInstruction(tokens.offset)),
+            }
+        } else {
+            Components::None
+        };
+
+        let mut operand = Operand{
+            ty, components,
+            indices: Vec::new(),
+            immediate: Vec::new(),
+            modifier: 0,
+            min_precision: 0,
+            non_uniform: false,
+        };
+        let mut extended = token >> 31 != 0;
+        while extended {
+            let ext = tokens.next()?;
+            if ext & 0x3f == 1 {
+                operand.modifier = (ext >> 6) & 0xff;
+                operand.min_precision = (ext >> 14) & 0x7;
+                operand.non_uniform = ext & 0x20000 != 0;
+            }
+            extended = ext >> 31 != 0;
+        }
+
+        let immediate_count = match ty {
+            OPERAND_IMMEDIATE32 => component_count,
+            OPERAND_IMMEDIATE64 => component_count * 2,
+            _ => 0,
+        };
+        for _ in 0..immediate_count {
+            operand.immediate.push(tokens.next()?);
+        }
+
+        for i in 0..((token >> 20) & 0x3) {
+            let representation = (token >> (22 + i * 3)) & 0x7;
+            let immediate = match representation {
+                0 | 3 => Some(u64::from(tokens.next()?)),
+                1 | 4 => {
+                    let low = u64::from(tokens.next()?);
+                    Some(low | u64::from(tokens.next()?) << 32)
+                },
+                2 => None,
+                _ => return Err(DxbcError::InvalidInstruction(tokens.offset)),
+            };
+            let relative = if representation >= 2 {
+                Some(Box::new(Operand::parse(tokens)?))
+            } else {
+                None
+            };
+            operand.indices.push(Index{immediate, relative});
+        }
+        Ok(operand)
+    }
+
+    /// the register file name
+    fn prefix(&self) -> &'static str {
+        REGISTER_PREFIXES.get(self.ty as usize).cloned().unwrap_or("?")
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let (before, after) = match self.modifier {
+            1 => ("-", ""),
+            2 => ("|", "|"),
+            3 => ("-|", "|"),
+            _ => ("", ""),
+        };
+        f.write_str(before)?;
+        match self.ty {
+            OPERAND_IMMEDIATE32 => {
+                let values = self.immediate.iter()
+                    .map(|&value| Immediate32(value).to_string())
+                    .collect::<Vec<_>>();
+                write!(f, "l({})", values.join(", "))?;
+            },
+            OPERAND_IMMEDIATE64 => {
+                let values = self.immediate.chunks(2)
+                    .map(|pair| {
+                        let bits = u64::from(pair[0]) | u64::from(pair[1]) << 32;
+                        format!("{:.6}l", f64::from_bits(bits))
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "d({})", values.join(", "))?;
+            },
+            ty => {
+                // shader model 5.1 resources are named by their range
+                // id, in upper case
+                let prefix = self.prefix();
+                let upper = match ty {
+                    6 | 7 | 30 => self.indices.len() >= 2,
+                    8 => self.indices.len() >= 3,
+                    _ => false,
+                };
+                if upper {
+                    f.write_str(&prefix.to_uppercase())?;
+                } else {
+                    f.write_str(prefix)?;
+                }
+                // most register files put the first index right after their
+                // name, e.g. `r0` and `cb0[1]`
+                let appended = match ty {
+                    1 => self.indices.len() == 1,
+                    9 | 25 | 26 | 29 => false,
+                    _ => true,
+                };
+                let mut indices = self.indices.iter();
+                if appended {
+                    if let Some(index) = self.indices.first() {
+                        if index.relative.is_none() {
+                            write!(f, "{}", index)?;
+                            indices.next();
+                        }
+                    }
+                }
+                for index in indices {
+                    write!(f, "[{}]", index)?;
+                }
+            },
+        }
+        match self.components {
+            Components::Mask(0) | Components::None => (),
+            Components::Mask(mask) => write!(f, ".{}", Mask(mask))?,
+            Components::Swizzle(ref swizzle) => {
+                f.write_str(".")?;
+                for &component in swizzle {
+                    write!(f, "{}", COMPONENTS[component as usize])?;
+                }
+            },
+            Components::Select(component) => write!(f, ".{}", COMPONENTS[component as usize])?,
+        }
+        f.write_str(after)?;
+        match self.min_precision {
+            1 => f.write_str(" {min16f}")?,
+            2 => f.write_str(" {min2_8f}")?,
+            4 => f.write_str(" {min16i}")?,
+            5 => f.write_str(" {min16u}")?,
+            _ => (),
+        }
+        if self.non_uniform {
+            f.write_str(" {nonuniform}")?;
+        }
+        Ok(())
+    }
+}
+
+const COMPONENTS: [char; 4] = ['x', 'y', 'z', 'w'];
+
+/// the components of a write mask, e.g. `xzw`
+struct Mask(u8);
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for (i, &c) in COMPONENTS.iter().enumerate() {
+            if self.0 & (1 << i) != 0 { write!(f, "{}", c)?; }
+        }
+        Ok(())
+    }
+}
+
+/// a 32 bit immediate, printed as a float when it looks like one and as
+/// an integer otherwise, as the program doesn't tell them apart
+struct Immediate32(u32);
+
+impl fmt::Display for Immediate32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match (self.0 >> 23) & 0xff {
+            0 => write!(f, "{}", self.0 as i32),
+            // small negative integers
+            0xff if self.0 >= 0xffff0000 => write!(f, "{}", self.0 as i32),
+            0xff => write!(f, "0x{:08x}", self.0),
+            _ => write!(f, "{:.6}", f32::from_bits(self.0)),
+        }
+    }
+}
+
+/// the four resource return types packed into a token, e.g. `float,float,float,float`
+struct ReturnTypes(u32);
+
+impl fmt::Display for ReturnTypes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for i in 0..4 {
+            if i != 0 { f.write_str(",")?; }
+            f.write_str(&name_of(RETURN_TYPES, (self.0 >> (i * 4)) & 0xf))?;
+        }
+        Ok(())
+    }
+}
+
+/// the name of an enumeration value, or the value itself if it has none
+fn name_of(names: &[&str], value: u32) -> String {
+    match names.get(value as usize) {
+        Some(name) if !name.is_empty() => (*name).to_owned(),
+        _ => value.to_string(),
+    }
+}
+
+const RESOURCE_DIMENSIONS: &[&str] = &[
+    "unknown", "buffer", "texture1d", "texture2d", "texture2dms", "texture3d",
+    "texturecube", "texture1darray", "texture2darray", "texture2dmsarray",
+    "texturecubearray", "raw_buffer", "structured_buffer",
+];
+
+const RETURN_TYPES: &[&str] = &[
+    "", "unorm", "snorm", "sint", "uint", "float", "mixed", "double", "continued", "unused",
+];
+
+const SYSTEM_NAMES: &[&str] = &[
+    "undefined", "position", "clip_distance", "cull_distance",
+    "rendertarget_array_index", "viewport_array_index", "vertex_id",
+    "primitive_id", "instance_id", "is_front_face", "sampleIndex",
+    "finalQuadUeq0EdgeTessFactor", "finalQuadVeq0EdgeTessFactor",
+    "finalQuadUeq1EdgeTessFactor", "finalQuadVeq1EdgeTessFactor",
+    "finalQuadUInsideTessFactor", "finalQuadVInsideTessFactor",
+    "finalTriUeq0EdgeTessFactor", "finalTriVeq0EdgeTessFactor",
+    "finalTriWeq0EdgeTessFactor", "finalTriInsideTessFactor",
+    "finalLineDetailTessFactor", "finalLineDensityTessFactor",
+];
+
+const INTERPOLATIONS: &[&str] = &[
+    "", "constant", "linear", "linear centroid", "linear noperspective",
+    "linear noperspective centroid", "linear sample", "linear noperspective sample",
+];
+
+const PRIMITIVES: &[&str] = &[
+    "undefined", "point", "line", "triangle", "", "", "lineadj", "triangleadj",
+];
+
+const TOPOLOGIES: &[&str] = &[
+    "undefined", "pointlist", "linelist", "linestrip", "trianglelist", "trianglestrip",
+    "", "", "", "", "linelist_adj", "linestrip_adj", "trianglelist_adj", "trianglestrip_adj",
+];
+
+const PARTITIONINGS: &[&str] = &[
+    "", "partitioning_integer", "partitioning_pow2",
+    "partitioning_fractional_odd", "partitioning_fractional_even",
+];
+
+const OUTPUT_PRIMITIVES: &[&str] = &[
+    "", "output_point", "output_line", "output_triangle_cw", "output_triangle_ccw",
+];
+
+/// `dcl_globalFlags` flags, from bit 11 of the opcode token on
+const GLOBAL_FLAGS: &[&str] = &[
+    "refactoringAllowed", "enableDoublePrecisionFloatOps", "forceEarlyDepthStencil",
+    "enableRawAndStructuredBuffers", "skipOptimization", "enableMinimumPrecision",
+    "enable11_1DoubleExtensions", "enable11_1ShaderExtensions", "allResourcesBound",
+];
+
+/// register file names, by operand type
+const REGISTER_PREFIXES: &[&str] = &[
+    "r", "v", "o", "x", "l", "d", "s", "t", "cb", "icb", "l", "vPrim", "oDepth",
+    "null", "rasterizer", "oMask", "m", "fb", "ft", "fp", "fi", "fo",
+    "vOutputControlPointID", "vForkInstanceID", "vJoinInstanceID", "vicp", "vocp",
+    "vpc", "vDomain", "this", "u", "g", "vThreadID", "vThreadGroupID",
+    "vThreadIDInGroup", "vCoverage", "vThreadIDInGroupFlattened", "vGSInstanceID",
+    "oDepthGE", "oDepthLE", "vCycleCounter", "oStencilRef", "vInnerCoverage",
+];
+
+/// mnemonics, by opcode. reserved opcodes are empty.
+const OPCODE_NAMES: &[&str] = &[
+    // 0
+    "add", "and", "break", "breakc", "call", "callc", "case", "continue",
+    "continuec", "cut", "default", "deriv_rtx", "deriv_rty", "discard", "div", "dp2",
+    // 16
+    "dp3", "dp4", "else", "emit", "emit_then_cut", "endif", "endloop", "endswitch",
+    "eq", "exp", "frc", "ftoi", "ftou", "ge", "iadd", "if",
+    // 32
+    "ieq", "ige", "ilt", "imad", "imax", "imin", "imul", "ine",
+    "ineg", "ishl", "ishr", "itof", "label", "ld", "ldms", "log",
+    // 48
+    "loop", "lt", "mad", "min", "max", "customdata", "mov", "movc",
+    "mul", "ne", "nop", "not", "or", "resinfo", "ret", "retc",
+    // 64
+    "round_ne", "round_ni", "round_pi", "round_z", "rsq", "sample", "sample_c", "sample_c_lz",
+    "sample_l", "sample_d", "sample_b", "sqrt", "switch", "sincos", "udiv", "ult",
+    // 80
+    "uge", "umul", "umad", "umax", "umin", "ushr", "utof", "xor",
+    "dcl_resource", "dcl_constantbuffer", "dcl_sampler", "dcl_indexrange",
+    "dcl_outputtopology", "dcl_inputprimitive", "dcl_maxout", "dcl_input",
+    // 96
+    "dcl_input_sgv", "dcl_input_siv", "dcl_input_ps", "dcl_input_ps_sgv",
+    "dcl_input_ps_siv", "dcl_output", "dcl_output_sgv", "dcl_output_siv",
+    "dcl_temps", "dcl_indexableTemp", "dcl_globalFlags", "",
+    "lod", "gather4", "sample_pos", "sample_info",
+    // 112
+    "", "hs_decls", "hs_control_point_phase", "hs_fork_phase",
+    "hs_join_phase", "emit_stream", "cut_stream", "emit_then_cut_stream",
+    "fcall", "bufinfo", "deriv_rtx_coarse", "deriv_rtx_fine",
+    "deriv_rty_coarse", "deriv_rty_fine", "gather4_c", "gather4_po",
+    // 128
+    "gather4_po_c", "rcp", "f32tof16", "f16tof32", "uaddc", "usubb", "countbits", "firstbit_hi",
+    "firstbit_lo", "firstbit_shi", "ubfe", "ibfe", "bfi", "bfrev", "swapc", "dcl_stream",
+    // 144
+    "dcl_function_body", "dcl_function_table", "dcl_interface",
+    "dcl_input_control_point_count", "dcl_output_control_point_count",
+    "dcl_tessellator_domain", "dcl_tessellator_partitioning",
+    "dcl_tessellator_output_primitive", "dcl_hs_max_tessfactor",
+    "dcl_hs_fork_phase_instance_count", "dcl_hs_join_phase_instance_count",
+    "dcl_thread_group", "dcl_uav_typed", "dcl_uav_raw", "dcl_uav_structured", "dcl_tgsm_raw",
+    // 160
+    "dcl_tgsm_structured", "dcl_resource_raw", "dcl_resource_structured",
+    "ld_uav_typed", "store_uav_typed", "ld_raw", "store_raw", "ld_structured",
+    "store_structured", "atomic_and", "atomic_or", "atomic_xor",
+    "atomic_cmp_store", "atomic_iadd", "atomic_imax", "atomic_imin",
+    // 176
+    "atomic_umax", "atomic_umin", "imm_atomic_alloc", "imm_atomic_consume",
+    "imm_atomic_iadd", "imm_atomic_and", "imm_atomic_or", "imm_atomic_xor",
+    "imm_atomic_exch", "imm_atomic_cmp_exch", "imm_atomic_imax", "imm_atomic_imin",
+    "imm_atomic_umax", "imm_atomic_umin", "sync", "dadd",
+    // 192
+    "dmax", "dmin", "dmul", "deq", "dge", "dlt", "dne", "dmov",
+    "dmovc", "dtof", "ftod", "eval_snapped", "eval_sample_index", "eval_centroid",
+    "dcl_gsinstances", "abort",
+    // 208
+    "debug_break", "", "ddiv", "dfma", "drcp", "msad", "dtoi", "dtou",
+    "itod", "utod", "", "gather4_s", "gather4_c_s", "gather4_po_s", "gather4_po_c_s", "ld_s",
+    // 224
+    "ldms_s", "ld_uav_typed_s", "ld_raw_s", "ld_structured_s", "sample_l_s",
+    "sample_c_lz_s", "sample_cl_s", "sample_b_cl_s", "sample_d_cl_s", "sample_c_cl_s",
+    "check_access_fully_mapped",
+];
@@ -0,0 +1,291 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! parts specific to shader model 6 DXIL containers: the `DXIL` program,
+//! the `HASH` shader hash and the `PSV0` pipeline state validation.
+
+use super::{DxbcError, ShaderStage, ShaderVersion, u32_at, bytes_at, str_at};
+use std::convert::TryFrom;
+
+/// the LLVM bitcode program of a `DXIL` part
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DxilProgram<'a> {
+    pub version: ShaderVersion,
+    /// major version of the DXIL bitcode format
+    pub dxil_major: u8,
+    /// minor version of the DXIL bitcode format
+    pub dxil_minor: u8,
+    pub bitcode: &'a [u8],
+}
+
+impl<'a> DxilProgram<'a> {
+    /// parse the content of a `DXIL` part
+    pub fn parse(data: &'a [u8]) -> Result<DxilProgram<'a>, DxbcError> {
+        let version = ShaderVersion::from_token(u32_at(data, 0)?)?;
+        // the bitcode header follows the version and the size in DWORDs
+        if bytes_at(data, 8, 4)? != b"DXIL" { return Err(DxbcError::NotAContainer); }
+        let dxil_version = u32_at(data, 12)?;
+        let bitcode_offset = 8 + u32_at(data, 16)? as usize;
+        let bitcode_size = u32_at(data, 20)? as usize;
+        Ok(DxilProgram{
+            version,
+            dxil_major: (dxil_version >> 8) as u8,
+            dxil_minor: dxil_version as u8,
+            bitcode: bytes_at(data, bitcode_offset, bitcode_size)?,
+        })
+    }
+}
+
+/// the shader hash of a `HASH` part, identifying the shader to tools
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ShaderHash {
+    /// whether the hash covers the source and compile options rather than
+    /// the program alone
+    pub includes_source: bool,
+    pub digest: [u8; 16],
+}
+
+impl ShaderHash {
+    /// parse the content of a `HASH` part
+    pub fn parse(data: &[u8]) -> Result<ShaderHash, DxbcError> {
+        let mut digest = [0; 16];
+        digest.copy_from_slice(bytes_at(data, 4, 16)?);
+        Ok(ShaderHash{
+            includes_source: u32_at(data, 0)? & 0x1 != 0,
+            digest,
+        })
+    }
+}
+
+d3d_enum!{
+    /// type of a resource binding in the pipeline state validation
+    pub enum PsvResourceType {
+        INVALID                       = 0,
+        SAMPLER                       = 1,
+        CBV                           = 2,
+        SRV_TYPED                     = 3,
+        SRV_RAW                       = 4,
+        SRV_STRUCTURED                = 5,
+        UAV_TYPED                     = 6,
+        UAV_RAW                       = 7,
+        UAV_STRUCTURED                = 8,
+        UAV_STRUCTURED_WITH_COUNTER   = 9,
+    }
+}
+
+d3d_enum!{
+    /// shape of a resource binding in the pipeline state validation
+    pub enum PsvResourceKind {
+        INVALID                  = 0,
+        TEXTURE1D                = 1,
+        TEXTURE2D                = 2,
+        TEXTURE2DMS              = 3,
+        TEXTURE3D                = 4,
+        TEXTURECUBE              = 5,
+        TEXTURE1DARRAY           = 6,
+        TEXTURE2DARRAY           = 7,
+        TEXTURE2DMSARRAY         = 8,
+        TEXTURECUBEARRAY         = 9,
+        TYPEDBUFFER              = 10,
+        RAWBUFFER                = 11,
+        STRUCTUREDBUFFER         = 12,
+        CBUFFER                  = 13,
+        SAMPLER                  = 14,
+        TBUFFER                  = 15,
+        RTACCELERATIONSTRUCTURE  = 16,
+        FEEDBACKTEXTURE2D        = 17,
+        FEEDBACKTEXTURE2DARRAY   = 18,
+    }
+}
+
+bitflags!{
+    /// flags of a resource binding in the pipeline state validation
+    pub struct PsvResourceFlags: u32 {
+        const USED_BY_ATOMIC64 = 0x1;
+    }
+}
+
+/// a resource binding in the pipeline state validation
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PsvResourceBinding {
+    pub resource_type: PsvResourceType,
+    pub register_space: u32,
+    /// the first register
+    pub lower_bound: u32,
+    /// the last register, inclusive. `0xffffffff` for unbounded arrays
+    pub upper_bound: u32,
+    /// `INVALID` for older compilers
+    pub kind: PsvResourceKind,
+    pub flags: PsvResourceFlags,
+}
+
+/// stage specific information of the pipeline state validation
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PsvStageInfo {
+    Vertex{output_position_present: bool},
+    Hull{
+        input_control_points: u32,
+        output_control_points: u32,
+        /// a `D3D_TESSELLATOR_DOMAIN`
+        tessellator_domain: u32,
+        /// a `D3D_TESSELLATOR_OUTPUT_PRIMITIVE`
+        tessellator_output_primitive: u32,
+    },
+    Domain{
+        input_control_points: u32,
+        output_position_present: bool,
+        /// a `D3D_TESSELLATOR_DOMAIN`
+        tessellator_domain: u32,
+    },
+    Geometry{
+        /// a `D3D_PRIMITIVE`
+        input_primitive: u32,
+        /// a `D3D_PRIMITIVE_TOPOLOGY`
+        output_topology: u32,
+        output_stream_mask: u32,
+        output_position_present: bool,
+    },
+    Pixel{depth_output: bool, sample_frequency: bool},
+    Amplification{payload_size: u32},
+    Mesh{
+        group_shared_bytes_used: u32,
+        group_shared_bytes_dependent_on_view_id: u32,
+        payload_size: u32,
+        max_output_vertices: u16,
+        max_output_primitives: u16,
+    },
+    /// compute shaders and libraries have no stage specific information
+    None,
+}
+
+/// pipeline state validation of a DXIL shader, the `PSV0` part, used by
+/// the runtime to check pipeline states without parsing the program
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PipelineStateValidation {
+    pub stage_info: PsvStageInfo,
+    pub min_wave_lane_count: u32,
+    /// `0xffffffff` if the shader works with any wave size
+    pub max_wave_lane_count: u32,
+    /// the remaining fields are zero or `None` for older compilers
+    pub uses_view_id: bool,
+    pub input_elements: u8,
+    pub output_elements: u8,
+    pub patch_constant_or_primitive_elements: u8,
+    /// thread group size of compute, mesh and amplification shaders
+    pub num_threads: Option<[u32; 3]>,
+    pub entry_name: Option<String>,
+    pub resources: Vec<PsvResourceBinding>,
+}
+
+impl PipelineStateValidation {
+    /// parse the content of a `PSV0` part of a shader of `stage`
+    pub fn parse(data: &[u8], stage: ShaderStage) -> Result<PipelineStateValidation, DxbcError> {
+        let info_size = u32_at(data, 0)? as usize;
+        let info = bytes_at(data, 4, info_size)?;
+        if info_size < 24 { return Err(DxbcError::Truncated); }
+        let dword = |offset: usize| u32_at(info, offset).unwrap_or(0);
+        let byte = |offset: usize| info.get(offset).cloned().unwrap_or(0);
+
+        let stage_info = match stage {
+            ShaderStage::VERTEX => PsvStageInfo::Vertex{output_position_present: byte(0) != 0},
+            ShaderStage::HULL => PsvStageInfo::Hull{
+                input_control_points: dword(0),
+                output_control_points: dword(4),
+                tessellator_domain: dword(8),
+                tessellator_output_primitive: dword(12),
+            },
+            ShaderStage::DOMAIN => PsvStageInfo::Domain{
+                input_control_points: dword(0),
+                output_position_present: byte(4) != 0,
+                tessellator_domain: dword(8),
+            },
+            ShaderStage::GEOMETRY => PsvStageInfo::Geometry{
+                input_primitive: dword(0),
+                output_topology: dword(4),
+                output_stream_mask: dword(8),
+                output_position_present: byte(12) != 0,
+            },
+            ShaderStage::PIXEL => PsvStageInfo::Pixel{
+                depth_output: byte(0) != 0,
+                sample_frequency: byte(1) != 0,
+            },
+            ShaderStage::AMPLIFICATION => PsvStageInfo::Amplification{payload_size: dword(0)},
+            ShaderStage::MESH => PsvStageInfo::Mesh{
+                group_shared_bytes_used: dword(0),
+                group_shared_bytes_dependent_on_view_id: dword(4),
+                payload_size: dword(8),
+                max_output_vertices: dword(12) as u16,
+                max_output_primitives: (dword(12) >> 16) as u16,
+            },
+            ShaderStage::COMPUTE | ShaderStage::LIBRARY => PsvStageInfo::None,
+        };
+
+        // version 1 adds the signature element counts, version 2 the
+        // thread group size and version 3 the entry name
+        let version = match info_size {
+            0..=35 => 0,
+            36..=47 => 1,
+            48..=51 => 2,
+            _ => 3,
+        };
+
+        let mut offset = 4 + info_size;
+        let resource_count = u32_at(data, offset)? as usize;
+        offset += 4;
+        let mut resources = Vec::with_capacity(::std::cmp::min(resource_count, data.len() / 16));
+        if resource_count > 0 {
+            let resource_size = u32_at(data, offset)? as usize;
+            offset += 4;
+            if resource_size < 16 { return Err(DxbcError::Truncated); }
+            for _ in 0..resource_count {
+                let resource = bytes_at(data, offset, resource_size)?;
+                let (kind, flags) = if resource_size >= 24 {
+                    (
+                        PsvResourceKind::try_from(u32_at(resource, 16)?)?,
+                        PsvResourceFlags::from_bits_truncate(u32_at(resource, 20)?),
+                    )
+                } else {
+                    (PsvResourceKind::INVALID, PsvResourceFlags::empty())
+                };
+                resources.push(PsvResourceBinding{
+                    resource_type: PsvResourceType::try_from(u32_at(resource, 0)?)?,
+                    register_space: u32_at(resource, 4)?,
+                    lower_bound: u32_at(resource, 8)?,
+                    upper_bound: u32_at(resource, 12)?,
+                    kind, flags,
+                });
+                offset += resource_size;
+            }
+        }
+
+        let entry_name = if version >= 3 {
+            let strings_size = u32_at(data, offset)? as usize;
+            let strings = bytes_at(data, offset + 4, strings_size)?;
+            Some(str_at(strings, dword(48) as usize)?)
+        } else {
+            None
+        };
+
+        Ok(PipelineStateValidation{
+            stage_info,
+            min_wave_lane_count: dword(16),
+            max_wave_lane_count: dword(20),
+            uses_view_id: byte(25) != 0,
+            input_elements: byte(28),
+            output_elements: byte(29),
+            patch_constant_or_primitive_elements: byte(30),
+            num_threads: if version >= 2 {
+                Some([dword(36), dword(40), dword(44)])
+            } else {
+                None
+            },
+            entry_name,
+            resources,
+        })
+    }
+}
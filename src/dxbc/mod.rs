@@ -0,0 +1,452 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! the DXBC container format, holding compiled shaders and serialized
+//! root signatures as a list of parts tagged by four character codes.
+//!
+//! `DxbcContainer` lists the parts, and parses signatures, resource
+//! bindings, feature flags and statistics without `D3DReflect`, and
+//! disassembles shader model 4 and 5 programs. `DxbcBuilder` rewrites
+//! containers. shader model 6 DXIL containers share the format, with their
+//! own parts.
+
+use error::InvalidEnumValue;
+use std::{error, fmt, str};
+
+mod signature;
+mod rdef;
+mod stat;
+mod dxil;
+mod disasm;
+mod writer;
+pub use self::signature::*;
+pub use self::rdef::*;
+pub use self::stat::*;
+pub use self::dxil::*;
+pub use self::disasm::*;
+pub use self::writer::*;
+
+/// an error reading a DXBC container or one of its parts
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DxbcError {
+    /// the data doesn't start with `DXBC`
+    NotAContainer,
+    /// the data ends before a structure it refers to
+    Truncated,
+    /// the container has no part with this tag
+    MissingPart([u8; 4]),
+    /// a name that isn't valid UTF-8
+    InvalidString,
+    /// an out of range enumeration value
+    InvalidValue(InvalidEnumValue),
+    /// a malformed shader program instruction, at this DWORD offset
+    InvalidInstruction(usize),
+}
+
+impl fmt::Display for DxbcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            DxbcError::NotAContainer =>
+                write!(f, "not a DXBC container"),
+            DxbcError::Truncated =>
+                write!(f, "the container is truncated"),
+            DxbcError::MissingPart(ref fourcc) =>
+                write!(f, "the container has no {} part", String::from_utf8_lossy(fourcc)),
+            DxbcError::InvalidString =>
+                write!(f, "a name in the container is not valid UTF-8"),
+            DxbcError::InvalidValue(ref err) =>
+                write!(f, "{}", err),
+            DxbcError::InvalidInstruction(offset) =>
+                write!(f, "malformed instruction at DWORD {} of the shader program", offset),
+        }
+    }
+}
+
+impl error::Error for DxbcError {}
+
+impl From<InvalidEnumValue> for DxbcError {
+    #[inline]
+    fn from(err: InvalidEnumValue) -> Self {
+        DxbcError::InvalidValue(err)
+    }
+}
+
+/// a part of a container
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DxbcPart<'a> {
+    /// the four character code, e.g. `ISGN`
+    pub fourcc: [u8; 4],
+    /// content of the part
+    pub data: &'a [u8],
+}
+
+/// a parsed DXBC container, borrowing the underlying bytes
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DxbcContainer<'a> {
+    bytes: &'a [u8],
+    parts: Vec<DxbcPart<'a>>,
+}
+
+impl<'a> DxbcContainer<'a> {
+    /// parse the part list of the container in `bytes`, e.g. the content
+    /// of a shader byte code
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<DxbcContainer<'a>, DxbcError> {
+        if !bytes.starts_with(b"DXBC") { return Err(DxbcError::NotAContainer); }
+        let size = u32_at(bytes, 24)? as usize;
+        let bytes = bytes.get(..size).ok_or(DxbcError::Truncated)?;
+        let part_count = u32_at(bytes, 28)? as usize;
+        let mut parts = Vec::with_capacity(::std::cmp::min(part_count, bytes.len() / 4));
+        for i in 0..part_count {
+            let offset = u32_at(bytes, HEADER_SIZE + i * 4)? as usize;
+            let fourcc = bytes_at(bytes, offset, 4)?;
+            let size = u32_at(bytes, offset + 4)? as usize;
+            parts.push(DxbcPart{
+                fourcc: [fourcc[0], fourcc[1], fourcc[2], fourcc[3]],
+                data: bytes_at(bytes, offset + 8, size)?,
+            });
+        }
+        Ok(DxbcContainer{bytes, parts})
+    }
+
+    /// the whole container
+    #[inline]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// the checksum stored in the container
+    #[inline]
+    pub fn hash(&self) -> [u8; 16] {
+        let mut ret = [0; 16];
+        ret.copy_from_slice(&self.bytes[4..CHECKSUM_SKIP]);
+        ret
+    }
+
+    /// compute the checksum of the container
+    #[inline]
+    pub fn compute_hash(&self) -> [u8; 16] {
+        checksum(&self.bytes[CHECKSUM_SKIP..])
+    }
+
+    /// whether the stored checksum matches the content, as the runtime
+    /// requires. unsigned DXIL containers have a zero checksum instead.
+    #[inline]
+    pub fn has_valid_hash(&self) -> bool {
+        self.hash() == self.compute_hash()
+    }
+
+    /// the parts, in container order
+    #[inline]
+    pub fn parts(&self) -> &[DxbcPart<'a>] {
+        &self.parts
+    }
+
+    /// content of the first part tagged `fourcc`
+    #[inline]
+    pub fn part(&self, fourcc: &[u8; 4]) -> Option<&'a [u8]> {
+        self.parts.iter().find(|part| &part.fourcc == fourcc).map(|part| part.data)
+    }
+
+    /// content of the first part tagged with any of `fourccs`, in order of preference
+    fn any_part(&self, fourccs: &[&[u8; 4]]) -> Result<&'a [u8], DxbcError> {
+        fourccs.iter()
+            .filter_map(|fourcc| self.part(fourcc))
+            .next()
+            .ok_or_else(|| DxbcError::MissingPart(*fourccs[0]))
+    }
+
+    /// whether this is a shader model 6 container, holding a `DXIL` program
+    #[inline]
+    pub fn is_dxil(&self) -> bool {
+        self.part(b"DXIL").is_some()
+    }
+
+    /// stage and shader model of the `SHDR`, `SHEX` or `DXIL` shader program
+    pub fn shader_version(&self) -> Result<ShaderVersion, DxbcError> {
+        let program = self.any_part(&[b"SHEX", b"SHDR", b"DXIL"])?;
+        ShaderVersion::from_token(u32_at(program, 0)?)
+    }
+
+    /// the input signature, from `ISG1` or `ISGN`
+    pub fn input_signature(&self) -> Result<Signature, DxbcError> {
+        let (fourcc, data) = self.signature_part(&[b"ISG1", b"ISGN"])?;
+        Signature::parse(fourcc, data)
+    }
+
+    /// the output signature, from `OSG1`, `OSG5` or `OSGN`
+    pub fn output_signature(&self) -> Result<Signature, DxbcError> {
+        let (fourcc, data) = self.signature_part(&[b"OSG1", b"OSG5", b"OSGN"])?;
+        Signature::parse(fourcc, data)
+    }
+
+    /// the hull shader patch constant signature, or the mesh shader
+    /// primitive signature, from `PSG1` or `PCSG`
+    pub fn patch_constant_signature(&self) -> Result<Signature, DxbcError> {
+        let (fourcc, data) = self.signature_part(&[b"PSG1", b"PCSG"])?;
+        Signature::parse(fourcc, data)
+    }
+
+    fn signature_part(&self, fourccs: &[&[u8; 4]]) -> Result<([u8; 4], &'a [u8]), DxbcError> {
+        fourccs.iter()
+            .filter_map(|&fourcc| self.part(fourcc).map(|data| (*fourcc, data)))
+            .next()
+            .ok_or_else(|| DxbcError::MissingPart(*fourccs[0]))
+    }
+
+    /// constant buffers and resource bindings, from `RDEF`
+    #[inline]
+    pub fn resource_defs(&self) -> Result<ResourceDefs, DxbcError> {
+        ResourceDefs::parse(self.any_part(&[b"RDEF"])?)
+    }
+
+    /// optional features the shader requires, from `SFI0`. shaders
+    /// requiring none may have no `SFI0` part.
+    pub fn shader_requires(&self) -> Result<ShaderRequires, DxbcError> {
+        match self.part(b"SFI0") {
+            Some(data) => ShaderRequires::parse(data),
+            None => Ok(ShaderRequires::empty()),
+        }
+    }
+
+    /// instruction statistics, from `STAT`. the `STAT` part of a DXIL
+    /// container holds a reflection program instead, for which this
+    /// returns `MissingPart`.
+    #[inline]
+    pub fn statistics(&self) -> Result<ShaderStatistics, DxbcError> {
+        if self.is_dxil() { return Err(DxbcError::MissingPart(*b"STAT")); }
+        ShaderStatistics::parse(self.any_part(&[b"STAT"])?)
+    }
+
+    /// the DXIL program, from `DXIL`
+    #[inline]
+    pub fn dxil_program(&self) -> Result<DxilProgram<'a>, DxbcError> {
+        DxilProgram::parse(self.any_part(&[b"DXIL"])?)
+    }
+
+    /// the shader hash, from `HASH`
+    #[inline]
+    pub fn shader_hash(&self) -> Result<ShaderHash, DxbcError> {
+        ShaderHash::parse(self.any_part(&[b"HASH"])?)
+    }
+
+    /// the pipeline state validation of a DXIL shader, from `PSV0`
+    pub fn pipeline_state_validation(&self) -> Result<PipelineStateValidation, DxbcError> {
+        let data = self.any_part(&[b"PSV0"])?;
+        PipelineStateValidation::parse(data, self.shader_version()?.stage)
+    }
+}
+
+d3d_enum!{
+    /// the stage of a shader program
+    pub enum ShaderStage {
+        PIXEL         = 0,
+        VERTEX        = 1,
+        GEOMETRY      = 2,
+        HULL          = 3,
+        DOMAIN        = 4,
+        COMPUTE       = 5,
+        /// a shader model 6.3 library, e.g. of ray tracing shaders
+        LIBRARY       = 6,
+        MESH          = 13,
+        AMPLIFICATION = 14,
+    }
+}
+
+/// stage and shader model of a shader program
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ShaderVersion {
+    pub stage: ShaderStage,
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl ShaderVersion {
+    /// decode the version token opening a shader program
+    #[inline]
+    pub fn from_token(token: u32) -> Result<ShaderVersion, DxbcError> {
+        use std::convert::TryFrom;
+        Ok(ShaderVersion{
+            stage: ShaderStage::try_from(token >> 16)?,
+            major: ((token >> 4) & 0xf) as u8,
+            minor: (token & 0xf) as u8,
+        })
+    }
+}
+
+impl fmt::Display for ShaderVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let prefix = match self.stage {
+            ShaderStage::PIXEL => "ps",
+            ShaderStage::VERTEX => "vs",
+            ShaderStage::GEOMETRY => "gs",
+            ShaderStage::HULL => "hs",
+            ShaderStage::DOMAIN => "ds",
+            ShaderStage::COMPUTE => "cs",
+            ShaderStage::LIBRARY => "lib",
+            ShaderStage::MESH => "ms",
+            ShaderStage::AMPLIFICATION => "as",
+        };
+        write!(f, "{}_{}_{}", prefix, self.major, self.minor)
+    }
+}
+
+/// the little endian `u32` at `offset`
+#[inline]
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, DxbcError> {
+    read_u32(bytes, offset).ok_or(DxbcError::Truncated)
+}
+
+/// `len` bytes at `offset`
+#[inline]
+fn bytes_at(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], DxbcError> {
+    offset.checked_add(len)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or(DxbcError::Truncated)
+}
+
+/// the nul terminated string at `offset`
+fn str_at(bytes: &[u8], offset: usize) -> Result<String, DxbcError> {
+    let rest = bytes.get(offset..).ok_or(DxbcError::Truncated)?;
+    let len = rest.iter().position(|&b| b == 0).ok_or(DxbcError::Truncated)?;
+    str::from_utf8(&rest[..len])
+        .map(ToOwned::to_owned)
+        .map_err(|_| DxbcError::InvalidString)
+}
+
+/// size of the container header before the part offsets
+const HEADER_SIZE: usize = 32;
+/// bytes at the start of the container not covered by the checksum
+const CHECKSUM_SKIP: usize = 20;
+
+/// find the first part tagged `fourcc` in the container `bytes`
+pub(crate) fn find_part<'a>(bytes: &'a [u8], fourcc: &[u8; 4]) -> Option<&'a [u8]> {
+    if bytes.len() < HEADER_SIZE || &bytes[..4] != b"DXBC" { return None; }
+    let part_count = read_u32(bytes, 28)? as usize;
+    for i in 0..part_count {
+        let offset = read_u32(bytes, HEADER_SIZE + i * 4)? as usize;
+        let size = read_u32(bytes, offset.checked_add(4)?)? as usize;
+        if bytes.get(offset..offset + 4)? == &fourcc[..] {
+            return bytes.get(offset + 8..(offset + 8).checked_add(size)?);
+        }
+    }
+    None
+}
+
+/// write a container holding `parts`, with a valid checksum
+pub(crate) fn write_container(parts: &[([u8; 4], &[u8])]) -> Vec<u8> {
+    let parts_start = HEADER_SIZE + parts.len() * 4;
+    let size = parts_start + parts.iter().map(|&(_, data)| 8 + data.len()).sum::<usize>();
+
+    let mut ret = Vec::with_capacity(size);
+    ret.extend_from_slice(b"DXBC");
+    ret.extend_from_slice(&[0; 16]);
+    push_u32(&mut ret, 1); // version 1.0
+    push_u32(&mut ret, size as u32);
+    push_u32(&mut ret, parts.len() as u32);
+    let mut offset = parts_start;
+    for &(_, data) in parts {
+        push_u32(&mut ret, offset as u32);
+        offset += 8 + data.len();
+    }
+    for &(fourcc, data) in parts {
+        ret.extend_from_slice(&fourcc);
+        push_u32(&mut ret, data.len() as u32);
+        ret.extend_from_slice(data);
+    }
+
+    let hash = checksum(&ret[CHECKSUM_SKIP..]);
+    ret[4..CHECKSUM_SKIP].copy_from_slice(&hash);
+    ret
+}
+
+#[inline]
+pub(crate) fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let b = bytes.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16 | u32::from(b[3]) << 24)
+}
+
+#[inline]
+pub(crate) fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+/// the DXBC checksum of `data`, the container after the hash field.
+///
+/// it's MD5 with a different padding: the bit length goes into the first
+/// word of the final block, and `(bits >> 2) | 1` into its last word.
+pub(crate) fn checksum(data: &[u8]) -> [u8; 16] {
+    let mut state = [0x67452301u32, 0xefcdab89, 0x98badcfe, 0x10325476];
+    let full = data.len() & !63;
+    for block in data[..full].chunks(64) {
+        md5_transform(&mut state, block);
+    }
+
+    let rest = &data[full..];
+    let num_bits = (data.len() as u32).wrapping_mul(8);
+    let mut last = [0u8; 64];
+    if rest.len() >= 56 {
+        let mut block = [0u8; 64];
+        block[..rest.len()].copy_from_slice(rest);
+        block[rest.len()] = 0x80;
+        md5_transform(&mut state, &block);
+    } else {
+        last[4..4 + rest.len()].copy_from_slice(rest);
+        last[4 + rest.len()] = 0x80;
+    }
+    last[..4].copy_from_slice(&num_bits.to_le_bytes());
+    last[60..].copy_from_slice(&((num_bits >> 2) | 1).to_le_bytes());
+    md5_transform(&mut state, &last);
+
+    let mut ret = [0u8; 16];
+    for (i, word) in state.iter().enumerate() {
+        ret[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+    ret
+}
+
+fn md5_transform(state: &mut [u32; 4], block: &[u8]) {
+    const S: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+        5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+        4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+        6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    const K: [u32; 64] = [
+        0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+        0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+        0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+        0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+        0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+        0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+        0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+        0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+    ];
+
+    let mut m = [0u32; 16];
+    for (i, word) in m.iter_mut().enumerate() {
+        *word = read_u32(block, i * 4).unwrap();
+    }
+    let [mut a, mut b, mut c, mut d] = *state;
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let rotated = a.wrapping_add(f).wrapping_add(K[i]).wrapping_add(m[g]).rotate_left(S[i]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(rotated);
+    }
+    state[0] = state[0].wrapping_add(a);
+    state[1] = state[1].wrapping_add(b);
+    state[2] = state[2].wrapping_add(c);
+    state[3] = state[3].wrapping_add(d);
+}
@@ -0,0 +1,388 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! resource bindings and constant buffer layouts, the `RDEF` part.
+
+use super::{DxbcError, u32_at, bytes_at, str_at};
+use std::convert::TryFrom;
+
+d3d_enum!{
+    /// type of a resource binding
+    pub enum ShaderInputType {
+        CBUFFER                       = 0,
+        TBUFFER                       = 1,
+        TEXTURE                       = 2,
+        SAMPLER                       = 3,
+        UAV_RWTYPED                   = 4,
+        STRUCTURED                    = 5,
+        UAV_RWSTRUCTURED              = 6,
+        BYTEADDRESS                   = 7,
+        UAV_RWBYTEADDRESS             = 8,
+        UAV_APPEND_STRUCTURED         = 9,
+        UAV_CONSUME_STRUCTURED        = 10,
+        UAV_RWSTRUCTURED_WITH_COUNTER = 11,
+        RTACCELERATIONSTRUCTURE       = 12,
+        UAV_FEEDBACKTEXTURE           = 13,
+    }
+}
+
+d3d_enum!{
+    /// type of the values returned by a typed resource
+    pub enum ResourceReturnType {
+        UNORM     = 1,
+        SNORM     = 2,
+        SINT      = 3,
+        UINT      = 4,
+        FLOAT     = 5,
+        MIXED     = 6,
+        DOUBLE    = 7,
+        CONTINUED = 8,
+    }
+}
+
+d3d_enum!{
+    /// dimension of a resource binding
+    pub enum ShaderInputDimension {
+        UNKNOWN          = 0,
+        BUFFER           = 1,
+        TEXTURE1D        = 2,
+        TEXTURE1DARRAY   = 3,
+        TEXTURE2D        = 4,
+        TEXTURE2DARRAY   = 5,
+        TEXTURE2DMS      = 6,
+        TEXTURE2DMSARRAY = 7,
+        TEXTURE3D        = 8,
+        TEXTURECUBE      = 9,
+        TEXTURECUBEARRAY = 10,
+        BUFFEREX         = 11,
+    }
+}
+
+bitflags!{
+    /// flags of a resource binding
+    pub struct ShaderInputFlags: u32 {
+        const USERPACKED          = 0x1;
+        const COMPARISON_SAMPLER  = 0x2;
+        const TEXTURE_COMPONENT_0 = 0x4;
+        const TEXTURE_COMPONENT_1 = 0x8;
+        const UNUSED              = 0x10;
+    }
+}
+
+d3d_enum!{
+    /// kind of a constant buffer
+    pub enum CbufferType {
+        CBUFFER            = 0,
+        TBUFFER            = 1,
+        INTERFACE_POINTERS = 2,
+        RESOURCE_BIND_INFO = 3,
+    }
+}
+
+bitflags!{
+    /// flags of a constant buffer variable
+    pub struct VariableFlags: u32 {
+        const USERPACKED          = 0x1;
+        const USED                = 0x2;
+        const INTERFACE_POINTER   = 0x4;
+        const INTERFACE_PARAMETER = 0x8;
+    }
+}
+
+d3d_enum!{
+    /// class of a variable type
+    pub enum VariableClass {
+        SCALAR            = 0,
+        VECTOR            = 1,
+        MATRIX_ROWS       = 2,
+        MATRIX_COLUMNS    = 3,
+        OBJECT            = 4,
+        STRUCT            = 5,
+        INTERFACE_CLASS   = 6,
+        INTERFACE_POINTER = 7,
+    }
+}
+
+d3d_enum!{
+    /// base type of a variable type
+    pub enum VariableBaseType {
+        VOID                      = 0,
+        BOOL                      = 1,
+        INT                       = 2,
+        FLOAT                     = 3,
+        STRING                    = 4,
+        TEXTURE                   = 5,
+        TEXTURE1D                 = 6,
+        TEXTURE2D                 = 7,
+        TEXTURE3D                 = 8,
+        TEXTURECUBE               = 9,
+        SAMPLER                   = 10,
+        SAMPLER1D                 = 11,
+        SAMPLER2D                 = 12,
+        SAMPLER3D                 = 13,
+        SAMPLERCUBE               = 14,
+        PIXELSHADER               = 15,
+        VERTEXSHADER              = 16,
+        PIXELFRAGMENT             = 17,
+        VERTEXFRAGMENT            = 18,
+        UINT                      = 19,
+        UINT8                     = 20,
+        GEOMETRYSHADER            = 21,
+        RASTERIZER                = 22,
+        DEPTHSTENCIL              = 23,
+        BLEND                     = 24,
+        BUFFER                    = 25,
+        CBUFFER                   = 26,
+        TBUFFER                   = 27,
+        TEXTURE1DARRAY            = 28,
+        TEXTURE2DARRAY            = 29,
+        RENDERTARGETVIEW          = 30,
+        DEPTHSTENCILVIEW          = 31,
+        TEXTURE2DMS               = 32,
+        TEXTURE2DMSARRAY          = 33,
+        TEXTURECUBEARRAY          = 34,
+        HULLSHADER                = 35,
+        DOMAINSHADER              = 36,
+        INTERFACE_POINTER         = 37,
+        COMPUTESHADER             = 38,
+        DOUBLE                    = 39,
+        RWTEXTURE1D               = 40,
+        RWTEXTURE1DARRAY          = 41,
+        RWTEXTURE2D               = 42,
+        RWTEXTURE2DARRAY          = 43,
+        RWTEXTURE3D               = 44,
+        RWBUFFER                  = 45,
+        BYTEADDRESS_BUFFER        = 46,
+        RWBYTEADDRESS_BUFFER      = 47,
+        STRUCTURED_BUFFER         = 48,
+        RWSTRUCTURED_BUFFER       = 49,
+        APPEND_STRUCTURED_BUFFER  = 50,
+        CONSUME_STRUCTURED_BUFFER = 51,
+        MIN8FLOAT                 = 52,
+        MIN10FLOAT                = 53,
+        MIN16FLOAT                = 54,
+        MIN12INT                  = 55,
+        MIN16INT                  = 56,
+        MIN16UINT                 = 57,
+        INT16                     = 58,
+        UINT16                    = 59,
+        FLOAT16                   = 60,
+        INT64                     = 61,
+        UINT64                    = 62,
+    }
+}
+
+/// a resource bound to a shader register
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceBinding {
+    pub name: String,
+    pub input_type: ShaderInputType,
+    /// the return type of typed resources
+    pub return_type: Option<ResourceReturnType>,
+    pub dimension: ShaderInputDimension,
+    /// sample count of multisampled textures, or the stride of
+    /// structured buffers
+    pub sample_count: u32,
+    pub bind_point: u32,
+    /// number of registers bound, 0 for unbounded arrays
+    pub bind_count: u32,
+    pub flags: ShaderInputFlags,
+    /// register space, 0 before shader model 5.1
+    pub register_space: u32,
+    /// the range id programs refer to the binding by since shader model
+    /// 5.1, e.g. `T0`, 0 before
+    pub id: u32,
+}
+
+/// the type of a constant buffer variable
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VariableType {
+    pub class: VariableClass,
+    pub base_type: VariableBaseType,
+    pub rows: u32,
+    pub columns: u32,
+    /// number of array elements, 0 if not an array
+    pub elements: u32,
+    /// the type name, since shader model 5
+    pub name: Option<String>,
+    /// members of structs
+    pub members: Vec<TypeMember>,
+}
+
+/// a member of a struct type
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TypeMember {
+    pub name: String,
+    /// offset from the start of the struct in bytes
+    pub offset: u32,
+    pub ty: VariableType,
+}
+
+/// a variable of a constant buffer
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CbufferVariable {
+    pub name: String,
+    /// offset from the start of the buffer in bytes
+    pub offset: u32,
+    pub size: u32,
+    pub flags: VariableFlags,
+    pub ty: VariableType,
+    /// the initial value, if any
+    pub default_value: Option<Vec<u8>>,
+}
+
+/// layout of a constant buffer
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CbufferDesc {
+    pub name: String,
+    pub cbuffer_type: CbufferType,
+    /// size in bytes, a multiple of 16
+    pub size: u32,
+    pub flags: u32,
+    pub variables: Vec<CbufferVariable>,
+}
+
+/// resource bindings and constant buffers of a shader
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceDefs {
+    /// the compiler that created the shader
+    pub creator: String,
+    /// the `D3DCOMPILE` flags the shader is compiled with
+    pub compile_flags: u32,
+    pub constant_buffers: Vec<CbufferDesc>,
+    pub bindings: Vec<ResourceBinding>,
+}
+
+/// nesting limit of struct types, guarding against cyclic type offsets,
+/// which are reported as truncated data
+const MAX_TYPE_DEPTH: u32 = 64;
+
+impl ResourceDefs {
+    /// parse the content of an `RDEF` part
+    pub fn parse(data: &[u8]) -> Result<ResourceDefs, DxbcError> {
+        let cbuffer_count = u32_at(data, 0)? as usize;
+        let cbuffer_offset = u32_at(data, 4)? as usize;
+        let binding_count = u32_at(data, 8)? as usize;
+        let binding_offset = u32_at(data, 12)? as usize;
+        // the low half holds the major and minor shader model, the high
+        // half the program type
+        let target = u32_at(data, 16)?;
+        let compile_flags = u32_at(data, 20)?;
+        let creator = str_at(data, u32_at(data, 24)? as usize)?;
+        let model = target & 0xffff;
+        let sm5 = model >= 0x500;
+        let sm5_1 = model >= 0x501;
+
+        let binding_size = if sm5_1 { 40 } else { 32 };
+        let mut bindings = Vec::with_capacity(::std::cmp::min(binding_count, data.len() / binding_size));
+        for i in 0..binding_count {
+            let offset = binding_offset + i * binding_size;
+            bytes_at(data, offset, binding_size)?;
+            let return_type = match u32_at(data, offset + 8)? {
+                0 => None,
+                value => Some(ResourceReturnType::try_from(value)?),
+            };
+            bindings.push(ResourceBinding{
+                name: str_at(data, u32_at(data, offset)? as usize)?,
+                input_type: ShaderInputType::try_from(u32_at(data, offset + 4)?)?,
+                return_type,
+                dimension: ShaderInputDimension::try_from(u32_at(data, offset + 12)?)?,
+                sample_count: u32_at(data, offset + 16)?,
+                bind_point: u32_at(data, offset + 20)?,
+                bind_count: u32_at(data, offset + 24)?,
+                flags: ShaderInputFlags::from_bits_truncate(u32_at(data, offset + 28)?),
+                register_space: if sm5_1 { u32_at(data, offset + 32)? } else { 0 },
+                id: if sm5_1 { u32_at(data, offset + 36)? } else { 0 },
+            });
+        }
+
+        let mut constant_buffers = Vec::with_capacity(::std::cmp::min(cbuffer_count, data.len() / 24));
+        for i in 0..cbuffer_count {
+            let offset = cbuffer_offset + i * 24;
+            let variable_count = u32_at(data, offset + 4)? as usize;
+            let variable_offset = u32_at(data, offset + 8)? as usize;
+            let variable_size = if sm5 { 40 } else { 24 };
+            let mut variables = Vec::with_capacity(::std::cmp::min(variable_count, data.len() / variable_size));
+            for j in 0..variable_count {
+                let offset = variable_offset + j * variable_size;
+                bytes_at(data, offset, variable_size)?;
+                let size = u32_at(data, offset + 8)?;
+                let default_value = match u32_at(data, offset + 20)? as usize {
+                    0 => None,
+                    value_offset => Some(bytes_at(data, value_offset, size as usize)?.to_vec()),
+                };
+                variables.push(CbufferVariable{
+                    name: str_at(data, u32_at(data, offset)? as usize)?,
+                    offset: u32_at(data, offset + 4)?,
+                    size,
+                    flags: VariableFlags::from_bits_truncate(u32_at(data, offset + 12)?),
+                    ty: parse_type(data, u32_at(data, offset + 16)? as usize, sm5, 0)?,
+                    default_value,
+                });
+            }
+            constant_buffers.push(CbufferDesc{
+                name: str_at(data, u32_at(data, offset)? as usize)?,
+                size: u32_at(data, offset + 12)?,
+                flags: u32_at(data, offset + 16)?,
+                cbuffer_type: CbufferType::try_from(u32_at(data, offset + 20)?)?,
+                variables,
+            });
+        }
+
+        Ok(ResourceDefs{creator, compile_flags, constant_buffers, bindings})
+    }
+
+    /// the constant buffer named `name`
+    pub fn constant_buffer(&self, name: &str) -> Option<&CbufferDesc> {
+        self.constant_buffers.iter().find(|cbuffer| cbuffer.name == name)
+    }
+
+    /// the resource binding named `name`
+    pub fn binding(&self, name: &str) -> Option<&ResourceBinding> {
+        self.bindings.iter().find(|binding| binding.name == name)
+    }
+}
+
+/// parse the type at `offset`, and its members
+fn parse_type(data: &[u8], offset: usize, sm5: bool, depth: u32) -> Result<VariableType, DxbcError> {
+    if depth > MAX_TYPE_DEPTH { return Err(DxbcError::Truncated); }
+    let class_and_type = u32_at(data, offset)?;
+    let rows_and_columns = u32_at(data, offset + 4)?;
+    let elements_and_members = u32_at(data, offset + 8)?;
+    let member_offset = u32_at(data, offset + 12)? as usize;
+    // shader model 5 types carry 4 more unknown DWORDs before the name
+    let name = if sm5 {
+        match u32_at(data, offset + 32)? as usize {
+            0 => None,
+            name_offset => Some(str_at(data, name_offset)?),
+        }
+    } else {
+        None
+    };
+
+    let member_count = (elements_and_members >> 16) as usize;
+    let mut members = Vec::with_capacity(::std::cmp::min(member_count, data.len() / 12));
+    for i in 0..member_count {
+        let offset = member_offset + i * 12;
+        members.push(TypeMember{
+            name: str_at(data, u32_at(data, offset)? as usize)?,
+            ty: parse_type(data, u32_at(data, offset + 4)? as usize, sm5, depth + 1)?,
+            offset: u32_at(data, offset + 8)?,
+        });
+    }
+
+    Ok(VariableType{
+        class: VariableClass::try_from(class_and_type & 0xffff)?,
+        base_type: VariableBaseType::try_from(class_and_type >> 16)?,
+        rows: rows_and_columns & 0xffff,
+        columns: rows_and_columns >> 16,
+        elements: elements_and_members & 0xffff,
+        name,
+        members,
+    })
+}
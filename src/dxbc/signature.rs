@@ -0,0 +1,149 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! input, output and patch constant signatures.
+
+use super::{DxbcError, u32_at, bytes_at, str_at};
+use std::convert::TryFrom;
+
+d3d_enum!{
+    /// system value semantic of a signature element
+    pub enum SystemValue {
+        UNDEFINED                     = 0,
+        POSITION                      = 1,
+        CLIP_DISTANCE                 = 2,
+        CULL_DISTANCE                 = 3,
+        RENDER_TARGET_ARRAY_INDEX     = 4,
+        VIEWPORT_ARRAY_INDEX          = 5,
+        VERTEX_ID                     = 6,
+        PRIMITIVE_ID                  = 7,
+        INSTANCE_ID                   = 8,
+        IS_FRONT_FACE                 = 9,
+        SAMPLE_INDEX                  = 10,
+        FINAL_QUAD_EDGE_TESSFACTOR    = 11,
+        FINAL_QUAD_INSIDE_TESSFACTOR  = 12,
+        FINAL_TRI_EDGE_TESSFACTOR     = 13,
+        FINAL_TRI_INSIDE_TESSFACTOR   = 14,
+        FINAL_LINE_DETAIL_TESSFACTOR  = 15,
+        FINAL_LINE_DENSITY_TESSFACTOR = 16,
+        BARYCENTRICS                  = 23,
+        SHADINGRATE                   = 24,
+        CULLPRIMITIVE                 = 25,
+        TARGET                        = 64,
+        DEPTH                         = 65,
+        COVERAGE                      = 66,
+        DEPTH_GREATER_EQUAL           = 67,
+        DEPTH_LESS_EQUAL              = 68,
+        STENCIL_REF                   = 69,
+        INNER_COVERAGE                = 70,
+    }
+}
+
+d3d_enum!{
+    /// component type of a signature element register
+    pub enum RegisterComponentType {
+        UNKNOWN = 0,
+        UINT32  = 1,
+        SINT32  = 2,
+        FLOAT32 = 3,
+        UINT16  = 4,
+        SINT16  = 5,
+        FLOAT16 = 6,
+        UINT64  = 7,
+        SINT64  = 8,
+        FLOAT64 = 9,
+    }
+}
+
+d3d_enum!{
+    /// minimum precision of a signature element, as in `D3D_MIN_PRECISION`
+    pub enum MinPrecision {
+        DEFAULT   = 0,
+        FLOAT_16  = 1,
+        FLOAT_2_8 = 2,
+        RESERVED  = 3,
+        SINT_16   = 4,
+        UINT_16   = 5,
+        ANY_16    = 0xf0,
+        ANY_10    = 0xf1,
+    }
+}
+
+/// an element of a signature
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SignatureElement {
+    pub semantic_name: String,
+    pub semantic_index: u32,
+    pub system_value: SystemValue,
+    pub component_type: RegisterComponentType,
+    pub register: u32,
+    /// the declared components, one bit per component
+    pub mask: u8,
+    /// for inputs, the components the shader reads. for outputs, the
+    /// components the shader never writes.
+    pub rw_mask: u8,
+    /// the geometry shader output stream
+    pub stream: u32,
+    /// only given by `ISG1`, `OSG1` and `PSG1` parts
+    pub min_precision: MinPrecision,
+}
+
+/// an input, output or patch constant signature
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Signature {
+    pub elements: Vec<SignatureElement>,
+}
+
+impl Signature {
+    /// parse the content of a signature part tagged `fourcc`
+    pub fn parse(fourcc: [u8; 4], data: &[u8]) -> Result<Signature, DxbcError> {
+        // `OSG5` elements start with the stream index, DXIL `ISG1`,
+        // `OSG1` and `PSG1` elements also end with the minimum precision
+        let (element_size, has_stream) = match &fourcc {
+            b"ISG1" | b"OSG1" | b"PSG1" => (32, true),
+            b"OSG5" => (28, true),
+            _ => (24, false),
+        };
+        let count = u32_at(data, 0)? as usize;
+        let elements_offset = u32_at(data, 4)? as usize;
+        let mut elements = Vec::with_capacity(::std::cmp::min(count, data.len() / element_size));
+        for i in 0..count {
+            let offset = elements_offset + i * element_size;
+            bytes_at(data, offset, element_size)?;
+            let (stream, offset) = if has_stream {
+                (u32_at(data, offset)?, offset + 4)
+            } else {
+                (0, offset)
+            };
+            elements.push(SignatureElement{
+                semantic_name: str_at(data, u32_at(data, offset)? as usize)?,
+                semantic_index: u32_at(data, offset + 4)?,
+                system_value: SystemValue::try_from(u32_at(data, offset + 8)?)?,
+                component_type: RegisterComponentType::try_from(u32_at(data, offset + 12)?)?,
+                register: u32_at(data, offset + 16)?,
+                mask: data[offset + 20],
+                rw_mask: data[offset + 21],
+                stream,
+                min_precision: if element_size == 32 {
+                    MinPrecision::try_from(u32_at(data, offset + 24)?)?
+                } else {
+                    MinPrecision::DEFAULT
+                },
+            });
+        }
+        Ok(Signature{elements})
+    }
+
+    /// the element with a semantic, compared case insensitively as HLSL does
+    pub fn find(&self, semantic_name: &str, semantic_index: u32) -> Option<&SignatureElement> {
+        self.elements.iter().find(|element| {
+            element.semantic_index == semantic_index &&
+            element.semantic_name.eq_ignore_ascii_case(semantic_name)
+        })
+    }
+}
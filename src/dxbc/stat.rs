@@ -0,0 +1,149 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! required features, the `SFI0` part, and instruction statistics, the
+//! `STAT` part.
+
+use super::{DxbcError, read_u32, bytes_at};
+
+bitflags!{
+    /// optional features required by a shader
+    pub struct ShaderRequires: u64 {
+        const DOUBLES                                = 0x1;
+        const EARLY_DEPTH_STENCIL                    = 0x2;
+        const UAVS_AT_EVERY_STAGE                    = 0x4;
+        const UAVS_64                                = 0x8;
+        const MINIMUM_PRECISION                      = 0x10;
+        const DOUBLE_EXTENSIONS_11_1                 = 0x20;
+        const SHADER_EXTENSIONS_11_1                 = 0x40;
+        const LEVEL_9_COMPARISON_FILTERING           = 0x80;
+        const TILED_RESOURCES                        = 0x100;
+        const STENCIL_REF                            = 0x200;
+        const INNER_COVERAGE                         = 0x400;
+        const TYPED_UAV_LOAD_ADDITIONAL_FORMATS      = 0x800;
+        const ROVS                                   = 0x1000;
+        const VIEWPORT_AND_RT_ARRAY_INDEX_FROM_ANY_SHADER_FEEDING_RASTERIZER = 0x2000;
+        const WAVE_OPS                               = 0x4000;
+        const INT64_OPS                              = 0x8000;
+        const VIEW_ID                                = 0x10000;
+        const BARYCENTRICS                           = 0x20000;
+        const NATIVE_16BIT_OPS                       = 0x40000;
+        const SHADING_RATE                           = 0x80000;
+        const RAYTRACING_TIER_1_1                    = 0x100000;
+        const SAMPLER_FEEDBACK                       = 0x200000;
+        const ATOMIC_INT64_ON_TYPED_RESOURCE         = 0x400000;
+        const ATOMIC_INT64_ON_GROUP_SHARED           = 0x800000;
+        const DERIVATIVES_IN_MESH_AND_AMPLIFICATION_SHADERS = 0x1000000;
+        const RESOURCE_DESCRIPTOR_HEAP_INDEXING      = 0x2000000;
+        const SAMPLER_DESCRIPTOR_HEAP_INDEXING       = 0x4000000;
+        const WAVE_MMA                               = 0x8000000;
+        const ATOMIC_INT64_ON_DESCRIPTOR_HEAP_RESOURCE = 0x10000000;
+    }
+}
+
+impl ShaderRequires {
+    /// parse the content of an `SFI0` part. flags unknown to this crate
+    /// are dropped.
+    pub fn parse(data: &[u8]) -> Result<ShaderRequires, DxbcError> {
+        let bytes = bytes_at(data, 0, 8)?;
+        let mut bits = [0; 8];
+        bits.copy_from_slice(bytes);
+        Ok(ShaderRequires::from_bits_truncate(u64::from_le_bytes(bits)))
+    }
+}
+
+/// instruction statistics of a shader, as in `D3D11_SHADER_DESC`.
+/// fields missing from older compilers are zero.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShaderStatistics {
+    pub instruction_count: u32,
+    pub temp_register_count: u32,
+    pub def_count: u32,
+    pub dcl_count: u32,
+    pub float_instruction_count: u32,
+    pub int_instruction_count: u32,
+    pub uint_instruction_count: u32,
+    pub static_flow_control_count: u32,
+    pub dynamic_flow_control_count: u32,
+    pub macro_instruction_count: u32,
+    pub temp_array_count: u32,
+    pub array_instruction_count: u32,
+    pub cut_instruction_count: u32,
+    pub emit_instruction_count: u32,
+    pub texture_normal_instructions: u32,
+    pub texture_load_instructions: u32,
+    pub texture_comp_instructions: u32,
+    pub texture_bias_instructions: u32,
+    pub texture_gradient_instructions: u32,
+    pub mov_instruction_count: u32,
+    pub movc_instruction_count: u32,
+    pub conversion_instruction_count: u32,
+    pub bitwise_instruction_count: u32,
+    /// a `D3D_PRIMITIVE`, the geometry shader input primitive
+    pub gs_input_primitive: u32,
+    /// a `D3D_PRIMITIVE_TOPOLOGY`, the geometry shader output topology
+    pub gs_output_topology: u32,
+    pub gs_max_output_vertex_count: u32,
+    pub gs_instance_count: u32,
+    pub control_points: u32,
+    /// a `D3D_TESSELLATOR_OUTPUT_PRIMITIVE`
+    pub hs_output_primitive: u32,
+    /// a `D3D_TESSELLATOR_PARTITIONING`
+    pub hs_partitioning: u32,
+    /// a `D3D_TESSELLATOR_DOMAIN`
+    pub tessellator_domain: u32,
+    pub barrier_instructions: u32,
+    pub interlocked_instructions: u32,
+    pub texture_store_instructions: u32,
+}
+
+impl ShaderStatistics {
+    /// parse the content of an `STAT` part. shader model 4 compilers
+    /// write 28 or 29 DWORDs, shader model 5 ones 37.
+    pub fn parse(data: &[u8]) -> Result<ShaderStatistics, DxbcError> {
+        if data.len() < 28 * 4 { return Err(DxbcError::Truncated); }
+        let dword = |index: usize| read_u32(data, index * 4).unwrap_or(0);
+        Ok(ShaderStatistics{
+            instruction_count: dword(0),
+            temp_register_count: dword(1),
+            def_count: dword(2),
+            dcl_count: dword(3),
+            float_instruction_count: dword(4),
+            int_instruction_count: dword(5),
+            uint_instruction_count: dword(6),
+            static_flow_control_count: dword(7),
+            dynamic_flow_control_count: dword(8),
+            macro_instruction_count: dword(9),
+            temp_array_count: dword(10),
+            array_instruction_count: dword(11),
+            cut_instruction_count: dword(12),
+            emit_instruction_count: dword(13),
+            texture_normal_instructions: dword(14),
+            texture_load_instructions: dword(15),
+            texture_comp_instructions: dword(16),
+            texture_bias_instructions: dword(17),
+            texture_gradient_instructions: dword(18),
+            mov_instruction_count: dword(19),
+            movc_instruction_count: dword(20),
+            conversion_instruction_count: dword(21),
+            bitwise_instruction_count: dword(22),
+            gs_input_primitive: dword(23),
+            gs_output_topology: dword(24),
+            gs_max_output_vertex_count: dword(25),
+            // 26 to 28 are unknown
+            gs_instance_count: dword(29),
+            control_points: dword(30),
+            hs_output_primitive: dword(31),
+            hs_partitioning: dword(32),
+            tessellator_domain: dword(33),
+            barrier_instructions: dword(34),
+            interlocked_instructions: dword(35),
+            texture_store_instructions: dword(36),
+        })
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! rewriting containers with parts removed, replaced or added.
+
+use super::{DxbcContainer, write_container};
+
+bitflags!{
+    /// groups of parts removed by `DxbcBuilder::strip`, as in `D3DCOMPILER_STRIP_FLAGS`
+    pub struct StripFlags: u32 {
+        /// `RDEF` and `STAT`
+        const REFLECTION_DATA = 0x1;
+        /// `SDBG`, `SPDB`, `ILDB`, `ILDN` and `SRCI`
+        const DEBUG_INFO      = 0x2;
+        /// `PRIV`, data attached with `D3DSetBlobPart`
+        const PRIVATE_DATA    = 0x8;
+        /// `RTS0`
+        const ROOT_SIGNATURE  = 0x10;
+    }
+}
+
+impl StripFlags {
+    /// the groups a part tagged `fourcc` belongs to
+    pub fn of_part(fourcc: &[u8; 4]) -> StripFlags {
+        match fourcc {
+            b"RDEF" | b"STAT" => StripFlags::REFLECTION_DATA,
+            b"SDBG" | b"SPDB" | b"ILDB" | b"ILDN" | b"SRCI" => StripFlags::DEBUG_INFO,
+            b"PRIV" => StripFlags::PRIVATE_DATA,
+            b"RTS0" => StripFlags::ROOT_SIGNATURE,
+            _ => StripFlags::empty(),
+        }
+    }
+}
+
+/// builder of a DXBC container, e.g. a copy of a compiled shader with
+/// debug information stripped or a root signature embedded.
+///
+/// `to_bytes` computes the checksum of the new container. the runtime
+/// takes a valid checksum on a DXIL container as proof that the validator
+/// accepted it, so only rewrite DXIL containers holding validated programs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DxbcBuilder {
+    parts: Vec<([u8; 4], Vec<u8>)>,
+}
+
+impl DxbcBuilder {
+    /// an empty container
+    #[inline]
+    pub fn new() -> DxbcBuilder {
+        Default::default()
+    }
+
+    /// a copy of the parts of `container`, in container order
+    pub fn from_container(container: &DxbcContainer) -> DxbcBuilder {
+        DxbcBuilder{
+            parts: container.parts().iter()
+                .map(|part| (part.fourcc, part.data.to_vec()))
+                .collect(),
+        }
+    }
+
+    /// the parts, in container order
+    #[inline]
+    pub fn parts(&self) -> &[([u8; 4], Vec<u8>)] {
+        &self.parts
+    }
+
+    /// content of the first part tagged `fourcc`
+    #[inline]
+    pub fn part(&self, fourcc: &[u8; 4]) -> Option<&[u8]> {
+        self.parts.iter().find(|part| &part.0 == fourcc).map(|part| &part.1[..])
+    }
+
+    /// set the content of the part tagged `fourcc`, replacing the first
+    /// existing one, or adding it after the others
+    pub fn set_part(&mut self, fourcc: [u8; 4], data: Vec<u8>) -> &mut Self {
+        match self.parts.iter().position(|part| part.0 == fourcc) {
+            Some(idx) => self.parts[idx].1 = data,
+            None => self.parts.push((fourcc, data)),
+        }
+        self
+    }
+
+    /// remove the first part tagged `fourcc`
+    pub fn remove_part(&mut self, fourcc: &[u8; 4]) -> Option<Vec<u8>> {
+        self.parts.iter().position(|part| &part.0 == fourcc)
+            .map(|idx| self.parts.remove(idx).1)
+    }
+
+    /// keep only the parts for which `f` returns true
+    pub fn retain_parts<F>(&mut self, mut f: F) -> &mut Self
+        where F: FnMut(&[u8; 4], &[u8]) -> bool
+    {
+        self.parts.retain(|part| f(&part.0, &part.1));
+        self
+    }
+
+    /// remove the parts in any of the groups of `flags`, as `D3DStripShader` does
+    pub fn strip(&mut self, flags: StripFlags) -> &mut Self {
+        self.retain_parts(|fourcc, _| !StripFlags::of_part(fourcc).intersects(flags))
+    }
+
+    /// write the container, with a valid checksum
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let parts = self.parts.iter()
+            .map(|part| (part.0, &part.1[..]))
+            .collect::<Vec<_>>();
+        write_container(&parts)
+    }
+}
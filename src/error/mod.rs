@@ -8,21 +8,25 @@
 
 //! error types
 
+#[cfg(windows)]
 use ::winapi::{HRESULT, SUCCEEDED};
 
 /// an winerror
 // TODO: add useful error messages
+#[cfg(windows)]
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct WinError {
     pub hr: HRESULT,
 }
 
+#[cfg(windows)]
 impl WinError {
     #[inline]
     pub fn description(&self) -> &'static str {
         match self.hr {
             ::winapi::E_OUTOFMEMORY => "E_OUYOFMEMORY",
             ::winapi::E_INVALIDARG => "E_INVALIDARG",
+            ::winapi::E_UNEXPECTED => "E_UNEXPECTED",
             ::winapi::DXGI_ERROR_INVALID_CALL => "DXGI_ERROR_INVALID_CALL",
             ::winapi::DXGI_ERROR_DEVICE_HUNG => "DXGI_ERROR_DEVICE_HUNG",
             ::winapi::DXGI_ERROR_DEVICE_REMOVED => "DXGI_ERROR_DEVICE_REMOVED",
@@ -33,6 +37,7 @@ impl WinError {
     }
 }
 
+#[cfg(windows)]
 impl ::std::fmt::Debug for WinError {
     #[inline]
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
@@ -40,6 +45,7 @@ impl ::std::fmt::Debug for WinError {
     }
 }
 
+#[cfg(windows)]
 impl WinError {
     /// construct an error from an `HRESULT`
     #[inline]
@@ -57,3 +63,30 @@ impl WinError {
         else { Err(WinError{hr}) }
     }
 }
+
+/// an integer value that doesn't map to any variant of an enumeration
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InvalidEnumValue {
+    /// name of the target enumeration
+    pub type_name: &'static str,
+    /// the rejected value
+    pub value: u64,
+}
+
+impl ::std::fmt::Display for InvalidEnumValue {
+    #[inline]
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        write!(f, "{:#X} is not a valid `{}`", self.value, self.type_name)
+    }
+}
+
+impl ::std::error::Error for InvalidEnumValue {}
+
+#[cfg(windows)]
+impl From<InvalidEnumValue> for WinError {
+    /// a value reported by the runtime that this crate doesn't know about
+    #[inline]
+    fn from(_: InvalidEnumValue) -> WinError {
+        WinError{hr: ::winapi::E_UNEXPECTED}
+    }
+}
@@ -12,7 +12,8 @@
 
 use comptr::ComPtr;
 use winapi::{IDXGIFactory4, IDXGIAdapter3, IDXGIAdapter1, IDXGISwapChain3, IDXGISwapChain1, IDXGIOutput};
-use error::WinError;
+use error::{WinError, InvalidEnumValue};
+use std::convert::TryFrom;
 use std::os::raw::c_void;
 use swapchain::{SwapChain, SwapChainDesc, FullScreenDesc};
 use command::CommandQueue;
@@ -46,6 +47,20 @@ impl Factory {
         }
     }
 
+    /// get the adapter with the given locally unique id
+    pub fn enum_adapter_by_luid(&mut self, luid: ::winapi::LUID) -> Result<Adapter, WinError> {
+        unsafe {
+            let mut padapter: *mut IDXGIAdapter3 = ::std::mem::uninitialized();
+            let hr = self.ptr.EnumAdapterByLuid(
+                luid, & ::dxguid::IID_IDXGIAdapter3,
+                &mut padapter as *mut *mut _ as *mut *mut c_void
+            );
+            WinError::from_hresult_or_ok(hr, || Adapter{
+                ptr: ComPtr::new(padapter)
+            })
+        }
+    }
+
     /// create a swap chain
     #[inline]
     pub fn create_swapchain_for_hwnd(
@@ -155,6 +170,17 @@ impl Adapter {
         }
     }
 
+    /// get the user mode driver version of the adapter
+    pub fn get_driver_version(&mut self) -> Result<u64, WinError> {
+        unsafe {
+            let mut ret: ::winapi::LARGE_INTEGER = 0;
+            let hr = self.ptr.CheckInterfaceSupport(
+                & ::dxguid::IID_IDXGIDevice, &mut ret
+            );
+            WinError::from_hresult_or_ok(hr, || ret as u64)
+        }
+    }
+
     /// enumerate outputs of this adapter
     #[inline]
     pub fn enumerate_outputs(&mut self) -> OutputIter {
@@ -204,6 +230,8 @@ pub struct AdapterDesc {
     pub vendor_id: u32,
     /// PCI ID of the hardware device
     pub device_id: u32,
+    /// PCI ID of the subsystem
+    pub sub_sys_id: u32,
     /// PCI ID of the revision number of the adapter
     pub revision: u32,
     /// dedicated video memory not shared with CPU
@@ -220,7 +248,7 @@ pub struct AdapterDesc {
 
 impl ::std::fmt::Debug for AdapterDesc {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
-        write!(f, "AdapterDesc {{ description: {:?}, vendor_id: {:?}, device_id: {:?}, revision: {:?}, dedicated_vmem: {:?}, dedicated_smem: {:?}, shared_smem: {:?}, luid: {:?}, flags: {:?} }}", ::format::from_wchar_slice(&self.description), self.vendor_id, self.device_id, self.revision, self.dedicated_vmem, self.dedicated_smem, self.shared_smem, self.luid, self.flags)
+        write!(f, "AdapterDesc {{ description: {:?}, vendor_id: {:?}, device_id: {:?}, sub_sys_id: {:?}, revision: {:?}, dedicated_vmem: {:?}, dedicated_smem: {:?}, shared_smem: {:?}, luid: {:?}, flags: {:?} }}", ::format::from_wchar_slice(&self.description), self.vendor_id, self.device_id, self.sub_sys_id, self.revision, self.dedicated_vmem, self.dedicated_smem, self.shared_smem, self.luid, self.flags)
     }
 }
 
@@ -276,14 +304,14 @@ pub struct Output {
 impl Output {
     // TODO: add more methods?
 
-    /// get basic description for the output
+    /// get basic description for the output.
+    ///
+    /// fails with `E_UNEXPECTED` if the runtime reports a value unknown to this crate.
     #[inline]
     pub fn get_desc(&mut self) -> Result<OutputDesc, WinError> {
-        unsafe {
-            let mut ret = ::std::mem::uninitialized();
-            let hr = self.ptr.GetDesc(&mut ret as *mut _ as *mut _);
-            WinError::from_hresult_or_ok(hr, || ret)
-        }
+        let mut ret = unsafe { ::std::mem::zeroed() };
+        WinError::from_hresult(unsafe { self.ptr.GetDesc(&mut ret) })?;
+        Ok(OutputDesc::try_from(ret)?)
     }
 }
 
@@ -297,20 +325,34 @@ pub struct OutputDesc {
     pub hmonitor: ::winapi::HMONITOR,
 }
 
+impl TryFrom<::winapi::DXGI_OUTPUT_DESC> for OutputDesc {
+    type Error = InvalidEnumValue;
+
+    #[inline]
+    fn try_from(desc: ::winapi::DXGI_OUTPUT_DESC) -> Result<Self, InvalidEnumValue> {
+        Ok(OutputDesc{
+            name: desc.DeviceName,
+            descktop_coordinates: desc.DesktopCoordinates,
+            attached_to_desktop: (desc.AttachedToDesktop != 0).into(),
+            rotation: RotationMode::try_from(desc.Rotation.0)?,
+            hmonitor: desc.Monitor,
+        })
+    }
+}
+
 impl ::std::fmt::Display for OutputDesc {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
         write!(f, "{:?}", ::format::from_wchar_slice(&self.name))
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// rotation mode for the monitor
-    #[repr(C)]
-    pub struct RotationMode: u32 {
-        const UNSPECIFIED = 0;
-        const IDENTITY = 1;
-        const ROTATE90 = 2;
-        const ROTATE180 = 3;
-        const ROTATE270 = 4;
+    pub enum RotationMode {
+        UNSPECIFIED = 0,
+        IDENTITY = 1,
+        ROTATE90 = 2,
+        ROTATE180 = 3,
+        ROTATE270 = 4,
     }
 }
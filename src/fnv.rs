@@ -0,0 +1,86 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! a deterministic hasher, for hashes that must stay the same across runs
+
+use std::hash::Hasher;
+
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0100_0000_01b3;
+
+/// 64 bit FNV-1a hasher
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    #[inline]
+    fn default() -> FnvHasher {
+        FnvHasher(OFFSET_BASIS)
+    }
+}
+
+impl Hasher for FnvHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(PRIME);
+        }
+    }
+}
+
+impl FnvHasher {
+    /// feed `value` as 4 little endian bytes
+    #[inline]
+    pub(crate) fn write_le_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes())
+    }
+
+    /// feed `value` as 8 little endian bytes
+    #[inline]
+    pub(crate) fn write_le_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes())
+    }
+
+    /// feed `bytes`, prefixed by their length as a `u64`
+    #[inline]
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_le_u64(bytes.len() as u64);
+        self.write(bytes)
+    }
+}
+
+/// values fed into a `FnvHasher` field by field as fixed width little
+/// endian integers, so that, unlike `Hash`, the hash doesn't depend on the
+/// host, e.g. on the width of `usize` or on the byte order.
+pub(crate) trait StableHash {
+    fn stable_hash(&self, hasher: &mut FnvHasher);
+}
+
+impl<T: StableHash> StableHash for Option<T> {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut FnvHasher) {
+        match *self {
+            Some(ref value) => { hasher.write_le_u32(1); value.stable_hash(hasher); },
+            None => hasher.write_le_u32(0),
+        }
+    }
+}
+
+/// hash `value` with a `FnvHasher`, field by field, see `StableHash`
+#[inline]
+pub(crate) fn stable_hash<T: StableHash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = FnvHasher::default();
+    value.stable_hash(&mut hasher);
+    hasher.finish()
+}
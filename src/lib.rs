@@ -6,30 +6,50 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-#![cfg(windows)]
-
 //! `redirect` aims to be a lightweight and type-safe wrapper around the DirectX12 API for the Rust Programming Language.
 
 #[macro_use]
 extern crate bitflags;
+#[cfg(windows)]
 extern crate winapi;
+#[cfg(windows)]
 extern crate d3d12;
+#[cfg(windows)]
 extern crate dxguid;
+#[cfg(windows)]
 extern crate dxgi;
+#[cfg(windows)]
 extern crate d3dcompiler;
+#[cfg(windows)]
 extern crate kernel32;
 extern crate smallvec;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "derive")]
+extern crate redirect_derive;
 
+// description types, container parsing and validation are plain rust and
+// build on every host. only the COM wrappers need the windows sdk.
+#[macro_use]
+mod macros;
+#[cfg(windows)]
 mod comptr;
+mod fnv;
+pub mod dxbc;
 pub mod error;
 pub mod format;
 pub mod swapchain;
 pub mod resource;
+#[cfg(windows)]
 pub mod device;
+#[cfg(windows)]
 pub mod factory;
 pub mod command;
 pub mod pipeline;
 pub mod shader;
+#[cfg(windows)]
 pub mod fence;
 pub mod descriptor;
+#[cfg(windows)]
 pub mod event;
@@ -0,0 +1,204 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! crate-wide utility macros
+
+// declares a `#[repr(u32)]` enumeration mirroring a D3D12/DXGI enum whose
+// values are mutually exclusive, together with a checked `TryFrom<u32>`.
+// variant names follow the `bitflags!` style of the rest of the crate, and
+// can be looked up with `name` and `from_name`. the representation defaults
+// to `u32`, and can be given after the name for 64 bit values.
+macro_rules! d3d_enum {
+    (
+        $(#[$outer: meta])*
+        pub enum $Enum: ident {
+            $(
+                $(#[$inner: meta])*
+                $Variant: ident = $value: expr,
+            )+
+        }
+    ) => {
+        d3d_enum!{
+            $(#[$outer])*
+            pub enum $Enum: u32 {
+                $(
+                    $(#[$inner])*
+                    $Variant = $value,
+                )+
+            }
+        }
+    };
+    (
+        $(#[$outer: meta])*
+        pub enum $Enum: ident: $Repr: ident {
+            $(
+                $(#[$inner: meta])*
+                $Variant: ident = $value: expr,
+            )+
+        }
+    ) => {
+        $(#[$outer])*
+        #[repr($Repr)]
+        #[allow(non_camel_case_types)]
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+        pub enum $Enum {
+            $(
+                $(#[$inner])*
+                $Variant = $value,
+            )+
+        }
+
+        impl ::std::convert::TryFrom<$Repr> for $Enum {
+            type Error = ::error::InvalidEnumValue;
+
+            #[inline]
+            fn try_from(value: $Repr) -> Result<$Enum, ::error::InvalidEnumValue> {
+                $(
+                    if value == $Enum::$Variant as $Repr {
+                        return Ok($Enum::$Variant);
+                    }
+                )+
+                Err(::error::InvalidEnumValue{
+                    type_name: stringify!($Enum), value: u64::from(value)
+                })
+            }
+        }
+
+        impl From<$Enum> for $Repr {
+            #[inline]
+            fn from(value: $Enum) -> $Repr {
+                value as $Repr
+            }
+        }
+
+        impl $Enum {
+            /// the name of the variant
+            #[inline]
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($Enum::$Variant => stringify!($Variant),)+
+                }
+            }
+
+            /// the variant with the given name
+            #[inline]
+            pub fn from_name(name: &str) -> Option<$Enum> {
+                $(
+                    if name == stringify!($Variant) {
+                        return Some($Enum::$Variant);
+                    }
+                )+
+                None
+            }
+        }
+    }
+}
+
+// (de)serializes a `bitflags!` type through its raw bits, rejecting
+// bits that don't correspond to any flag. no-op without the `serde` feature.
+macro_rules! impl_bitflags_serde {
+    ($Flags: ident, $Bits: ty) => {
+        #[cfg(feature = "serde")]
+        impl ::serde::Serialize for $Flags {
+            #[inline]
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                ::serde::Serialize::serialize(&self.bits(), serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> ::serde::Deserialize<'de> for $Flags {
+            #[inline]
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<$Flags, D::Error> {
+                let bits = <$Bits as ::serde::Deserialize>::deserialize(deserializer)?;
+                $Flags::from_bits(bits).ok_or_else(|| <D::Error as ::serde::de::Error>::custom(
+                    format_args!("invalid bits {:#X} for `{}`", bits, stringify!($Flags))
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command::{CommandListType, CommandQueuePriority};
+    use error::InvalidEnumValue;
+    use pipeline::ComparisonFunc;
+    use pipeline::blend::Blend;
+    use pipeline::ia::PrimitiveTopology;
+    use pipeline::sampler::Filter;
+    use resource::{HeapAlignment, HeapType, ResourceAlignment, ResourceDimension};
+    use std::convert::TryFrom;
+
+    // every value accepted by `try_from` converts back to itself and
+    // round trips through its name, returning the number of variants
+    macro_rules! check_values {
+        ($Enum: ident) => {{
+            let mut count = 0;
+            for value in 0..0x2000u32 {
+                match $Enum::try_from(value) {
+                    Ok(variant) => {
+                        assert_eq!(u32::from(variant), value);
+                        assert_eq!($Enum::from_name(variant.name()), Some(variant));
+                        count += 1;
+                    },
+                    Err(err) => assert_eq!(err, InvalidEnumValue{
+                        type_name: stringify!($Enum), value: u64::from(value)
+                    }),
+                }
+            }
+            count
+        }}
+    }
+
+    #[test]
+    fn try_from_accepts_exactly_the_variants() {
+        assert_eq!(check_values!(CommandListType), 4);
+        assert_eq!(check_values!(HeapType), 4);
+        assert_eq!(check_values!(ResourceDimension), 5);
+        assert_eq!(check_values!(ComparisonFunc), 8);
+        assert_eq!(check_values!(Blend), 17);
+        assert_eq!(check_values!(Filter), 36);
+        // 10 lists and strips, and 32 patch lists
+        assert_eq!(check_values!(PrimitiveTopology), 42);
+    }
+
+    #[test]
+    fn command_queue_priorities() {
+        assert_eq!(CommandQueuePriority::try_from(0), Ok(CommandQueuePriority::NORMAL));
+        assert_eq!(CommandQueuePriority::try_from(100), Ok(CommandQueuePriority::HIGH));
+        assert_eq!(CommandQueuePriority::try_from(10000), Ok(CommandQueuePriority::REALTIME));
+        assert_eq!(CommandQueuePriority::try_from(1000), Err(InvalidEnumValue{
+            type_name: "CommandQueuePriority", value: 1000,
+        }));
+    }
+
+    #[test]
+    fn alignments() {
+        assert_eq!(ResourceAlignment::try_from(0x10_000), Ok(ResourceAlignment::SIXTY_FOUR_KB));
+        assert_eq!(u64::from(HeapAlignment::DEFAULT_MSAA_RESOURCE_PLACEMENT), 0x40_0000);
+        assert_eq!(HeapAlignment::try_from(0x1_0000_0000_0000), Err(InvalidEnumValue{
+            type_name: "HeapAlignment", value: 0x1_0000_0000_0000,
+        }));
+        assert_eq!(HeapAlignment::try_from(0x1_000), Err(InvalidEnumValue{
+            type_name: "HeapAlignment", value: 0x1_000,
+        }));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(HeapType::UPLOAD.name(), "UPLOAD");
+        assert_eq!(HeapType::from_name("READBACK"), Some(HeapType::READBACK));
+        assert_eq!(HeapType::from_name("readback"), None);
+        assert_eq!(
+            HeapType::try_from(7).unwrap_err().to_string(),
+            "0x7 is not a valid `HeapType`"
+        );
+    }
+}
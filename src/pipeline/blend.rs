@@ -12,7 +12,8 @@ use format::Bool;
 
 /// describes the blend state for the whole pipeline
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BlendDesc {
     /// whether to use [alpha-to-coverage](https://msdn.microsoft.com/library/windows/desktop/bb205072(v=vs.85).aspx#Alpha_To_Coverage) as a multisampling technique
     pub alpha_to_coverage: Bool,
@@ -34,7 +35,8 @@ impl Default for BlendDesc {
 
 /// describes the blend state for a render target
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RenderTargetBlendDesc {
     pub blend_enabled: Bool,
     pub logic_op_enabled: Bool,
@@ -88,62 +90,59 @@ impl Default for RenderTargetBlendDesc {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// a blend factor
-    #[repr(C)]
-    pub struct Blend: u32 {
-        const ZERO              = 1;
-        const ONE               = 2;
-        const SRC_COLOR         = 3;
-        const INV_SRC_COLOR     = 4;
-        const SRC_ALPHA         = 5;
-        const INV_SRC_ALPHA     = 6;
-        const DEST_ALPHA        = 7;
-        const INV_DEST_ALPHA    = 8;
-        const DEST_COLOR        = 9;
-        const INV_DEST_COLOR    = 10;
-        const SRC_ALPHA_SAT     = 11;
-        const FACTOR      = 14;
-        const INV_FACTOR  = 15;
-        const SRC1_COLOR        = 16;
-        const INV_SRC1_COLOR    = 17;
-        const SRC1_ALPHA        = 18;
-        const INV_SRC1_ALPHA    = 19;
+    pub enum Blend {
+        ZERO              = 1,
+        ONE               = 2,
+        SRC_COLOR         = 3,
+        INV_SRC_COLOR     = 4,
+        SRC_ALPHA         = 5,
+        INV_SRC_ALPHA     = 6,
+        DEST_ALPHA        = 7,
+        INV_DEST_ALPHA    = 8,
+        DEST_COLOR        = 9,
+        INV_DEST_COLOR    = 10,
+        SRC_ALPHA_SAT     = 11,
+        FACTOR      = 14,
+        INV_FACTOR  = 15,
+        SRC1_COLOR        = 16,
+        INV_SRC1_COLOR    = 17,
+        SRC1_ALPHA        = 18,
+        INV_SRC1_ALPHA    = 19,
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// a blend operation
-    #[repr(C)]
-    pub struct BlendOp: u32 {
-        const ADD           = 1;
-        const SUBTRACT      = 2;
-        const REV_SUBTRACT  = 3;
-        const MIN           = 4;
-        const MAX           = 5;
+    pub enum BlendOp {
+        ADD           = 1,
+        SUBTRACT      = 2,
+        REV_SUBTRACT  = 3,
+        MIN           = 4,
+        MAX           = 5,
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// a logic operation
-    #[repr(C)]
-    pub struct LogicOp: u32 {
-        const CLEAR          = 0;
-        const SET            = LogicOp::CLEAR.bits +1;
-        const COPY           = LogicOp::SET.bits +1;
-        const COPY_INVERTED  = LogicOp::COPY.bits +1;
-        const NOOP           = LogicOp::COPY_INVERTED.bits +1;
-        const INVERT         = LogicOp::NOOP.bits +1;
-        const AND            = LogicOp::INVERT.bits +1;
-        const NAND           = LogicOp::AND.bits +1;
-        const OR             = LogicOp::NAND.bits +1;
-        const NOR            = LogicOp::OR.bits +1;
-        const XOR            = LogicOp::NOR.bits +1;
-        const EQUIV          = LogicOp::XOR.bits +1;
-        const AND_REVERSE    = LogicOp::EQUIV.bits +1;
-        const AND_INVERTED   = LogicOp::AND_REVERSE.bits +1;
-        const OR_REVERSE     = LogicOp::AND_INVERTED.bits +1;
-        const OR_INVERTED    = LogicOp::OR_REVERSE.bits +1;
+    pub enum LogicOp {
+        CLEAR          = 0,
+        SET            = 1,
+        COPY           = 2,
+        COPY_INVERTED  = 3,
+        NOOP           = 4,
+        INVERT         = 5,
+        AND            = 6,
+        NAND           = 7,
+        OR             = 8,
+        NOR            = 9,
+        XOR            = 10,
+        EQUIV          = 11,
+        AND_REVERSE    = 12,
+        AND_INVERTED   = 13,
+        OR_REVERSE     = 14,
+        OR_INVERTED    = 15,
     }
 }
 
@@ -161,3 +160,5 @@ bitflags!{
                            ColorWriteMask::ENABLE_ALPHA.bits;
     }
 }
+
+impl_bitflags_serde!(ColorWriteMask, u8);
@@ -0,0 +1,250 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! in-memory deduplication of pipeline state objects
+
+#[cfg(windows)]
+use super::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// a thread safe cache mapping pipeline descriptions to pipeline states.
+///
+/// the cache itself doesn't know how to build a value, so that its keying
+/// behavior can be used, or tested, independent of a device.
+#[derive(Debug)]
+pub struct PipelineCache<K: Hash + Eq, V: Clone> {
+    entries: RwLock<HashMap<K, V>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+/// a cache of graphics pipeline states
+#[cfg(windows)]
+pub type GraphicsPipelineCache = PipelineCache<GraphicsPipelineDesc, GraphicsPipelineState>;
+
+/// a cache of compute pipeline states
+#[cfg(windows)]
+pub type ComputePipelineCache = PipelineCache<ComputePipelineDesc, ComputePipelineState>;
+
+/// hit and miss statistics of a `PipelineCache`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PipelineCacheStats {
+    /// number of lookups returning an existing value
+    pub hits: usize,
+    /// number of lookups that had to build a value
+    pub misses: usize,
+}
+
+impl<K: Hash + Eq, V: Clone> PipelineCache<K, V> {
+    /// construct an empty cache
+    #[inline]
+    pub fn new() -> Self {
+        PipelineCache{
+            entries: RwLock::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// look up `key` without building a value on a miss.
+    /// doesn't affect the statistics.
+    #[inline]
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.entries.read().unwrap().get(key).cloned()
+    }
+
+    /// return the value for `key`, calling `build` to create it on a miss.
+    ///
+    /// `build` is called without holding the lock, so that lookups from other
+    /// threads aren't blocked. if two threads miss on the same key at the same
+    /// time, both may build, and the value inserted first is kept and returned
+    /// to both of them.
+    pub fn get_or_insert_with<F, E>(&self, key: K, build: F) -> Result<V, E>
+        where F: FnOnce(&K) -> Result<V, E>
+    {
+        if let Some(value) = self.entries.read().unwrap().get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value.clone());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = build(&key)?;
+        let mut entries = self.entries.write().unwrap();
+        Ok(entries.entry(key).or_insert(value).clone())
+    }
+
+    /// insert a value for `key`, returning the previous one if any
+    #[inline]
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.entries.write().unwrap().insert(key, value)
+    }
+
+    /// number of cached values
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// get the hit and miss statistics
+    #[inline]
+    pub fn stats(&self) -> PipelineCacheStats {
+        PipelineCacheStats{
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// remove all cached values. statistics are preserved.
+    #[inline]
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear()
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Default for PipelineCache<K, V> {
+    #[inline]
+    fn default() -> Self {
+        PipelineCache::new()
+    }
+}
+
+#[cfg(windows)]
+impl GraphicsPipelineCache {
+    /// return the pipeline state for `desc`, building it with `device` on a
+    /// miss. the device is shared, so that threads can build through the
+    /// same cache and device at the same time.
+    #[inline]
+    pub fn get_or_build(
+        &self, desc: GraphicsPipelineDesc, device: &Device
+    ) -> Result<GraphicsPipelineState, WinError> {
+        self.get_or_insert_with(desc, |desc| desc.build(&mut device.clone()))
+    }
+}
+
+#[cfg(windows)]
+impl ComputePipelineCache {
+    /// return the pipeline state for `desc`, building it with `device` on a
+    /// miss. the device is shared, so that threads can build through the
+    /// same cache and device at the same time.
+    #[inline]
+    pub fn get_or_build(
+        &self, desc: ComputePipelineDesc, device: &Device
+    ) -> Result<ComputePipelineState, WinError> {
+        self.get_or_insert_with(desc, |desc| desc.build(&mut device.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn hits_and_misses() {
+        let cache = PipelineCache::<u32, String>::new();
+        let builds = Cell::new(0);
+        let build = |key: &u32| -> Result<String, ()> {
+            builds.set(builds.get() + 1);
+            Ok(key.to_string())
+        };
+        assert_eq!(cache.get_or_insert_with(1, build), Ok("1".to_owned()));
+        assert_eq!(cache.get_or_insert_with(1, build), Ok("1".to_owned()));
+        assert_eq!(cache.get_or_insert_with(2, build), Ok("2".to_owned()));
+        assert_eq!(cache.get_or_insert_with(1, build), Ok("1".to_owned()));
+        assert_eq!(builds.get(), 2);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats(), PipelineCacheStats{hits: 2, misses: 2});
+
+        // plain lookups don't count
+        assert_eq!(cache.get(&2), Some("2".to_owned()));
+        assert_eq!(cache.get(&3), None);
+        assert_eq!(cache.stats(), PipelineCacheStats{hits: 2, misses: 2});
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats(), PipelineCacheStats{hits: 2, misses: 2});
+    }
+
+    #[test]
+    fn failed_builds_are_not_cached() {
+        let cache = PipelineCache::<u32, u32>::new();
+        assert_eq!(cache.get_or_insert_with(1, |_| Err("rejected")), Err("rejected"));
+        assert!(cache.is_empty());
+        assert_eq!(cache.get_or_insert_with(1, |_| Ok::<_, ()>(7)), Ok(7));
+        assert_eq!(cache.stats(), PipelineCacheStats{hits: 0, misses: 2});
+    }
+
+    #[test]
+    fn first_insert_wins() {
+        let cache = PipelineCache::<u32, u32>::new();
+        assert_eq!(cache.insert(1, 10), None);
+        assert_eq!(cache.get_or_insert_with(1, |_| Ok::<_, ()>(20)), Ok(10));
+        assert_eq!(cache.insert(1, 30), Some(10));
+    }
+
+    #[test]
+    fn concurrent_misses_share_a_value() {
+        let cache = PipelineCache::<u32, usize>::new();
+        let values = ::std::thread::scope(|scope| {
+            let handles = (0..8).map(|i| {
+                let cache = &cache;
+                scope.spawn(move || cache.get_or_insert_with(1, |_| Ok::<_, ()>(i)).unwrap())
+            }).collect::<Vec<_>>();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+        });
+        assert!(values.iter().all(|&value| value == values[0]));
+        assert_eq!(cache.len(), 1);
+        let stats = cache.stats();
+        assert_eq!(stats.hits + stats.misses, 8);
+    }
+
+    #[test]
+    fn keyed_by_description() {
+        use pipeline::{GraphicsPipelineDesc, PipelineStateFlags};
+        use pipeline::rootsig::{RootSig, RootSigBuilder, RootSigVersion};
+        use shader::VsShaderBytecode;
+
+        let bytes = RootSigBuilder::new().to_bytes(RootSigVersion::V1_1);
+        let cache = PipelineCache::<GraphicsPipelineDesc, usize>::new();
+        let builds = Cell::new(0);
+        let build = |_: &GraphicsPipelineDesc| -> Result<usize, ()> {
+            builds.set(builds.get() + 1);
+            Ok(builds.get())
+        };
+
+        // root signatures and byte codes are compared by content
+        let mut desc = GraphicsPipelineDesc::new(RootSig::from_desc(&bytes, 0));
+        desc.vs = Some(VsShaderBytecode::from_static(VS).unwrap());
+        let mut same = GraphicsPipelineDesc::new(RootSig::from_desc(&bytes, 0));
+        same.vs = Some(VsShaderBytecode::from_static(VS).unwrap());
+        assert_eq!(cache.get_or_insert_with(desc.clone(), build), Ok(1));
+        assert_eq!(cache.get_or_insert_with(same, build), Ok(1));
+
+        let mut other_nodes = desc.clone();
+        other_nodes.rootsig = RootSig::from_desc(&bytes, 1);
+        assert_eq!(cache.get_or_insert_with(other_nodes, build), Ok(2));
+        let mut other_flags = desc.clone();
+        other_flags.flags = PipelineStateFlags::TOOL_DEBUG;
+        assert_eq!(cache.get_or_insert_with(other_flags, build), Ok(3));
+        let mut no_vs = desc.clone();
+        no_vs.vs = None;
+        assert_eq!(cache.get_or_insert_with(no_vs, build), Ok(4));
+
+        assert_eq!(cache.get_or_insert_with(desc, build), Ok(1));
+        assert_eq!(cache.stats(), PipelineCacheStats{hits: 2, misses: 4});
+    }
+
+    const VS: &[u8] = include_bytes!("../../tests/fixtures/passthrough_vs.cso");
+}
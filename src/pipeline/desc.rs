@@ -0,0 +1,406 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! owned pipeline descriptions with value semantics.
+//!
+//! unlike the builders, these descriptions own everything they refer to,
+//! and can be cloned, compared and hashed, e.g. to deduplicate pipeline
+//! states. shader byte codes are compared by content, and root signatures
+//! by the serialized description they were created from, so that
+//! `stable_hash` stays the same across runs.
+
+use super::*;
+use fnv::{FnvHasher, StableHash};
+use format::Bool;
+
+/// an owned graphics pipeline description
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GraphicsPipelineDesc {
+    pub rootsig: rootsig::RootSig,
+    pub vs: Option<VsShaderBytecode>,
+    pub ps: Option<PsShaderBytecode>,
+    pub ds: Option<DsShaderBytecode>,
+    pub hs: Option<HsShaderBytecode>,
+    pub gs: Option<GsShaderBytecode>,
+    pub stream_output: so::StreamOutputDesc,
+    pub blend_state: blend::BlendDesc,
+    pub sample_mask: u32,
+    pub rasterizer_state: rasterizer::RasterizerDesc,
+    pub depth_stencil_state: ds::DepthStencilDesc,
+    pub input_layout: ia::InputLayout,
+    pub strip_cut_value: ia::StripCutValue,
+    pub primitive_topology_type: ia::PrimitiveTopologyType,
+    pub num_render_targets: u32,
+    pub rtv_formats: [DxgiFormat; 8],
+    pub dsv_format: DxgiFormat,
+    pub sample_desc: SampleDesc,
+    pub node_mask: u32,
+    pub flags: PipelineStateFlags,
+}
+
+impl GraphicsPipelineDesc {
+    /// construct a description with the same defaults as `GraphicsPipelineStateBuilder`
+    #[inline]
+    pub fn new(rootsig: rootsig::RootSig) -> Self {
+        GraphicsPipelineDesc{
+            rootsig,
+            vs: None, ps: None, ds: None, hs: None, gs: None,
+            stream_output: Default::default(),
+            blend_state: Default::default(),
+            sample_mask: u32::MAX,
+            rasterizer_state: Default::default(),
+            depth_stencil_state: Default::default(),
+            input_layout: Default::default(),
+            strip_cut_value: Default::default(),
+            primitive_topology_type: ia::PrimitiveTopologyType::TRIANGLE,
+            num_render_targets: 1,
+            rtv_formats: [::format::DXGI_FORMAT_UNKNOWN; 8],
+            dsv_format: ::format::DXGI_FORMAT_D24_UNORM_S8_UINT,
+            sample_desc: Default::default(),
+            node_mask: 0,
+            flags: PipelineStateFlags::NONE,
+        }
+    }
+
+    /// get a builder borrowing from `self`, without a cached blob
+    pub fn to_builder(&self) -> GraphicsPipelineStateBuilder<'_> {
+        GraphicsPipelineStateBuilder{
+            rootsig: &self.rootsig,
+            vs: self.vs.clone(),
+            ps: self.ps.clone(),
+            ds: self.ds.clone(),
+            hs: self.hs.clone(),
+            gs: self.gs.clone(),
+            stream_output: self.stream_output.to_builder(),
+            blend_state: self.blend_state,
+            sample_mask: self.sample_mask,
+            rasterizer_state: self.rasterizer_state,
+            depth_stencil_state: self.depth_stencil_state,
+            input_layout: self.input_layout.to_builder(),
+            strip_cut_value: self.strip_cut_value,
+            primitive_topology_type: self.primitive_topology_type,
+            num_render_targets: self.num_render_targets,
+            rtv_formats: self.rtv_formats,
+            dsv_format: self.dsv_format,
+            sample_desc: self.sample_desc,
+            node_mask: self.node_mask,
+            cache: None,
+            flags: self.flags,
+        }
+    }
+
+    /// build a pipeline state object with this description using `device`
+    #[cfg(windows)]
+    #[inline]
+    pub fn build(&self, device: &mut Device) -> Result<GraphicsPipelineState, WinError> {
+        self.to_builder().build(device)
+    }
+
+    /// a hash of this description that stays the same across runs and
+    /// hosts, used to key a `PipelineBlobStore`
+    #[inline]
+    pub fn stable_hash(&self) -> u64 {
+        ::fnv::stable_hash(self)
+    }
+
+    /// build a pipeline state object with this description using `device`,
+    /// starting from the cached blob in `store` if any.
+    ///
+    /// if the cached blob can't be loaded or the driver rejects it, the
+    /// pipeline state is compiled from scratch, and its cached blob
+    /// replaces the rejected one in `store`.
+    #[cfg(windows)]
+    pub fn build_with_store(
+        &self, device: &mut Device, store: &mut PipelineBlobStore
+    ) -> Result<GraphicsPipelineState, WinError> {
+        let key = self.stable_hash();
+        let cache = store.get(key)
+            .and_then(|bytes| GraphicsPipelineStateCache::from_bytes(bytes).ok());
+        if let Some(cache) = cache {
+            let mut builder = self.to_builder();
+            builder.cache = Some(cache);
+            if let Ok(pso) = builder.build(device) {
+                return Ok(pso);
+            }
+        }
+        let mut pso = self.build(device)?;
+        match pso.cached() {
+            Ok(cache) => { store.insert(key, cache.as_bytes().to_vec()); },
+            Err(_) => { store.remove(key); },
+        }
+        Ok(pso)
+    }
+}
+
+/// an owned compute pipeline description
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ComputePipelineDesc {
+    pub rootsig: rootsig::RootSig,
+    pub cs: Option<CsShaderBytecode>,
+    pub node_mask: u32,
+    pub flags: PipelineStateFlags,
+}
+
+impl ComputePipelineDesc {
+    #[inline]
+    pub fn new(rootsig: rootsig::RootSig) -> Self {
+        ComputePipelineDesc{
+            rootsig,
+            cs: None,
+            node_mask: 0,
+            flags: PipelineStateFlags::NONE,
+        }
+    }
+
+    /// get a builder borrowing from `self`, without a cached blob
+    #[inline]
+    pub fn to_builder(&self) -> ComputePipelineStateBuilder<'_> {
+        ComputePipelineStateBuilder{
+            rootsig: &self.rootsig,
+            cs: self.cs.clone(),
+            node_mask: self.node_mask,
+            cache: None,
+            flags: self.flags,
+        }
+    }
+
+    /// build a pipeline state object with this description using `device`
+    #[cfg(windows)]
+    #[inline]
+    pub fn build(&self, device: &mut Device) -> Result<ComputePipelineState, WinError> {
+        self.to_builder().build(device)
+    }
+
+    /// a hash of this description that stays the same across runs and
+    /// hosts, used to key a `PipelineBlobStore`
+    #[inline]
+    pub fn stable_hash(&self) -> u64 {
+        ::fnv::stable_hash(self)
+    }
+
+    /// build a pipeline state object with this description using `device`,
+    /// starting from the cached blob in `store` if any.
+    ///
+    /// if the cached blob can't be loaded or the driver rejects it, the
+    /// pipeline state is compiled from scratch, and its cached blob
+    /// replaces the rejected one in `store`.
+    #[cfg(windows)]
+    pub fn build_with_store(
+        &self, device: &mut Device, store: &mut PipelineBlobStore
+    ) -> Result<ComputePipelineState, WinError> {
+        let key = self.stable_hash();
+        let cache = store.get(key)
+            .and_then(|bytes| ComputePipelineStateCache::from_bytes(bytes).ok());
+        if let Some(cache) = cache {
+            let mut builder = self.to_builder();
+            builder.cache = Some(cache);
+            if let Ok(pso) = builder.build(device) {
+                return Ok(pso);
+            }
+        }
+        let mut pso = self.build(device)?;
+        match pso.cached() {
+            Ok(cache) => { store.insert(key, cache.as_bytes().to_vec()); },
+            Err(_) => { store.remove(key); },
+        }
+        Ok(pso)
+    }
+}
+
+impl StableHash for GraphicsPipelineDesc {
+    fn stable_hash(&self, hasher: &mut FnvHasher) {
+        self.rootsig.stable_hash(hasher);
+        self.vs.stable_hash(hasher);
+        self.ps.stable_hash(hasher);
+        self.ds.stable_hash(hasher);
+        self.hs.stable_hash(hasher);
+        self.gs.stable_hash(hasher);
+        self.stream_output.stable_hash(hasher);
+        self.blend_state.stable_hash(hasher);
+        hasher.write_le_u32(self.sample_mask);
+        self.rasterizer_state.stable_hash(hasher);
+        self.depth_stencil_state.stable_hash(hasher);
+        self.input_layout.stable_hash(hasher);
+        hasher.write_le_u32(self.strip_cut_value as u32);
+        hasher.write_le_u32(self.primitive_topology_type as u32);
+        hasher.write_le_u32(self.num_render_targets);
+        for format in &self.rtv_formats {
+            hasher.write_le_u32(format.0);
+        }
+        hasher.write_le_u32(self.dsv_format.0);
+        hasher.write_le_u32(self.sample_desc.count);
+        hasher.write_le_u32(self.sample_desc.quality);
+        hasher.write_le_u32(self.node_mask);
+        hasher.write_le_u32(self.flags.bits());
+    }
+}
+
+impl StableHash for ComputePipelineDesc {
+    fn stable_hash(&self, hasher: &mut FnvHasher) {
+        self.rootsig.stable_hash(hasher);
+        self.cs.stable_hash(hasher);
+        hasher.write_le_u32(self.node_mask);
+        hasher.write_le_u32(self.flags.bits());
+    }
+}
+
+#[inline]
+fn write_bool(hasher: &mut FnvHasher, value: Bool) {
+    hasher.write_le_u32(value.is_true() as u32);
+}
+
+impl StableHash for so::StreamOutputDesc {
+    fn stable_hash(&self, hasher: &mut FnvHasher) {
+        hasher.write_le_u32(self.entries.len() as u32);
+        for entry in self.entries.iter() {
+            hasher.write_le_u32(entry.stream);
+            match entry.semantic_name {
+                Some(ref name) => {
+                    hasher.write_le_u32(1);
+                    hasher.write_bytes(name.as_bytes());
+                },
+                None => hasher.write_le_u32(0),
+            }
+            hasher.write_le_u32(entry.semantic_index);
+            hasher.write_le_u32(u32::from(entry.start_component));
+            hasher.write_le_u32(u32::from(entry.component_count));
+            hasher.write_le_u32(u32::from(entry.output_slot));
+        }
+        hasher.write_le_u32(self.strides.len() as u32);
+        for &stride in self.strides.iter() {
+            hasher.write_le_u32(stride);
+        }
+        hasher.write_le_u32(self.rasterized_stream);
+    }
+}
+
+impl StableHash for blend::BlendDesc {
+    fn stable_hash(&self, hasher: &mut FnvHasher) {
+        write_bool(hasher, self.alpha_to_coverage);
+        write_bool(hasher, self.independent_blend);
+        for rt in &self.render_targets {
+            write_bool(hasher, rt.blend_enabled);
+            write_bool(hasher, rt.logic_op_enabled);
+            hasher.write_le_u32(rt.src_blend as u32);
+            hasher.write_le_u32(rt.dst_blend as u32);
+            hasher.write_le_u32(rt.blend_op as u32);
+            hasher.write_le_u32(rt.src_alpha as u32);
+            hasher.write_le_u32(rt.dst_alpha as u32);
+            hasher.write_le_u32(rt.blend_op_alpha as u32);
+            hasher.write_le_u32(rt.logic_op as u32);
+            hasher.write_le_u32(u32::from(rt.write_mask.bits()));
+        }
+    }
+}
+
+impl StableHash for rasterizer::RasterizerDesc {
+    fn stable_hash(&self, hasher: &mut FnvHasher) {
+        hasher.write_le_u32(self.fill as u32);
+        hasher.write_le_u32(self.cull as u32);
+        write_bool(hasher, self.front_ccw);
+        hasher.write_le_u32(self.depth_bias as u32);
+        hasher.write_le_u32(self.depth_bias_clamp.to_bits());
+        hasher.write_le_u32(self.slope_scaled_depth_bias.to_bits());
+        write_bool(hasher, self.depth_clip);
+        write_bool(hasher, self.multisample);
+        write_bool(hasher, self.antialiased_line);
+        hasher.write_le_u32(self.forced_sample_count);
+        hasher.write_le_u32(self.conservative_raster as u32);
+    }
+}
+
+impl StableHash for ds::DepthStencilDesc {
+    fn stable_hash(&self, hasher: &mut FnvHasher) {
+        write_bool(hasher, self.depth);
+        hasher.write_le_u32(self.depth_write_mask as u32);
+        hasher.write_le_u32(self.depth_func as u32);
+        write_bool(hasher, self.stencil);
+        hasher.write_le_u32(u32::from(self.stencil_read_mask));
+        hasher.write_le_u32(u32::from(self.stencil_write_mask));
+        for op in &[self.front_op, self.back_op] {
+            hasher.write_le_u32(op.fail as u32);
+            hasher.write_le_u32(op.depth_fail as u32);
+            hasher.write_le_u32(op.pass as u32);
+            hasher.write_le_u32(op.func as u32);
+        }
+    }
+}
+
+impl StableHash for ia::InputLayout {
+    fn stable_hash(&self, hasher: &mut FnvHasher) {
+        hasher.write_le_u32(self.elements.len() as u32);
+        for element in self.elements.iter() {
+            hasher.write_bytes(element.semantic_name.as_bytes());
+            hasher.write_le_u32(element.semantic_index);
+            hasher.write_le_u32(element.format.0);
+            hasher.write_le_u32(element.input_slot);
+            hasher.write_le_u32(element.aligned_byte_offset);
+            hasher.write_le_u32(element.input_slot_class as u32);
+            hasher.write_le_u32(element.instance_data_step_rate);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pipeline::rootsig::{RootSig, RootSigBuilder, RootSigVersion};
+
+    fn rootsig(node_mask: u32) -> RootSig {
+        let bytes = RootSigBuilder::new().to_bytes(RootSigVersion::V1_1);
+        RootSig::from_desc(&bytes, node_mask)
+    }
+
+    #[test]
+    fn rootsigs_compare_descriptions_and_nodes() {
+        assert_eq!(rootsig(0), rootsig(0));
+        assert_ne!(rootsig(0), rootsig(1));
+        let other = RootSigBuilder::new().to_bytes(RootSigVersion::V1_0);
+        assert_ne!(rootsig(0), RootSig::from_desc(&other, 0));
+    }
+
+    #[test]
+    fn stable_hash_is_pinned() {
+        // hashing goes through fixed width fields only, so the hash of a
+        // description is the same on every host. a change here invalidates
+        // every saved `PipelineBlobStore` entry.
+        let desc = GraphicsPipelineDesc::new(rootsig(0));
+        assert_eq!(desc.stable_hash(), 0xd84d_8e67_9647_711a);
+        let desc = ComputePipelineDesc::new(rootsig(0));
+        assert_eq!(desc.stable_hash(), 0x5518_dc78_f3c7_bf6b);
+    }
+
+    #[test]
+    fn stable_hash_covers_fields() {
+        let desc = GraphicsPipelineDesc::new(rootsig(0));
+        let mut other = desc.clone();
+        other.rootsig = rootsig(1);
+        assert_ne!(desc.stable_hash(), other.stable_hash());
+        let mut other = desc.clone();
+        other.rtv_formats[7] = ::format::DXGI_FORMAT_R8G8B8A8_UNORM;
+        assert_ne!(desc.stable_hash(), other.stable_hash());
+        let mut other = desc.clone();
+        other.rasterizer_state.depth_bias_clamp = 1.0;
+        assert_ne!(desc.stable_hash(), other.stable_hash());
+        let mut other = desc.clone();
+        other.depth_stencil_state.back_op.func = ComparisonFunc::NEVER;
+        assert_ne!(desc.stable_hash(), other.stable_hash());
+        let mut other = desc.clone();
+        other.blend_state.render_targets[3].write_mask = blend::ColorWriteMask::ENABLE_RED;
+        assert_ne!(desc.stable_hash(), other.stable_hash());
+        let mut other = desc.clone();
+        other.stream_output.strides.push(16);
+        assert_ne!(desc.stable_hash(), other.stable_hash());
+        let mut other = desc.clone();
+        other.input_layout.elements.push(ia::InputElement::new(
+            ::std::ffi::CString::new("POSITION").unwrap(), ::format::DXGI_FORMAT_R32G32B32_FLOAT
+        ));
+        assert_ne!(desc.stable_hash(), other.stable_hash());
+        assert_eq!(desc.stable_hash(), desc.clone().stable_hash());
+    }
+}
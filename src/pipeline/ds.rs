@@ -12,7 +12,8 @@ use format::Bool;
 
 /// describes the depth-stencil state
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DepthStencilDesc{
     pub depth: Bool,
     pub depth_write_mask: DepthWriteMask,
@@ -42,7 +43,8 @@ impl Default for DepthStencilDesc {
 
 /// operations based on the stencil test
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StencilOpDesc {
     /// operation when stencil test fails
     pub fail: StencilOp,
@@ -65,28 +67,26 @@ impl Default for StencilOpDesc{
     }
 }
 
-bitflags!{
-    #[repr(C)]
-    pub struct DepthWriteMask: u32 {
-        const ZERO = 0;
-        const ALL = 1;
+d3d_enum!{
+    pub enum DepthWriteMask {
+        ZERO = 0,
+        ALL = 1,
     }
 }
 
-bitflags!{
-    #[repr(C)]
-    pub struct StencilOp: u32 {
-        const KEEP      = 1;
-        const ZERO      = 2;
-        const REPLACE   = 3;
+d3d_enum!{
+    pub enum StencilOp {
+        KEEP      = 1,
+        ZERO      = 2,
+        REPLACE   = 3,
         /// increment and clamp
-        const INCR_SAT  = 4;
+        INCR_SAT  = 4,
         /// decrement and clamp
-        const DECR_SAT  = 5;
-        const INVERT    = 6;
+        DECR_SAT  = 5,
+        INVERT    = 6,
         /// increment and wrap
-        const INCR      = 7;
+        INCR      = 7,
         /// decrement and wrap
-        const DECR      = 8;
+        DECR      = 8,
     }
 }
@@ -12,7 +12,8 @@ use std::os::raw::c_char;
 use format::DxgiFormat;
 use smallvec::SmallVec;
 use std::marker::PhantomData;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
+use dxbc::{Signature, SignatureElement, SystemValue, RegisterComponentType};
 
 /// a index buffer view
 #[repr(C)]
@@ -32,7 +33,11 @@ pub struct VertexBufferView {
     pub stride: u32,
 }
 
-/// a vertex
+#[cfg(feature = "derive")]
+pub use redirect_derive::Vertex;
+
+/// a vertex. with the `derive` feature enabled, can be derived with
+/// `#[derive(Vertex)]`.
 pub trait Vertex: Sized {
     /// get the stride per vertex
     #[inline]
@@ -41,7 +46,7 @@ pub trait Vertex: Sized {
     }
 
     /// get the input layout of this vertex
-    fn get_input_layout(&self) -> InputLayoutBuilder;
+    fn get_input_layout(&self) -> InputLayoutBuilder<'_>;
 }
 
 /// a input layout constructor
@@ -80,29 +85,507 @@ impl<'a> InputElementDesc<'a>{
         InputElementDesc{
             semantic_name: semantic_name.as_ptr(), 
             semantic_index: 0, format, input_slot: 0, 
-            aligned_byte_offset: ::winapi::D3D12_APPEND_ALIGNED_ELEMENT,
+            aligned_byte_offset: APPEND_ALIGNED_ELEMENT,
+            input_slot_class: InputClassification::PER_VERTEX,
+            instance_data_step_rate: 0,
+            _pd: Default::default(),
+        }
+    }
+
+    /// get the semantic name
+    #[inline]
+    pub fn get_semantic_name(&self) -> &'a CStr {
+        unsafe { CStr::from_ptr(self.semantic_name) }
+    }
+}
+
+/// an owned input layout, see `InputLayoutBuilder`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct InputLayout {
+    pub elements: SmallVec<[InputElement; 8]>,
+}
+
+impl InputLayout {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// get a builder borrowing semantic names from `self`
+    pub fn to_builder(&self) -> InputLayoutBuilder<'_> {
+        InputLayoutBuilder{
+            elements: self.elements.iter().map(|element| element.as_desc()).collect(),
+        }
+    }
+
+    /// replace append aligned offsets with explicit ones, see `InputLayoutBuilder::resolve_offsets`
+    pub fn resolve_offsets(&mut self) -> Result<(), Vec<InputLayoutDiagnostic>> {
+        let offsets = self.to_builder().resolved_offsets()?;
+        for (element, offset) in self.elements.iter_mut().zip(offsets) {
+            element.aligned_byte_offset = offset;
+        }
+        Ok(())
+    }
+
+    /// see `InputLayoutBuilder::validate`
+    #[inline]
+    pub fn validate(&self) -> Result<SmallVec<[SlotLayout; 4]>, Vec<InputLayoutDiagnostic>> {
+        self.to_builder().validate()
+    }
+
+    /// see `InputLayoutBuilder::check_vertex_buffers`
+    #[inline]
+    pub fn check_vertex_buffers(
+        &self, start_slot: u32, vbvs: &[VertexBufferView]
+    ) -> Result<(), Vec<InputLayoutDiagnostic>> {
+        self.to_builder().check_vertex_buffers(start_slot, vbvs)
+    }
+
+    /// derive a layout from the input signature of a vertex shader, e.g.
+    /// `vs.container()?.input_signature()?`.
+    ///
+    /// each input is fed as described by the binding of the same semantic
+    /// in `bindings`, or per-vertex from slot 0 if there's none. formats
+    /// not given by a binding are inferred from the component type and
+    /// mask of the input, e.g. `float3` as `R32G32B32_FLOAT`. elements are
+    /// append aligned in signature order. bindings for semantics the
+    /// shader doesn't read are ignored, system generated values such as
+    /// `SV_VertexID` are skipped.
+    pub fn from_signature(
+        signature: &Signature, bindings: &[SemanticBinding]
+    ) -> Result<InputLayout, Vec<InputLayoutDiagnostic>> {
+        let mut ret = InputLayout::new();
+        let mut diagnostics = Vec::new();
+        for input in signature.elements.iter().filter(|input| !is_system_generated(input)) {
+            let binding = bindings.iter().find(|binding| {
+                binding.semantic_index == input.semantic_index &&
+                binding.semantic_name.eq_ignore_ascii_case(&input.semantic_name)
+            });
+            let format = match binding.and_then(|binding| binding.format).or_else(|| infer_format(input)) {
+                Some(format) => format,
+                None => {
+                    diagnostics.push(InputLayoutDiagnostic::UninferableFormat{
+                        semantic_name: input.semantic_name.clone(),
+                        semantic_index: input.semantic_index,
+                    });
+                    continue;
+                },
+            };
+            // names in a signature are nul terminated, thus free of nuls
+            let mut element = InputElement::new(CString::new(input.semantic_name.clone()).unwrap(), format);
+            element.semantic_index = input.semantic_index;
+            if let Some(binding) = binding {
+                element.input_slot = binding.input_slot;
+                element.input_slot_class = binding.input_slot_class;
+                element.instance_data_step_rate = binding.instance_data_step_rate;
+            }
+            ret.elements.push(element);
+        }
+        if diagnostics.is_empty() { Ok(ret) } else { Err(diagnostics) }
+    }
+
+    /// see `InputLayoutBuilder::check_signature`
+    #[inline]
+    pub fn check_signature(&self, signature: &Signature) -> Result<(), Vec<InputLayoutDiagnostic>> {
+        self.to_builder().check_signature(signature)
+    }
+}
+
+/// how to feed a vertex shader input, see `InputLayout::from_signature`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SemanticBinding<'a> {
+    pub semantic_name: &'a str,
+    pub semantic_index: u32,
+    /// format of the element, inferred from the signature if `None`
+    pub format: Option<DxgiFormat>,
+    pub input_slot: u32,
+    pub input_slot_class: InputClassification,
+    pub instance_data_step_rate: u32,
+}
+
+impl<'a> SemanticBinding<'a> {
+    /// a per-vertex binding in slot 0 with an inferred format
+    #[inline]
+    pub fn new(semantic_name: &'a str, semantic_index: u32) -> Self {
+        SemanticBinding{
+            semantic_name, semantic_index,
+            format: None,
+            input_slot: 0,
+            input_slot_class: InputClassification::PER_VERTEX,
+            instance_data_step_rate: 0,
+        }
+    }
+}
+
+/// an owned input element, see `InputElementDesc`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InputElement {
+    pub semantic_name: CString,
+    pub semantic_index: u32,
+    pub format: DxgiFormat,
+    pub input_slot: u32,
+    pub aligned_byte_offset: u32,
+    pub input_slot_class: InputClassification,
+    pub instance_data_step_rate: u32,
+}
+
+impl InputElement {
+    #[inline]
+    pub fn new(semantic_name: CString, format: DxgiFormat) -> Self {
+        InputElement{
+            semantic_name,
+            semantic_index: 0, format, input_slot: 0,
+            aligned_byte_offset: APPEND_ALIGNED_ELEMENT,
             input_slot_class: InputClassification::PER_VERTEX,
             instance_data_step_rate: 0,
+        }
+    }
+
+    /// get an element description borrowing the semantic name from `self`
+    #[inline]
+    pub fn as_desc(&self) -> InputElementDesc<'_> {
+        InputElementDesc{
+            semantic_name: self.semantic_name.as_ptr(),
+            semantic_index: self.semantic_index,
+            format: self.format,
+            input_slot: self.input_slot,
+            aligned_byte_offset: self.aligned_byte_offset,
+            input_slot_class: self.input_slot_class,
+            instance_data_step_rate: self.instance_data_step_rate,
             _pd: Default::default(),
         }
     }
 }
 
-bitflags!{
+/// number of input slots
+pub const INPUT_SLOT_COUNT: u32 = 32;
+
+/// marks an element as directly following the previous one in its slot
+pub const APPEND_ALIGNED_ELEMENT: u32 = 0xffffffff;
+
+/// layout of an input slot, as implied by an input layout
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SlotLayout {
+    pub slot: u32,
+    /// the minimal stride covering all elements in the slot
+    pub stride: u32,
+    pub input_slot_class: InputClassification,
+}
+
+/// a problem found in an input layout. elements are referred to by their indices.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InputLayoutDiagnostic {
+    /// the size of the element format is unknown, e.g. a block compressed format
+    UnsizedFormat{element: usize, format: DxgiFormat},
+    /// the input slot is beyond `INPUT_SLOT_COUNT`
+    SlotOutOfRange{element: usize, slot: u32},
+    /// the offset isn't a multiple of the format size, or 4 for larger formats
+    MisalignedOffset{element: usize, offset: u32},
+    /// the element ends beyond `u32::MAX` bytes into its slot
+    OffsetOverflow{element: usize},
+    /// two elements in the same slot overlap
+    Overlap{element: usize, other: usize},
+    /// two elements share the same semantic name and index
+    DuplicateSemantic{element: usize, other: usize},
+    /// a per-vertex element has a non-zero instance step rate
+    StepRateOnPerVertex{element: usize, step_rate: u32},
+    /// a slot contains both per-vertex and per-instance elements
+    MixedClassification{slot: u32},
+    /// no vertex buffer view is bound to a slot in use
+    MissingVertexBuffer{slot: u32},
+    /// the stride of a vertex buffer view doesn't cover the elements of its slot
+    StrideTooSmall{slot: u32, stride: u32, required: u32},
+    /// the vertex shader reads a semantic no element provides
+    MissingSemantic{semantic_name: String, semantic_index: u32},
+    /// the element format is of a float, uint or sint type other than
+    /// the component type the vertex shader reads
+    TypeMismatch{element: usize, format: DxgiFormat, component_type: RegisterComponentType},
+    /// no format is given for a vertex shader input, and none can be
+    /// inferred from its component type, e.g. for 64 bit inputs
+    UninferableFormat{semantic_name: String, semantic_index: u32},
+}
+
+impl ::std::fmt::Display for InputLayoutDiagnostic {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        match *self {
+            InputLayoutDiagnostic::UnsizedFormat{element, format} =>
+                write!(f, "element {} has format {}, which has no per element size", element, format.0),
+            InputLayoutDiagnostic::SlotOutOfRange{element, slot} =>
+                write!(f, "element {} uses slot {}, but only {} slots are available", element, slot, INPUT_SLOT_COUNT),
+            InputLayoutDiagnostic::MisalignedOffset{element, offset} =>
+                write!(f, "element {} has misaligned offset {}", element, offset),
+            InputLayoutDiagnostic::OffsetOverflow{element} =>
+                write!(f, "element {} ends beyond the largest offset", element),
+            InputLayoutDiagnostic::Overlap{element, other} =>
+                write!(f, "element {} overlaps element {}", element, other),
+            InputLayoutDiagnostic::DuplicateSemantic{element, other} =>
+                write!(f, "element {} has the same semantic as element {}", element, other),
+            InputLayoutDiagnostic::StepRateOnPerVertex{element, step_rate} =>
+                write!(f, "element {} is per-vertex, but has step rate {}", element, step_rate),
+            InputLayoutDiagnostic::MixedClassification{slot} =>
+                write!(f, "slot {} mixes per-vertex and per-instance elements", slot),
+            InputLayoutDiagnostic::MissingVertexBuffer{slot} =>
+                write!(f, "no vertex buffer is bound to slot {}", slot),
+            InputLayoutDiagnostic::StrideTooSmall{slot, stride, required} =>
+                write!(f, "vertex buffer in slot {} has stride {}, but its elements need {}", slot, stride, required),
+            InputLayoutDiagnostic::MissingSemantic{ref semantic_name, semantic_index} =>
+                write!(f, "no element provides {}{}, read by the vertex shader", semantic_name, semantic_index),
+            InputLayoutDiagnostic::TypeMismatch{element, format, component_type} =>
+                write!(f, "element {} has format {}, but the vertex shader reads {}",
+                    element, format.0, component_type.name()),
+            InputLayoutDiagnostic::UninferableFormat{ref semantic_name, semantic_index} =>
+                write!(f, "no format can be inferred for {}{}", semantic_name, semantic_index),
+        }
+    }
+}
+
+impl<'a> InputLayoutBuilder<'a> {
+    /// get the offsets of all elements, with append aligned offsets resolved.
+    ///
+    /// an append aligned element is placed right after the previous element
+    /// in the same slot, aligned to its format size, or 4 for larger formats.
+    pub fn resolved_offsets(&self) -> Result<SmallVec<[u32; 8]>, Vec<InputLayoutDiagnostic>> {
+        let mut diagnostics = Vec::new();
+        let offsets = self.resolve(&mut diagnostics)
+            .into_iter().map(|span| span.map_or(0, |(offset, _)| offset)).collect();
+        if diagnostics.is_empty() { Ok(offsets) } else { Err(diagnostics) }
+    }
+
+    /// replace append aligned offsets with explicit ones
+    pub fn resolve_offsets(&mut self) -> Result<(), Vec<InputLayoutDiagnostic>> {
+        let offsets = self.resolved_offsets()?;
+        for (element, offset) in self.elements.iter_mut().zip(offsets) {
+            element.aligned_byte_offset = offset;
+        }
+        Ok(())
+    }
+
+    /// check the layout for overlapping elements, duplicated semantics and
+    /// inconsistent slot classifications, returning the layouts of all slots
+    /// in use, ordered by slot
+    pub fn validate(&self) -> Result<SmallVec<[SlotLayout; 4]>, Vec<InputLayoutDiagnostic>> {
+        let mut diagnostics = Vec::new();
+        let spans = self.resolve(&mut diagnostics);
+
+        for (i, element) in self.elements.iter().enumerate() {
+            if element.input_slot_class == InputClassification::PER_VERTEX &&
+                element.instance_data_step_rate != 0 {
+                diagnostics.push(InputLayoutDiagnostic::StepRateOnPerVertex{
+                    element: i, step_rate: element.instance_data_step_rate,
+                });
+            }
+            for (j, other) in self.elements[..i].iter().enumerate() {
+                if element.semantic_index == other.semantic_index &&
+                    element.get_semantic_name().to_bytes().eq_ignore_ascii_case(
+                        other.get_semantic_name().to_bytes()
+                    ) {
+                    diagnostics.push(InputLayoutDiagnostic::DuplicateSemantic{element: i, other: j});
+                }
+                if element.input_slot != other.input_slot { continue; }
+                if let (Some((offset, size)), Some((other_offset, other_size))) = (spans[i], spans[j]) {
+                    if offset < other_offset + other_size && other_offset < offset + size {
+                        diagnostics.push(InputLayoutDiagnostic::Overlap{element: i, other: j});
+                    }
+                }
+            }
+        }
+
+        let mut slots: SmallVec<[SlotLayout; 4]> = SmallVec::new();
+        for (element, span) in self.elements.iter().zip(spans) {
+            let (offset, size) = match span { Some(span) => span, None => continue, };
+            let slot = element.input_slot;
+            match slots.iter().position(|layout| layout.slot == slot) {
+                Some(idx) => {
+                    let layout = &mut slots[idx];
+                    layout.stride = ::std::cmp::max(layout.stride, offset + size);
+                    if layout.input_slot_class != element.input_slot_class &&
+                        !diagnostics.contains(&InputLayoutDiagnostic::MixedClassification{slot}) {
+                        diagnostics.push(InputLayoutDiagnostic::MixedClassification{slot});
+                    }
+                },
+                None => slots.push(SlotLayout{
+                    slot, stride: offset + size, input_slot_class: element.input_slot_class,
+                }),
+            }
+        }
+        slots.sort_by_key(|layout| layout.slot);
+
+        if diagnostics.is_empty() { Ok(slots) } else { Err(diagnostics) }
+    }
+
+    /// check that `vbvs`, bound starting at `start_slot`, cover every slot
+    /// in use with a large enough stride. a zero stride is accepted, since
+    /// it's a valid way to repeat the same vertex.
+    pub fn check_vertex_buffers(
+        &self, start_slot: u32, vbvs: &[VertexBufferView]
+    ) -> Result<(), Vec<InputLayoutDiagnostic>> {
+        let mut diagnostics = Vec::new();
+        for layout in self.validate()? {
+            let vbv = if layout.slot >= start_slot {
+                vbvs.get((layout.slot - start_slot) as usize)
+            } else {
+                None
+            };
+            match vbv {
+                None => diagnostics.push(InputLayoutDiagnostic::MissingVertexBuffer{slot: layout.slot}),
+                Some(vbv) if vbv.stride != 0 && vbv.stride < layout.stride => {
+                    diagnostics.push(InputLayoutDiagnostic::StrideTooSmall{
+                        slot: layout.slot, stride: vbv.stride, required: layout.stride,
+                    });
+                },
+                Some(_) => {},
+            }
+        }
+        if diagnostics.is_empty() { Ok(()) } else { Err(diagnostics) }
+    }
+
+    /// check that the layout provides every input of a vertex shader
+    /// signature, with formats of the component types the shader reads.
+    /// extra elements are fine, and fewer components than read default
+    /// to `(0, 0, 0, 1)`.
+    pub fn check_signature(&self, signature: &Signature) -> Result<(), Vec<InputLayoutDiagnostic>> {
+        let mut diagnostics = Vec::new();
+        for input in signature.elements.iter().filter(|input| !is_system_generated(input)) {
+            let found = self.elements.iter().position(|element| {
+                element.semantic_index == input.semantic_index &&
+                element.get_semantic_name().to_bytes().eq_ignore_ascii_case(input.semantic_name.as_bytes())
+            });
+            match found {
+                None => diagnostics.push(InputLayoutDiagnostic::MissingSemantic{
+                    semantic_name: input.semantic_name.clone(),
+                    semantic_index: input.semantic_index,
+                }),
+                Some(i) => {
+                    let format = self.elements[i].format;
+                    if !format_matches(format, input.component_type) {
+                        diagnostics.push(InputLayoutDiagnostic::TypeMismatch{
+                            element: i, format, component_type: input.component_type,
+                        });
+                    }
+                },
+            }
+        }
+        if diagnostics.is_empty() { Ok(()) } else { Err(diagnostics) }
+    }
+
+    /// get the offset and size of every element, `None` if unknown
+    fn resolve(&self, diagnostics: &mut Vec<InputLayoutDiagnostic>) -> SmallVec<[Option<(u32, u32)>; 8]> {
+        let mut ends = [0u32; INPUT_SLOT_COUNT as usize];
+        let mut spans = SmallVec::new();
+        for (i, element) in self.elements.iter().enumerate() {
+            let slot = element.input_slot;
+            if slot >= INPUT_SLOT_COUNT {
+                diagnostics.push(InputLayoutDiagnostic::SlotOutOfRange{element: i, slot});
+                spans.push(None);
+                continue;
+            }
+            let size = match ::format::format_size(element.format) {
+                Some(size) => size,
+                None => {
+                    diagnostics.push(InputLayoutDiagnostic::UnsizedFormat{element: i, format: element.format});
+                    spans.push(None);
+                    continue;
+                },
+            };
+            let alignment = ::std::cmp::min(size, 4);
+            let offset = if element.aligned_byte_offset == APPEND_ALIGNED_ELEMENT {
+                ends[slot as usize].checked_next_multiple_of(alignment)
+            } else {
+                if element.aligned_byte_offset % alignment != 0 {
+                    diagnostics.push(InputLayoutDiagnostic::MisalignedOffset{
+                        element: i, offset: element.aligned_byte_offset,
+                    });
+                }
+                Some(element.aligned_byte_offset)
+            };
+            // spans end within `u32`, so later sums of offsets and sizes can't overflow
+            match offset.and_then(|offset| offset.checked_add(size).map(|end| (offset, end))) {
+                Some((offset, end)) => {
+                    ends[slot as usize] = end;
+                    spans.push(Some((offset, size)));
+                },
+                None => {
+                    diagnostics.push(InputLayoutDiagnostic::OffsetOverflow{element: i});
+                    spans.push(None);
+                },
+            }
+        }
+        spans
+    }
+}
+
+/// whether the input is generated by the input assembler rather than
+/// read from vertex buffers
+#[inline]
+fn is_system_generated(input: &SignatureElement) -> bool {
+    matches!(input.system_value,
+        SystemValue::VERTEX_ID | SystemValue::INSTANCE_ID | SystemValue::PRIMITIVE_ID)
+}
+
+/// the format with as many components as `input` declares, of its component type
+fn infer_format(input: &SignatureElement) -> Option<DxgiFormat> {
+    use format::*;
+    let formats = match input.component_type {
+        RegisterComponentType::FLOAT32 => [
+            DXGI_FORMAT_R32_FLOAT, DXGI_FORMAT_R32G32_FLOAT,
+            DXGI_FORMAT_R32G32B32_FLOAT, DXGI_FORMAT_R32G32B32A32_FLOAT,
+        ],
+        RegisterComponentType::UINT32 => [
+            DXGI_FORMAT_R32_UINT, DXGI_FORMAT_R32G32_UINT,
+            DXGI_FORMAT_R32G32B32_UINT, DXGI_FORMAT_R32G32B32A32_UINT,
+        ],
+        RegisterComponentType::SINT32 => [
+            DXGI_FORMAT_R32_SINT, DXGI_FORMAT_R32G32_SINT,
+            DXGI_FORMAT_R32G32B32_SINT, DXGI_FORMAT_R32G32B32A32_SINT,
+        ],
+        // there are no 3 component 16 bit formats
+        RegisterComponentType::FLOAT16 => [
+            DXGI_FORMAT_R16_FLOAT, DXGI_FORMAT_R16G16_FLOAT,
+            DXGI_FORMAT_R16G16B16A16_FLOAT, DXGI_FORMAT_R16G16B16A16_FLOAT,
+        ],
+        RegisterComponentType::UINT16 => [
+            DXGI_FORMAT_R16_UINT, DXGI_FORMAT_R16G16_UINT,
+            DXGI_FORMAT_R16G16B16A16_UINT, DXGI_FORMAT_R16G16B16A16_UINT,
+        ],
+        RegisterComponentType::SINT16 => [
+            DXGI_FORMAT_R16_SINT, DXGI_FORMAT_R16G16_SINT,
+            DXGI_FORMAT_R16G16B16A16_SINT, DXGI_FORMAT_R16G16B16A16_SINT,
+        ],
+        _ => return None,
+    };
+    // the components in use are x, xy, xyz or xyzw
+    let components = 8 - (input.mask & 0xf).leading_zeros() as usize;
+    if components == 0 { None } else { Some(formats[components - 1]) }
+}
+
+/// whether a vertex shader input of `component_type` can be fed with `format`.
+/// normalized formats are read as floats.
+fn format_matches(format: DxgiFormat, component_type: RegisterComponentType) -> bool {
+    match component_type {
+        RegisterComponentType::UINT32 | RegisterComponentType::UINT16 |
+        RegisterComponentType::UINT64 => ::format::is_uint_format(format),
+        RegisterComponentType::SINT32 | RegisterComponentType::SINT16 |
+        RegisterComponentType::SINT64 => ::format::is_sint_format(format),
+        RegisterComponentType::FLOAT32 | RegisterComponentType::FLOAT16 |
+        RegisterComponentType::FLOAT64 =>
+            !::format::is_uint_format(format) && !::format::is_sint_format(format),
+        RegisterComponentType::UNKNOWN => true,
+    }
+}
+
+d3d_enum!{
     /// identifies the type of input data
-    #[repr(C)]
-    pub struct InputClassification: u32 {
-        const PER_VERTEX = 0;
-        const PER_INSTANCE = 1;
+    pub enum InputClassification {
+        PER_VERTEX = 0,
+        PER_INSTANCE = 1,
     }
 }
 
-bitflags!{
-    #[repr(C)]
-    pub struct StripCutValue: u32 {
-        const DISABLED = 0;
-        const FFFF = 1;
-        const FFFFFFFF = 2;
+d3d_enum!{
+    pub enum StripCutValue {
+        DISABLED = 0,
+        FFFF = 1,
+        FFFFFFFF = 2,
     }
 }
 
@@ -113,67 +596,223 @@ impl Default for StripCutValue {
     }
 }
 
-bitflags!{
-    #[repr(C)]
-    pub struct PrimitiveTopologyType: u32 {
-        const UNDEFINED  = 0;
-        const POINT      = 1;
-        const LINE       = 2;
-        const TRIANGLE   = 3;
-        const PATCH      = 4;
-    }
-}
-
-bitflags!{
-    #[repr(C)]
-    pub struct PrimitiveTopology: u32 {
-        const UNDEFINED                     = 0;
-        const POINTLIST                     = 1;
-        const LINELIST                      = 2;
-        const LINESTRIP                     = 3;
-        const TRIANGLELIST                  = 4;
-        const TRIANGLESTRIP                 = 5;
-        const LINELIST_ADJ                  = 10;
-        const LINESTRIP_ADJ                 = 11;
-        const TRIANGLELIST_ADJ              = 12;
-        const TRIANGLESTRIP_ADJ             = 13;
-        const CONTROL_POINT_PATCHLIST_1     = 33;
-        const CONTROL_POINT_PATCHLIST_2     = 34;
-        const CONTROL_POINT_PATCHLIST_3     = 35;
-        const CONTROL_POINT_PATCHLIST_4     = 36;
-        const CONTROL_POINT_PATCHLIST_5     = 37;
-        const CONTROL_POINT_PATCHLIST_6     = 38;
-        const CONTROL_POINT_PATCHLIST_7     = 39;
-        const CONTROL_POINT_PATCHLIST_8     = 40;
-        const CONTROL_POINT_PATCHLIST_9     = 41;
-        const CONTROL_POINT_PATCHLIST_10    = 42;
-        const CONTROL_POINT_PATCHLIST_11    = 43;
-        const CONTROL_POINT_PATCHLIST_12    = 44;
-        const CONTROL_POINT_PATCHLIST_13    = 45;
-        const CONTROL_POINT_PATCHLIST_14    = 46;
-        const CONTROL_POINT_PATCHLIST_15    = 47;
-        const CONTROL_POINT_PATCHLIST_16    = 48;
-        const CONTROL_POINT_PATCHLIST_17    = 49;
-        const CONTROL_POINT_PATCHLIST_18    = 50;
-        const CONTROL_POINT_PATCHLIST_19    = 51;
-        const CONTROL_POINT_PATCHLIST_20    = 52;
-        const CONTROL_POINT_PATCHLIST_21    = 53;
-        const CONTROL_POINT_PATCHLIST_22    = 54;
-        const CONTROL_POINT_PATCHLIST_23    = 55;
-        const CONTROL_POINT_PATCHLIST_24    = 56;
-        const CONTROL_POINT_PATCHLIST_25    = 57;
-        const CONTROL_POINT_PATCHLIST_26    = 58;
-        const CONTROL_POINT_PATCHLIST_27    = 59;
-        const CONTROL_POINT_PATCHLIST_28    = 60;
-        const CONTROL_POINT_PATCHLIST_29    = 61;
-        const CONTROL_POINT_PATCHLIST_30    = 62;
-        const CONTROL_POINT_PATCHLIST_31    = 63;
-        const CONTROL_POINT_PATCHLIST_32    = 64;
-    }
-}
-
-impl Default for PrimitiveTopology {
-    fn default() -> Self {
-        PrimitiveTopology::TRIANGLELIST
+d3d_enum!{
+    pub enum PrimitiveTopologyType {
+        UNDEFINED  = 0,
+        POINT      = 1,
+        LINE       = 2,
+        TRIANGLE   = 3,
+        PATCH      = 4,
+    }
+}
+
+d3d_enum!{
+    #[derive(Default)]
+    pub enum PrimitiveTopology {
+        UNDEFINED                     = 0,
+        POINTLIST                     = 1,
+        LINELIST                      = 2,
+        LINESTRIP                     = 3,
+        #[default]
+        TRIANGLELIST                  = 4,
+        TRIANGLESTRIP                 = 5,
+        LINELIST_ADJ                  = 10,
+        LINESTRIP_ADJ                 = 11,
+        TRIANGLELIST_ADJ              = 12,
+        TRIANGLESTRIP_ADJ             = 13,
+        CONTROL_POINT_PATCHLIST_1     = 33,
+        CONTROL_POINT_PATCHLIST_2     = 34,
+        CONTROL_POINT_PATCHLIST_3     = 35,
+        CONTROL_POINT_PATCHLIST_4     = 36,
+        CONTROL_POINT_PATCHLIST_5     = 37,
+        CONTROL_POINT_PATCHLIST_6     = 38,
+        CONTROL_POINT_PATCHLIST_7     = 39,
+        CONTROL_POINT_PATCHLIST_8     = 40,
+        CONTROL_POINT_PATCHLIST_9     = 41,
+        CONTROL_POINT_PATCHLIST_10    = 42,
+        CONTROL_POINT_PATCHLIST_11    = 43,
+        CONTROL_POINT_PATCHLIST_12    = 44,
+        CONTROL_POINT_PATCHLIST_13    = 45,
+        CONTROL_POINT_PATCHLIST_14    = 46,
+        CONTROL_POINT_PATCHLIST_15    = 47,
+        CONTROL_POINT_PATCHLIST_16    = 48,
+        CONTROL_POINT_PATCHLIST_17    = 49,
+        CONTROL_POINT_PATCHLIST_18    = 50,
+        CONTROL_POINT_PATCHLIST_19    = 51,
+        CONTROL_POINT_PATCHLIST_20    = 52,
+        CONTROL_POINT_PATCHLIST_21    = 53,
+        CONTROL_POINT_PATCHLIST_22    = 54,
+        CONTROL_POINT_PATCHLIST_23    = 55,
+        CONTROL_POINT_PATCHLIST_24    = 56,
+        CONTROL_POINT_PATCHLIST_25    = 57,
+        CONTROL_POINT_PATCHLIST_26    = 58,
+        CONTROL_POINT_PATCHLIST_27    = 59,
+        CONTROL_POINT_PATCHLIST_28    = 60,
+        CONTROL_POINT_PATCHLIST_29    = 61,
+        CONTROL_POINT_PATCHLIST_30    = 62,
+        CONTROL_POINT_PATCHLIST_31    = 63,
+        CONTROL_POINT_PATCHLIST_32    = 64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use format::*;
+    use resource::GpuVAddress;
+
+    fn element(semantic_name: &str, format: DxgiFormat, input_slot: u32, aligned_byte_offset: u32) -> InputElement {
+        let mut element = InputElement::new(CString::new(semantic_name).unwrap(), format);
+        element.input_slot = input_slot;
+        element.aligned_byte_offset = aligned_byte_offset;
+        element
+    }
+
+    fn layout(elements: Vec<InputElement>) -> InputLayout {
+        InputLayout{elements: elements.into_iter().collect()}
+    }
+
+    fn vbv(stride: u32) -> VertexBufferView {
+        VertexBufferView{location: GpuVAddress{ptr: 0}, size: 1024, stride}
+    }
+
+    #[test]
+    fn append_aligned_offsets() {
+        let mut layout = layout(vec![
+            element("COLOR", DXGI_FORMAT_R8_UNORM, 0, APPEND_ALIGNED_ELEMENT),
+            // aligned to its size
+            element("TEXCOORD", DXGI_FORMAT_R16_UINT, 0, APPEND_ALIGNED_ELEMENT),
+            // aligned to 4
+            element("POSITION", DXGI_FORMAT_R32G32B32_FLOAT, 0, APPEND_ALIGNED_ELEMENT),
+            // other slots start over
+            element("NORMAL", DXGI_FORMAT_R16G16_FLOAT, 1, APPEND_ALIGNED_ELEMENT),
+            element("TANGENT", DXGI_FORMAT_R8G8B8A8_UNORM, 0, 40),
+            // appended after the last element of the slot, not the furthest
+            element("BINORMAL", DXGI_FORMAT_R8_UNORM, 0, APPEND_ALIGNED_ELEMENT),
+            element("BLENDINDICES", DXGI_FORMAT_R8_UNORM, 1, APPEND_ALIGNED_ELEMENT),
+        ]);
+        assert_eq!(layout.to_builder().resolved_offsets().unwrap().as_slice(), &[0, 2, 4, 0, 40, 44, 4]);
+
+        layout.resolve_offsets().unwrap();
+        let offsets: Vec<u32> = layout.elements.iter().map(|element| element.aligned_byte_offset).collect();
+        assert_eq!(offsets, [0, 2, 4, 0, 40, 44, 4]);
+        assert_eq!(layout.validate().unwrap().as_slice(), &[
+            SlotLayout{slot: 0, stride: 45, input_slot_class: InputClassification::PER_VERTEX},
+            SlotLayout{slot: 1, stride: 5, input_slot_class: InputClassification::PER_VERTEX},
+        ]);
+    }
+
+    #[test]
+    fn unresolvable_offsets() {
+        let layout = layout(vec![
+            element("POSITION", DXGI_FORMAT_BC1_UNORM, 0, 0),
+            element("COLOR", DXGI_FORMAT_R8G8B8A8_UNORM, INPUT_SLOT_COUNT, 0),
+            element("NORMAL", DXGI_FORMAT_R32G32B32_FLOAT, 0, 2),
+            element("TEXCOORD", DXGI_FORMAT_R16_UINT, 0, APPEND_ALIGNED_ELEMENT - 1),
+        ]);
+        assert_eq!(layout.to_builder().resolved_offsets(), Err(vec![
+            InputLayoutDiagnostic::UnsizedFormat{element: 0, format: DXGI_FORMAT_BC1_UNORM},
+            InputLayoutDiagnostic::SlotOutOfRange{element: 1, slot: INPUT_SLOT_COUNT},
+            InputLayoutDiagnostic::MisalignedOffset{element: 2, offset: 2},
+            InputLayoutDiagnostic::OffsetOverflow{element: 3},
+        ]));
+    }
+
+    #[test]
+    fn offset_overflow() {
+        let layout = layout(vec![
+            element("POSITION", DXGI_FORMAT_R32G32B32_FLOAT, 0, 0xffff_fff0),
+            // the first ends at `u32::MAX - 3`, there's no aligned offset after it
+            element("TEXCOORD", DXGI_FORMAT_R8G8B8A8_UNORM, 0, APPEND_ALIGNED_ELEMENT),
+            element("NORMAL", DXGI_FORMAT_R32G32B32_FLOAT, 1, 0xffff_fff8),
+            element("COLOR", DXGI_FORMAT_R8_UNORM, 2, 0xffff_fffe),
+        ]);
+        assert_eq!(layout.validate(), Err(vec![
+            InputLayoutDiagnostic::OffsetOverflow{element: 1},
+            InputLayoutDiagnostic::OffsetOverflow{element: 2},
+        ]));
+
+        // elements ending right at the largest offset are fine
+        let layout = self::layout(vec![
+            element("POSITION", DXGI_FORMAT_R32G32B32_FLOAT, 0, 0xffff_fff0),
+            element("COLOR", DXGI_FORMAT_R8_UNORM, 0, APPEND_ALIGNED_ELEMENT),
+        ]);
+        assert_eq!(layout.validate().unwrap()[0].stride, 0xffff_fffd);
+    }
+
+    #[test]
+    fn overlap() {
+        let layout = layout(vec![
+            element("POSITION", DXGI_FORMAT_R32G32B32_FLOAT, 0, 0),
+            element("NORMAL", DXGI_FORMAT_R32G32B32_FLOAT, 0, 8),
+            // the same offsets in another slot
+            element("TEXCOORD", DXGI_FORMAT_R32G32B32_FLOAT, 1, 8),
+            // adjacent
+            element("COLOR", DXGI_FORMAT_R8G8B8A8_UNORM, 0, 20),
+        ]);
+        assert_eq!(layout.validate(), Err(vec![InputLayoutDiagnostic::Overlap{element: 1, other: 0}]));
+    }
+
+    #[test]
+    fn duplicate_semantics() {
+        let mut layout = layout(vec![
+            element("TEXCOORD", DXGI_FORMAT_R16_UINT, 0, APPEND_ALIGNED_ELEMENT),
+            element("TEXCOORD", DXGI_FORMAT_R16_UINT, 0, APPEND_ALIGNED_ELEMENT),
+            element("texcoord", DXGI_FORMAT_R16_UINT, 1, APPEND_ALIGNED_ELEMENT),
+        ]);
+        layout.elements[1].semantic_index = 1;
+        assert_eq!(layout.validate(), Err(vec![InputLayoutDiagnostic::DuplicateSemantic{element: 2, other: 0}]));
+    }
+
+    #[test]
+    fn classification() {
+        let mut layout = layout(vec![
+            element("POSITION", DXGI_FORMAT_R32G32B32_FLOAT, 0, APPEND_ALIGNED_ELEMENT),
+            element("COLOR", DXGI_FORMAT_R8G8B8A8_UNORM, 0, APPEND_ALIGNED_ELEMENT),
+            element("NORMAL", DXGI_FORMAT_R32G32B32_FLOAT, 1, APPEND_ALIGNED_ELEMENT),
+        ]);
+        layout.elements[0].instance_data_step_rate = 1;
+        layout.elements[1].input_slot_class = InputClassification::PER_INSTANCE;
+        layout.elements[1].instance_data_step_rate = 2;
+        assert_eq!(layout.validate(), Err(vec![
+            InputLayoutDiagnostic::StepRateOnPerVertex{element: 0, step_rate: 1},
+            InputLayoutDiagnostic::MixedClassification{slot: 0},
+        ]));
+    }
+
+    #[test]
+    fn strides() {
+        let layout = layout(vec![
+            element("POSITION", DXGI_FORMAT_R32G32B32_FLOAT, 1, APPEND_ALIGNED_ELEMENT),
+            element("COLOR", DXGI_FORMAT_R8G8B8A8_UNORM, 1, APPEND_ALIGNED_ELEMENT),
+            element("NORMAL", DXGI_FORMAT_R16G16_FLOAT, 3, 8),
+        ]);
+        let builder = layout.to_builder();
+        assert_eq!(builder.validate().unwrap().as_slice(), &[
+            SlotLayout{slot: 1, stride: 16, input_slot_class: InputClassification::PER_VERTEX},
+            SlotLayout{slot: 3, stride: 12, input_slot_class: InputClassification::PER_VERTEX},
+        ]);
+
+        assert_eq!(builder.check_vertex_buffers(1, &[vbv(16), vbv(0), vbv(12)]), Ok(()));
+        // a zero stride repeats the first vertex
+        assert_eq!(builder.check_vertex_buffers(1, &[vbv(0), vbv(0), vbv(0)]), Ok(()));
+        assert_eq!(builder.check_vertex_buffers(1, &[vbv(15), vbv(0), vbv(32)]), Err(vec![
+            InputLayoutDiagnostic::StrideTooSmall{slot: 1, stride: 15, required: 16},
+        ]));
+        assert_eq!(builder.check_vertex_buffers(2, &[vbv(12)]), Err(vec![
+            InputLayoutDiagnostic::MissingVertexBuffer{slot: 1},
+            InputLayoutDiagnostic::MissingVertexBuffer{slot: 3},
+        ]));
+        assert_eq!(builder.check_vertex_buffers(0, &[vbv(0), vbv(16)]), Err(vec![
+            InputLayoutDiagnostic::MissingVertexBuffer{slot: 3},
+        ]));
+    }
+
+    #[test]
+    fn messages() {
+        assert_eq!(InputLayoutDiagnostic::OffsetOverflow{element: 2}.to_string(),
+            "element 2 ends beyond the largest offset");
+        assert_eq!(InputLayoutDiagnostic::StrideTooSmall{slot: 1, stride: 15, required: 16}.to_string(),
+            "vertex buffer in slot 1 has stride 15, but its elements need 16");
     }
 }
@@ -11,12 +11,17 @@
 // TODO: add graphic pipeline state description structure
 // blockers: shader, rootsignature
 
+#[cfg(windows)]
 use device::Device;
 use format::DxgiFormat;
+#[cfg(windows)]
 use winapi::{ID3D12PipelineState, ID3DBlob};
+#[cfg(windows)]
 use error::WinError;
+#[cfg(windows)]
 use comptr::ComPtr;
 use shader::*;
+#[cfg(windows)]
 use std::mem::transmute;
 
 pub mod so;
@@ -26,20 +31,34 @@ pub mod rasterizer;
 pub mod ds;
 pub mod ia;
 pub mod sampler;
+pub mod stream;
+
+mod desc;
+pub use self::desc::*;
+
+mod cache;
+pub use self::cache::*;
+mod store;
+pub use self::store::*;
+mod validate;
+pub use self::validate::*;
 
 pub type SampleDesc = ::swapchain::SampleDesc;
 
 /// a pipeline state object
+#[cfg(windows)]
 pub trait PipelineState {
     fn as_raw_ptr(&self) -> &ComPtr<ID3D12PipelineState>;
 }
 
 /// a graphics pipeline state object
+#[cfg(windows)]
 #[derive(Clone, Debug)]
 pub struct GraphicsPipelineState {
     pub(crate) ptr: ComPtr<ID3D12PipelineState>,
 }
 
+#[cfg(windows)]
 impl PipelineState for GraphicsPipelineState {
     #[inline]
     fn as_raw_ptr(&self) -> &ComPtr<ID3D12PipelineState> { 
@@ -50,15 +69,18 @@ impl PipelineState for GraphicsPipelineState {
 /// a graphics pipeline state cached blob
 #[derive(Clone, Debug)]
 pub struct GraphicsPipelineStateCache {
+    #[cfg(windows)]
     pub(crate) ptr: ComPtr<ID3DBlob>,
 }
 
 /// a pipeline state object
+#[cfg(windows)]
 #[derive(Clone, Debug)]
 pub struct ComputePipelineState {
     pub(crate) ptr: ComPtr<ID3D12PipelineState>,
 }
 
+#[cfg(windows)]
 impl PipelineState for ComputePipelineState {
     #[inline]
     fn as_raw_ptr(&self) -> &ComPtr<ID3D12PipelineState> { 
@@ -69,12 +91,41 @@ impl PipelineState for ComputePipelineState {
 /// a pipeline state cached blob
 #[derive(Clone, Debug)]
 pub struct ComputePipelineStateCache {
+    #[cfg(windows)]
     pub(crate) ptr: ComPtr<ID3DBlob>,
 }
 
 macro_rules! impl_cache_methods {
     ($PS: ident, $PSC: ident) => {
+#[cfg(windows)]
 impl $PSC {
+    /// copy `bytes`, e.g. loaded from a previous run, into a new cached blob
+    pub fn from_bytes(bytes: &[u8]) -> Result<$PSC, WinError> {
+        unsafe {
+            let mut ret = ::std::mem::uninitialized();
+            let hr = ::d3dcompiler::D3DCreateBlob(bytes.len() as _, &mut ret);
+            WinError::from_hresult_or_ok(hr, || {
+                let ptr = ComPtr::new(ret);
+                ::std::ptr::copy_nonoverlapping(
+                    bytes.as_ptr(), (*ret).GetBufferPointer() as *mut u8, bytes.len()
+                );
+                $PSC{ptr}
+            })
+        }
+    }
+
+    /// get the content of the cached blob
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            let blob = self.ptr.as_mut_ptr();
+            ::std::slice::from_raw_parts(
+                (*blob).GetBufferPointer() as *const u8,
+                (*blob).GetBufferSize() as usize
+            )
+        }
+    }
+
     #[inline]
     pub fn to_ffi_cache(&mut self) -> ::winapi::D3D12_CACHED_PIPELINE_STATE {
         unsafe {::winapi::D3D12_CACHED_PIPELINE_STATE{
@@ -84,6 +135,7 @@ impl $PSC {
     }
 }
 
+#[cfg(windows)]
 impl $PS {
     /// get the cached blob
     #[inline]
@@ -103,6 +155,16 @@ impl $PS {
 impl_cache_methods!(GraphicsPipelineState, GraphicsPipelineStateCache);
 impl_cache_methods!(ComputePipelineState, ComputePipelineStateCache);
 
+// pipeline states are free-threaded, and immutable once created
+#[cfg(windows)]
+unsafe impl Send for GraphicsPipelineState {}
+#[cfg(windows)]
+unsafe impl Sync for GraphicsPipelineState {}
+#[cfg(windows)]
+unsafe impl Send for ComputePipelineState {}
+#[cfg(windows)]
+unsafe impl Sync for ComputePipelineState {}
+
 /// graphics pso builder
 #[derive(Clone, Debug)]
 pub struct GraphicsPipelineStateBuilder<'a> {
@@ -137,7 +199,7 @@ impl<'a> GraphicsPipelineStateBuilder<'a> {
             vs: None, ps: None, ds: None, hs: None, gs: None,
             stream_output: Default::default(),
             blend_state: Default::default(),
-            sample_mask: ::std::u32::MAX,
+            sample_mask: u32::MAX,
             rasterizer_state: Default::default(),
             depth_stencil_state: Default::default(),
             input_layout: Default::default(),
@@ -153,6 +215,7 @@ impl<'a> GraphicsPipelineStateBuilder<'a> {
         }
     }
 
+    #[cfg(windows)]
     pub fn build(&mut self, device: &mut Device) -> Result<GraphicsPipelineState, WinError> {
         unsafe {
             let mut desc: ::winapi::D3D12_GRAPHICS_PIPELINE_STATE_DESC = ::std::mem::zeroed();
@@ -213,6 +276,7 @@ impl<'a> ComputePipelineStateBuilder<'a> {
         }
     }
 
+    #[cfg(windows)]
     pub fn build(&mut self, device: &mut Device) -> Result<ComputePipelineState, WinError> {
         unsafe {
             let mut desc: ::winapi::D3D12_COMPUTE_PIPELINE_STATE_DESC = ::std::mem::zeroed();
@@ -249,17 +313,16 @@ impl Default for PipelineStateFlags{
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// comparison options, specifying under which circumstance the comparison passes
-    #[repr(C)]
-    pub struct ComparisonFunc: u32 {
-        const NEVER          = 1;
-        const LESS           = 2;
-        const EQUAL          = 3;
-        const LESS_EQUAL     = 4;
-        const GREATER        = 5;
-        const NOT_EQUAL      = 6;
-        const GREATER_EQUAL  = 7;
-        const ALWAYS         = 8;
+    pub enum ComparisonFunc {
+        NEVER          = 1,
+        LESS           = 2,
+        EQUAL          = 3,
+        LESS_EQUAL     = 4,
+        GREATER        = 5,
+        NOT_EQUAL      = 6,
+        GREATER_EQUAL  = 7,
+        ALWAYS         = 8,
     }
 }
@@ -9,10 +9,12 @@
 //! defines the rasterizer state
 
 use format::Bool;
+use std::hash::{Hash, Hasher};
 
 /// describes the rasterizer state. [more info](https://msdn.microsoft.com/library/windows/desktop/dn770387(v=vs.85).aspx)
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RasterizerDesc {
     pub fill: FillMode,
     pub cull: CullMode,
@@ -46,27 +48,60 @@ impl Default for RasterizerDesc {
     }
 }
 
-bitflags!{
-    #[repr(C)]
-    pub struct FillMode: u32 {
-        const WIREFRAME = 2;
-        const SOLID = 3;
+// floating point fields are compared bitwise, so that the description
+// can be used as a hash key
+impl PartialEq for RasterizerDesc {
+    fn eq(&self, other: &RasterizerDesc) -> bool {
+        self.fill == other.fill &&
+        self.cull == other.cull &&
+        self.front_ccw == other.front_ccw &&
+        self.depth_bias == other.depth_bias &&
+        self.depth_bias_clamp.to_bits() == other.depth_bias_clamp.to_bits() &&
+        self.slope_scaled_depth_bias.to_bits() == other.slope_scaled_depth_bias.to_bits() &&
+        self.depth_clip == other.depth_clip &&
+        self.multisample == other.multisample &&
+        self.antialiased_line == other.antialiased_line &&
+        self.forced_sample_count == other.forced_sample_count &&
+        self.conservative_raster == other.conservative_raster
     }
 }
 
-bitflags!{
-    #[repr(C)]
-    pub struct CullMode: u32 {
-        const NONE = 1;
-        const FRONT = 2;
-        const BACK = 3;
+impl Eq for RasterizerDesc {}
+
+impl Hash for RasterizerDesc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fill.hash(state);
+        self.cull.hash(state);
+        self.front_ccw.hash(state);
+        self.depth_bias.hash(state);
+        self.depth_bias_clamp.to_bits().hash(state);
+        self.slope_scaled_depth_bias.to_bits().hash(state);
+        self.depth_clip.hash(state);
+        self.multisample.hash(state);
+        self.antialiased_line.hash(state);
+        self.forced_sample_count.hash(state);
+        self.conservative_raster.hash(state);
+    }
+}
+
+d3d_enum!{
+    pub enum FillMode {
+        WIREFRAME = 2,
+        SOLID = 3,
+    }
+}
+
+d3d_enum!{
+    pub enum CullMode {
+        NONE = 1,
+        FRONT = 2,
+        BACK = 3,
     }
 }
 
-bitflags!{
-    #[repr(C)]
-    pub struct ConservativeMode: u32 {
-        const OFF = 0;
-        const ON = 1;
+d3d_enum!{
+    pub enum ConservativeMode {
+        OFF = 0,
+        ON = 1,
     }
 }
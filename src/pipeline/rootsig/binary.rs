@@ -0,0 +1,571 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! the serialized root signature format, the `RTS0` part of a DXBC container.
+//!
+//! the layout follows the runtime serializer: a header, the root parameter
+//! array, the payload of each parameter in order, with descriptor ranges
+//! right after their table, then the static sampler array.
+
+use super::*;
+use dxbc::{self, read_u32, push_u32};
+use error::InvalidEnumValue;
+use pipeline::ComparisonFunc;
+use pipeline::sampler::{Filter, TextureAddressMode, BorderColor};
+use std::convert::TryFrom;
+use std::{error, fmt};
+
+d3d_enum!{
+    /// version of a serialized root signature
+    pub enum RootSigVersion {
+        V1_0 = 1,
+        V1_1 = 2,
+    }
+}
+
+/// an error decoding a serialized root signature
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RootSigDecodeError {
+    /// the container has no `RTS0` part
+    MissingPart,
+    /// the data ends before a structure it refers to
+    Truncated,
+    /// an unknown root signature version
+    UnsupportedVersion(u32),
+    /// an unknown root parameter type
+    InvalidParamType(u32),
+    /// an out of range enumeration value
+    InvalidValue(InvalidEnumValue),
+    /// unknown root signature flags
+    InvalidFlags(u32),
+    /// unknown descriptor range or root descriptor flags
+    InvalidDescriptorFlags(u32),
+}
+
+impl fmt::Display for RootSigDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            RootSigDecodeError::MissingPart =>
+                write!(f, "the container has no RTS0 part"),
+            RootSigDecodeError::Truncated =>
+                write!(f, "the root signature is truncated"),
+            RootSigDecodeError::UnsupportedVersion(version) =>
+                write!(f, "unsupported root signature version {}", version),
+            RootSigDecodeError::InvalidParamType(param_type) =>
+                write!(f, "invalid root parameter type {}", param_type),
+            RootSigDecodeError::InvalidValue(ref err) =>
+                write!(f, "{}", err),
+            RootSigDecodeError::InvalidFlags(flags) =>
+                write!(f, "invalid root signature flags {:#x}", flags),
+            RootSigDecodeError::InvalidDescriptorFlags(flags) =>
+                write!(f, "invalid descriptor range or root descriptor flags {:#x}", flags),
+        }
+    }
+}
+
+impl error::Error for RootSigDecodeError {}
+
+impl From<InvalidEnumValue> for RootSigDecodeError {
+    #[inline]
+    fn from(err: InvalidEnumValue) -> Self {
+        RootSigDecodeError::InvalidValue(err)
+    }
+}
+
+const PARAM_TYPE_TABLE: u32 = 0;
+const PARAM_TYPE_CONSTANTS: u32 = 1;
+const PARAM_TYPE_CBV: u32 = 2;
+const PARAM_TYPE_SRV: u32 = 3;
+const PARAM_TYPE_UAV: u32 = 4;
+
+const HEADER_SIZE: usize = 24;
+const PARAM_SIZE: usize = 12;
+const SAMPLER_SIZE: usize = 52;
+
+impl RootSigBuilder {
+    /// serialize the description into a DXBC container holding an `RTS0`
+    /// part, as `D3D12SerializeRootSignature` does, without calling into
+    /// the runtime. version 1.0 drops the descriptor and data flags.
+    pub fn to_bytes(&self, version: RootSigVersion) -> Vec<u8> {
+        dxbc::write_container(&[(*b"RTS0", &self.to_rts0(version))])
+    }
+
+    /// serialize the description into the content of an `RTS0` part
+    pub fn to_rts0(&self, version: RootSigVersion) -> Vec<u8> {
+        let v1_1 = version == RootSigVersion::V1_1;
+        let params_offset = HEADER_SIZE;
+        let payloads_offset = params_offset + PARAM_SIZE * self.root_params.len();
+
+        // payloads follow the root parameters
+        let mut payloads = Vec::new();
+        let mut params = Vec::with_capacity(PARAM_SIZE * self.root_params.len());
+        for param in self.root_params.iter() {
+            let payload_offset = (payloads_offset + payloads.len()) as u32;
+            let param_type = match param.param_type {
+                RootParamType::DescriptorTable{ref descriptor_ranges} => {
+                    push_u32(&mut payloads, descriptor_ranges.len() as u32);
+                    push_u32(&mut payloads, payload_offset + 8);
+                    for range in descriptor_ranges.iter() {
+                        push_u32(&mut payloads, range.range_type as u32);
+                        push_u32(&mut payloads, range.num_descriptors);
+                        push_u32(&mut payloads, range.base_shader_register);
+                        push_u32(&mut payloads, range.register_space);
+                        if v1_1 { push_u32(&mut payloads, range.flags.bits()); }
+                        push_u32(&mut payloads, range.offset_from_table_start);
+                    }
+                    PARAM_TYPE_TABLE
+                },
+                RootParamType::Constant{shader_register, register_space, num_32bit_values} => {
+                    push_u32(&mut payloads, shader_register);
+                    push_u32(&mut payloads, register_space);
+                    push_u32(&mut payloads, num_32bit_values);
+                    PARAM_TYPE_CONSTANTS
+                },
+                RootParamType::Cbv{shader_register, register_space, flags} |
+                RootParamType::Srv{shader_register, register_space, flags} |
+                RootParamType::Uav{shader_register, register_space, flags} => {
+                    push_u32(&mut payloads, shader_register);
+                    push_u32(&mut payloads, register_space);
+                    if v1_1 { push_u32(&mut payloads, flags.bits()); }
+                    match param.param_type {
+                        RootParamType::Cbv{..} => PARAM_TYPE_CBV,
+                        RootParamType::Srv{..} => PARAM_TYPE_SRV,
+                        _ => PARAM_TYPE_UAV,
+                    }
+                },
+            };
+            push_u32(&mut params, param_type);
+            push_u32(&mut params, param.visibility as u32);
+            push_u32(&mut params, payload_offset);
+        }
+
+        let samplers_offset = payloads_offset + payloads.len();
+        let mut ret = Vec::with_capacity(samplers_offset + SAMPLER_SIZE * self.static_samplers.len());
+        push_u32(&mut ret, version as u32);
+        push_u32(&mut ret, self.root_params.len() as u32);
+        push_u32(&mut ret, params_offset as u32);
+        push_u32(&mut ret, self.static_samplers.len() as u32);
+        push_u32(&mut ret, samplers_offset as u32);
+        push_u32(&mut ret, self.flags.bits());
+        ret.extend_from_slice(&params);
+        ret.extend_from_slice(&payloads);
+        for sampler in self.static_samplers.iter() {
+            push_u32(&mut ret, sampler.filter as u32);
+            push_u32(&mut ret, sampler.address_u as u32);
+            push_u32(&mut ret, sampler.address_v as u32);
+            push_u32(&mut ret, sampler.address_w as u32);
+            push_u32(&mut ret, sampler.mip_bias.to_bits());
+            push_u32(&mut ret, sampler.max_anisotropy);
+            push_u32(&mut ret, sampler.comparison_func as u32);
+            push_u32(&mut ret, sampler.border_color as u32);
+            push_u32(&mut ret, sampler.min_lod.to_bits());
+            push_u32(&mut ret, sampler.max_lod.to_bits());
+            push_u32(&mut ret, sampler.shader_register);
+            push_u32(&mut ret, sampler.register_space);
+            push_u32(&mut ret, sampler.visibility as u32);
+        }
+        ret
+    }
+
+    /// decode a serialized root signature, either a DXBC container, e.g. a
+    /// shader with an embedded root signature, or the content of an `RTS0` part
+    pub fn from_bytes(bytes: &[u8]) -> Result<(RootSigBuilder, RootSigVersion), RootSigDecodeError> {
+        if bytes.starts_with(b"DXBC") {
+            let part = dxbc::find_part(bytes, b"RTS0").ok_or(RootSigDecodeError::MissingPart)?;
+            RootSigBuilder::from_rts0(part)
+        } else {
+            RootSigBuilder::from_rts0(bytes)
+        }
+    }
+
+    /// decode the content of an `RTS0` part
+    pub fn from_rts0(bytes: &[u8]) -> Result<(RootSigBuilder, RootSigVersion), RootSigDecodeError> {
+        let reader = Reader(bytes);
+        let raw_version = reader.u32(0)?;
+        let version = RootSigVersion::try_from(raw_version)
+            .map_err(|_| RootSigDecodeError::UnsupportedVersion(raw_version))?;
+        let v1_1 = version == RootSigVersion::V1_1;
+        let num_params = reader.u32(4)? as usize;
+        let params_offset = reader.u32(8)? as usize;
+        let num_samplers = reader.u32(12)? as usize;
+        let samplers_offset = reader.u32(16)? as usize;
+        let raw_flags = reader.u32(20)?;
+        let flags = RootSigFlags::from_bits(raw_flags)
+            .ok_or(RootSigDecodeError::InvalidFlags(raw_flags))?;
+
+        let mut builder = RootSigBuilder::new();
+        builder.flags = flags;
+        for i in 0..num_params {
+            let offset = reader.offset(params_offset, i, PARAM_SIZE)?;
+            let raw_type = reader.u32(offset)?;
+            let visibility = ShaderVisibility::try_from(reader.u32(offset + 4)?)?;
+            let payload = reader.u32(offset + 8)? as usize;
+            let param_type = match raw_type {
+                PARAM_TYPE_TABLE => {
+                    let num_ranges = reader.u32(payload)? as usize;
+                    let ranges_offset = reader.u32(payload + 4)? as usize;
+                    let range_size = if v1_1 { 24 } else { 20 };
+                    let mut descriptor_ranges = SmallVec::new();
+                    for j in 0..num_ranges {
+                        let offset = reader.offset(ranges_offset, j, range_size)?;
+                        let flags = if v1_1 {
+                            let raw_flags = reader.u32(offset + 16)?;
+                            DescriptorRangeFlags::from_bits(raw_flags)
+                                .ok_or(RootSigDecodeError::InvalidDescriptorFlags(raw_flags))?
+                        } else {
+                            DescriptorRangeFlags::NONE
+                        };
+                        descriptor_ranges.push(DescriptorRange{
+                            range_type: DescriptorRangeType::try_from(reader.u32(offset)?)?,
+                            num_descriptors: reader.u32(offset + 4)?,
+                            base_shader_register: reader.u32(offset + 8)?,
+                            register_space: reader.u32(offset + 12)?,
+                            offset_from_table_start: reader.u32(offset + range_size - 4)?,
+                            flags,
+                        });
+                    }
+                    RootParamType::DescriptorTable{descriptor_ranges}
+                },
+                PARAM_TYPE_CONSTANTS => RootParamType::Constant{
+                    shader_register: reader.u32(payload)?,
+                    register_space: reader.u32(payload + 4)?,
+                    num_32bit_values: reader.u32(payload + 8)?,
+                },
+                PARAM_TYPE_CBV | PARAM_TYPE_SRV | PARAM_TYPE_UAV => {
+                    let shader_register = reader.u32(payload)?;
+                    let register_space = reader.u32(payload + 4)?;
+                    let flags = if v1_1 {
+                        let raw_flags = reader.u32(payload + 8)?;
+                        RootDescriptorFlags::from_bits(raw_flags)
+                            .ok_or(RootSigDecodeError::InvalidDescriptorFlags(raw_flags))?
+                    } else {
+                        RootDescriptorFlags::NONE
+                    };
+                    match raw_type {
+                        PARAM_TYPE_CBV => RootParamType::Cbv{shader_register, register_space, flags},
+                        PARAM_TYPE_SRV => RootParamType::Srv{shader_register, register_space, flags},
+                        _ => RootParamType::Uav{shader_register, register_space, flags},
+                    }
+                },
+                _ => return Err(RootSigDecodeError::InvalidParamType(raw_type)),
+            };
+            builder.root_params.push(RootParam{visibility, param_type});
+        }
+
+        for i in 0..num_samplers {
+            let offset = reader.offset(samplers_offset, i, SAMPLER_SIZE)?;
+            builder.static_samplers.push(StaticSamplerDesc{
+                filter: Filter::try_from(reader.u32(offset)?)?,
+                address_u: TextureAddressMode::try_from(reader.u32(offset + 4)?)?,
+                address_v: TextureAddressMode::try_from(reader.u32(offset + 8)?)?,
+                address_w: TextureAddressMode::try_from(reader.u32(offset + 12)?)?,
+                mip_bias: f32::from_bits(reader.u32(offset + 16)?),
+                max_anisotropy: reader.u32(offset + 20)?,
+                comparison_func: ComparisonFunc::try_from(reader.u32(offset + 24)?)?,
+                border_color: BorderColor::try_from(reader.u32(offset + 28)?)?,
+                min_lod: f32::from_bits(reader.u32(offset + 32)?),
+                max_lod: f32::from_bits(reader.u32(offset + 36)?),
+                shader_register: reader.u32(offset + 40)?,
+                register_space: reader.u32(offset + 44)?,
+                visibility: ShaderVisibility::try_from(reader.u32(offset + 48)?)?,
+            });
+        }
+
+        Ok((builder, version))
+    }
+}
+
+impl<'a> dxbc::DxbcContainer<'a> {
+    /// decode the root signature embedded in the shader, from `RTS0`
+    #[inline]
+    pub fn root_signature(&self) -> Result<(RootSigBuilder, RootSigVersion), RootSigDecodeError> {
+        let part = self.part(b"RTS0").ok_or(RootSigDecodeError::MissingPart)?;
+        RootSigBuilder::from_rts0(part)
+    }
+}
+
+impl dxbc::DxbcBuilder {
+    /// embed `rootsig` as the `RTS0` part, replacing any existing one
+    #[inline]
+    pub fn set_root_signature(&mut self, rootsig: &RootSigBuilder, version: RootSigVersion) -> &mut Self {
+        self.set_part(*b"RTS0", rootsig.to_rts0(version))
+    }
+}
+
+#[cfg(windows)]
+impl RootSigDescBlob {
+    /// copy `bytes`, e.g. produced by `RootSigBuilder::to_bytes`, into a new blob
+    pub fn from_bytes(bytes: &[u8]) -> Result<RootSigDescBlob, WinError> {
+        unsafe {
+            let mut ret = ::std::mem::uninitialized();
+            let hr = ::d3dcompiler::D3DCreateBlob(bytes.len() as _, &mut ret);
+            WinError::from_hresult_or_ok(hr, || {
+                let ptr = ComPtr::new(ret);
+                ::std::ptr::copy_nonoverlapping(
+                    bytes.as_ptr(), (*ret).GetBufferPointer() as *mut u8, bytes.len()
+                );
+                RootSigDescBlob{ptr}
+            })
+        }
+    }
+
+    /// decode the serialized description
+    #[inline]
+    pub fn to_builder(&self) -> Result<(RootSigBuilder, RootSigVersion), RootSigDecodeError> {
+        RootSigBuilder::from_bytes(self.as_bytes())
+    }
+}
+
+/// bounds checked little endian reads
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn u32(&self, offset: usize) -> Result<u32, RootSigDecodeError> {
+        read_u32(self.0, offset).ok_or(RootSigDecodeError::Truncated)
+    }
+
+    /// offset of the `index`th element of an array at `base`
+    #[inline]
+    fn offset(&self, base: usize, index: usize, size: usize) -> Result<usize, RootSigDecodeError> {
+        index.checked_mul(size)
+            .and_then(|offset| offset.checked_add(base))
+            .filter(|&offset| offset.checked_add(size).is_some_and(|end| end <= self.0.len()))
+            .ok_or(RootSigDecodeError::Truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // root signatures compiled by dxc, from `SDL_shaders_d3d12.c` of SDL 2,
+    // zlib license
+    const SDL_COLOR: &[u8] = &[
+        0x44, 0x58, 0x42, 0x43, 0x16, 0xb1, 0xe2, 0x29, 0x64, 0xb1, 0xb2, 0xe8,
+        0x3c, 0xf7, 0xf6, 0x21, 0xad, 0x40, 0xa9, 0x50, 0x01, 0x00, 0x00, 0x00,
+        0x5c, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x24, 0x00, 0x00, 0x00,
+        0x52, 0x54, 0x53, 0x30, 0x30, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x30, 0x00, 0x00, 0x00, 0x1d, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00,
+    ];
+    const SDL_TEXTURE: &[u8] = &[
+        0x44, 0x58, 0x42, 0x43, 0x7a, 0x3c, 0xe5, 0xd1, 0x3c, 0x38, 0x44, 0x34,
+        0xda, 0xbc, 0x9b, 0xfc, 0xb3, 0xe7, 0x95, 0xaa, 0x01, 0x00, 0x00, 0x00,
+        0xb4, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x24, 0x00, 0x00, 0x00,
+        0x52, 0x54, 0x53, 0x30, 0x88, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+        0x03, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x88, 0x00, 0x00, 0x00, 0x1d, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x05, 0x00, 0x00, 0x00, 0x48, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x05, 0x00, 0x00, 0x00, 0x68, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x50, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xff, 0xff, 0xff, 0xff, 0x01, 0x00, 0x00, 0x00, 0x70, 0x00, 0x00, 0x00,
+        0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff,
+    ];
+    const SDL_YUV: &[u8] = &[
+        0x44, 0x58, 0x42, 0x43, 0x65, 0xa2, 0x64, 0x32, 0x6e, 0x35, 0x8d, 0x76,
+        0xf7, 0x54, 0x3c, 0x62, 0x2e, 0x8e, 0xa4, 0xbc, 0x01, 0x00, 0x00, 0x00,
+        0x0c, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x24, 0x00, 0x00, 0x00,
+        0x52, 0x54, 0x53, 0x30, 0xe0, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+        0x05, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xe0, 0x00, 0x00, 0x00, 0x1d, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x54, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x05, 0x00, 0x00, 0x00, 0x60, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x05, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x05, 0x00, 0x00, 0x00, 0xa0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x05, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x68, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xff, 0xff, 0xff, 0xff, 0x01, 0x00, 0x00, 0x00, 0x88, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff,
+        0x01, 0x00, 0x00, 0x00, 0xa8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x01, 0x00, 0x00, 0x00,
+        0xc8, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xff, 0xff, 0xff, 0xff,
+    ];
+
+    fn texture_rootsig() -> RootSigBuilder {
+        let mut builder = RootSigBuilder::new();
+        builder.flags = RootSigFlags::ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT
+            | RootSigFlags::DENY_HULL_SHADER_ROOT_ACCESS
+            | RootSigFlags::DENY_DOMAIN_SHADER_ROOT_ACCESS
+            | RootSigFlags::DENY_GEOMETRY_SHADER_ROOT_ACCESS;
+        builder.root_params.push(RootParam{
+            visibility: ShaderVisibility::ALL,
+            param_type: RootParamType::Constant{
+                shader_register: 0, register_space: 0, num_32bit_values: 32,
+            },
+        });
+        for &(range_type, base_shader_register) in &[
+            (DescriptorRangeType::SRV, 0), (DescriptorRangeType::SAMPLER, 0)
+        ] {
+            let mut descriptor_ranges = SmallVec::new();
+            descriptor_ranges.push(DescriptorRange{
+                range_type, num_descriptors: 1, base_shader_register,
+                register_space: 0, offset_from_table_start: 0xffffffff,
+                flags: DescriptorRangeFlags::NONE,
+            });
+            builder.root_params.push(RootParam{
+                visibility: ShaderVisibility::PIXEL,
+                param_type: RootParamType::DescriptorTable{descriptor_ranges},
+            });
+        }
+        builder
+    }
+
+    #[test]
+    fn encode_v1_1() {
+        assert_eq!(texture_rootsig().to_bytes(RootSigVersion::V1_1), SDL_TEXTURE);
+    }
+
+    #[test]
+    fn decode_v1_1() {
+        let (builder, version) = RootSigBuilder::from_bytes(SDL_TEXTURE).unwrap();
+        assert_eq!(version, RootSigVersion::V1_1);
+        assert_eq!(builder.flags, texture_rootsig().flags);
+        assert_eq!(builder.root_params.len(), 3);
+        assert!(builder.static_samplers.is_empty());
+        match builder.root_params[0].param_type {
+            RootParamType::Constant{shader_register: 0, register_space: 0, num_32bit_values: 32} => {},
+            ref other => panic!("unexpected parameter {:?}", other),
+        }
+        match builder.root_params[2].param_type {
+            RootParamType::DescriptorTable{ref descriptor_ranges} => {
+                assert_eq!(descriptor_ranges.len(), 1);
+                assert_eq!(descriptor_ranges[0].range_type, DescriptorRangeType::SAMPLER);
+                assert_eq!(descriptor_ranges[0].offset_from_table_start, 0xffffffff);
+            },
+            ref other => panic!("unexpected parameter {:?}", other),
+        }
+        assert_eq!(builder.root_params[2].visibility, ShaderVisibility::PIXEL);
+    }
+
+    #[test]
+    fn round_trip_v1_1() {
+        for &blob in &[SDL_COLOR, SDL_TEXTURE, SDL_YUV] {
+            let (builder, version) = RootSigBuilder::from_bytes(blob).unwrap();
+            assert_eq!(builder.to_bytes(version), blob);
+        }
+    }
+
+    fn sampled_rootsig() -> RootSigBuilder {
+        let mut builder = RootSigBuilder::new();
+        builder.flags = RootSigFlags::ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT;
+        builder.root_params.push(RootParam{
+            visibility: ShaderVisibility::VERTEX,
+            param_type: RootParamType::Cbv{
+                shader_register: 0, register_space: 0,
+                flags: RootDescriptorFlags::DATA_STATIC,
+            },
+        });
+        let mut descriptor_ranges = SmallVec::new();
+        descriptor_ranges.push(DescriptorRange{
+            range_type: DescriptorRangeType::SRV, num_descriptors: 2,
+            base_shader_register: 0, register_space: 0, offset_from_table_start: 0,
+            flags: DescriptorRangeFlags::DESCRIPTORS_VOLATILE,
+        });
+        builder.root_params.push(RootParam{
+            visibility: ShaderVisibility::PIXEL,
+            param_type: RootParamType::DescriptorTable{descriptor_ranges},
+        });
+        let mut sampler = StaticSamplerDesc::new(Filter::MIN_MAG_MIP_LINEAR, 0, 0);
+        sampler.address_u = TextureAddressMode::CLAMP;
+        sampler.address_v = TextureAddressMode::CLAMP;
+        sampler.address_w = TextureAddressMode::CLAMP;
+        sampler.max_lod = f32::MAX;
+        sampler.visibility = ShaderVisibility::PIXEL;
+        builder.static_samplers.push(sampler);
+        builder
+    }
+
+    /// `sampled_rootsig` as version 1.0, laid out by hand after the runtime
+    /// serializer: header, parameters, payloads, then static samplers
+    const SAMPLED_V1_0: &[u32] = &[
+        // header: version, parameters, samplers, flags
+        1, 2, 0x18, 1, 0x54, 0x1,
+        // parameters: type, visibility, payload
+        2, 1, 0x30,
+        0, 5, 0x38,
+        // root cbv without flags
+        0, 0,
+        // descriptor table, then its range without flags
+        1, 0x40,
+        0, 2, 0, 0, 0,
+        // static sampler
+        0x15, 3, 3, 3, 0, 1, 8, 0, 0, 0x7f7fffff, 0, 0, 5,
+    ];
+
+    #[test]
+    fn encode_v1_0() {
+        let mut expected = Vec::new();
+        for &word in SAMPLED_V1_0 { push_u32(&mut expected, word); }
+        assert_eq!(sampled_rootsig().to_rts0(RootSigVersion::V1_0), expected);
+    }
+
+    #[test]
+    fn decode_v1_0_drops_flags() {
+        let bytes = sampled_rootsig().to_bytes(RootSigVersion::V1_0);
+        let (builder, version) = RootSigBuilder::from_bytes(&bytes).unwrap();
+        assert_eq!(version, RootSigVersion::V1_0);
+        match builder.root_params[0].param_type {
+            RootParamType::Cbv{flags, ..} => assert_eq!(flags, RootDescriptorFlags::NONE),
+            ref other => panic!("unexpected parameter {:?}", other),
+        }
+        assert_eq!(builder.static_samplers.len(), 1);
+        let sampler = &builder.static_samplers[0];
+        assert_eq!(sampler.visibility, ShaderVisibility::PIXEL);
+        assert_eq!(sampler.address_w, TextureAddressMode::CLAMP);
+        assert_eq!(sampler.max_lod, f32::MAX);
+        assert_eq!(builder.to_bytes(RootSigVersion::V1_0), bytes);
+    }
+
+    #[test]
+    fn static_sampler_visibility_v1_1() {
+        let bytes = sampled_rootsig().to_rts0(RootSigVersion::V1_1);
+        let (builder, _) = RootSigBuilder::from_rts0(&bytes).unwrap();
+        assert_eq!(builder.static_samplers[0].visibility, ShaderVisibility::PIXEL);
+        assert_eq!(builder.to_rts0(RootSigVersion::V1_1), bytes);
+    }
+
+    #[test]
+    fn checksum() {
+        // the hashed data of these ends 8, 32 and 56 bytes into the last
+        // block, the last needing an extra block for the length
+        for &blob in &[SDL_COLOR, SDL_TEXTURE, SDL_YUV] {
+            assert_eq!(&dxbc::checksum(&blob[20..])[..], &blob[4..20]);
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(
+            RootSigBuilder::from_rts0(&SDL_COLOR[44..60]).err(),
+            Some(RootSigDecodeError::Truncated)
+        );
+        let mut bytes = SDL_COLOR[44..].to_vec();
+        bytes[0] = 3;
+        assert_eq!(
+            RootSigBuilder::from_rts0(&bytes).err(),
+            Some(RootSigDecodeError::UnsupportedVersion(3))
+        );
+        assert_eq!(
+            RootSigBuilder::from_bytes(&dxbc::write_container(&[])).err(),
+            Some(RootSigDecodeError::MissingPart)
+        );
+    }
+}
@@ -0,0 +1,578 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! generation of root signatures from the bindings reflected from shaders.
+
+use super::*;
+use dxbc::{DxbcContainer, DxbcError, ResourceBinding, ShaderInputType, ShaderInputFlags};
+use dxbc::{PsvResourceBinding, PsvResourceType, ShaderStage};
+use std::cmp::{self, Reverse};
+
+/// a register range read by a shader, e.g. reflected from its container
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReflectedBinding {
+    /// name of the resource, empty if unknown
+    pub name: String,
+    pub range_type: DescriptorRangeType,
+    pub shader_register: u32,
+    pub register_space: u32,
+    /// number of registers, `0xffffffff` for unbounded arrays
+    pub num_descriptors: u32,
+}
+
+impl ReflectedBinding {
+    /// convert a binding of an `RDEF` part. unused bindings are `None`.
+    pub fn from_resource_binding(binding: &ResourceBinding) -> Option<ReflectedBinding> {
+        if binding.flags.contains(ShaderInputFlags::UNUSED) { return None; }
+        let range_type = match binding.input_type {
+            ShaderInputType::CBUFFER => DescriptorRangeType::CBV,
+            ShaderInputType::SAMPLER => DescriptorRangeType::SAMPLER,
+            ShaderInputType::TBUFFER | ShaderInputType::TEXTURE |
+            ShaderInputType::STRUCTURED | ShaderInputType::BYTEADDRESS |
+            ShaderInputType::RTACCELERATIONSTRUCTURE => DescriptorRangeType::SRV,
+            _ => DescriptorRangeType::UAV,
+        };
+        Some(ReflectedBinding{
+            name: binding.name.clone(),
+            range_type,
+            shader_register: binding.bind_point,
+            register_space: binding.register_space,
+            num_descriptors: if binding.bind_count == 0 { UNBOUNDED } else { binding.bind_count },
+        })
+    }
+
+    /// convert a binding of a `PSV0` part, which has no names.
+    /// invalid bindings are `None`.
+    pub fn from_psv(binding: &PsvResourceBinding) -> Option<ReflectedBinding> {
+        let range_type = match binding.resource_type {
+            PsvResourceType::INVALID => return None,
+            PsvResourceType::SAMPLER => DescriptorRangeType::SAMPLER,
+            PsvResourceType::CBV => DescriptorRangeType::CBV,
+            PsvResourceType::SRV_TYPED | PsvResourceType::SRV_RAW |
+            PsvResourceType::SRV_STRUCTURED => DescriptorRangeType::SRV,
+            _ => DescriptorRangeType::UAV,
+        };
+        let num_descriptors = if binding.upper_bound == UNBOUNDED {
+            UNBOUNDED
+        } else {
+            binding.upper_bound.saturating_sub(binding.lower_bound).saturating_add(1)
+        };
+        Some(ReflectedBinding{
+            name: String::new(),
+            range_type,
+            shader_register: binding.lower_bound,
+            register_space: binding.register_space,
+            num_descriptors,
+        })
+    }
+}
+
+/// how often the resources of a binding change, from least to most often
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UpdateFrequency {
+    PerFrame,
+    PerPass,
+    PerMaterial,
+    PerDraw,
+}
+
+/// how `RootSigGenerator` lays out root parameters
+#[derive(Clone, Debug)]
+pub struct RootSigPolicy {
+    /// frequencies of bindings by name, compared case sensitively.
+    /// takes precedence over `space_frequencies`.
+    pub name_frequencies: Vec<(String, UpdateFrequency)>,
+    /// frequencies of bindings by register space
+    pub space_frequencies: SmallVec<[(u32, UpdateFrequency); 4]>,
+    /// frequency of the remaining bindings
+    pub default_frequency: UpdateFrequency,
+    /// single constant buffers updated at least this often become root
+    /// CBVs instead of descriptor table ranges
+    pub root_cbv_frequency: Option<UpdateFrequency>,
+    /// give bindings of different visibilities separate tables, instead
+    /// of sharing a table visible to all stages
+    pub split_by_visibility: bool,
+    /// static samplers, replacing the sampler bindings of their registers
+    pub static_samplers: SmallVec<[StaticSamplerDesc; 8]>,
+}
+
+impl Default for RootSigPolicy {
+    #[inline]
+    fn default() -> Self {
+        RootSigPolicy{
+            name_frequencies: Vec::new(),
+            space_frequencies: SmallVec::new(),
+            default_frequency: UpdateFrequency::PerDraw,
+            root_cbv_frequency: None,
+            split_by_visibility: true,
+            static_samplers: SmallVec::new(),
+        }
+    }
+}
+
+impl RootSigPolicy {
+    /// the frequency of `binding`
+    pub fn frequency_of(&self, binding: &ReflectedBinding) -> UpdateFrequency {
+        self.name_frequencies.iter()
+            .find(|&(name, _)| !binding.name.is_empty() && *name == binding.name)
+            .map(|&(_, frequency)| frequency)
+            .or_else(|| self.space_frequencies.iter()
+                .find(|&&(space, _)| space == binding.register_space)
+                .map(|&(_, frequency)| frequency))
+            .unwrap_or(self.default_frequency)
+    }
+}
+
+/// generates a root signature covering the bindings of a set of shaders.
+///
+/// bindings are grouped into descriptor tables by update frequency, the
+/// most frequently updated first, with the tightest visibility. stages
+/// reading nothing, or not in the set, are denied root access. the output
+/// depends only on the bindings and the policy, not on the order shaders
+/// are added in, so its `to_hlsl` is fit for snapshots.
+#[derive(Clone, Debug, Default)]
+pub struct RootSigGenerator {
+    pub stages: SmallVec<[(ShaderStage, Vec<ReflectedBinding>); 5]>,
+    pub policy: RootSigPolicy,
+}
+
+impl RootSigGenerator {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// add the bindings read by a shader of `stage`
+    pub fn add_stage<I>(&mut self, stage: ShaderStage, bindings: I) -> &mut Self
+        where I: IntoIterator<Item = ReflectedBinding>
+    {
+        self.stages.push((stage, bindings.into_iter().collect()));
+        self
+    }
+
+    /// add the bindings of a compiled shader, from its `RDEF` part, or
+    /// its `PSV0` part for DXIL shaders
+    pub fn add_container(&mut self, container: &DxbcContainer) -> Result<&mut Self, DxbcError> {
+        let stage = container.shader_version()?.stage;
+        let bindings: Vec<_> = if container.part(b"RDEF").is_none() && container.part(b"PSV0").is_some() {
+            container.pipeline_state_validation()?.resources.iter()
+                .filter_map(ReflectedBinding::from_psv)
+                .collect()
+        } else {
+            container.resource_defs()?.bindings.iter()
+                .filter_map(ReflectedBinding::from_resource_binding)
+                .collect()
+        };
+        Ok(self.add_stage(stage, bindings))
+    }
+
+    /// generate the root signature
+    pub fn generate(&self) -> RootSigBuilder {
+        let policy = &self.policy;
+
+        // stages are tracked as bit masks indexed by `ShaderVisibility`,
+        // with bit 0 for stages needing `ALL`, i.e. compute and libraries
+        let mut added_stages = 0u32;
+        let mut accessing_stages = 0u32;
+        let mut entries = Vec::new();
+        for &(stage, ref bindings) in self.stages.iter() {
+            let stage_bit = 1 << visibility_of(stage) as u32;
+            added_stages |= stage_bit;
+            for binding in bindings {
+                accessing_stages |= stage_bit;
+                if binding.range_type == DescriptorRangeType::SAMPLER && binding.num_descriptors == 1 &&
+                    policy.static_samplers.iter().any(|sampler| {
+                        sampler.shader_register == binding.shader_register &&
+                        sampler.register_space == binding.register_space
+                    }) {
+                    continue;
+                }
+                let start = binding.shader_register as u64;
+                entries.push(Entry{
+                    range_type: binding.range_type,
+                    register_space: binding.register_space,
+                    start,
+                    end: if binding.num_descriptors == UNBOUNDED {
+                        REGISTER_END
+                    } else {
+                        cmp::min(start + binding.num_descriptors as u64, REGISTER_END)
+                    },
+                    stages: stage_bit,
+                    frequency: policy.frequency_of(binding),
+                });
+            }
+        }
+
+        // merge the same or overlapping registers read by several stages
+        entries.sort_by_key(|entry| (entry.range_type, entry.register_space, entry.start, entry.end));
+        let mut merged: Vec<Entry> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            if let Some(last) = merged.last_mut() {
+                if last.range_type == entry.range_type &&
+                    last.register_space == entry.register_space && entry.start < last.end {
+                    last.end = cmp::max(last.end, entry.end);
+                    last.stages |= entry.stages;
+                    last.frequency = cmp::max(last.frequency, entry.frequency);
+                    continue;
+                }
+            }
+            merged.push(entry);
+        }
+
+        let mut params: Vec<(ParamKey, RootParam)> = Vec::new();
+        let mut tables: Vec<(ParamKey, SmallVec<[&Entry; 4]>)> = Vec::new();
+        for (i, entry) in merged.iter().enumerate() {
+            let visibility = entry.visibility();
+            let root_cbv = entry.range_type == DescriptorRangeType::CBV && entry.end - entry.start == 1 &&
+                policy.root_cbv_frequency.is_some_and(|frequency| entry.frequency >= frequency);
+            if root_cbv {
+                params.push((
+                    ParamKey{frequency: Reverse(entry.frequency), kind: 0, visibility, unbounded: None},
+                    RootParam{visibility, param_type: RootParamType::Cbv{
+                        shader_register: entry.start as u32,
+                        register_space: entry.register_space,
+                        flags: RootDescriptorFlags::NONE,
+                    }},
+                ));
+                continue;
+            }
+            let key = ParamKey{
+                frequency: Reverse(entry.frequency),
+                kind: if entry.range_type == DescriptorRangeType::SAMPLER { 2 } else { 1 },
+                // shared tables get their visibility once all entries are in
+                visibility: if policy.split_by_visibility { visibility } else { ShaderVisibility::ALL },
+                // an unbounded range must end its table, give each its own
+                unbounded: if entry.end == REGISTER_END { Some(i) } else { None },
+            };
+            match tables.iter_mut().find(|&&mut (ref table_key, _)| *table_key == key) {
+                Some(&mut (_, ref mut table)) => table.push(entry),
+                None => {
+                    let mut table = SmallVec::new();
+                    table.push(entry);
+                    tables.push((key, table));
+                },
+            }
+        }
+
+        for (mut key, table) in tables {
+            if !policy.split_by_visibility {
+                let first = table[0].visibility();
+                if table.iter().all(|entry| entry.visibility() == first) {
+                    key.visibility = first;
+                }
+            }
+            let mut offset = 0u32;
+            let descriptor_ranges = table.iter().map(|entry| {
+                let num_descriptors = if entry.end == REGISTER_END {
+                    UNBOUNDED
+                } else {
+                    (entry.end - entry.start) as u32
+                };
+                let range = DescriptorRange{
+                    range_type: entry.range_type,
+                    num_descriptors,
+                    base_shader_register: entry.start as u32,
+                    register_space: entry.register_space,
+                    offset_from_table_start: offset,
+                    flags: DescriptorRangeFlags::NONE,
+                };
+                offset = offset.saturating_add(num_descriptors);
+                range
+            }).collect();
+            params.push((key, RootParam{
+                visibility: key.visibility,
+                param_type: RootParamType::DescriptorTable{descriptor_ranges},
+            }));
+        }
+        // stable, so parameters of equal keys keep their register order
+        params.sort_by_key(|&(key, _)| key);
+
+        let mut builder = RootSigBuilder::new();
+        builder.root_params = params.into_iter().map(|(_, param)| param).collect();
+        builder.static_samplers = policy.static_samplers.clone();
+        builder.flags = if added_stages & GRAPHICS_STAGES == 0 {
+            RootSigFlags::NONE
+        } else {
+            let mut flags = if added_stages & (1 << ShaderVisibility::VERTEX as u32) != 0 {
+                RootSigFlags::ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT
+            } else {
+                RootSigFlags::NONE
+            };
+            for &(visibility, deny) in DENY_FLAGS {
+                if accessing_stages & (1 << visibility as u32) == 0 {
+                    flags |= deny;
+                }
+            }
+            flags
+        };
+        builder
+    }
+}
+
+/// a merged register range
+struct Entry {
+    range_type: DescriptorRangeType,
+    register_space: u32,
+    start: u64,
+    end: u64,
+    stages: u32,
+    frequency: UpdateFrequency,
+}
+
+impl Entry {
+    /// the tightest visibility covering all stages reading the range
+    #[inline]
+    fn visibility(&self) -> ShaderVisibility {
+        use std::convert::TryFrom;
+        if self.stages.count_ones() == 1 {
+            ShaderVisibility::try_from(self.stages.trailing_zeros()).unwrap_or(ShaderVisibility::ALL)
+        } else {
+            ShaderVisibility::ALL
+        }
+    }
+}
+
+/// orders root parameters: the most frequently updated first, then root
+/// CBVs, resource tables and sampler tables, then by visibility
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct ParamKey {
+    frequency: Reverse<UpdateFrequency>,
+    kind: u32,
+    visibility: ShaderVisibility,
+    unbounded: Option<usize>,
+}
+
+/// the visibility of a shader of `stage`
+#[inline]
+fn visibility_of(stage: ShaderStage) -> ShaderVisibility {
+    match stage {
+        ShaderStage::VERTEX => ShaderVisibility::VERTEX,
+        ShaderStage::HULL => ShaderVisibility::HULL,
+        ShaderStage::DOMAIN => ShaderVisibility::DOMAIN,
+        ShaderStage::GEOMETRY => ShaderVisibility::GEOMETRY,
+        ShaderStage::PIXEL => ShaderVisibility::PIXEL,
+        ShaderStage::AMPLIFICATION => ShaderVisibility::AMPLIFICATION,
+        ShaderStage::MESH => ShaderVisibility::MESH,
+        ShaderStage::COMPUTE | ShaderStage::LIBRARY => ShaderVisibility::ALL,
+    }
+}
+
+/// the flag denying each graphics stage root access
+const DENY_FLAGS: &[(ShaderVisibility, RootSigFlags)] = &[
+    (ShaderVisibility::VERTEX, RootSigFlags::DENY_VERTEX_SHADER_ROOT_ACCESS),
+    (ShaderVisibility::HULL, RootSigFlags::DENY_HULL_SHADER_ROOT_ACCESS),
+    (ShaderVisibility::DOMAIN, RootSigFlags::DENY_DOMAIN_SHADER_ROOT_ACCESS),
+    (ShaderVisibility::GEOMETRY, RootSigFlags::DENY_GEOMETRY_SHADER_ROOT_ACCESS),
+    (ShaderVisibility::PIXEL, RootSigFlags::DENY_PIXEL_SHADER_ROOT_ACCESS),
+    (ShaderVisibility::AMPLIFICATION, RootSigFlags::DENY_AMPLIFICATION_SHADER_ROOT_ACCESS),
+    (ShaderVisibility::MESH, RootSigFlags::DENY_MESH_SHADER_ROOT_ACCESS),
+];
+
+/// stage bits of the graphics stages
+const GRAPHICS_STAGES: u32 = !1;
+
+/// `num_descriptors` of an unbounded descriptor range
+const UNBOUNDED: u32 = 0xffff_ffff;
+/// one past the last register
+const REGISTER_END: u64 = 1 << 32;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(
+        name: &str, range_type: DescriptorRangeType, shader_register: u32,
+        register_space: u32, num_descriptors: u32
+    ) -> ReflectedBinding {
+        ReflectedBinding{name: name.into(), range_type, shader_register, register_space, num_descriptors}
+    }
+
+    /// the generated root signature, checking that it's valid
+    fn generate(generator: &RootSigGenerator) -> String {
+        let builder = generator.generate();
+        assert_eq!(builder.validate(), Ok(()));
+        builder.to_hlsl()
+    }
+
+    #[test]
+    fn merges_stages() {
+        let vs = vec![
+            binding("Camera", DescriptorRangeType::CBV, 0, 0, 1),
+            binding("Heights", DescriptorRangeType::SRV, 0, 0, 1),
+            binding("Bones", DescriptorRangeType::SRV, 4, 0, 2),
+        ];
+        let ps = vec![
+            binding("Camera", DescriptorRangeType::CBV, 0, 0, 1),
+            binding("Textures", DescriptorRangeType::SRV, 0, 0, 2),
+            binding("Lights", DescriptorRangeType::SRV, 5, 0, 2),
+            binding("Linear", DescriptorRangeType::SAMPLER, 0, 0, 1),
+        ];
+        let expected = "\
+RootFlags(ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | DENY_HULL_SHADER_ROOT_ACCESS | \
+DENY_DOMAIN_SHADER_ROOT_ACCESS | DENY_GEOMETRY_SHADER_ROOT_ACCESS | \
+DENY_AMPLIFICATION_SHADER_ROOT_ACCESS | DENY_MESH_SHADER_ROOT_ACCESS), \
+DescriptorTable(SRV(t0, numDescriptors = 2, offset = 0), SRV(t4, numDescriptors = 3, offset = 2), \
+CBV(b0, offset = 5)), \
+DescriptorTable(Sampler(s0, offset = 0), visibility = SHADER_VISIBILITY_PIXEL)";
+
+        let mut generator = RootSigGenerator::new();
+        generator.add_stage(ShaderStage::VERTEX, vs.clone())
+            .add_stage(ShaderStage::PIXEL, ps.clone());
+        assert_eq!(generate(&generator), expected);
+
+        // the order stages are added in doesn't matter
+        let mut generator = RootSigGenerator::new();
+        generator.add_stage(ShaderStage::PIXEL, ps)
+            .add_stage(ShaderStage::VERTEX, vs);
+        assert_eq!(generate(&generator), expected);
+    }
+
+    #[test]
+    fn shared_tables() {
+        let mut generator = RootSigGenerator::new();
+        generator.policy.split_by_visibility = false;
+        generator.add_stage(ShaderStage::VERTEX, vec![
+            binding("Camera", DescriptorRangeType::CBV, 0, 0, 1),
+        ]).add_stage(ShaderStage::PIXEL, vec![
+            binding("Albedo", DescriptorRangeType::SRV, 0, 0, 1),
+            binding("Linear", DescriptorRangeType::SAMPLER, 0, 0, 1),
+        ]);
+        // a table read by a single stage keeps its visibility
+        assert_eq!(generate(&generator), "\
+RootFlags(ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | DENY_HULL_SHADER_ROOT_ACCESS | \
+DENY_DOMAIN_SHADER_ROOT_ACCESS | DENY_GEOMETRY_SHADER_ROOT_ACCESS | \
+DENY_AMPLIFICATION_SHADER_ROOT_ACCESS | DENY_MESH_SHADER_ROOT_ACCESS), \
+DescriptorTable(SRV(t0, offset = 0), CBV(b0, offset = 1)), \
+DescriptorTable(Sampler(s0, offset = 0), visibility = SHADER_VISIBILITY_PIXEL)");
+    }
+
+    #[test]
+    fn deny_flags() {
+        // stages reading nothing are denied too
+        let mut generator = RootSigGenerator::new();
+        generator.add_stage(ShaderStage::VERTEX, vec![
+            binding("Camera", DescriptorRangeType::CBV, 0, 0, 1),
+        ]).add_stage(ShaderStage::PIXEL, vec![]);
+        assert_eq!(generate(&generator), "\
+RootFlags(ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | DENY_HULL_SHADER_ROOT_ACCESS | \
+DENY_DOMAIN_SHADER_ROOT_ACCESS | DENY_GEOMETRY_SHADER_ROOT_ACCESS | \
+DENY_PIXEL_SHADER_ROOT_ACCESS | DENY_AMPLIFICATION_SHADER_ROOT_ACCESS | \
+DENY_MESH_SHADER_ROOT_ACCESS), \
+DescriptorTable(CBV(b0, offset = 0), visibility = SHADER_VISIBILITY_VERTEX)");
+
+        // without a vertex shader, there is no input assembler layout
+        let mut generator = RootSigGenerator::new();
+        generator.add_stage(ShaderStage::MESH, vec![
+            binding("Meshlets", DescriptorRangeType::SRV, 0, 0, 1),
+        ]).add_stage(ShaderStage::PIXEL, vec![
+            binding("Albedo", DescriptorRangeType::SRV, 1, 0, 1),
+        ]);
+        assert_eq!(generate(&generator), "\
+RootFlags(DENY_VERTEX_SHADER_ROOT_ACCESS | DENY_HULL_SHADER_ROOT_ACCESS | \
+DENY_DOMAIN_SHADER_ROOT_ACCESS | DENY_GEOMETRY_SHADER_ROOT_ACCESS | \
+DENY_AMPLIFICATION_SHADER_ROOT_ACCESS), \
+DescriptorTable(SRV(t1, offset = 0), visibility = SHADER_VISIBILITY_PIXEL), \
+DescriptorTable(SRV(t0, offset = 0), visibility = SHADER_VISIBILITY_MESH)");
+
+        // compute signatures have no flags
+        let mut generator = RootSigGenerator::new();
+        generator.add_stage(ShaderStage::COMPUTE, vec![
+            binding("Output", DescriptorRangeType::UAV, 0, 0, 1),
+        ]);
+        assert_eq!(generate(&generator), "DescriptorTable(UAV(u0, offset = 0))");
+    }
+
+    #[test]
+    fn root_cbvs() {
+        let mut generator = RootSigGenerator::new();
+        generator.policy.root_cbv_frequency = Some(UpdateFrequency::PerDraw);
+        generator.policy.name_frequencies.push(("Frame".into(), UpdateFrequency::PerFrame));
+        generator.policy.space_frequencies.push((1, UpdateFrequency::PerMaterial));
+        generator.add_stage(ShaderStage::VERTEX, vec![
+            binding("Frame", DescriptorRangeType::CBV, 0, 0, 1),
+            binding("Object", DescriptorRangeType::CBV, 1, 0, 1),
+            binding("Bones", DescriptorRangeType::CBV, 2, 0, 2),
+        ]).add_stage(ShaderStage::PIXEL, vec![
+            binding("Albedo", DescriptorRangeType::SRV, 0, 1, 1),
+            binding("Material", DescriptorRangeType::CBV, 0, 1, 1),
+        ]);
+        // only single constant buffers updated per draw become root CBVs,
+        // and parameters go from the most to the least frequently updated
+        assert_eq!(generate(&generator), "\
+RootFlags(ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | DENY_HULL_SHADER_ROOT_ACCESS | \
+DENY_DOMAIN_SHADER_ROOT_ACCESS | DENY_GEOMETRY_SHADER_ROOT_ACCESS | \
+DENY_AMPLIFICATION_SHADER_ROOT_ACCESS | DENY_MESH_SHADER_ROOT_ACCESS), \
+CBV(b1, visibility = SHADER_VISIBILITY_VERTEX), \
+DescriptorTable(CBV(b2, numDescriptors = 2, offset = 0), visibility = SHADER_VISIBILITY_VERTEX), \
+DescriptorTable(SRV(t0, space = 1, offset = 0), CBV(b0, space = 1, offset = 1), \
+visibility = SHADER_VISIBILITY_PIXEL), \
+DescriptorTable(CBV(b0, offset = 0), visibility = SHADER_VISIBILITY_VERTEX)");
+
+        // everything updated at least per material
+        generator.policy.root_cbv_frequency = Some(UpdateFrequency::PerMaterial);
+        assert_eq!(generate(&generator), "\
+RootFlags(ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | DENY_HULL_SHADER_ROOT_ACCESS | \
+DENY_DOMAIN_SHADER_ROOT_ACCESS | DENY_GEOMETRY_SHADER_ROOT_ACCESS | \
+DENY_AMPLIFICATION_SHADER_ROOT_ACCESS | DENY_MESH_SHADER_ROOT_ACCESS), \
+CBV(b1, visibility = SHADER_VISIBILITY_VERTEX), \
+DescriptorTable(CBV(b2, numDescriptors = 2, offset = 0), visibility = SHADER_VISIBILITY_VERTEX), \
+CBV(b0, space = 1, visibility = SHADER_VISIBILITY_PIXEL), \
+DescriptorTable(SRV(t0, space = 1, offset = 0), visibility = SHADER_VISIBILITY_PIXEL), \
+DescriptorTable(CBV(b0, offset = 0), visibility = SHADER_VISIBILITY_VERTEX)");
+    }
+
+    #[test]
+    fn unbounded_ranges() {
+        let mut generator = RootSigGenerator::new();
+        generator.add_stage(ShaderStage::PIXEL, vec![
+            binding("Albedo", DescriptorRangeType::SRV, 0, 0, 1),
+            binding("Textures", DescriptorRangeType::SRV, 8, 0, UNBOUNDED),
+            binding("Buffers", DescriptorRangeType::UAV, 0, 1, UNBOUNDED),
+            binding("Output", DescriptorRangeType::UAV, 0, 0, 1),
+        ]);
+        // each unbounded range ends a table of its own
+        assert_eq!(generate(&generator), "\
+RootFlags(DENY_VERTEX_SHADER_ROOT_ACCESS | DENY_HULL_SHADER_ROOT_ACCESS | \
+DENY_DOMAIN_SHADER_ROOT_ACCESS | DENY_GEOMETRY_SHADER_ROOT_ACCESS | \
+DENY_AMPLIFICATION_SHADER_ROOT_ACCESS | DENY_MESH_SHADER_ROOT_ACCESS), \
+DescriptorTable(SRV(t0, offset = 0), UAV(u0, offset = 1), visibility = SHADER_VISIBILITY_PIXEL), \
+DescriptorTable(SRV(t8, numDescriptors = unbounded, offset = 0), visibility = SHADER_VISIBILITY_PIXEL), \
+DescriptorTable(UAV(u0, numDescriptors = unbounded, space = 1, offset = 0), visibility = SHADER_VISIBILITY_PIXEL)");
+
+        // bounded ranges of other stages within an unbounded one merge into it
+        generator.add_stage(ShaderStage::VERTEX, vec![
+            binding("Heights", DescriptorRangeType::SRV, 9, 0, 2),
+        ]);
+        assert_eq!(generate(&generator), "\
+RootFlags(ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | DENY_HULL_SHADER_ROOT_ACCESS | \
+DENY_DOMAIN_SHADER_ROOT_ACCESS | DENY_GEOMETRY_SHADER_ROOT_ACCESS | \
+DENY_AMPLIFICATION_SHADER_ROOT_ACCESS | DENY_MESH_SHADER_ROOT_ACCESS), \
+DescriptorTable(SRV(t8, numDescriptors = unbounded, offset = 0)), \
+DescriptorTable(SRV(t0, offset = 0), UAV(u0, offset = 1), visibility = SHADER_VISIBILITY_PIXEL), \
+DescriptorTable(UAV(u0, numDescriptors = unbounded, space = 1, offset = 0), visibility = SHADER_VISIBILITY_PIXEL)");
+    }
+
+    #[test]
+    fn static_samplers() {
+        let mut generator = RootSigGenerator::new();
+        let samplers = RootSigBuilder::from_hlsl(
+            "StaticSampler(s0, filter = FILTER_MIN_MAG_MIP_POINT, visibility = SHADER_VISIBILITY_PIXEL)"
+        ).unwrap().static_samplers;
+        generator.policy.static_samplers = samplers;
+        generator.add_stage(ShaderStage::PIXEL, vec![
+            binding("Point", DescriptorRangeType::SAMPLER, 0, 0, 1),
+            binding("Shadow", DescriptorRangeType::SAMPLER, 1, 0, 1),
+        ]);
+        assert_eq!(generate(&generator), "\
+RootFlags(DENY_VERTEX_SHADER_ROOT_ACCESS | DENY_HULL_SHADER_ROOT_ACCESS | \
+DENY_DOMAIN_SHADER_ROOT_ACCESS | DENY_GEOMETRY_SHADER_ROOT_ACCESS | \
+DENY_AMPLIFICATION_SHADER_ROOT_ACCESS | DENY_MESH_SHADER_ROOT_ACCESS), \
+DescriptorTable(Sampler(s1, offset = 0), visibility = SHADER_VISIBILITY_PIXEL), \
+StaticSampler(s0, filter = FILTER_MIN_MAG_MIP_POINT, visibility = SHADER_VISIBILITY_PIXEL)");
+    }
+}
@@ -0,0 +1,389 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! describes what kind of resources are to be bound to the pipeline.
+
+#[cfg(windows)]
+use device::Device;
+use smallvec::SmallVec;
+#[cfg(windows)]
+use comptr::ComPtr;
+#[cfg(windows)]
+use winapi::{ID3D12RootSignature, ID3DBlob};
+#[cfg(windows)]
+use error::WinError;
+use super::sampler::StaticSamplerDesc;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use fnv::{FnvHasher, StableHash};
+
+mod text;
+mod binary;
+mod validate;
+mod generate;
+pub use self::text::*;
+pub use self::binary::*;
+pub use self::validate::*;
+pub use self::generate::*;
+
+/// a root signature
+#[derive(Clone, Debug)]
+pub struct RootSig {
+    #[cfg(windows)]
+    pub(crate) ptr: ComPtr<ID3D12RootSignature>,
+    /// the serialized description this root signature was created from
+    pub(crate) desc: Arc<[u8]>,
+    pub(crate) node_mask: u32,
+}
+
+impl RootSig {
+    /// get the serialized description this root signature was created from
+    #[inline]
+    pub fn desc_bytes(&self) -> &[u8] {
+        &self.desc
+    }
+
+    #[inline]
+    pub fn node_mask(&self) -> u32 {
+        self.node_mask
+    }
+}
+
+#[cfg(test)]
+impl RootSig {
+    /// a root signature without a runtime object, to test descriptions with
+    pub(crate) fn from_desc(desc: &[u8], node_mask: u32) -> RootSig {
+        RootSig{
+            #[cfg(windows)]
+            ptr: ComPtr::inert(),
+            desc: desc.into(),
+            node_mask,
+        }
+    }
+}
+
+// root signatures created from the same serialized description on the same
+// nodes are interchangeable, so they are compared by the description, which,
+// unlike the pointer, stays the same across runs.
+impl PartialEq for RootSig {
+    #[inline]
+    fn eq(&self, other: &RootSig) -> bool {
+        self.node_mask == other.node_mask && self.desc == other.desc
+    }
+}
+
+impl Eq for RootSig {}
+
+impl Hash for RootSig {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.desc.hash(state);
+        self.node_mask.hash(state);
+    }
+}
+
+impl StableHash for RootSig {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut FnvHasher) {
+        hasher.write_bytes(&self.desc);
+        hasher.write_le_u32(self.node_mask);
+    }
+}
+
+// root signatures are free-threaded, and immutable once created
+#[cfg(windows)]
+unsafe impl Send for RootSig {}
+#[cfg(windows)]
+unsafe impl Sync for RootSig {}
+
+/// a serialized root signature description blob
+#[cfg(windows)]
+#[derive(Clone, Debug)]
+pub struct RootSigDescBlob {
+    pub(crate) ptr: ComPtr<ID3DBlob>,
+}
+
+#[cfg(windows)]
+impl RootSigDescBlob {
+    /// get the serialized description
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            let blob = self.ptr.as_mut_ptr();
+            ::std::slice::from_raw_parts(
+                (*blob).GetBufferPointer() as *const u8,
+                (*blob).GetBufferSize() as usize
+            )
+        }
+    }
+}
+
+/// builder for a root signature
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RootSigBuilder {
+    pub root_params: SmallVec<[RootParam; 8]>,
+    pub static_samplers: SmallVec<[StaticSamplerDesc; 8]>,
+    pub flags: RootSigFlags,
+}
+
+impl RootSigBuilder {
+    /// construct a new builder
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// build a root signature with description in this builder using `device`,
+    /// serialized as version 1.0.
+    ///
+    /// this drops the 1.1 `DescriptorRangeFlags` and `RootDescriptorFlags`
+    /// set on the description. use `build_with_version` with
+    /// `RootSigVersion::V1_1` to honour them.
+    #[cfg(windows)]
+    pub fn build(&self, device: &mut Device, node_mask: u32) -> Result<RootSig, WinError> {
+        let blob = self.serialize()?;
+        device.create_root_sig(node_mask, &blob)
+    }
+
+    /// build a root signature serialized as `version`, or as the highest
+    /// version `device` supports if lower. see `serialize_versioned` for
+    /// what a downgrade to 1.0 drops.
+    ///
+    /// `DescriptorRangeFlags` and `RootDescriptorFlags` default to `NONE`,
+    /// which 1.1 reads as the strictest promise: descriptors are static,
+    /// i.e. written before their table is set and left unchanged until the
+    /// commands using it finish executing, and data is static while set at
+    /// execute, or volatile for uavs. 1.0 treats every descriptor as
+    /// volatile instead, so a description written for 1.0 must set
+    /// `DESCRIPTORS_VOLATILE` on ranges updated after being bound before it's
+    /// built as 1.1, or the driver may read stale descriptors.
+    #[cfg(windows)]
+    pub fn build_with_version(
+        &self, device: &mut Device, node_mask: u32, version: RootSigVersion
+    ) -> Result<RootSig, WinError> {
+        let version = ::std::cmp::min(version, device.get_root_sig_version());
+        let blob = self.serialize_versioned(version)?;
+        device.create_root_sig(node_mask, &blob)
+    }
+
+    /// serialize the description into a blob as version 1.0, dropping the
+    /// descriptor and data flags. see `serialize_versioned`.
+    #[cfg(windows)]
+    pub fn serialize(&self) -> Result<RootSigDescBlob, WinError> {
+        self.serialize_versioned(RootSigVersion::V1_0)
+    }
+
+    /// serialize the description into a blob as `version`, encoded by
+    /// `to_bytes`.
+    ///
+    /// 1.0 has no descriptor or data flags, which are dropped, so the
+    /// runtime falls back to the conservative 1.0 behavior: descriptors
+    /// are volatile, and data is static while set at execute, or volatile
+    /// for uavs.
+    #[cfg(windows)]
+    pub fn serialize_versioned(&self, version: RootSigVersion) -> Result<RootSigDescBlob, WinError> {
+        RootSigDescBlob::from_bytes(&self.to_bytes(version))
+    }
+}
+
+/// describes a root parameter
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RootParam {
+    /// shader visibility
+    pub visibility: ShaderVisibility,
+    pub param_type: RootParamType,
+}
+
+/// specifies a type of root parameter
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RootParamType {
+    /// a collection of descriptor ranges, appearing in sequence in a descriptor heap
+    DescriptorTable{
+        /// an array of descriptor ranges
+        descriptor_ranges: SmallVec<[DescriptorRange; 4]>,
+    },
+    /// cbv descriptor inlined in the signature
+    Cbv{
+        /// the shader register
+        shader_register: u32,
+        /// the register space
+        register_space: u32,
+        /// version 1.1 data flags
+        #[cfg_attr(feature = "serde", serde(default))]
+        flags: RootDescriptorFlags,
+    },
+    /// srv descriptor inlined in the signature
+    Srv{
+        /// the shader register
+        shader_register: u32,
+        /// the register space
+        register_space: u32,
+        /// version 1.1 data flags
+        #[cfg_attr(feature = "serde", serde(default))]
+        flags: RootDescriptorFlags,
+    },
+    /// uav descriptor inlined in the signature
+    Uav{
+        /// the shader register
+        shader_register: u32,
+        /// the register space
+        register_space: u32,
+        /// version 1.1 data flags
+        #[cfg_attr(feature = "serde", serde(default))]
+        flags: RootDescriptorFlags,
+    },
+    /// constants inlined in the signature that appear in shaders as one constant buffer
+    Constant{
+        /// shader register of this constant
+        shader_register: u32,
+        /// register space of this constant
+        register_space: u32,
+        /// number of 32bit values in this constant slot
+        num_32bit_values: u32,
+    },
+}
+
+/// descriptor range. unlike `D3D12_DESCRIPTOR_RANGE`, it carries the 1.1
+/// `flags`, so it isn't layout compatible with either runtime structure.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DescriptorRange {
+    pub range_type: DescriptorRangeType,
+    pub num_descriptors: u32,
+    pub base_shader_register: u32,
+    pub register_space: u32,
+    pub offset_from_table_start: u32,
+    /// version 1.1 descriptor and data flags
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub flags: DescriptorRangeFlags,
+}
+
+#[cfg(windows)]
+impl<'a> From<&'a DescriptorRange> for ::winapi::D3D12_DESCRIPTOR_RANGE {
+    #[inline]
+    fn from(range: &'a DescriptorRange) -> Self {
+        ::winapi::D3D12_DESCRIPTOR_RANGE{
+            RangeType: ::winapi::D3D12_DESCRIPTOR_RANGE_TYPE(range.range_type as u32),
+            NumDescriptors: range.num_descriptors,
+            BaseShaderRegister: range.base_shader_register,
+            RegisterSpace: range.register_space,
+            OffsetInDescriptorsFromTableStart: range.offset_from_table_start,
+        }
+    }
+}
+
+bitflags!{
+    /// version 1.1 flags of a descriptor range. without `DESCRIPTORS_VOLATILE`,
+    /// descriptors must be set before the table is bound and not change
+    /// until the commands using it finish executing. the default, `NONE`,
+    /// is stricter than 1.0, see `RootSigBuilder::build_with_version`.
+    #[repr(C)]
+    pub struct DescriptorRangeFlags: u32 {
+        const NONE                             = 0;
+        const DESCRIPTORS_VOLATILE             = 0x1;
+        const DATA_VOLATILE                    = 0x2;
+        const DATA_STATIC_WHILE_SET_AT_EXECUTE = 0x4;
+        const DATA_STATIC                      = 0x8;
+    }
+}
+
+impl_bitflags_serde!(DescriptorRangeFlags, u32);
+
+impl Default for DescriptorRangeFlags {
+    #[inline]
+    fn default() -> Self {
+        DescriptorRangeFlags::NONE
+    }
+}
+
+bitflags!{
+    /// version 1.1 flags of a root descriptor
+    #[repr(C)]
+    pub struct RootDescriptorFlags: u32 {
+        const NONE                             = 0;
+        const DATA_VOLATILE                    = 0x2;
+        const DATA_STATIC_WHILE_SET_AT_EXECUTE = 0x4;
+        const DATA_STATIC                      = 0x8;
+    }
+}
+
+impl_bitflags_serde!(RootDescriptorFlags, u32);
+
+impl Default for RootDescriptorFlags {
+    #[inline]
+    fn default() -> Self {
+        RootDescriptorFlags::NONE
+    }
+}
+
+d3d_enum!{
+    /// type of a descriptor range
+    pub enum DescriptorRangeType {
+        SRV      = 0,
+        UAV      = 1,
+        CBV      = 2,
+        SAMPLER  = 3,
+    }
+}
+
+d3d_enum!{
+    /// specifies which shader can access content of a given root parameter
+    pub enum ShaderVisibility {
+        ALL           = 0,
+        VERTEX        = 1,
+        HULL          = 2,
+        DOMAIN        = 3,
+        GEOMETRY      = 4,
+        PIXEL         = 5,
+        AMPLIFICATION = 6,
+        MESH          = 7,
+    }
+}
+
+impl Default for ShaderVisibility {
+    #[inline]
+    fn default() -> ShaderVisibility {
+        ShaderVisibility::ALL
+    }
+}
+
+bitflags!{
+    /// misc flags for a root signature
+    #[repr(C)]
+    pub struct RootSigFlags: u32 {
+        const NONE                                  = 0;
+        const ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT    = 0x1;
+        const DENY_VERTEX_SHADER_ROOT_ACCESS        = 0x2;
+        const DENY_HULL_SHADER_ROOT_ACCESS          = 0x4;
+        const DENY_DOMAIN_SHADER_ROOT_ACCESS        = 0x8;
+        const DENY_GEOMETRY_SHADER_ROOT_ACCESS      = 0x10;
+        const DENY_PIXEL_SHADER_ROOT_ACCESS         = 0x20;
+        const ALLOW_STREAM_OUTPUT                   = 0x40;
+        const DENY_AMPLIFICATION_SHADER_ROOT_ACCESS = 0x100;
+        const DENY_MESH_SHADER_ROOT_ACCESS          = 0x200;
+    }
+}
+
+impl_bitflags_serde!(RootSigFlags, u32);
+
+impl Default for RootSigFlags {
+    #[inline]
+    fn default() -> Self {
+        RootSigFlags::ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT
+    }
+}
+
+#[cfg(windows)]
+impl From<RootSigFlags> for ::winapi::D3D12_ROOT_SIGNATURE_FLAGS {
+    #[inline]
+    fn from(flags: RootSigFlags) -> Self {
+        unsafe{ ::std::mem::transmute(flags)}
+    }
+}
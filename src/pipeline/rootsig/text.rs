@@ -0,0 +1,925 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! the HLSL root signature language, as used by `[RootSignature("...")]`.
+//! [more info](https://msdn.microsoft.com/en-us/library/windows/desktop/dn913202(v=vs.85).aspx)
+
+use super::*;
+use pipeline::ComparisonFunc;
+use pipeline::sampler::{Filter, TextureAddressMode, BorderColor};
+use std::{error, fmt};
+use std::fmt::Write;
+use std::ops::BitOr;
+
+/// an error in a root signature string
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RootSigParseError {
+    /// byte offset of the error in the source
+    pub offset: usize,
+    /// line of the error, starting from 1
+    pub line: usize,
+    /// column of the error in characters, starting from 1
+    pub column: usize,
+    /// what went wrong
+    pub message: String,
+}
+
+impl RootSigParseError {
+    fn new(source: &str, offset: usize, message: String) -> Self {
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+        let column = before[line_start..].chars().count() + 1;
+        RootSigParseError{offset, line, column, message}
+    }
+}
+
+impl fmt::Display for RootSigParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl error::Error for RootSigParseError {}
+
+/// root signature flags by their names in the root signature language
+const ROOT_FLAG_NAMES: &[(&str, RootSigFlags)] = &[
+    ("ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT", RootSigFlags::ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT),
+    ("DENY_VERTEX_SHADER_ROOT_ACCESS", RootSigFlags::DENY_VERTEX_SHADER_ROOT_ACCESS),
+    ("DENY_HULL_SHADER_ROOT_ACCESS", RootSigFlags::DENY_HULL_SHADER_ROOT_ACCESS),
+    ("DENY_DOMAIN_SHADER_ROOT_ACCESS", RootSigFlags::DENY_DOMAIN_SHADER_ROOT_ACCESS),
+    ("DENY_GEOMETRY_SHADER_ROOT_ACCESS", RootSigFlags::DENY_GEOMETRY_SHADER_ROOT_ACCESS),
+    ("DENY_PIXEL_SHADER_ROOT_ACCESS", RootSigFlags::DENY_PIXEL_SHADER_ROOT_ACCESS),
+    ("ALLOW_STREAM_OUTPUT", RootSigFlags::ALLOW_STREAM_OUTPUT),
+    ("DENY_AMPLIFICATION_SHADER_ROOT_ACCESS", RootSigFlags::DENY_AMPLIFICATION_SHADER_ROOT_ACCESS),
+    ("DENY_MESH_SHADER_ROOT_ACCESS", RootSigFlags::DENY_MESH_SHADER_ROOT_ACCESS),
+];
+
+/// descriptor range flags by their names in the root signature language
+const RANGE_FLAG_NAMES: &[(&str, DescriptorRangeFlags)] = &[
+    ("DESCRIPTORS_VOLATILE", DescriptorRangeFlags::DESCRIPTORS_VOLATILE),
+    ("DATA_VOLATILE", DescriptorRangeFlags::DATA_VOLATILE),
+    ("DATA_STATIC_WHILE_SET_AT_EXECUTE", DescriptorRangeFlags::DATA_STATIC_WHILE_SET_AT_EXECUTE),
+    ("DATA_STATIC", DescriptorRangeFlags::DATA_STATIC),
+];
+
+/// root descriptor flags by their names in the root signature language
+const ROOT_DESCRIPTOR_FLAG_NAMES: &[(&str, RootDescriptorFlags)] = &[
+    ("DATA_VOLATILE", RootDescriptorFlags::DATA_VOLATILE),
+    ("DATA_STATIC_WHILE_SET_AT_EXECUTE", RootDescriptorFlags::DATA_STATIC_WHILE_SET_AT_EXECUTE),
+    ("DATA_STATIC", RootDescriptorFlags::DATA_STATIC),
+];
+
+/// `numDescriptors = unbounded`
+const UNBOUNDED: u32 = 0xffff_ffff;
+/// `offset = DESCRIPTOR_RANGE_OFFSET_APPEND`
+const OFFSET_APPEND: u32 = 0xffff_ffff;
+
+impl RootSigBuilder {
+    /// parse a root signature string, e.g.
+    /// `"RootFlags(0), CBV(b0), DescriptorTable(SRV(t0, numDescriptors = 4))"`.
+    ///
+    /// omitted arguments take the defaults of the root signature language,
+    /// which differ from `StaticSamplerDesc::new`, and flags are empty
+    /// without a `RootFlags` clause or a `flags` argument.
+    pub fn from_hlsl(source: &str) -> Result<RootSigBuilder, RootSigParseError> {
+        let tokens = tokenize(source)?;
+        Parser{source, tokens, pos: 0}.root_sig()
+    }
+
+    /// print the description in the root signature language, omitting
+    /// arguments with default values, and `RootFlags` without flags
+    pub fn to_hlsl(&self) -> String {
+        let mut elements = Vec::new();
+
+        let flags: Vec<&str> = ROOT_FLAG_NAMES.iter()
+            .filter(|&&(_, flag)| self.flags.contains(flag))
+            .map(|&(name, _)| name)
+            .collect();
+        if !flags.is_empty() {
+            elements.push(format!("RootFlags({})", flags.join(" | ")));
+        }
+
+        for param in self.root_params.iter() {
+            let mut element = String::new();
+            match param.param_type {
+                RootParamType::Constant{shader_register, register_space, num_32bit_values} => {
+                    let _ = write!(element, "RootConstants(num32BitConstants = {}, b{}", num_32bit_values, shader_register);
+                    write_space(&mut element, register_space);
+                },
+                RootParamType::Cbv{shader_register, register_space, flags} => {
+                    let _ = write!(element, "CBV(b{}", shader_register);
+                    write_space(&mut element, register_space);
+                    write_flags(&mut element, ROOT_DESCRIPTOR_FLAG_NAMES, |flag| flags.contains(flag));
+                },
+                RootParamType::Srv{shader_register, register_space, flags} => {
+                    let _ = write!(element, "SRV(t{}", shader_register);
+                    write_space(&mut element, register_space);
+                    write_flags(&mut element, ROOT_DESCRIPTOR_FLAG_NAMES, |flag| flags.contains(flag));
+                },
+                RootParamType::Uav{shader_register, register_space, flags} => {
+                    let _ = write!(element, "UAV(u{}", shader_register);
+                    write_space(&mut element, register_space);
+                    write_flags(&mut element, ROOT_DESCRIPTOR_FLAG_NAMES, |flag| flags.contains(flag));
+                },
+                RootParamType::DescriptorTable{ref descriptor_ranges} => {
+                    element.push_str("DescriptorTable(");
+                    for (i, range) in descriptor_ranges.iter().enumerate() {
+                        if i > 0 { element.push_str(", "); }
+                        write_range(&mut element, range);
+                    }
+                },
+            }
+            if param.visibility != ShaderVisibility::ALL {
+                if !element.ends_with('(') { element.push_str(", "); }
+                let _ = write!(element, "visibility = SHADER_VISIBILITY_{}", param.visibility.name());
+            }
+            element.push(')');
+            elements.push(element);
+        }
+
+        for sampler in self.static_samplers.iter() {
+            elements.push(sampler_to_hlsl(sampler));
+        }
+
+        elements.join(", ")
+    }
+}
+
+fn write_space(out: &mut String, register_space: u32) {
+    if register_space != 0 {
+        let _ = write!(out, ", space = {}", register_space);
+    }
+}
+
+fn write_range(out: &mut String, range: &DescriptorRange) {
+    let (name, class) = match range.range_type {
+        DescriptorRangeType::CBV => ("CBV", 'b'),
+        DescriptorRangeType::SRV => ("SRV", 't'),
+        DescriptorRangeType::UAV => ("UAV", 'u'),
+        DescriptorRangeType::SAMPLER => ("Sampler", 's'),
+    };
+    let _ = write!(out, "{}({}{}", name, class, range.base_shader_register);
+    match range.num_descriptors {
+        1 => (),
+        UNBOUNDED => out.push_str(", numDescriptors = unbounded"),
+        num => { let _ = write!(out, ", numDescriptors = {}", num); },
+    }
+    write_space(out, range.register_space);
+    if range.offset_from_table_start != OFFSET_APPEND {
+        let _ = write!(out, ", offset = {}", range.offset_from_table_start);
+    }
+    write_flags(out, RANGE_FLAG_NAMES, |flag| range.flags.contains(flag));
+    out.push(')');
+}
+
+/// write the `flags` argument, unless no flag is set
+fn write_flags<F: Copy, C: Fn(F) -> bool>(out: &mut String, names: &[(&str, F)], contains: C) {
+    let flags: Vec<&str> = names.iter()
+        .filter(|&&(_, flag)| contains(flag))
+        .map(|&(name, _)| name)
+        .collect();
+    if !flags.is_empty() {
+        let _ = write!(out, ", flags = {}", flags.join(" | "));
+    }
+}
+
+fn sampler_to_hlsl(sampler: &StaticSamplerDesc) -> String {
+    let defaults = default_sampler(sampler.shader_register);
+    let mut out = format!("StaticSampler(s{}", sampler.shader_register);
+    if sampler.filter != defaults.filter {
+        let _ = write!(out, ", filter = FILTER_{}", sampler.filter.name());
+    }
+    for &(name, mode, default) in [
+        ("addressU", sampler.address_u, defaults.address_u),
+        ("addressV", sampler.address_v, defaults.address_v),
+        ("addressW", sampler.address_w, defaults.address_w),
+    ].iter() {
+        if mode != default {
+            let _ = write!(out, ", {} = TEXTURE_ADDRESS_{}", name, mode.name());
+        }
+    }
+    if sampler.mip_bias != defaults.mip_bias {
+        let _ = write!(out, ", mipLODBias = {:?}", sampler.mip_bias);
+    }
+    if sampler.max_anisotropy != defaults.max_anisotropy {
+        let _ = write!(out, ", maxAnisotropy = {}", sampler.max_anisotropy);
+    }
+    if sampler.comparison_func != defaults.comparison_func {
+        let _ = write!(out, ", comparisonFunc = COMPARISON_{}", sampler.comparison_func.name());
+    }
+    if sampler.border_color != defaults.border_color {
+        let _ = write!(out, ", borderColor = STATIC_BORDER_COLOR_{}", sampler.border_color.name());
+    }
+    if sampler.min_lod != defaults.min_lod {
+        let _ = write!(out, ", minLOD = {:?}", sampler.min_lod);
+    }
+    if sampler.max_lod != defaults.max_lod {
+        let _ = write!(out, ", maxLOD = {:?}", sampler.max_lod);
+    }
+    write_space(&mut out, sampler.register_space);
+    if sampler.visibility != ShaderVisibility::ALL {
+        let _ = write!(out, ", visibility = SHADER_VISIBILITY_{}", sampler.visibility.name());
+    }
+    out.push(')');
+    out
+}
+
+/// a static sampler with the defaults of the root signature language
+fn default_sampler(shader_register: u32) -> StaticSamplerDesc {
+    let mut sampler = StaticSamplerDesc::new(Filter::ANISOTROPIC, shader_register, 0);
+    sampler.max_anisotropy = 16;
+    sampler.comparison_func = ComparisonFunc::LESS_EQUAL;
+    sampler.border_color = BorderColor::OPAQUE_WHITE;
+    sampler.max_lod = f32::MAX;
+    sampler
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TokenKind {
+    Ident,
+    Number,
+    LParen,
+    RParen,
+    Comma,
+    Equal,
+    Pipe,
+    End,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    offset: usize,
+}
+
+fn tokenize<'a>(source: &'a str) -> Result<Vec<Token<'a>>, RootSigParseError> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        let start = pos;
+        let kind = match c {
+            b' ' | b'\t' | b'\r' | b'\n' => { pos += 1; continue; },
+            b'(' => TokenKind::LParen,
+            b')' => TokenKind::RParen,
+            b',' => TokenKind::Comma,
+            b'=' => TokenKind::Equal,
+            b'|' => TokenKind::Pipe,
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                    pos += 1;
+                }
+                tokens.push(Token{kind: TokenKind::Ident, text: &source[start..pos], offset: start});
+                continue;
+            },
+            b'0'..=b'9' | b'.' | b'-' | b'+' => {
+                pos += 1;
+                while pos < bytes.len() {
+                    let c = bytes[pos];
+                    let exponent_sign = (c == b'-' || c == b'+') &&
+                        (bytes[pos - 1] == b'e' || bytes[pos - 1] == b'E') &&
+                        !source[start..pos].starts_with("0x");
+                    if c.is_ascii_alphanumeric() || c == b'.' || exponent_sign {
+                        pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token{kind: TokenKind::Number, text: &source[start..pos], offset: start});
+                continue;
+            },
+            _ => {
+                let c = source[start..].chars().next().unwrap();
+                return Err(RootSigParseError::new(source, start, format!("unexpected character `{}`", c)));
+            },
+        };
+        pos += 1;
+        tokens.push(Token{kind, text: &source[start..pos], offset: start});
+    }
+    tokens.push(Token{kind: TokenKind::End, text: "", offset: source.len()});
+    Ok(tokens)
+}
+
+/// arguments of a clause, either positional or `name = value | value`
+struct Args<'a> {
+    clause: Token<'a>,
+    end: Token<'a>,
+    positional: SmallVec<[Token<'a>; 2]>,
+    named: SmallVec<[(Token<'a>, SmallVec<[Token<'a>; 2]>); 8]>,
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, offset: usize, message: String) -> Result<T, RootSigParseError> {
+        Err(RootSigParseError::new(self.source, offset, message))
+    }
+
+    #[inline]
+    fn peek(&self) -> Token<'a> {
+        self.tokens[self.pos]
+    }
+
+    #[inline]
+    fn peek_kind(&self, offset: usize) -> TokenKind {
+        self.tokens.get(self.pos + offset).map_or(TokenKind::End, |token| token.kind)
+    }
+
+    #[inline]
+    fn next(&mut self) -> Token<'a> {
+        let token = self.tokens[self.pos];
+        if token.kind != TokenKind::End { self.pos += 1; }
+        token
+    }
+
+    fn expect(&mut self, kind: TokenKind, what: &str) -> Result<Token<'a>, RootSigParseError> {
+        let token = self.next();
+        if token.kind == kind {
+            Ok(token)
+        } else {
+            self.unexpected(token, what)
+        }
+    }
+
+    fn unexpected<T>(&self, token: Token<'a>, what: &str) -> Result<T, RootSigParseError> {
+        if token.kind == TokenKind::End {
+            self.error(token.offset, format!("expected {}, found the end", what))
+        } else {
+            self.error(token.offset, format!("expected {}, found `{}`", what, token.text))
+        }
+    }
+
+    fn root_sig(&mut self) -> Result<RootSigBuilder, RootSigParseError> {
+        let mut builder = RootSigBuilder::new();
+        builder.flags = RootSigFlags::empty();
+        let mut has_flags = false;
+        if self.peek().kind == TokenKind::End { return Ok(builder); }
+        loop {
+            let clause = self.expect(TokenKind::Ident, "a root signature element")?;
+            let keyword = clause.text;
+            if keyword.eq_ignore_ascii_case("RootFlags") {
+                if has_flags {
+                    return self.error(clause.offset, "duplicate `RootFlags`".to_owned());
+                }
+                has_flags = true;
+                builder.flags = self.root_flags()?;
+            } else if keyword.eq_ignore_ascii_case("RootConstants") {
+                let mut args = self.args(clause)?;
+                let shader_register = self.register(&mut args, 'b')?;
+                let num_32bit_values = match args.take("num32BitConstants") {
+                    Some(values) => self.number(&values)?,
+                    None => return self.error(clause.offset, "`RootConstants` requires `num32BitConstants`".to_owned()),
+                };
+                let register_space = self.space(&mut args)?;
+                let visibility = self.visibility(&mut args)?;
+                self.finish(&args)?;
+                builder.root_params.push(RootParam{
+                    visibility,
+                    param_type: RootParamType::Constant{shader_register, register_space, num_32bit_values},
+                });
+            } else if let Some(class) = root_descriptor_class(keyword) {
+                let mut args = self.args(clause)?;
+                let shader_register = self.register(&mut args, class)?;
+                let register_space = self.space(&mut args)?;
+                let visibility = self.visibility(&mut args)?;
+                let flags = self.flags(&mut args, ROOT_DESCRIPTOR_FLAG_NAMES, RootDescriptorFlags::NONE)?;
+                self.finish(&args)?;
+                let param_type = match class {
+                    'b' => RootParamType::Cbv{shader_register, register_space, flags},
+                    't' => RootParamType::Srv{shader_register, register_space, flags},
+                    _ => RootParamType::Uav{shader_register, register_space, flags},
+                };
+                builder.root_params.push(RootParam{visibility, param_type});
+            } else if keyword.eq_ignore_ascii_case("DescriptorTable") {
+                builder.root_params.push(self.descriptor_table()?);
+            } else if keyword.eq_ignore_ascii_case("StaticSampler") {
+                let mut args = self.args(clause)?;
+                builder.static_samplers.push(self.static_sampler(&mut args)?);
+            } else {
+                return self.error(clause.offset, format!("unknown root signature element `{}`", keyword));
+            }
+
+            let token = self.next();
+            match token.kind {
+                TokenKind::Comma => continue,
+                TokenKind::End => break,
+                _ => return self.unexpected(token, "`,`"),
+            }
+        }
+        Ok(builder)
+    }
+
+    fn root_flags(&mut self) -> Result<RootSigFlags, RootSigParseError> {
+        self.expect(TokenKind::LParen, "`(`")?;
+        let mut flags = RootSigFlags::empty();
+        if self.peek().kind == TokenKind::RParen {
+            self.next();
+            return Ok(flags);
+        }
+        loop {
+            let token = self.next();
+            flags |= match token.kind {
+                TokenKind::Number if parse_u32(token.text) == Some(0) => RootSigFlags::empty(),
+                TokenKind::Ident => match lookup_root_flag(token.text) {
+                    Some(flag) => flag,
+                    None => return self.error(token.offset, format!("unknown root flag `{}`", token.text)),
+                },
+                _ => return self.unexpected(token, "a root flag"),
+            };
+            let token = self.next();
+            match token.kind {
+                TokenKind::Pipe => continue,
+                TokenKind::RParen => return Ok(flags),
+                _ => return self.unexpected(token, "`|` or `)`"),
+            }
+        }
+    }
+
+    fn descriptor_table(&mut self) -> Result<RootParam, RootSigParseError> {
+        self.expect(TokenKind::LParen, "`(`")?;
+        let mut descriptor_ranges = SmallVec::new();
+        let mut visibility = None;
+        if self.peek().kind == TokenKind::RParen {
+            self.next();
+        } else {
+            loop {
+                let token = self.expect(TokenKind::Ident, "a descriptor range or `visibility`")?;
+                if token.text.eq_ignore_ascii_case("visibility") && self.peek().kind == TokenKind::Equal {
+                    if visibility.is_some() {
+                        return self.error(token.offset, "duplicate `visibility`".to_owned());
+                    }
+                    self.next();
+                    let value = self.expect(TokenKind::Ident, "a shader visibility")?;
+                    visibility = Some(self.enum_value(value, "SHADER_VISIBILITY_", ShaderVisibility::from_name)?);
+                } else if let Some((range_type, class)) = range_type(token.text) {
+                    let mut args = self.args(token)?;
+                    let base_shader_register = self.register(&mut args, class)?;
+                    let num_descriptors = match args.take("numDescriptors") {
+                        Some(ref values) if values.len() == 1 && values[0].text.eq_ignore_ascii_case("unbounded") => UNBOUNDED,
+                        Some(values) => self.number(&values)?,
+                        None => 1,
+                    };
+                    let register_space = self.space(&mut args)?;
+                    let offset_from_table_start = match args.take("offset") {
+                        Some(ref values) if values.len() == 1 && values[0].text.eq_ignore_ascii_case("DESCRIPTOR_RANGE_OFFSET_APPEND") => OFFSET_APPEND,
+                        Some(values) => self.number(&values)?,
+                        None => OFFSET_APPEND,
+                    };
+                    let flags = self.flags(&mut args, RANGE_FLAG_NAMES, DescriptorRangeFlags::NONE)?;
+                    self.finish(&args)?;
+                    descriptor_ranges.push(DescriptorRange{
+                        range_type, num_descriptors, base_shader_register,
+                        register_space, offset_from_table_start, flags,
+                    });
+                } else {
+                    return self.unexpected(token, "a descriptor range or `visibility`");
+                }
+
+                let token = self.next();
+                match token.kind {
+                    TokenKind::Comma => continue,
+                    TokenKind::RParen => break,
+                    _ => return self.unexpected(token, "`,` or `)`"),
+                }
+            }
+        }
+        Ok(RootParam{
+            visibility: visibility.unwrap_or(ShaderVisibility::ALL),
+            param_type: RootParamType::DescriptorTable{descriptor_ranges},
+        })
+    }
+
+    fn static_sampler(&self, args: &mut Args<'a>) -> Result<StaticSamplerDesc, RootSigParseError> {
+        let shader_register = self.register(args, 's')?;
+        let mut sampler = default_sampler(shader_register);
+        if let Some(values) = args.take("filter") {
+            sampler.filter = self.enum_value(self.single(&values)?, "FILTER_", Filter::from_name)?;
+        }
+        if let Some(values) = args.take("addressU") {
+            sampler.address_u = self.enum_value(self.single(&values)?, "TEXTURE_ADDRESS_", TextureAddressMode::from_name)?;
+        }
+        if let Some(values) = args.take("addressV") {
+            sampler.address_v = self.enum_value(self.single(&values)?, "TEXTURE_ADDRESS_", TextureAddressMode::from_name)?;
+        }
+        if let Some(values) = args.take("addressW") {
+            sampler.address_w = self.enum_value(self.single(&values)?, "TEXTURE_ADDRESS_", TextureAddressMode::from_name)?;
+        }
+        if let Some(values) = args.take("mipLODBias") {
+            sampler.mip_bias = self.float(&values)?;
+        }
+        if let Some(values) = args.take("maxAnisotropy") {
+            sampler.max_anisotropy = self.number(&values)?;
+        }
+        if let Some(values) = args.take("comparisonFunc") {
+            sampler.comparison_func = self.enum_value(self.single(&values)?, "COMPARISON_", ComparisonFunc::from_name)?;
+        }
+        if let Some(values) = args.take("borderColor") {
+            sampler.border_color = self.enum_value(self.single(&values)?, "STATIC_BORDER_COLOR_", BorderColor::from_name)?;
+        }
+        if let Some(values) = args.take("minLOD") {
+            sampler.min_lod = self.float(&values)?;
+        }
+        if let Some(values) = args.take("maxLOD") {
+            sampler.max_lod = self.float(&values)?;
+        }
+        sampler.register_space = self.space(args)?;
+        sampler.visibility = self.visibility(args)?;
+        self.finish(args)?;
+        Ok(sampler)
+    }
+
+    /// parse the parenthesized arguments of `clause`
+    fn args(&mut self, clause: Token<'a>) -> Result<Args<'a>, RootSigParseError> {
+        self.expect(TokenKind::LParen, "`(`")?;
+        let mut args = Args{
+            clause, end: clause, positional: SmallVec::new(), named: SmallVec::new(),
+        };
+        if self.peek().kind == TokenKind::RParen {
+            args.end = self.next();
+            return Ok(args);
+        }
+        loop {
+            let token = self.next();
+            match token.kind {
+                TokenKind::Ident if self.peek_kind(0) == TokenKind::Equal => {
+                    if args.named.iter().any(|&(name, _)| name.text.eq_ignore_ascii_case(token.text)) {
+                        return self.error(token.offset, format!("duplicate `{}`", token.text));
+                    }
+                    self.next();
+                    let mut values = SmallVec::new();
+                    loop {
+                        let value = self.next();
+                        match value.kind {
+                            TokenKind::Ident | TokenKind::Number => values.push(value),
+                            _ => return self.unexpected(value, "a value"),
+                        }
+                        if self.peek().kind != TokenKind::Pipe { break; }
+                        self.next();
+                    }
+                    args.named.push((token, values));
+                },
+                TokenKind::Ident | TokenKind::Number => args.positional.push(token),
+                _ => return self.unexpected(token, "an argument"),
+            }
+            let token = self.next();
+            match token.kind {
+                TokenKind::Comma => continue,
+                TokenKind::RParen => {
+                    args.end = token;
+                    return Ok(args);
+                },
+                _ => return self.unexpected(token, "`,` or `)`"),
+            }
+        }
+    }
+
+    /// take the register argument of class `class`, e.g. `b0`
+    fn register(&self, args: &mut Args<'a>, class: char) -> Result<u32, RootSigParseError> {
+        if args.positional.is_empty() {
+            return self.error(args.end.offset, format!(
+                "`{}` requires a `{}` register", args.clause.text, class
+            ));
+        }
+        let token = args.positional.remove(0);
+        let mut chars = token.text.chars();
+        let register = match chars.next() {
+            Some(c) if c.to_ascii_lowercase() == class => parse_u32(chars.as_str()),
+            _ => None,
+        };
+        match register {
+            Some(register) if token.kind == TokenKind::Ident => Ok(register),
+            _ => self.error(token.offset, format!(
+                "expected a `{}` register, found `{}`", class, token.text
+            )),
+        }
+    }
+
+    fn space(&self, args: &mut Args<'a>) -> Result<u32, RootSigParseError> {
+        match args.take("space") {
+            Some(values) => self.number(&values),
+            None => Ok(0),
+        }
+    }
+
+    fn visibility(&self, args: &mut Args<'a>) -> Result<ShaderVisibility, RootSigParseError> {
+        match args.take("visibility") {
+            Some(values) => self.enum_value(self.single(&values)?, "SHADER_VISIBILITY_", ShaderVisibility::from_name),
+            None => Ok(ShaderVisibility::ALL),
+        }
+    }
+
+    /// root signature 1.1 flags, looked up in `names`
+    fn flags<F: Copy + BitOr<Output = F>>(
+        &self, args: &mut Args<'a>, names: &[(&str, F)], mut flags: F
+    ) -> Result<F, RootSigParseError> {
+        if let Some(values) = args.take("flags") {
+            for value in values.iter() {
+                if value.kind == TokenKind::Number && parse_u32(value.text) == Some(0) { continue; }
+                match names.iter().find(|&&(name, _)| name == value.text) {
+                    Some(&(_, flag)) => flags = flags | flag,
+                    None => return self.error(value.offset, format!(
+                        "unknown flag `{}` in `{}`", value.text, args.clause.text
+                    )),
+                }
+            }
+        }
+        Ok(flags)
+    }
+
+    /// reject arguments left over after parsing a clause
+    fn finish(&self, args: &Args<'a>) -> Result<(), RootSigParseError> {
+        if let Some(token) = args.positional.first() {
+            return self.error(token.offset, format!(
+                "unexpected `{}` in `{}`", token.text, args.clause.text
+            ));
+        }
+        if let Some(&(name, _)) = args.named.first() {
+            return self.error(name.offset, format!(
+                "unknown argument `{}` in `{}`", name.text, args.clause.text
+            ));
+        }
+        Ok(())
+    }
+
+    fn single(&self, values: &[Token<'a>]) -> Result<Token<'a>, RootSigParseError> {
+        if values.len() > 1 {
+            return self.error(values[1].offset, "expected a single value".to_owned());
+        }
+        Ok(values[0])
+    }
+
+    fn number(&self, values: &[Token<'a>]) -> Result<u32, RootSigParseError> {
+        let token = self.single(values)?;
+        match parse_u32(token.text) {
+            Some(value) if token.kind == TokenKind::Number => Ok(value),
+            _ => self.error(token.offset, format!("expected an unsigned integer, found `{}`", token.text)),
+        }
+    }
+
+    fn float(&self, values: &[Token<'a>]) -> Result<f32, RootSigParseError> {
+        let token = self.single(values)?;
+        let text = token.text.trim_end_matches(['f', 'F']);
+        match text.parse() {
+            Ok(value) if token.kind == TokenKind::Number => Ok(value),
+            _ => self.error(token.offset, format!("expected a number, found `{}`", token.text)),
+        }
+    }
+
+    fn enum_value<T, F>(&self, token: Token<'a>, prefix: &str, from_name: F) -> Result<T, RootSigParseError>
+        where F: Fn(&str) -> Option<T>,
+    {
+        let value = if token.text.starts_with(prefix) {
+            from_name(&token.text[prefix.len()..])
+        } else {
+            None
+        };
+        match value {
+            Some(value) if token.kind == TokenKind::Ident => Ok(value),
+            _ => self.error(token.offset, format!(
+                "expected a `{}*` value, found `{}`", prefix, token.text
+            )),
+        }
+    }
+}
+
+impl<'a> Args<'a> {
+    /// take the values of the named argument `name`
+    fn take(&mut self, name: &str) -> Option<SmallVec<[Token<'a>; 2]>> {
+        let index = self.named.iter().position(|&(token, _)| token.text.eq_ignore_ascii_case(name))?;
+        Some(self.named.remove(index).1)
+    }
+}
+
+fn lookup_root_flag(name: &str) -> Option<RootSigFlags> {
+    ROOT_FLAG_NAMES.iter()
+        .find(|&&(flag_name, _)| flag_name == name)
+        .map(|&(_, flag)| flag)
+}
+
+fn root_descriptor_class(keyword: &str) -> Option<char> {
+    if keyword.eq_ignore_ascii_case("CBV") {
+        Some('b')
+    } else if keyword.eq_ignore_ascii_case("SRV") {
+        Some('t')
+    } else if keyword.eq_ignore_ascii_case("UAV") {
+        Some('u')
+    } else {
+        None
+    }
+}
+
+fn range_type(keyword: &str) -> Option<(DescriptorRangeType, char)> {
+    match root_descriptor_class(keyword) {
+        Some('b') => Some((DescriptorRangeType::CBV, 'b')),
+        Some('t') => Some((DescriptorRangeType::SRV, 't')),
+        Some(_) => Some((DescriptorRangeType::UAV, 'u')),
+        None if keyword.eq_ignore_ascii_case("Sampler") => Some((DescriptorRangeType::SAMPLER, 's')),
+        None => None,
+    }
+}
+
+/// parse a decimal or `0x` prefixed hexadecimal integer
+fn parse_u32(text: &str) -> Option<u32> {
+    if text.starts_with("0x") || text.starts_with("0X") {
+        u32::from_str_radix(&text[2..], 16).ok()
+    } else if !text.is_empty() && text.bytes().all(|c| c.is_ascii_digit()) {
+        text.parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the example of the root signature language documentation
+    const DOCUMENTED: &str = "\
+        RootFlags( ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | DENY_VERTEX_SHADER_ROOT_ACCESS ), \
+        CBV(b0, space = 1, flags = DATA_STATIC), \
+        SRV(t0), \
+        UAV(u0), \
+        DescriptorTable( CBV(b1), \
+                         SRV(t1, numDescriptors = 8, flags = DESCRIPTORS_VOLATILE), \
+                         UAV(u1, numDescriptors = unbounded, flags = DESCRIPTORS_VOLATILE)), \
+        DescriptorTable(Sampler(s0, space=1, numDescriptors = 4)), \
+        RootConstants(num32BitConstants=3, b10), \
+        StaticSampler(s1), \
+        StaticSampler(s2, \
+                      addressU = TEXTURE_ADDRESS_CLAMP, \
+                      filter = FILTER_MIN_MAG_MIP_LINEAR )";
+
+    fn assert_round_trip(builder: &RootSigBuilder) {
+        let hlsl = builder.to_hlsl();
+        let parsed = RootSigBuilder::from_hlsl(&hlsl)
+            .unwrap_or_else(|err| panic!("can't parse `{}`: {}", hlsl, err));
+        assert_eq!(parsed.to_bytes(RootSigVersion::V1_1), builder.to_bytes(RootSigVersion::V1_1), "{}", hlsl);
+        assert_eq!(parsed.to_hlsl(), hlsl);
+    }
+
+    #[test]
+    fn documented_example() {
+        let builder = RootSigBuilder::from_hlsl(DOCUMENTED).unwrap();
+        assert_eq!(builder.flags, RootSigFlags::ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | RootSigFlags::DENY_VERTEX_SHADER_ROOT_ACCESS);
+        assert_eq!(builder.root_params.len(), 6);
+        assert_eq!(builder.static_samplers.len(), 2);
+        match builder.root_params[0].param_type {
+            RootParamType::Cbv{shader_register: 0, register_space: 1, flags} =>
+                assert_eq!(flags, RootDescriptorFlags::DATA_STATIC),
+            ref other => panic!("unexpected parameter {:?}", other),
+        }
+        match builder.root_params[3].param_type {
+            RootParamType::DescriptorTable{ref descriptor_ranges} => {
+                assert_eq!(descriptor_ranges.len(), 3);
+                assert_eq!(descriptor_ranges[2].range_type, DescriptorRangeType::UAV);
+                assert_eq!(descriptor_ranges[2].num_descriptors, UNBOUNDED);
+                assert_eq!(descriptor_ranges[2].offset_from_table_start, OFFSET_APPEND);
+                assert_eq!(descriptor_ranges[2].flags, DescriptorRangeFlags::DESCRIPTORS_VOLATILE);
+            },
+            ref other => panic!("unexpected parameter {:?}", other),
+        }
+        let sampler = &builder.static_samplers[1];
+        assert_eq!(sampler.filter, Filter::MIN_MAG_MIP_LINEAR);
+        assert_eq!(sampler.address_u, TextureAddressMode::CLAMP);
+        assert_eq!(sampler.address_v, TextureAddressMode::WRAP);
+        assert_eq!(sampler.max_anisotropy, 16);
+        assert_eq!(sampler.visibility, ShaderVisibility::ALL);
+
+        assert_eq!(builder.to_hlsl(), "\
+RootFlags(ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | DENY_VERTEX_SHADER_ROOT_ACCESS), \
+CBV(b0, space = 1, flags = DATA_STATIC), \
+SRV(t0), \
+UAV(u0), \
+DescriptorTable(CBV(b1), SRV(t1, numDescriptors = 8, flags = DESCRIPTORS_VOLATILE), \
+UAV(u1, numDescriptors = unbounded, flags = DESCRIPTORS_VOLATILE)), \
+DescriptorTable(Sampler(s0, numDescriptors = 4, space = 1)), \
+RootConstants(num32BitConstants = 3, b10), \
+StaticSampler(s1), \
+StaticSampler(s2, filter = FILTER_MIN_MAG_MIP_LINEAR, addressU = TEXTURE_ADDRESS_CLAMP)");
+        assert_round_trip(&builder);
+    }
+
+    #[test]
+    fn static_sampler_visibility() {
+        let builder = RootSigBuilder::from_hlsl(
+            "StaticSampler(s0, space = 2, visibility = SHADER_VISIBILITY_PIXEL), StaticSampler(s1, visibility = SHADER_VISIBILITY_ALL)"
+        ).unwrap();
+        assert_eq!(builder.static_samplers[0].visibility, ShaderVisibility::PIXEL);
+        assert_eq!(builder.static_samplers[0].register_space, 2);
+        assert_eq!(builder.static_samplers[1].visibility, ShaderVisibility::ALL);
+        assert_eq!(builder.to_hlsl(),
+            "StaticSampler(s0, space = 2, visibility = SHADER_VISIBILITY_PIXEL), StaticSampler(s1)");
+        assert_round_trip(&builder);
+    }
+
+    #[test]
+    fn empty_root_flags() {
+        let mut builder = RootSigBuilder::new();
+        builder.flags = RootSigFlags::empty();
+        assert_eq!(builder.to_hlsl(), "");
+        assert_round_trip(&builder);
+
+        builder.root_params.push(RootParam{
+            visibility: ShaderVisibility::ALL,
+            param_type: RootParamType::Constant{shader_register: 0, register_space: 0, num_32bit_values: 1},
+        });
+        assert_eq!(builder.to_hlsl(), "RootConstants(num32BitConstants = 1, b0)");
+        assert_round_trip(&builder);
+
+        for source in &["RootFlags(0), RootConstants(num32BitConstants = 1, b0)", "RootFlags(), RootConstants(b0, num32BitConstants = 1)"] {
+            let parsed = RootSigBuilder::from_hlsl(source).unwrap();
+            assert_eq!(parsed.flags, RootSigFlags::empty());
+            assert_eq!(parsed.to_hlsl(), "RootConstants(num32BitConstants = 1, b0)");
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut builder = RootSigBuilder::new();
+        builder.flags = RootSigFlags::all();
+        builder.root_params.push(RootParam{
+            visibility: ShaderVisibility::MESH,
+            param_type: RootParamType::Uav{
+                shader_register: 3, register_space: 4,
+                flags: RootDescriptorFlags::DATA_STATIC_WHILE_SET_AT_EXECUTE,
+            },
+        });
+        let mut descriptor_ranges = SmallVec::new();
+        for (i, &(range_type, flags)) in [
+            (DescriptorRangeType::CBV, DescriptorRangeFlags::DATA_VOLATILE),
+            (DescriptorRangeType::SRV, DescriptorRangeFlags::DESCRIPTORS_VOLATILE | DescriptorRangeFlags::DATA_VOLATILE),
+            (DescriptorRangeType::UAV, DescriptorRangeFlags::NONE),
+        ].iter().enumerate() {
+            descriptor_ranges.push(DescriptorRange{
+                range_type, flags,
+                num_descriptors: if i == 2 { UNBOUNDED } else { i as u32 + 2 },
+                base_shader_register: 7, register_space: i as u32,
+                offset_from_table_start: if i == 1 { 16 } else { OFFSET_APPEND },
+            });
+        }
+        builder.root_params.push(RootParam{
+            visibility: ShaderVisibility::HULL,
+            param_type: RootParamType::DescriptorTable{descriptor_ranges},
+        });
+        builder.root_params.push(RootParam{
+            visibility: ShaderVisibility::ALL,
+            param_type: RootParamType::DescriptorTable{descriptor_ranges: SmallVec::new()},
+        });
+        let mut sampler = StaticSamplerDesc::new(Filter::COMPARISON_MIN_MAG_MIP_POINT, 5, 6);
+        sampler.address_w = TextureAddressMode::MIRROR_ONCE;
+        sampler.mip_bias = -0.5;
+        sampler.max_anisotropy = 4;
+        sampler.comparison_func = ComparisonFunc::GREATER;
+        sampler.border_color = BorderColor::TRANSPARENT_BLACK;
+        sampler.min_lod = 1.25;
+        sampler.max_lod = 8.0;
+        sampler.visibility = ShaderVisibility::DOMAIN;
+        builder.static_samplers.push(sampler);
+
+        assert_eq!(builder.to_hlsl(), format!("\
+RootFlags({}), \
+UAV(u3, space = 4, flags = DATA_STATIC_WHILE_SET_AT_EXECUTE, visibility = SHADER_VISIBILITY_MESH), \
+DescriptorTable(CBV(b7, numDescriptors = 2, flags = DATA_VOLATILE), \
+SRV(t7, numDescriptors = 3, space = 1, offset = 16, flags = DESCRIPTORS_VOLATILE | DATA_VOLATILE), \
+UAV(u7, numDescriptors = unbounded, space = 2), visibility = SHADER_VISIBILITY_HULL), \
+DescriptorTable(), \
+StaticSampler(s5, filter = FILTER_COMPARISON_MIN_MAG_MIP_POINT, \
+addressW = TEXTURE_ADDRESS_MIRROR_ONCE, mipLODBias = -0.5, maxAnisotropy = 4, \
+comparisonFunc = COMPARISON_GREATER, borderColor = STATIC_BORDER_COLOR_TRANSPARENT_BLACK, minLOD = 1.25, \
+maxLOD = 8.0, space = 6, visibility = SHADER_VISIBILITY_DOMAIN)",
+            ROOT_FLAG_NAMES.iter().map(|&(name, _)| name).collect::<Vec<_>>().join(" | ")));
+        assert_round_trip(&builder);
+        assert_round_trip(&RootSigBuilder::from_hlsl(DOCUMENTED).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        let err = RootSigBuilder::from_hlsl("CBV(b0),\n  SRV(b1)").unwrap_err();
+        assert_eq!((err.offset, err.line, err.column), (15, 2, 7));
+        assert_eq!(err.to_string(), "2:7: expected a `t` register, found `b1`");
+
+        let err = RootSigBuilder::from_hlsl("StaticSampler(s0, visibility = PIXEL)").unwrap_err();
+        assert_eq!(err.message, "expected a `SHADER_VISIBILITY_*` value, found `PIXEL`");
+        let err = RootSigBuilder::from_hlsl("RootFlags(0), RootFlags(0)").unwrap_err();
+        assert_eq!(err.message, "duplicate `RootFlags`");
+        let err = RootSigBuilder::from_hlsl("RootConstants(b0)").unwrap_err();
+        assert_eq!(err.message, "`RootConstants` requires `num32BitConstants`");
+        let err = RootSigBuilder::from_hlsl("CBV(b0, flags = DESCRIPTORS_VOLATILE)").unwrap_err();
+        assert_eq!(err.message, "unknown flag `DESCRIPTORS_VOLATILE` in `CBV`");
+
+        let err: Box<dyn error::Error> = Box::new(err);
+        assert_eq!(err.to_string(), "1:17: unknown flag `DESCRIPTORS_VOLATILE` in `CBV`");
+    }
+}
@@ -0,0 +1,524 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! validation of root signature descriptions.
+
+use super::*;
+use std::fmt;
+
+/// the maximum size of a root signature in DWORDs
+pub const ROOT_SIG_MAX_DWORDS: u32 = 64;
+
+/// where a register binding of a root signature is declared
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RootSigBinding {
+    /// a root constant or root descriptor parameter
+    Param{param: u32},
+    /// a range of a descriptor table parameter
+    Range{param: u32, range: u32},
+    /// a static sampler
+    StaticSampler{sampler: u32},
+}
+
+impl RootSigBinding {
+    /// index of the root parameter, if any
+    #[inline]
+    pub fn param(self) -> Option<u32> {
+        match self {
+            RootSigBinding::Param{param} | RootSigBinding::Range{param, ..} => Some(param),
+            RootSigBinding::StaticSampler{..} => None,
+        }
+    }
+}
+
+impl fmt::Display for RootSigBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            RootSigBinding::Param{param} => write!(f, "parameter {}", param),
+            RootSigBinding::Range{param, range} => write!(f, "range {} of parameter {}", range, param),
+            RootSigBinding::StaticSampler{sampler} => write!(f, "static sampler {}", sampler),
+        }
+    }
+}
+
+/// a problem found in a root signature description
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RootSigDiagnostic {
+    /// the root signature exceeds `ROOT_SIG_MAX_DWORDS`
+    TooLarge{dwords: u32},
+    /// two bindings visible to a common stage use the same register
+    OverlappingRegisters{
+        binding: RootSigBinding,
+        other: RootSigBinding,
+        range_type: DescriptorRangeType,
+        register_space: u32,
+    },
+    /// a descriptor range with no descriptors
+    EmptyRange{param: u32, range: u32},
+    /// a descriptor range whose registers go beyond `u32::MAX`
+    RegisterOverflow{param: u32, range: u32},
+    /// a descriptor table mixing sampler ranges with cbv, srv or uav ranges
+    MixedSamplerTable{param: u32},
+    /// an unbounded descriptor range followed by a range appended to it,
+    /// i.e. with `offset_from_table_start` of `DESCRIPTOR_RANGE_OFFSET_APPEND`
+    UnboundedRangeNotLast{param: u32, range: u32},
+    /// mutually exclusive 1.1 flags, or data flags on a sampler range
+    InvalidFlags{binding: RootSigBinding},
+}
+
+impl RootSigDiagnostic {
+    /// index of the root parameter the problem is found in, if any
+    pub fn param(&self) -> Option<u32> {
+        match *self {
+            RootSigDiagnostic::TooLarge{..} => None,
+            RootSigDiagnostic::OverlappingRegisters{binding, other, ..} =>
+                binding.param().or(other.param()),
+            RootSigDiagnostic::EmptyRange{param, ..} |
+            RootSigDiagnostic::RegisterOverflow{param, ..} |
+            RootSigDiagnostic::MixedSamplerTable{param} |
+            RootSigDiagnostic::UnboundedRangeNotLast{param, ..} => Some(param),
+            RootSigDiagnostic::InvalidFlags{binding} => binding.param(),
+        }
+    }
+}
+
+impl fmt::Display for RootSigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            RootSigDiagnostic::TooLarge{dwords} =>
+                write!(f, "the root signature takes {} DWORDs, at most {} are supported", dwords, ROOT_SIG_MAX_DWORDS),
+            RootSigDiagnostic::OverlappingRegisters{binding, other, range_type, register_space} =>
+                write!(f, "{} and {} both bind {} registers in space {} to a common stage",
+                    binding, other, range_type.name(), register_space),
+            RootSigDiagnostic::EmptyRange{param, range} =>
+                write!(f, "range {} of parameter {} has no descriptors", range, param),
+            RootSigDiagnostic::RegisterOverflow{param, range} =>
+                write!(f, "range {} of parameter {} goes beyond the last register", range, param),
+            RootSigDiagnostic::MixedSamplerTable{param} =>
+                write!(f, "descriptor table {} mixes sampler ranges with CBV, SRV or UAV ranges", param),
+            RootSigDiagnostic::UnboundedRangeNotLast{param, range} =>
+                write!(f, "unbounded range {} of parameter {} is followed by an appended range", range, param),
+            RootSigDiagnostic::InvalidFlags{binding} =>
+                write!(f, "{} has invalid descriptor or data flags", binding),
+        }
+    }
+}
+
+/// a register range bound by a root signature, `end` exclusive
+struct Registers {
+    binding: RootSigBinding,
+    range_type: DescriptorRangeType,
+    register_space: u32,
+    visibility: ShaderVisibility,
+    start: u64,
+    end: u64,
+}
+
+impl Registers {
+    #[inline]
+    fn overlaps(&self, other: &Registers) -> bool {
+        self.range_type == other.range_type &&
+        self.register_space == other.register_space &&
+        (self.visibility == ShaderVisibility::ALL ||
+            other.visibility == ShaderVisibility::ALL ||
+            self.visibility == other.visibility) &&
+        self.start < other.end && other.start < self.end
+    }
+}
+
+impl RootSigBuilder {
+    /// size of the root signature in DWORDs. descriptor tables take 1,
+    /// root descriptors 2, and root constants 1 per value.
+    pub fn dword_cost(&self) -> u32 {
+        self.root_params.iter().fold(0u32, |cost, param| {
+            cost.saturating_add(match param.param_type {
+                RootParamType::DescriptorTable{..} => 1,
+                RootParamType::Constant{num_32bit_values, ..} => num_32bit_values,
+                RootParamType::Cbv{..} | RootParamType::Srv{..} | RootParamType::Uav{..} => 2,
+            })
+        })
+    }
+
+    /// check the description for layouts the runtime rejects, returning
+    /// all problems found
+    pub fn validate(&self) -> Result<(), Vec<RootSigDiagnostic>> {
+        let mut diagnostics = Vec::new();
+
+        let dwords = self.dword_cost();
+        if dwords > ROOT_SIG_MAX_DWORDS {
+            diagnostics.push(RootSigDiagnostic::TooLarge{dwords});
+        }
+
+        let mut registers = Vec::new();
+        for (param, root_param) in self.root_params.iter().enumerate() {
+            let param = param as u32;
+            let visibility = root_param.visibility;
+            let (range_type, shader_register, register_space) = match root_param.param_type {
+                RootParamType::DescriptorTable{ref descriptor_ranges} => {
+                    validate_table(param, visibility, descriptor_ranges, &mut registers, &mut diagnostics);
+                    continue;
+                },
+                RootParamType::Constant{shader_register, register_space, ..} =>
+                    (DescriptorRangeType::CBV, shader_register, register_space),
+                RootParamType::Cbv{shader_register, register_space, flags} |
+                RootParamType::Srv{shader_register, register_space, flags} |
+                RootParamType::Uav{shader_register, register_space, flags} => {
+                    if (flags.bits() & DATA_FLAGS).count_ones() > 1 {
+                        diagnostics.push(RootSigDiagnostic::InvalidFlags{
+                            binding: RootSigBinding::Param{param},
+                        });
+                    }
+                    let range_type = match root_param.param_type {
+                        RootParamType::Cbv{..} => DescriptorRangeType::CBV,
+                        RootParamType::Srv{..} => DescriptorRangeType::SRV,
+                        _ => DescriptorRangeType::UAV,
+                    };
+                    (range_type, shader_register, register_space)
+                },
+            };
+            registers.push(Registers{
+                binding: RootSigBinding::Param{param},
+                range_type, register_space, visibility,
+                start: shader_register as u64,
+                end: shader_register as u64 + 1,
+            });
+        }
+
+        for (sampler, desc) in self.static_samplers.iter().enumerate() {
+            registers.push(Registers{
+                binding: RootSigBinding::StaticSampler{sampler: sampler as u32},
+                range_type: DescriptorRangeType::SAMPLER,
+                register_space: desc.register_space,
+                visibility: desc.visibility,
+                start: desc.shader_register as u64,
+                end: desc.shader_register as u64 + 1,
+            });
+        }
+
+        for (i, registers_a) in registers.iter().enumerate() {
+            for registers_b in registers[i + 1..].iter() {
+                if registers_a.overlaps(registers_b) {
+                    diagnostics.push(RootSigDiagnostic::OverlappingRegisters{
+                        binding: registers_a.binding,
+                        other: registers_b.binding,
+                        range_type: registers_a.range_type,
+                        register_space: registers_a.register_space,
+                    });
+                }
+            }
+        }
+
+        if diagnostics.is_empty() { Ok(()) } else { Err(diagnostics) }
+    }
+}
+
+/// check a descriptor table, collecting the registers of its ranges
+fn validate_table(
+    param: u32, visibility: ShaderVisibility, descriptor_ranges: &[DescriptorRange],
+    registers: &mut Vec<Registers>, diagnostics: &mut Vec<RootSigDiagnostic>
+) {
+    let samplers = descriptor_ranges.iter()
+        .filter(|range| range.range_type == DescriptorRangeType::SAMPLER)
+        .count();
+    if samplers > 0 && samplers < descriptor_ranges.len() {
+        diagnostics.push(RootSigDiagnostic::MixedSamplerTable{param});
+    }
+
+    for (range, desc) in descriptor_ranges.iter().enumerate() {
+        let range = range as u32;
+        let binding = RootSigBinding::Range{param, range};
+        let start = desc.base_shader_register as u64;
+        let end = if desc.num_descriptors == UNBOUNDED_RANGE {
+            // the offset of an appended range can't follow an unbounded one,
+            // ranges at explicit offsets can
+            let next = descriptor_ranges.get(range as usize + 1);
+            if next.is_some_and(|next| next.offset_from_table_start == DESCRIPTOR_RANGE_OFFSET_APPEND) {
+                diagnostics.push(RootSigDiagnostic::UnboundedRangeNotLast{param, range});
+            }
+            REGISTER_END
+        } else {
+            start + desc.num_descriptors as u64
+        };
+        if desc.num_descriptors == 0 {
+            diagnostics.push(RootSigDiagnostic::EmptyRange{param, range});
+        } else if end > REGISTER_END {
+            diagnostics.push(RootSigDiagnostic::RegisterOverflow{param, range});
+        }
+
+        let flags = desc.flags;
+        let data_flags = flags.bits() & DATA_FLAGS;
+        let invalid_flags = data_flags.count_ones() > 1 ||
+            (desc.range_type == DescriptorRangeType::SAMPLER && data_flags != 0) ||
+            (flags.contains(DescriptorRangeFlags::DESCRIPTORS_VOLATILE) &&
+                flags.contains(DescriptorRangeFlags::DATA_STATIC));
+        if invalid_flags {
+            diagnostics.push(RootSigDiagnostic::InvalidFlags{binding});
+        }
+
+        registers.push(Registers{
+            binding, visibility, start, end,
+            range_type: desc.range_type,
+            register_space: desc.register_space,
+        });
+    }
+}
+
+/// `num_descriptors` of an unbounded descriptor range
+const UNBOUNDED_RANGE: u32 = 0xffff_ffff;
+/// `offset_from_table_start` of a range following the previous one
+const DESCRIPTOR_RANGE_OFFSET_APPEND: u32 = 0xffff_ffff;
+/// one past the last register
+const REGISTER_END: u64 = 1 << 32;
+
+/// the mutually exclusive data flags, shared by descriptor ranges and
+/// root descriptors
+const DATA_FLAGS: u32 = 0x2 | 0x4 | 0x8;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pipeline::sampler::Filter;
+
+    fn range(range_type: DescriptorRangeType, num_descriptors: u32, base_shader_register: u32) -> DescriptorRange {
+        DescriptorRange{
+            range_type, num_descriptors, base_shader_register,
+            register_space: 0, offset_from_table_start: DESCRIPTOR_RANGE_OFFSET_APPEND,
+            flags: DescriptorRangeFlags::NONE,
+        }
+    }
+
+    fn table(visibility: ShaderVisibility, ranges: &[DescriptorRange]) -> RootParam {
+        RootParam{
+            visibility,
+            param_type: RootParamType::DescriptorTable{descriptor_ranges: ranges.iter().cloned().collect()},
+        }
+    }
+
+    fn cbv(visibility: ShaderVisibility, shader_register: u32, flags: RootDescriptorFlags) -> RootParam {
+        RootParam{
+            visibility,
+            param_type: RootParamType::Cbv{shader_register, register_space: 0, flags},
+        }
+    }
+
+    fn constants(shader_register: u32, num_32bit_values: u32) -> RootParam {
+        RootParam{
+            visibility: ShaderVisibility::ALL,
+            param_type: RootParamType::Constant{shader_register, register_space: 0, num_32bit_values},
+        }
+    }
+
+    fn builder(root_params: Vec<RootParam>) -> RootSigBuilder {
+        let mut builder = RootSigBuilder::new();
+        builder.root_params.extend(root_params);
+        builder
+    }
+
+    #[test]
+    fn dword_cost() {
+        assert_eq!(RootSigBuilder::new().dword_cost(), 0);
+        let builder = builder(vec![
+            table(ShaderVisibility::ALL, &[
+                range(DescriptorRangeType::SRV, 4, 0), range(DescriptorRangeType::UAV, 1, 0),
+            ]),
+            constants(0, 5),
+            cbv(ShaderVisibility::ALL, 1, RootDescriptorFlags::NONE),
+            RootParam{
+                visibility: ShaderVisibility::ALL,
+                param_type: RootParamType::Srv{shader_register: 4, register_space: 0, flags: RootDescriptorFlags::NONE},
+            },
+            RootParam{
+                visibility: ShaderVisibility::ALL,
+                param_type: RootParamType::Uav{shader_register: 1, register_space: 0, flags: RootDescriptorFlags::NONE},
+            },
+        ]);
+        assert_eq!(builder.dword_cost(), 1 + 5 + 2 + 2 + 2);
+        assert_eq!(builder.validate(), Ok(()));
+
+        let builder = self::builder(vec![constants(0, u32::MAX), constants(1, 1)]);
+        assert_eq!(builder.dword_cost(), u32::MAX);
+    }
+
+    #[test]
+    fn too_large() {
+        let fits = builder(vec![constants(0, 62), cbv(ShaderVisibility::ALL, 1, RootDescriptorFlags::NONE)]);
+        assert_eq!(fits.dword_cost(), ROOT_SIG_MAX_DWORDS);
+        assert_eq!(fits.validate(), Ok(()));
+
+        let builder = builder(vec![constants(0, 63), cbv(ShaderVisibility::ALL, 1, RootDescriptorFlags::NONE)]);
+        assert_eq!(builder.validate(), Err(vec![RootSigDiagnostic::TooLarge{dwords: 65}]));
+    }
+
+    #[test]
+    fn overlapping_registers() {
+        // a root cbv and a table range, visible to the pixel shader
+        let builder = builder(vec![
+            cbv(ShaderVisibility::PIXEL, 2, RootDescriptorFlags::NONE),
+            table(ShaderVisibility::ALL, &[range(DescriptorRangeType::CBV, 4, 0)]),
+        ]);
+        assert_eq!(builder.validate(), Err(vec![RootSigDiagnostic::OverlappingRegisters{
+            binding: RootSigBinding::Param{param: 0},
+            other: RootSigBinding::Range{param: 1, range: 0},
+            range_type: DescriptorRangeType::CBV,
+            register_space: 0,
+        }]));
+
+        // stages don't share registers
+        let builder = self::builder(vec![
+            cbv(ShaderVisibility::PIXEL, 2, RootDescriptorFlags::NONE),
+            table(ShaderVisibility::VERTEX, &[range(DescriptorRangeType::CBV, 4, 0)]),
+        ]);
+        assert_eq!(builder.validate(), Ok(()));
+
+        // nor do register types and spaces
+        let mut other_space = range(DescriptorRangeType::CBV, 4, 0);
+        other_space.register_space = 1;
+        let builder = self::builder(vec![
+            cbv(ShaderVisibility::ALL, 2, RootDescriptorFlags::NONE),
+            table(ShaderVisibility::ALL, &[range(DescriptorRangeType::SRV, 4, 0), other_space]),
+        ]);
+        assert_eq!(builder.validate(), Ok(()));
+
+        // unbounded ranges take every following register
+        let builder = self::builder(vec![
+            table(ShaderVisibility::ALL, &[range(DescriptorRangeType::SRV, UNBOUNDED_RANGE, 8)]),
+            RootParam{
+                visibility: ShaderVisibility::ALL,
+                param_type: RootParamType::Srv{shader_register: u32::MAX, register_space: 0, flags: RootDescriptorFlags::NONE},
+            },
+        ]);
+        assert_eq!(builder.validate().unwrap_err().len(), 1);
+    }
+
+    #[test]
+    fn static_sampler_visibility() {
+        let mut builder = builder(vec![
+            table(ShaderVisibility::VERTEX, &[range(DescriptorRangeType::SAMPLER, 2, 0)]),
+        ]);
+        let mut sampler = StaticSamplerDesc::new(Filter::MIN_MAG_MIP_LINEAR, 1, 0);
+        sampler.visibility = ShaderVisibility::PIXEL;
+        builder.static_samplers.push(sampler);
+        assert_eq!(builder.validate(), Ok(()));
+
+        builder.static_samplers[0].visibility = ShaderVisibility::VERTEX;
+        assert_eq!(builder.validate(), Err(vec![RootSigDiagnostic::OverlappingRegisters{
+            binding: RootSigBinding::Range{param: 0, range: 0},
+            other: RootSigBinding::StaticSampler{sampler: 0},
+            range_type: DescriptorRangeType::SAMPLER,
+            register_space: 0,
+        }]));
+
+        builder.static_samplers[0].visibility = ShaderVisibility::ALL;
+        assert_eq!(builder.validate().unwrap_err().len(), 1);
+    }
+
+    #[test]
+    fn empty_range() {
+        let builder = builder(vec![
+            table(ShaderVisibility::ALL, &[range(DescriptorRangeType::SRV, 1, 0), range(DescriptorRangeType::SRV, 0, 1)]),
+        ]);
+        assert_eq!(builder.validate(), Err(vec![RootSigDiagnostic::EmptyRange{param: 0, range: 1}]));
+    }
+
+    #[test]
+    fn register_overflow() {
+        let builder = builder(vec![
+            table(ShaderVisibility::ALL, &[range(DescriptorRangeType::UAV, 2, u32::MAX - 1)]),
+        ]);
+        assert_eq!(builder.validate(), Ok(()));
+
+        let builder = self::builder(vec![
+            table(ShaderVisibility::ALL, &[range(DescriptorRangeType::UAV, 3, u32::MAX - 1)]),
+        ]);
+        assert_eq!(builder.validate(), Err(vec![RootSigDiagnostic::RegisterOverflow{param: 0, range: 0}]));
+    }
+
+    #[test]
+    fn mixed_sampler_table() {
+        let builder = builder(vec![
+            table(ShaderVisibility::ALL, &[range(DescriptorRangeType::SRV, 1, 0), range(DescriptorRangeType::SAMPLER, 1, 0)]),
+        ]);
+        assert_eq!(builder.validate(), Err(vec![RootSigDiagnostic::MixedSamplerTable{param: 0}]));
+    }
+
+    #[test]
+    fn unbounded_range_not_last() {
+        let unbounded = range(DescriptorRangeType::SRV, UNBOUNDED_RANGE, 0);
+        let mut explicit = range(DescriptorRangeType::SRV, 1, 0);
+        explicit.register_space = 1;
+        explicit.offset_from_table_start = 0;
+
+        // ranges at explicit offsets may follow an unbounded range
+        let builder = builder(vec![
+            table(ShaderVisibility::ALL, &[unbounded, explicit]),
+        ]);
+        assert_eq!(builder.validate(), Ok(()));
+
+        let mut appended = explicit;
+        appended.base_shader_register = 1;
+        appended.offset_from_table_start = DESCRIPTOR_RANGE_OFFSET_APPEND;
+        let builder = self::builder(vec![
+            table(ShaderVisibility::ALL, &[unbounded, appended]),
+        ]);
+        assert_eq!(builder.validate(), Err(vec![RootSigDiagnostic::UnboundedRangeNotLast{param: 0, range: 0}]));
+
+        // only the next range matters, later ones append to it
+        let builder = self::builder(vec![
+            table(ShaderVisibility::ALL, &[unbounded, explicit, appended]),
+        ]);
+        assert_eq!(builder.validate(), Ok(()));
+    }
+
+    #[test]
+    fn invalid_flags() {
+        let mut volatile_static = range(DescriptorRangeType::SRV, 1, 0);
+        volatile_static.flags = DescriptorRangeFlags::DESCRIPTORS_VOLATILE | DescriptorRangeFlags::DATA_STATIC;
+        let mut two_data_flags = range(DescriptorRangeType::SRV, 1, 1);
+        two_data_flags.flags = DescriptorRangeFlags::DATA_VOLATILE | DescriptorRangeFlags::DATA_STATIC;
+        let mut sampler_data = range(DescriptorRangeType::SAMPLER, 1, 0);
+        sampler_data.flags = DescriptorRangeFlags::DATA_STATIC;
+        let mut sampler_volatile = range(DescriptorRangeType::SAMPLER, 1, 1);
+        sampler_volatile.flags = DescriptorRangeFlags::DESCRIPTORS_VOLATILE;
+        let mut valid = range(DescriptorRangeType::SRV, 1, 2);
+        valid.flags = DescriptorRangeFlags::DESCRIPTORS_VOLATILE | DescriptorRangeFlags::DATA_VOLATILE;
+
+        let builder = builder(vec![
+            table(ShaderVisibility::ALL, &[volatile_static, two_data_flags, valid]),
+            table(ShaderVisibility::ALL, &[sampler_data, sampler_volatile]),
+            cbv(ShaderVisibility::ALL, 0, RootDescriptorFlags::DATA_VOLATILE | RootDescriptorFlags::DATA_STATIC_WHILE_SET_AT_EXECUTE),
+            cbv(ShaderVisibility::ALL, 1, RootDescriptorFlags::DATA_STATIC),
+        ]);
+        assert_eq!(builder.validate(), Err(vec![
+            RootSigDiagnostic::InvalidFlags{binding: RootSigBinding::Range{param: 0, range: 0}},
+            RootSigDiagnostic::InvalidFlags{binding: RootSigBinding::Range{param: 0, range: 1}},
+            RootSigDiagnostic::InvalidFlags{binding: RootSigBinding::Range{param: 1, range: 0}},
+            RootSigDiagnostic::InvalidFlags{binding: RootSigBinding::Param{param: 2}},
+        ]));
+    }
+
+    #[test]
+    fn params_and_messages() {
+        let overlap = RootSigDiagnostic::OverlappingRegisters{
+            binding: RootSigBinding::StaticSampler{sampler: 1},
+            other: RootSigBinding::Range{param: 3, range: 2},
+            range_type: DescriptorRangeType::SAMPLER,
+            register_space: 4,
+        };
+        assert_eq!(overlap.param(), Some(3));
+        assert_eq!(overlap.to_string(),
+            "static sampler 1 and range 2 of parameter 3 both bind SAMPLER registers in space 4 to a common stage");
+        assert_eq!(RootSigDiagnostic::TooLarge{dwords: 65}.param(), None);
+        assert_eq!(RootSigDiagnostic::TooLarge{dwords: 65}.to_string(),
+            "the root signature takes 65 DWORDs, at most 64 are supported");
+        assert_eq!(RootSigDiagnostic::UnboundedRangeNotLast{param: 1, range: 0}.to_string(),
+            "unbounded range 0 of parameter 1 is followed by an appended range");
+        let flags = RootSigDiagnostic::InvalidFlags{binding: RootSigBinding::Param{param: 2}};
+        assert_eq!(flags.param(), Some(2));
+        assert_eq!(flags.to_string(), "parameter 2 has invalid descriptor or data flags");
+    }
+}
@@ -13,6 +13,7 @@ use super::*;
 /// describes a static sampler
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StaticSamplerDesc {
     /// filtering method
     pub filter: Filter,
@@ -38,10 +39,14 @@ pub struct StaticSamplerDesc {
     pub shader_register: u32,
     /// register space
     pub register_space: u32,
+    /// shader stages the sampler is visible to
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub visibility: rootsig::ShaderVisibility,
 }
 
 impl StaticSamplerDesc {
-    /// construct a new description with the given filter and default options
+    /// construct a new description with the given filter and default
+    /// options, visible to all stages
     #[inline]
     pub fn new(filter: Filter, shader_register: u32, register_space: u32) -> StaticSamplerDesc {
         StaticSamplerDesc{
@@ -56,67 +61,66 @@ impl StaticSamplerDesc {
             max_lod: 1.0f32,
             shader_register,
             register_space,
+            visibility: rootsig::ShaderVisibility::ALL,
         }
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// filtering options for texture sampling. [more info](https://msdn.microsoft.com/library/windows/desktop/dn770367(v=vs.85).aspx)
-    #[repr(C)]
-    pub struct Filter: u32 {
-        const MIN_MAG_MIP_POINT                           = 0;
-        const MIN_MAG_POINT_MIP_LINEAR                    = 0x1;
-        const MIN_POINT_MAG_LINEAR_MIP_POINT              = 0x4;
-        const MIN_POINT_MAG_MIP_LINEAR                    = 0x5;
-        const MIN_LINEAR_MAG_MIP_POINT                    = 0x10;
-        const MIN_LINEAR_MAG_POINT_MIP_LINEAR             = 0x11;
-        const MIN_MAG_LINEAR_MIP_POINT                    = 0x14;
-        const MIN_MAG_MIP_LINEAR                          = 0x15;
-        const ANISOTROPIC                                 = 0x55;
-        const COMPARISON_MIN_MAG_MIP_POINT                = 0x80;
-        const COMPARISON_MIN_MAG_POINT_MIP_LINEAR         = 0x81;
-        const COMPARISON_MIN_POINT_MAG_LINEAR_MIP_POINT   = 0x84;
-        const COMPARISON_MIN_POINT_MAG_MIP_LINEAR         = 0x85;
-        const COMPARISON_MIN_LINEAR_MAG_MIP_POINT         = 0x90;
-        const COMPARISON_MIN_LINEAR_MAG_POINT_MIP_LINEAR  = 0x91;
-        const COMPARISON_MIN_MAG_LINEAR_MIP_POINT         = 0x94;
-        const COMPARISON_MIN_MAG_MIP_LINEAR               = 0x95;
-        const COMPARISON_ANISOTROPIC                      = 0xd5;
-        const MINIMUM_MIN_MAG_MIP_POINT                   = 0x100;
-        const MINIMUM_MIN_MAG_POINT_MIP_LINEAR            = 0x101;
-        const MINIMUM_MIN_POINT_MAG_LINEAR_MIP_POINT      = 0x104;
-        const MINIMUM_MIN_POINT_MAG_MIP_LINEAR            = 0x105;
-        const MINIMUM_MIN_LINEAR_MAG_MIP_POINT            = 0x110;
-        const MINIMUM_MIN_LINEAR_MAG_POINT_MIP_LINEAR     = 0x111;
-        const MINIMUM_MIN_MAG_LINEAR_MIP_POINT            = 0x114;
-        const MINIMUM_MIN_MAG_MIP_LINEAR                  = 0x115;
-        const MINIMUM_ANISOTROPIC                         = 0x155;
-        const MAXIMUM_MIN_MAG_MIP_POINT                   = 0x180;
-        const MAXIMUM_MIN_MAG_POINT_MIP_LINEAR            = 0x181;
-        const MAXIMUM_MIN_POINT_MAG_LINEAR_MIP_POINT      = 0x184;
-        const MAXIMUM_MIN_POINT_MAG_MIP_LINEAR            = 0x185;
-        const MAXIMUM_MIN_LINEAR_MAG_MIP_POINT            = 0x190;
-        const MAXIMUM_MIN_LINEAR_MAG_POINT_MIP_LINEAR     = 0x191;
-        const MAXIMUM_MIN_MAG_LINEAR_MIP_POINT            = 0x194;
-        const MAXIMUM_MIN_MAG_MIP_LINEAR                  = 0x195;
-        const MAXIMUM_ANISOTROPIC                         = 0x1d5;
+    pub enum Filter {
+        MIN_MAG_MIP_POINT                           = 0,
+        MIN_MAG_POINT_MIP_LINEAR                    = 0x1,
+        MIN_POINT_MAG_LINEAR_MIP_POINT              = 0x4,
+        MIN_POINT_MAG_MIP_LINEAR                    = 0x5,
+        MIN_LINEAR_MAG_MIP_POINT                    = 0x10,
+        MIN_LINEAR_MAG_POINT_MIP_LINEAR             = 0x11,
+        MIN_MAG_LINEAR_MIP_POINT                    = 0x14,
+        MIN_MAG_MIP_LINEAR                          = 0x15,
+        ANISOTROPIC                                 = 0x55,
+        COMPARISON_MIN_MAG_MIP_POINT                = 0x80,
+        COMPARISON_MIN_MAG_POINT_MIP_LINEAR         = 0x81,
+        COMPARISON_MIN_POINT_MAG_LINEAR_MIP_POINT   = 0x84,
+        COMPARISON_MIN_POINT_MAG_MIP_LINEAR         = 0x85,
+        COMPARISON_MIN_LINEAR_MAG_MIP_POINT         = 0x90,
+        COMPARISON_MIN_LINEAR_MAG_POINT_MIP_LINEAR  = 0x91,
+        COMPARISON_MIN_MAG_LINEAR_MIP_POINT         = 0x94,
+        COMPARISON_MIN_MAG_MIP_LINEAR               = 0x95,
+        COMPARISON_ANISOTROPIC                      = 0xd5,
+        MINIMUM_MIN_MAG_MIP_POINT                   = 0x100,
+        MINIMUM_MIN_MAG_POINT_MIP_LINEAR            = 0x101,
+        MINIMUM_MIN_POINT_MAG_LINEAR_MIP_POINT      = 0x104,
+        MINIMUM_MIN_POINT_MAG_MIP_LINEAR            = 0x105,
+        MINIMUM_MIN_LINEAR_MAG_MIP_POINT            = 0x110,
+        MINIMUM_MIN_LINEAR_MAG_POINT_MIP_LINEAR     = 0x111,
+        MINIMUM_MIN_MAG_LINEAR_MIP_POINT            = 0x114,
+        MINIMUM_MIN_MAG_MIP_LINEAR                  = 0x115,
+        MINIMUM_ANISOTROPIC                         = 0x155,
+        MAXIMUM_MIN_MAG_MIP_POINT                   = 0x180,
+        MAXIMUM_MIN_MAG_POINT_MIP_LINEAR            = 0x181,
+        MAXIMUM_MIN_POINT_MAG_LINEAR_MIP_POINT      = 0x184,
+        MAXIMUM_MIN_POINT_MAG_MIP_LINEAR            = 0x185,
+        MAXIMUM_MIN_LINEAR_MAG_MIP_POINT            = 0x190,
+        MAXIMUM_MIN_LINEAR_MAG_POINT_MIP_LINEAR     = 0x191,
+        MAXIMUM_MIN_MAG_LINEAR_MIP_POINT            = 0x194,
+        MAXIMUM_MIN_MAG_MIP_LINEAR                  = 0x195,
+        MAXIMUM_ANISOTROPIC                         = 0x1d5,
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// texture addressing modes when coordinates outside normalized boundary
-    #[repr(C)]
-    pub struct TextureAddressMode: u32 {
+    pub enum TextureAddressMode {
         /// tile at every integer junction, essentially repeating the textures
-        const WRAP = 1;
+        WRAP = 1,
         /// filp at every integer junction
-        const MIRROR = 2;
+        MIRROR = 2,
         /// clamp to values at normalized boundary
-        const CLAMP = 3;
+        CLAMP = 3,
         /// set to a "border color"
-        const BORDER = 4;
+        BORDER = 4,
         /// take the absolution value of texture coordinates, then clamp to the boundary
-        const MIRROR_ONCE = 5;
+        MIRROR_ONCE = 5,
     }
 }
 
@@ -127,13 +131,12 @@ impl Default for TextureAddressMode {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// border colors
-    #[repr(C)]
-    pub struct BorderColor: u32 {
-        const TRANSPARENT_BLACK = 0;
-        const OPAQUE_BLACK = BorderColor::TRANSPARENT_BLACK.bits + 1;
-        const OPAQUE_WHITE = BorderColor::OPAQUE_BLACK.bits + 1;
+    pub enum BorderColor {
+        TRANSPARENT_BLACK = 0,
+        OPAQUE_BLACK = 1,
+        OPAQUE_WHITE = 2,
     }
 }
 
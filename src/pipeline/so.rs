@@ -11,8 +11,10 @@
 use smallvec::SmallVec;
 use std::os::raw::c_char;
 use std::marker::PhantomData;
-use std::ffi::CStr;
-use resource::{RawResource, GpuVAddress};
+use std::ffi::{CStr, CString};
+#[cfg(windows)]
+use resource::RawResource;
+use resource::GpuVAddress;
 
 /// stream output buffer view
 #[repr(C)]
@@ -24,6 +26,7 @@ pub struct StreamOutputBufferView {
 }
 
 // TODO: find out a nicer way to deal with resources
+#[cfg(windows)]
 impl StreamOutputBufferView {
     pub fn new(resource: &mut RawResource, size: u64, filled_size: u64) -> Self {
         StreamOutputBufferView{
@@ -60,6 +63,7 @@ impl<'a> DescBuilder<'a> {
     }
 
     /// finalization
+    #[cfg(windows)]
     #[inline]
     pub fn build(&self) -> (::winapi::D3D12_STREAM_OUTPUT_DESC, PhantomData<&DescBuilder>) {
         (::winapi::D3D12_STREAM_OUTPUT_DESC{
@@ -97,3 +101,71 @@ pub struct DeclarationEntry<'a> {
     pub output_slot: u8,
     _pd: PhantomData<&'a CStr>, // TODO: check if legit
 }
+
+impl<'a> DeclarationEntry<'a> {
+    /// get the semantic name, `None` for a gap in the output
+    #[inline]
+    pub fn get_semantic_name(&self) -> Option<&'a CStr> {
+        if self.semantic_name.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(self.semantic_name) })
+        }
+    }
+}
+
+/// an owned stream output description, see `DescBuilder`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StreamOutputDesc {
+    pub entries: SmallVec<[Declaration; 8]>,
+    /// buffer strides
+    pub strides: SmallVec<[u32; 8]>,
+    /// index of the stream to be sent to the rasterizer stage
+    pub rasterized_stream: u32,
+}
+
+impl StreamOutputDesc {
+    /// get a builder borrowing semantic names from `self`
+    pub fn to_builder(&self) -> DescBuilder<'_> {
+        DescBuilder{
+            entries: self.entries.iter().map(|entry| entry.as_entry()).collect(),
+            strides: self.strides.clone(),
+            rasterized_stream: self.rasterized_stream,
+        }
+    }
+}
+
+/// an owned stream output entry, see `DeclarationEntry`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Declaration {
+    /// zero based stream index
+    pub stream: u32,
+    /// semantic name of the element, `None` for a gap in the output
+    pub semantic_name: Option<CString>,
+    /// zero based element index
+    pub semantic_index: u32,
+    /// component of the entry to begin writing to. valid in [0..3]
+    pub start_component: u8,
+    /// number of components of the entry to writing to. valid in [1..4]
+    pub component_count: u8,
+    /// associated stream output buffer that is bound to the pipeline. valid in [0..3]
+    pub output_slot: u8,
+}
+
+impl Declaration {
+    /// get a declaration entry borrowing the semantic name from `self`
+    #[inline]
+    pub fn as_entry(&self) -> DeclarationEntry<'_> {
+        DeclarationEntry{
+            stream: self.stream,
+            semantic_name: self.semantic_name.as_ref().map_or(
+                ::std::ptr::null(), |name| name.as_ptr()
+            ),
+            semantic_index: self.semantic_index,
+            start_component: self.start_component,
+            component_count: self.component_count,
+            output_slot: self.output_slot,
+            _pd: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,584 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! persistent storage of cached pipeline state blobs.
+//!
+//! a `PipelineBlobStore` maps the `stable_hash` of pipeline descriptions
+//! to the blobs returned by `cached()`. drivers only accept blobs created
+//! by the same adapter and driver, so a store is bound to an
+//! `AdapterIdentity`, and loading a store saved with a different identity
+//! fails with `PipelineStoreError::IdentityMismatch`.
+//!
+//! # file format
+//!
+//! all integers are little endian.
+//!
+//! ```text
+//! magic           b"RDPC"
+//! format version  u32
+//! crate version   u32 length, followed by utf-8 bytes
+//! adapter luid    u64
+//! vendor id       u32
+//! device id       u32
+//! driver version  u64
+//! entry count     u32
+//! header crc32    u32, of all the bytes above
+//! entries         entry count times:
+//!     key         u64
+//!     length      u32
+//!     crc32       u32, of the key, length and blob
+//!     blob        length bytes
+//! ```
+//!
+//! the crate version is part of the header since `stable_hash` is only
+//! stable across runs of the same version of this crate.
+
+#[cfg(windows)]
+use super::*;
+#[cfg(windows)]
+use device::Device;
+#[cfg(windows)]
+use factory::Factory;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const MAGIC: [u8; 4] = *b"RDPC";
+const FORMAT_VERSION: u32 = 1;
+const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// distinguishes the temporary files of concurrent saves within a process
+static SAVE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// identity of the adapter and driver cached blobs are created with
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AdapterIdentity {
+    /// locally unique id of the adapter
+    pub luid: u64,
+    /// PCI ID of the hardware vendor
+    pub vendor_id: u32,
+    /// PCI ID of the hardware device
+    pub device_id: u32,
+    /// user mode driver version
+    pub driver_version: u64,
+}
+
+#[cfg(windows)]
+impl AdapterIdentity {
+    /// query the identity of the adapter `device` is created on
+    pub fn query(factory: &mut Factory, device: &mut Device) -> Result<AdapterIdentity, WinError> {
+        let luid = device.get_adapter_luid();
+        let mut adapter = factory.enum_adapter_by_luid(luid)?;
+        let desc = adapter.get_desc()?;
+        let driver_version = adapter.get_driver_version()?;
+        Ok(AdapterIdentity{
+            luid: (u64::from(luid.HighPart as u32) << 32) | u64::from(luid.LowPart),
+            vendor_id: desc.vendor_id,
+            device_id: desc.device_id,
+            driver_version,
+        })
+    }
+}
+
+/// errors loading a `PipelineBlobStore`
+#[derive(Debug)]
+pub enum PipelineStoreError {
+    /// failed to read or write the file
+    Io(io::Error),
+    /// the file is not a pipeline blob store
+    BadMagic,
+    /// the file is of an unknown format version
+    UnsupportedVersion(u32),
+    /// the file is written by a different version of this crate
+    CrateVersionMismatch(String),
+    /// the file is written for a different adapter or driver
+    IdentityMismatch(AdapterIdentity),
+    /// the header or an entry is corrupted
+    Checksum,
+    /// the file ends unexpectedly
+    Truncated,
+}
+
+impl ::std::fmt::Display for PipelineStoreError {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        match *self {
+            PipelineStoreError::Io(ref err) => write!(f, "io error: {}", err),
+            PipelineStoreError::BadMagic => write!(f, "not a pipeline blob store"),
+            PipelineStoreError::UnsupportedVersion(version) => write!(f, "unsupported format version {}", version),
+            PipelineStoreError::CrateVersionMismatch(ref version) => write!(f, "written by crate version {}", version),
+            PipelineStoreError::IdentityMismatch(ref identity) => write!(f, "written for a different adapter: {:?}", identity),
+            PipelineStoreError::Checksum => write!(f, "checksum mismatch"),
+            PipelineStoreError::Truncated => write!(f, "unexpected end of file"),
+        }
+    }
+}
+
+impl ::std::error::Error for PipelineStoreError {}
+
+impl From<io::Error> for PipelineStoreError {
+    #[inline]
+    fn from(err: io::Error) -> PipelineStoreError {
+        PipelineStoreError::Io(err)
+    }
+}
+
+/// cached pipeline state blobs, keyed by the `stable_hash` of their descriptions
+#[derive(Clone, Debug)]
+pub struct PipelineBlobStore {
+    identity: AdapterIdentity,
+    blobs: HashMap<u64, Vec<u8>>,
+}
+
+impl PipelineBlobStore {
+    /// construct an empty store for the given adapter
+    #[inline]
+    pub fn new(identity: AdapterIdentity) -> Self {
+        PipelineBlobStore{identity, blobs: HashMap::new()}
+    }
+
+    /// get the adapter identity this store is bound to
+    #[inline]
+    pub fn identity(&self) -> AdapterIdentity {
+        self.identity
+    }
+
+    #[inline]
+    pub fn get(&self, key: u64) -> Option<&[u8]> {
+        self.blobs.get(&key).map(|blob| &blob[..])
+    }
+
+    /// insert a blob for `key`, returning the previous one if any
+    #[inline]
+    pub fn insert(&mut self, key: u64, blob: Vec<u8>) -> Option<Vec<u8>> {
+        self.blobs.insert(key, blob)
+    }
+
+    #[inline]
+    pub fn remove(&mut self, key: u64) -> Option<Vec<u8>> {
+        self.blobs.remove(&key)
+    }
+
+    /// number of stored blobs
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+
+    /// encode the store into bytes. entries are sorted by key, so that
+    /// equal stores are encoded identically.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&MAGIC);
+        put_u32(&mut bytes, FORMAT_VERSION);
+        put_u32(&mut bytes, CRATE_VERSION.len() as u32);
+        bytes.extend_from_slice(CRATE_VERSION.as_bytes());
+        put_u64(&mut bytes, self.identity.luid);
+        put_u32(&mut bytes, self.identity.vendor_id);
+        put_u32(&mut bytes, self.identity.device_id);
+        put_u64(&mut bytes, self.identity.driver_version);
+        put_u32(&mut bytes, self.blobs.len() as u32);
+        let crc = crc32(&bytes);
+        put_u32(&mut bytes, crc);
+
+        let mut keys: Vec<_> = self.blobs.keys().cloned().collect();
+        keys.sort();
+        for key in keys {
+            let blob = &self.blobs[&key];
+            let mut head = Vec::with_capacity(12);
+            put_u64(&mut head, key);
+            put_u32(&mut head, blob.len() as u32);
+            let crc = crc32_update(crc32(&head), blob);
+            bytes.extend_from_slice(&head);
+            put_u32(&mut bytes, crc);
+            bytes.extend_from_slice(blob);
+        }
+        bytes
+    }
+
+    /// decode a store from bytes, rejecting it if it isn't written for `identity`
+    pub fn decode(bytes: &[u8], identity: AdapterIdentity) -> Result<Self, PipelineStoreError> {
+        let mut reader = Reader{bytes, pos: 0};
+        if reader.take(4)? != MAGIC {
+            return Err(PipelineStoreError::BadMagic);
+        }
+        let version = reader.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(PipelineStoreError::UnsupportedVersion(version));
+        }
+        let crate_version_len = reader.u32()? as usize;
+        let crate_version = reader.take(crate_version_len)?;
+        let stored = AdapterIdentity{
+            luid: reader.u64()?,
+            vendor_id: reader.u32()?,
+            device_id: reader.u32()?,
+            driver_version: reader.u64()?,
+        };
+        let count = reader.u32()?;
+        let crc = crc32(&bytes[..reader.pos]);
+        if reader.u32()? != crc {
+            return Err(PipelineStoreError::Checksum);
+        }
+        if crate_version != CRATE_VERSION.as_bytes() {
+            return Err(PipelineStoreError::CrateVersionMismatch(
+                String::from_utf8_lossy(crate_version).into_owned()
+            ));
+        }
+        if stored != identity {
+            return Err(PipelineStoreError::IdentityMismatch(stored));
+        }
+
+        let mut store = PipelineBlobStore::new(identity);
+        for _ in 0..count {
+            let head = reader.take(12)?;
+            let mut head_reader = Reader{bytes: head, pos: 0};
+            let key = head_reader.u64()?;
+            let len = head_reader.u32()? as usize;
+            let crc = reader.u32()?;
+            let blob = reader.take(len)?;
+            if crc32_update(crc32(head), blob) != crc {
+                return Err(PipelineStoreError::Checksum);
+            }
+            store.blobs.insert(key, blob.to_vec());
+        }
+        Ok(store)
+    }
+
+    /// load a store from the file at `path`
+    pub fn load<P: AsRef<Path>>(path: P, identity: AdapterIdentity) -> Result<Self, PipelineStoreError> {
+        let bytes = fs::read(path)?;
+        PipelineBlobStore::decode(&bytes, identity)
+    }
+
+    /// load a store from the file at `path`, or construct an empty one if
+    /// the file is missing or rejected
+    #[inline]
+    pub fn load_or_new<P: AsRef<Path>>(path: P, identity: AdapterIdentity) -> Self {
+        PipelineBlobStore::load(path, identity)
+            .unwrap_or_else(|_| PipelineBlobStore::new(identity))
+    }
+
+    /// save the store to the file at `path`.
+    ///
+    /// the store is written to a temporary file next to `path` first, which
+    /// is then renamed over `path`, so that an interrupted save never leaves
+    /// a partially written store behind. the temporary file is named after
+    /// the process and a per process counter, and created only if it doesn't
+    /// exist, so that concurrent saves to the same path don't write to the
+    /// same temporary file; the last rename wins.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PipelineStoreError> {
+        let path = path.as_ref();
+        let mut tmp_name = path.file_name().map(|name| name.to_owned()).unwrap_or_default();
+        tmp_name.push(format!(
+            ".{}.{}.tmp", process::id(), SAVE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tmp_path: PathBuf = path.with_file_name(tmp_name);
+        let written = fs::OpenOptions::new().write(true).create_new(true).open(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(&self.encode())?;
+                file.sync_all()
+            })
+            .and_then(|()| fs::rename(&tmp_path, path));
+        written.map_err(|err| {
+            let _ = fs::remove_file(&tmp_path);
+            PipelineStoreError::Io(err)
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PipelineStoreError> {
+        if self.bytes.len() - self.pos < len {
+            return Err(PipelineStoreError::Truncated);
+        }
+        let ret = &self.bytes[self.pos..self.pos+len];
+        self.pos += len;
+        Ok(ret)
+    }
+
+    #[inline]
+    fn u32(&mut self) -> Result<u32, PipelineStoreError> {
+        let bytes = self.take(4)?;
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(bytes);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    #[inline]
+    fn u64(&mut self) -> Result<u64, PipelineStoreError> {
+        let bytes = self.take(8)?;
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        Ok(u64::from_le_bytes(buf))
+    }
+}
+
+#[inline]
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+#[inline]
+fn put_u64(bytes: &mut Vec<u8>, value: u64) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+/// crc32 (ieee 802.3) of `bytes`
+#[inline]
+fn crc32(bytes: &[u8]) -> u32 {
+    crc32_update(0, bytes)
+}
+
+/// continue a crc32 computation over `bytes`
+fn crc32_update(crc: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTITY: AdapterIdentity = AdapterIdentity{
+        luid: 0x0000_0001_0000_c0de,
+        vendor_id: 0x10de,
+        device_id: 0x2204,
+        driver_version: 0x001f_000f_000d_0e4c,
+    };
+
+    fn store() -> PipelineBlobStore {
+        let mut store = PipelineBlobStore::new(IDENTITY);
+        store.insert(0x2222, vec![4, 5]);
+        store.insert(0x1111, vec![1, 2, 3]);
+        store
+    }
+
+    /// a temporary directory removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path = ::std::env::temp_dir()
+                .join(format!("redirect-store-{}-{}", name, ::std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn file_format() {
+        let bytes = store().encode();
+        let mut expected = Vec::new();
+        expected.extend_from_slice(b"RDPC");
+        put_u32(&mut expected, FORMAT_VERSION);
+        put_u32(&mut expected, CRATE_VERSION.len() as u32);
+        expected.extend_from_slice(CRATE_VERSION.as_bytes());
+        put_u64(&mut expected, IDENTITY.luid);
+        put_u32(&mut expected, IDENTITY.vendor_id);
+        put_u32(&mut expected, IDENTITY.device_id);
+        put_u64(&mut expected, IDENTITY.driver_version);
+        put_u32(&mut expected, 2);
+        let crc = crc32(&expected);
+        put_u32(&mut expected, crc);
+        // entries sorted by key
+        for &(key, blob) in &[(0x1111u64, &[1u8, 2, 3][..]), (0x2222, &[4, 5][..])] {
+            let mut head = Vec::new();
+            put_u64(&mut head, key);
+            put_u32(&mut head, blob.len() as u32);
+            let mut data = head.clone();
+            data.extend_from_slice(blob);
+            expected.extend_from_slice(&head);
+            put_u32(&mut expected, crc32(&data));
+            expected.extend_from_slice(blob);
+        }
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = store().encode();
+        let decoded = PipelineBlobStore::decode(&bytes, IDENTITY).unwrap();
+        assert_eq!(decoded.identity(), IDENTITY);
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded.get(0x1111), Some(&[1, 2, 3][..]));
+        assert_eq!(decoded.get(0x2222), Some(&[4, 5][..]));
+        assert_eq!(decoded.encode(), bytes);
+    }
+
+    #[test]
+    fn rejects_corruption() {
+        let bytes = store().encode();
+        // two entry heads and crcs, and 5 bytes of blobs follow the header
+        let header_len = bytes.len() - 2 * 16 - 5;
+
+        // the entry count
+        let mut corrupted = bytes.clone();
+        corrupted[header_len - 5] ^= 1;
+        match PipelineBlobStore::decode(&corrupted, IDENTITY) {
+            Err(PipelineStoreError::Checksum) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        match PipelineBlobStore::decode(&corrupted, IDENTITY) {
+            Err(PipelineStoreError::Checksum) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+        match PipelineBlobStore::decode(&bytes[..bytes.len() - 1], IDENTITY) {
+            Err(PipelineStoreError::Truncated) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+        match PipelineBlobStore::decode(b"RDP", IDENTITY) {
+            Err(PipelineStoreError::Truncated) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+        match PipelineBlobStore::decode(b"DXBC\x01\x00\x00\x00", IDENTITY) {
+            Err(PipelineStoreError::BadMagic) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        match PipelineBlobStore::decode(&newer, IDENTITY) {
+            Err(PipelineStoreError::UnsupportedVersion(2)) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_other_crate_versions() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RDPC");
+        put_u32(&mut bytes, FORMAT_VERSION);
+        put_u32(&mut bytes, 5);
+        bytes.extend_from_slice(b"0.0.1");
+        put_u64(&mut bytes, IDENTITY.luid);
+        put_u32(&mut bytes, IDENTITY.vendor_id);
+        put_u32(&mut bytes, IDENTITY.device_id);
+        put_u64(&mut bytes, IDENTITY.driver_version);
+        put_u32(&mut bytes, 0);
+        let crc = crc32(&bytes);
+        put_u32(&mut bytes, crc);
+        match PipelineBlobStore::decode(&bytes, IDENTITY) {
+            Err(PipelineStoreError::CrateVersionMismatch(ref version)) if version == "0.0.1" => {},
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_other_adapters_and_drivers() {
+        let bytes = store().encode();
+        let others = [
+            AdapterIdentity{luid: 0x0000_0001_0000_beef, ..IDENTITY},
+            AdapterIdentity{vendor_id: 0x1002, ..IDENTITY},
+            AdapterIdentity{device_id: 0x2206, ..IDENTITY},
+            AdapterIdentity{driver_version: 0x001f_000f_000d_0e4d, ..IDENTITY},
+        ];
+        for other in &others {
+            match PipelineBlobStore::decode(&bytes, *other) {
+                Err(PipelineStoreError::IdentityMismatch(stored)) => assert_eq!(stored, IDENTITY),
+                result => panic!("unexpected {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn save_replaces_atomically() {
+        let dir = TempDir::new("save");
+        let path = dir.0.join("pipelines.bin");
+        store().save(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), store().encode());
+
+        let mut updated = store();
+        updated.remove(0x1111);
+        updated.insert(0x3333, vec![6]);
+        updated.save(&path).unwrap();
+        let loaded = PipelineBlobStore::load(&path, IDENTITY).unwrap();
+        assert_eq!(loaded.encode(), updated.encode());
+        // no temporary file is left behind
+        let names = fs::read_dir(&dir.0).unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![::std::ffi::OsString::from("pipelines.bin")]);
+    }
+
+    #[test]
+    fn concurrent_saves() {
+        let dir = TempDir::new("concurrent");
+        let path = dir.0.join("pipelines.bin");
+        ::std::thread::scope(|scope| {
+            for i in 0..8u8 {
+                let path = &path;
+                scope.spawn(move || {
+                    let mut store = store();
+                    store.insert(0x3333, vec![i; 4096]);
+                    store.save(path).unwrap();
+                });
+            }
+        });
+        // one of the saves, whole
+        let loaded = PipelineBlobStore::load(&path, IDENTITY).unwrap();
+        let blob = loaded.get(0x3333).unwrap();
+        assert_eq!(blob.len(), 4096);
+        assert!(blob.iter().all(|&byte| byte == blob[0]));
+        let names = fs::read_dir(&dir.0).unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![::std::ffi::OsString::from("pipelines.bin")]);
+    }
+
+    #[test]
+    fn load_or_new_starts_over() {
+        let dir = TempDir::new("load_or_new");
+        let path = dir.0.join("pipelines.bin");
+        assert!(PipelineBlobStore::load_or_new(&path, IDENTITY).is_empty());
+
+        store().save(&path).unwrap();
+        assert_eq!(PipelineBlobStore::load_or_new(&path, IDENTITY).len(), 2);
+        let other = AdapterIdentity{driver_version: 0, ..IDENTITY};
+        let reloaded = PipelineBlobStore::load_or_new(&path, other);
+        assert!(reloaded.is_empty());
+        assert_eq!(reloaded.identity(), other);
+
+        fs::write(&path, b"garbage").unwrap();
+        assert!(PipelineBlobStore::load_or_new(&path, IDENTITY).is_empty());
+    }
+}
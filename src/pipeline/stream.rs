@@ -0,0 +1,779 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! pipeline state subobject streams.
+//!
+//! a stream is a sequence of subobjects, each starting at a pointer aligned
+//! offset with its `SubobjectType`, followed by its description at the
+//! description's natural alignment. streams are the only way to reach
+//! pipeline features such as view instancing, depth bounds and mesh shaders.
+//!
+//! streams are encoded field by field in pure rust, so that the encoding is
+//! independent of padding bytes, and can be compared byte for byte.
+
+use super::*;
+use super::rootsig::RootSig;
+use format::Bool;
+use smallvec::SmallVec;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::os::raw::c_void;
+
+const PTR_ALIGNMENT: usize = size_of::<usize>();
+
+d3d_enum!{
+    /// type of a pipeline state subobject
+    pub enum SubobjectType {
+        ROOT_SIGNATURE        = 0,
+        VS                    = 1,
+        PS                    = 2,
+        DS                    = 3,
+        HS                    = 4,
+        GS                    = 5,
+        CS                    = 6,
+        STREAM_OUTPUT         = 7,
+        BLEND                 = 8,
+        SAMPLE_MASK           = 9,
+        RASTERIZER            = 10,
+        DEPTH_STENCIL         = 11,
+        INPUT_LAYOUT          = 12,
+        IB_STRIP_CUT_VALUE    = 13,
+        PRIMITIVE_TOPOLOGY    = 14,
+        RENDER_TARGET_FORMATS = 15,
+        DEPTH_STENCIL_FORMAT  = 16,
+        SAMPLE_DESC           = 17,
+        NODE_MASK             = 18,
+        CACHED_PSO            = 19,
+        FLAGS                 = 20,
+        DEPTH_STENCIL1        = 21,
+        VIEW_INSTANCING       = 22,
+        AS                    = 24,
+        MS                    = 25,
+    }
+}
+
+/// a subobject description the runtime can't represent
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SubobjectError {
+    /// more than 8 render target formats
+    TooManyRenderTargets(u32),
+    /// more than 4 view instance locations
+    TooManyViewInstances(u32),
+}
+
+impl fmt::Display for SubobjectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            SubobjectError::TooManyRenderTargets(num) =>
+                write!(f, "{} render target formats, at most 8 are supported", num),
+            SubobjectError::TooManyViewInstances(num) =>
+                write!(f, "{} view instance locations, at most 4 are supported", num),
+        }
+    }
+}
+
+impl error::Error for SubobjectError {}
+
+/// render target formats
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RtFormatArray {
+    pub formats: [DxgiFormat; 8],
+    pub num_render_targets: u32,
+}
+
+impl RtFormatArray {
+    /// construct from the formats of the bound render targets, failing with
+    /// `TooManyRenderTargets` for more than 8
+    #[inline]
+    pub fn new(formats: &[DxgiFormat]) -> Result<Self, SubobjectError> {
+        if formats.len() > 8 {
+            return Err(SubobjectError::TooManyRenderTargets(
+                u32::try_from(formats.len()).unwrap_or(u32::MAX)
+            ));
+        }
+        let mut ret = RtFormatArray{
+            formats: [::format::DXGI_FORMAT_UNKNOWN; 8],
+            num_render_targets: formats.len() as u32,
+        };
+        ret.formats[..formats.len()].copy_from_slice(formats);
+        Ok(ret)
+    }
+}
+
+/// depth stencil description with depth bounds test
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DepthStencilDesc1 {
+    pub desc: ds::DepthStencilDesc,
+    pub depth_bounds_test: Bool,
+}
+
+impl Default for DepthStencilDesc1 {
+    #[inline]
+    fn default() -> Self {
+        DepthStencilDesc1{
+            desc: Default::default(),
+            depth_bounds_test: false.into(),
+        }
+    }
+}
+
+/// viewport and render target array index a view instance renders to
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ViewInstanceLocation {
+    pub viewport_array_index: u32,
+    pub render_target_array_index: u32,
+}
+
+bitflags!{
+    /// view instancing flags
+    #[repr(C)]
+    pub struct ViewInstancingFlags: u32 {
+        const NONE                         = 0;
+        const ENABLE_VIEW_INSTANCE_MASKING = 0x1;
+    }
+}
+
+impl Default for ViewInstancingFlags {
+    #[inline]
+    fn default() -> Self {
+        ViewInstancingFlags::NONE
+    }
+}
+
+/// view instancing description
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ViewInstancingDesc {
+    locations: SmallVec<[ViewInstanceLocation; 4]>,
+    pub flags: ViewInstancingFlags,
+}
+
+impl ViewInstancingDesc {
+    /// construct from one location per view instance, failing with
+    /// `TooManyViewInstances` for more than 4
+    #[inline]
+    pub fn new(
+        locations: &[ViewInstanceLocation], flags: ViewInstancingFlags
+    ) -> Result<Self, SubobjectError> {
+        if locations.len() > 4 {
+            return Err(SubobjectError::TooManyViewInstances(
+                u32::try_from(locations.len()).unwrap_or(u32::MAX)
+            ));
+        }
+        Ok(ViewInstancingDesc{locations: locations.iter().cloned().collect(), flags})
+    }
+
+    /// the location of each view instance
+    #[inline]
+    pub fn locations(&self) -> &[ViewInstanceLocation] {
+        &self.locations
+    }
+}
+
+/// a pipeline state subobject
+#[derive(Clone, Debug)]
+pub enum Subobject<'a> {
+    RootSignature(&'a RootSig),
+    Vs(VsShaderBytecode),
+    Ps(PsShaderBytecode),
+    Ds(DsShaderBytecode),
+    Hs(HsShaderBytecode),
+    Gs(GsShaderBytecode),
+    Cs(CsShaderBytecode),
+    StreamOutput(so::DescBuilder<'a>),
+    Blend(blend::BlendDesc),
+    SampleMask(u32),
+    Rasterizer(rasterizer::RasterizerDesc),
+    DepthStencil(ds::DepthStencilDesc),
+    InputLayout(ia::InputLayoutBuilder<'a>),
+    IbStripCutValue(ia::StripCutValue),
+    PrimitiveTopology(ia::PrimitiveTopologyType),
+    RenderTargetFormats(RtFormatArray),
+    DepthStencilFormat(DxgiFormat),
+    SampleDesc(SampleDesc),
+    NodeMask(u32),
+    /// a cached blob, e.g. from `GraphicsPipelineStateCache::as_bytes`
+    CachedPso(&'a [u8]),
+    Flags(PipelineStateFlags),
+    DepthStencil1(DepthStencilDesc1),
+    ViewInstancing(ViewInstancingDesc),
+    As(AsShaderBytecode),
+    Ms(MsShaderBytecode),
+}
+
+impl<'a> Subobject<'a> {
+    /// get the type of this subobject
+    pub fn get_type(&self) -> SubobjectType {
+        match *self {
+            Subobject::RootSignature(_) => SubobjectType::ROOT_SIGNATURE,
+            Subobject::Vs(_) => SubobjectType::VS,
+            Subobject::Ps(_) => SubobjectType::PS,
+            Subobject::Ds(_) => SubobjectType::DS,
+            Subobject::Hs(_) => SubobjectType::HS,
+            Subobject::Gs(_) => SubobjectType::GS,
+            Subobject::Cs(_) => SubobjectType::CS,
+            Subobject::StreamOutput(_) => SubobjectType::STREAM_OUTPUT,
+            Subobject::Blend(_) => SubobjectType::BLEND,
+            Subobject::SampleMask(_) => SubobjectType::SAMPLE_MASK,
+            Subobject::Rasterizer(_) => SubobjectType::RASTERIZER,
+            Subobject::DepthStencil(_) => SubobjectType::DEPTH_STENCIL,
+            Subobject::InputLayout(_) => SubobjectType::INPUT_LAYOUT,
+            Subobject::IbStripCutValue(_) => SubobjectType::IB_STRIP_CUT_VALUE,
+            Subobject::PrimitiveTopology(_) => SubobjectType::PRIMITIVE_TOPOLOGY,
+            Subobject::RenderTargetFormats(_) => SubobjectType::RENDER_TARGET_FORMATS,
+            Subobject::DepthStencilFormat(_) => SubobjectType::DEPTH_STENCIL_FORMAT,
+            Subobject::SampleDesc(_) => SubobjectType::SAMPLE_DESC,
+            Subobject::NodeMask(_) => SubobjectType::NODE_MASK,
+            Subobject::CachedPso(_) => SubobjectType::CACHED_PSO,
+            Subobject::Flags(_) => SubobjectType::FLAGS,
+            Subobject::DepthStencil1(_) => SubobjectType::DEPTH_STENCIL1,
+            Subobject::ViewInstancing(_) => SubobjectType::VIEW_INSTANCING,
+            Subobject::As(_) => SubobjectType::AS,
+            Subobject::Ms(_) => SubobjectType::MS,
+        }
+    }
+
+    /// alignment of the subobject description
+    fn desc_alignment(&self) -> usize {
+        match *self {
+            Subobject::RootSignature(_) | Subobject::Vs(_) | Subobject::Ps(_) |
+            Subobject::Ds(_) | Subobject::Hs(_) | Subobject::Gs(_) |
+            Subobject::Cs(_) | Subobject::StreamOutput(_) |
+            Subobject::InputLayout(_) | Subobject::CachedPso(_) |
+            Subobject::ViewInstancing(_) | Subobject::As(_) |
+            Subobject::Ms(_) => PTR_ALIGNMENT,
+            _ => 4,
+        }
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.align(PTR_ALIGNMENT);
+        encoder.u32(self.get_type() as u32);
+        encoder.align(self.desc_alignment());
+        match *self {
+            Subobject::RootSignature(rootsig) => encoder.ptr(rootsig_ptr(rootsig)),
+            Subobject::Vs(ref shader) => encoder.bytes_ref(shader.as_bytes()),
+            Subobject::Ps(ref shader) => encoder.bytes_ref(shader.as_bytes()),
+            Subobject::Ds(ref shader) => encoder.bytes_ref(shader.as_bytes()),
+            Subobject::Hs(ref shader) => encoder.bytes_ref(shader.as_bytes()),
+            Subobject::Gs(ref shader) => encoder.bytes_ref(shader.as_bytes()),
+            Subobject::Cs(ref shader) => encoder.bytes_ref(shader.as_bytes()),
+            Subobject::As(ref shader) => encoder.bytes_ref(shader.as_bytes()),
+            Subobject::Ms(ref shader) => encoder.bytes_ref(shader.as_bytes()),
+            Subobject::StreamOutput(ref so) => {
+                encoder.ptr(so.entries.as_ptr());
+                encoder.u32(so.entries.len() as u32);
+                encoder.ptr(so.strides.as_ptr());
+                encoder.u32(so.strides.len() as u32);
+                encoder.u32(so.rasterized_stream);
+            },
+            Subobject::Blend(ref blend) => encoder.blend(blend),
+            Subobject::SampleMask(mask) => encoder.u32(mask),
+            Subobject::Rasterizer(ref rasterizer) => encoder.rasterizer(rasterizer),
+            Subobject::DepthStencil(ref ds) => encoder.depth_stencil(ds),
+            Subobject::InputLayout(ref layout) => {
+                encoder.ptr(layout.elements.as_ptr());
+                encoder.u32(layout.elements.len() as u32);
+            },
+            Subobject::IbStripCutValue(value) => encoder.u32(value as u32),
+            Subobject::PrimitiveTopology(topology) => encoder.u32(topology as u32),
+            Subobject::RenderTargetFormats(ref formats) => {
+                for format in &formats.formats {
+                    encoder.u32(format.0);
+                }
+                encoder.u32(formats.num_render_targets);
+            },
+            Subobject::DepthStencilFormat(format) => encoder.u32(format.0),
+            Subobject::SampleDesc(desc) => {
+                encoder.u32(desc.count);
+                encoder.u32(desc.quality);
+            },
+            Subobject::NodeMask(mask) => encoder.u32(mask),
+            Subobject::CachedPso(blob) => encoder.bytes_ref(blob),
+            Subobject::Flags(flags) => encoder.u32(flags.bits()),
+            Subobject::DepthStencil1(ref ds) => {
+                encoder.depth_stencil(&ds.desc);
+                encoder.u32(ds.depth_bounds_test.to_win_bool() as u32);
+            },
+            Subobject::ViewInstancing(ref desc) => {
+                encoder.u32(desc.locations.len() as u32);
+                encoder.ptr(desc.locations.as_ptr());
+                encoder.u32(desc.flags.bits());
+            },
+        }
+        encoder.align(PTR_ALIGNMENT);
+    }
+}
+
+/// a pipeline state stream builder
+#[derive(Clone, Debug, Default)]
+pub struct StreamBuilder<'a> {
+    pub subobjects: SmallVec<[Subobject<'a>; 16]>,
+}
+
+impl<'a> StreamBuilder<'a> {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// construct a stream equivalent to a graphics pipeline state builder
+    pub fn from_graphics(builder: &'a GraphicsPipelineStateBuilder<'a>) -> Self {
+        let mut ret = StreamBuilder::new();
+        ret.set(Subobject::RootSignature(builder.rootsig));
+        if let Some(ref vs) = builder.vs { ret.set(Subobject::Vs(vs.clone())); }
+        if let Some(ref ps) = builder.ps { ret.set(Subobject::Ps(ps.clone())); }
+        if let Some(ref ds) = builder.ds { ret.set(Subobject::Ds(ds.clone())); }
+        if let Some(ref hs) = builder.hs { ret.set(Subobject::Hs(hs.clone())); }
+        if let Some(ref gs) = builder.gs { ret.set(Subobject::Gs(gs.clone())); }
+        ret.set(Subobject::StreamOutput(builder.stream_output.clone()))
+            .set(Subobject::Blend(builder.blend_state))
+            .set(Subobject::SampleMask(builder.sample_mask))
+            .set(Subobject::Rasterizer(builder.rasterizer_state))
+            .set(Subobject::DepthStencil(builder.depth_stencil_state))
+            .set(Subobject::InputLayout(builder.input_layout.clone()))
+            .set(Subobject::IbStripCutValue(builder.strip_cut_value))
+            .set(Subobject::PrimitiveTopology(builder.primitive_topology_type))
+            .set(Subobject::RenderTargetFormats(RtFormatArray{
+                formats: builder.rtv_formats,
+                num_render_targets: builder.num_render_targets,
+            }))
+            .set(Subobject::DepthStencilFormat(builder.dsv_format))
+            .set(Subobject::SampleDesc(builder.sample_desc))
+            .set(Subobject::NodeMask(builder.node_mask))
+            .set(Subobject::Flags(builder.flags));
+        // caches only exist on windows
+        #[cfg(windows)]
+        {
+            if let Some(ref cache) = builder.cache {
+                ret.set(Subobject::CachedPso(cache.as_bytes()));
+            }
+        }
+        ret
+    }
+
+    /// set a subobject, replacing the existing one of the same type, since
+    /// the runtime rejects streams with duplicated subobject types
+    pub fn set(&mut self, subobject: Subobject<'a>) -> &mut Self {
+        let ty = subobject.get_type();
+        match self.subobjects.iter().position(|s| s.get_type() == ty) {
+            Some(idx) => self.subobjects[idx] = subobject,
+            None => self.subobjects.push(subobject),
+        }
+        self
+    }
+
+    /// get the subobject of the given type
+    #[inline]
+    pub fn get(&self, ty: SubobjectType) -> Option<&Subobject<'a>> {
+        self.subobjects.iter().find(|s| s.get_type() == ty)
+    }
+
+    /// remove the subobject of the given type
+    pub fn remove(&mut self, ty: SubobjectType) -> Option<Subobject<'a>> {
+        self.subobjects.iter().position(|s| s.get_type() == ty)
+            .map(|idx| self.subobjects.remove(idx))
+    }
+
+    /// encode the stream. the encoded stream points into `self`.
+    pub fn encode(&self) -> EncodedStream<'_> {
+        let mut encoder = Encoder{bytes: Vec::new()};
+        for subobject in &self.subobjects {
+            subobject.encode(&mut encoder);
+        }
+        let len = encoder.bytes.len();
+        let mut words = vec![0u64; len.div_ceil(8)];
+        unsafe {
+            ::std::ptr::copy_nonoverlapping(
+                encoder.bytes.as_ptr(), words.as_mut_ptr() as *mut u8, len
+            );
+        }
+        EncodedStream{words, len, _pd: PhantomData}
+    }
+
+    /// build a graphics pipeline state, including mesh shader pipelines
+    #[cfg(windows)]
+    pub fn build_graphics(&self, device: &mut Device) -> Result<GraphicsPipelineState, WinError> {
+        unsafe {
+            self.encode().create(device).map(|ptr| GraphicsPipelineState{ptr})
+        }
+    }
+
+    /// build a compute pipeline state
+    #[cfg(windows)]
+    pub fn build_compute(&self, device: &mut Device) -> Result<ComputePipelineState, WinError> {
+        unsafe {
+            self.encode().create(device).map(|ptr| ComputePipelineState{ptr})
+        }
+    }
+}
+
+#[cfg(windows)]
+fn rootsig_ptr(rootsig: &RootSig) -> *const c_void {
+    rootsig.ptr.as_ptr() as *const c_void
+}
+
+// there are no live root signatures off windows, the stream is only ever
+// inspected there, never handed to a device
+#[cfg(not(windows))]
+fn rootsig_ptr(_: &RootSig) -> *const c_void {
+    ::std::ptr::null()
+}
+
+/// an encoded pipeline state stream, borrowing from its `StreamBuilder`
+#[derive(Clone, Debug)]
+pub struct EncodedStream<'b> {
+    // stored as words to keep the stream pointer aligned
+    words: Vec<u64>,
+    len: usize,
+    _pd: PhantomData<&'b ()>,
+}
+
+impl<'b> EncodedStream<'b> {
+    /// get the encoded bytes
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            ::std::slice::from_raw_parts(self.words.as_ptr() as *const u8, self.len)
+        }
+    }
+
+    #[cfg(windows)]
+    unsafe fn create(&self, device: &mut Device) -> Result<ComPtr<ID3D12PipelineState>, WinError> {
+        let mut device2: *mut ID3D12Device2 = ::std::mem::uninitialized();
+        let hr = device.ptr.QueryInterface(
+            &IID_ID3D12DEVICE2,
+            &mut device2 as *mut *mut _ as *mut *mut _
+        );
+        WinError::from_hresult(hr)?;
+        let device2 = ComPtr::new(device2);
+
+        let desc = PipelineStateStreamDesc{
+            size: self.len,
+            stream: self.words.as_ptr() as *mut c_void,
+        };
+        let mut ret = ::std::mem::uninitialized();
+        let hr = ((*(*device2.as_mut_ptr()).vtbl).CreatePipelineState)(
+            device2.as_mut_ptr(), &desc, & ::dxguid::IID_ID3D12PipelineState,
+            &mut ret as *mut *mut _ as *mut *mut _
+        );
+        WinError::from_hresult_or_ok(hr, || ComPtr::new(ret))
+    }
+}
+
+/// writes subobject fields at their natural alignments
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    #[inline]
+    fn align(&mut self, alignment: usize) {
+        while !self.bytes.len().is_multiple_of(alignment) {
+            self.bytes.push(0);
+        }
+    }
+
+    #[inline]
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    #[inline]
+    fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.bytes.extend_from_slice(&value.to_ne_bytes());
+    }
+
+    #[inline]
+    fn usize(&mut self, value: usize) {
+        self.align(PTR_ALIGNMENT);
+        self.bytes.extend_from_slice(&value.to_ne_bytes());
+    }
+
+    #[inline]
+    fn ptr<T>(&mut self, ptr: *const T) {
+        self.usize(ptr as usize);
+    }
+
+    /// a pointer and size pair, e.g. `D3D12_SHADER_BYTECODE`
+    #[inline]
+    fn bytes_ref(&mut self, bytes: &[u8]) {
+        self.ptr(bytes.as_ptr());
+        self.usize(bytes.len());
+    }
+
+    fn blend(&mut self, blend: &blend::BlendDesc) {
+        self.u32(blend.alpha_to_coverage.to_win_bool() as u32);
+        self.u32(blend.independent_blend.to_win_bool() as u32);
+        for rt in &blend.render_targets {
+            self.u32(rt.blend_enabled.to_win_bool() as u32);
+            self.u32(rt.logic_op_enabled.to_win_bool() as u32);
+            self.u32(rt.src_blend as u32);
+            self.u32(rt.dst_blend as u32);
+            self.u32(rt.blend_op as u32);
+            self.u32(rt.src_alpha as u32);
+            self.u32(rt.dst_alpha as u32);
+            self.u32(rt.blend_op_alpha as u32);
+            self.u32(rt.logic_op as u32);
+            self.u8(rt.write_mask.bits());
+        }
+        self.align(4);
+    }
+
+    fn rasterizer(&mut self, rasterizer: &rasterizer::RasterizerDesc) {
+        self.u32(rasterizer.fill as u32);
+        self.u32(rasterizer.cull as u32);
+        self.u32(rasterizer.front_ccw.to_win_bool() as u32);
+        self.u32(rasterizer.depth_bias as u32);
+        self.u32(rasterizer.depth_bias_clamp.to_bits());
+        self.u32(rasterizer.slope_scaled_depth_bias.to_bits());
+        self.u32(rasterizer.depth_clip.to_win_bool() as u32);
+        self.u32(rasterizer.multisample.to_win_bool() as u32);
+        self.u32(rasterizer.antialiased_line.to_win_bool() as u32);
+        self.u32(rasterizer.forced_sample_count);
+        self.u32(rasterizer.conservative_raster as u32);
+    }
+
+    fn depth_stencil(&mut self, ds: &ds::DepthStencilDesc) {
+        self.u32(ds.depth.to_win_bool() as u32);
+        self.u32(ds.depth_write_mask as u32);
+        self.u32(ds.depth_func as u32);
+        self.u32(ds.stencil.to_win_bool() as u32);
+        self.u8(ds.stencil_read_mask);
+        self.u8(ds.stencil_write_mask);
+        for op in &[ds.front_op, ds.back_op] {
+            self.u32(op.fail as u32);
+            self.u32(op.depth_fail as u32);
+            self.u32(op.pass as u32);
+            self.u32(op.func as u32);
+        }
+    }
+}
+
+// `ID3D12Device2` isn't available in winapi 0.2, so its vtable is declared
+// here, up to `CreatePipelineState`.
+#[cfg(windows)]
+#[repr(C)]
+#[allow(non_snake_case)]
+struct ID3D12Device2Vtbl {
+    parent: ::winapi::ID3D12DeviceVtbl,
+    CreatePipelineLibrary: *const c_void,
+    SetEventOnMultipleFenceCompletion: *const c_void,
+    SetResidencyPriority: *const c_void,
+    CreatePipelineState: unsafe extern "system" fn(
+        This: *mut ID3D12Device2, pDesc: *const PipelineStateStreamDesc,
+        riid: ::winapi::REFIID, ppPipelineState: *mut *mut c_void
+    ) -> ::winapi::HRESULT,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+struct ID3D12Device2 {
+    vtbl: *const ID3D12Device2Vtbl,
+}
+
+#[cfg(windows)]
+const IID_ID3D12DEVICE2: ::winapi::GUID = ::winapi::GUID{
+    Data1: 0x30ba_a41e, Data2: 0xb15b, Data3: 0x475c,
+    Data4: [0xa0, 0xbb, 0x1a, 0xf5, 0xc5, 0xb6, 0x43, 0x28],
+};
+
+/// `D3D12_PIPELINE_STATE_STREAM_DESC`
+#[cfg(windows)]
+#[repr(C)]
+struct PipelineStateStreamDesc {
+    size: usize,
+    stream: *mut c_void,
+}
+
+// the expected streams spell out their padding for 8 byte pointers
+#[cfg(all(test, target_pointer_width = "64", target_endian = "little"))]
+mod tests {
+    use super::*;
+    use format::*;
+    use shader::{VsShaderBytecode, PsShaderBytecode, CsShaderBytecode};
+
+    const VS: &[u8] = include_bytes!("../../tests/fixtures/passthrough_vs.cso");
+    const PS: &[u8] = include_bytes!("../../tests/fixtures/textured_ps.cso");
+    const CS: &[u8] = include_bytes!("../../tests/fixtures/ranges_cs.cso");
+
+    /// expected stream bytes
+    #[derive(Default)]
+    struct Golden(Vec<u8>);
+
+    impl Golden {
+        fn u32s(mut self, values: &[u32]) -> Self {
+            for value in values {
+                self.0.extend_from_slice(&value.to_le_bytes());
+            }
+            self
+        }
+
+        fn u8s(mut self, values: &[u8]) -> Self {
+            self.0.extend_from_slice(values);
+            self
+        }
+
+        fn ptr<T>(mut self, ptr: *const T) -> Self {
+            self.0.extend_from_slice(&(ptr as u64).to_le_bytes());
+            self
+        }
+    }
+
+    fn check(stream: &StreamBuilder, expected: Golden) {
+        assert_eq!(stream.encode().as_bytes(), &expected.0[..]);
+    }
+
+    fn single(subobject: Subobject) -> StreamBuilder {
+        let mut stream = StreamBuilder::new();
+        stream.set(subobject);
+        stream
+    }
+
+    #[test]
+    fn rt_formats() {
+        let formats = RtFormatArray::new(&[DXGI_FORMAT_R8G8B8A8_UNORM, DXGI_FORMAT_R16G16B16A16_FLOAT]).unwrap();
+        assert_eq!(formats.num_render_targets, 2);
+        check(&single(Subobject::RenderTargetFormats(formats)),
+            Golden::default().u32s(&[15, 28, 10, 0, 0, 0, 0, 0, 0, 2]));
+
+        assert!(RtFormatArray::new(&[DXGI_FORMAT_R8G8B8A8_UNORM; 8]).is_ok());
+        assert_eq!(RtFormatArray::new(&[DXGI_FORMAT_R8G8B8A8_UNORM; 9]),
+            Err(SubobjectError::TooManyRenderTargets(9)));
+    }
+
+    #[test]
+    fn view_instances() {
+        let locations = [ViewInstanceLocation::default(); 5];
+        assert_eq!(ViewInstancingDesc::new(&locations[..4], ViewInstancingFlags::NONE).unwrap().locations().len(), 4);
+        assert_eq!(ViewInstancingDesc::new(&locations, ViewInstancingFlags::NONE),
+            Err(SubobjectError::TooManyViewInstances(5)));
+    }
+
+    #[test]
+    fn u32_subobjects() {
+        check(&single(Subobject::SampleMask(0xffff_ffff)), Golden::default().u32s(&[9, 0xffff_ffff]));
+        check(&single(Subobject::IbStripCutValue(ia::StripCutValue::FFFFFFFF)), Golden::default().u32s(&[13, 2]));
+        check(&single(Subobject::PrimitiveTopology(ia::PrimitiveTopologyType::TRIANGLE)), Golden::default().u32s(&[14, 3]));
+        check(&single(Subobject::DepthStencilFormat(DXGI_FORMAT_D32_FLOAT)), Golden::default().u32s(&[16, 40]));
+        check(&single(Subobject::NodeMask(2)), Golden::default().u32s(&[18, 2]));
+        check(&single(Subobject::Flags(PipelineStateFlags::TOOL_DEBUG)), Golden::default().u32s(&[20, 1]));
+        // padded to the next pointer
+        check(&single(Subobject::SampleDesc(SampleDesc{count: 4, quality: 1})), Golden::default().u32s(&[17, 4, 1, 0]));
+    }
+
+    #[test]
+    fn blend() {
+        let mut desc = blend::BlendDesc{alpha_to_coverage: true.into(), ..Default::default()};
+        desc.render_targets[1].set_blend(blend::Blend::SRC_ALPHA, blend::Blend::INV_SRC_ALPHA, blend::BlendOp::ADD);
+        let mut expected = Golden::default().u32s(&[8, 1, 0]);
+        for index in 0..8 {
+            let (enabled, src, dst) = if index == 1 { (1, 5, 6) } else { (0, 2, 1) };
+            expected = expected
+                .u32s(&[enabled, 0, src, dst, 1, 2, 1, 1, 4])
+                .u8s(&[0x0f, 0, 0, 0]);
+        }
+        check(&single(Subobject::Blend(desc)), expected.u32s(&[0]));
+    }
+
+    #[test]
+    fn rasterizer() {
+        let desc = rasterizer::RasterizerDesc{
+            depth_bias: -1, depth_bias_clamp: 1.0, forced_sample_count: 4,
+            ..Default::default()
+        };
+        check(&single(Subobject::Rasterizer(desc)),
+            Golden::default().u32s(&[10, 3, 3, 1, 0xffff_ffff, 0x3f80_0000, 0, 0, 0, 0, 4, 0]));
+    }
+
+    #[test]
+    fn depth_stencil() {
+        let mut desc = ds::DepthStencilDesc{stencil_write_mask: 0x0f, ..Default::default()};
+        desc.back_op.func = ComparisonFunc::NEVER;
+        let fields = |ty| Golden::default()
+            .u32s(&[ty, 1, 1, 2, 0])
+            .u8s(&[0xff, 0x0f, 0, 0])
+            .u32s(&[1, 1, 1, 8, 1, 1, 1, 1]);
+        check(&single(Subobject::DepthStencil(desc)), fields(11));
+
+        let desc1 = DepthStencilDesc1{desc, depth_bounds_test: true.into()};
+        check(&single(Subobject::DepthStencil1(desc1)), fields(21).u32s(&[1, 0]));
+    }
+
+    #[test]
+    fn shaders() {
+        let vs = VsShaderBytecode::from_static(VS).unwrap();
+        check(&single(Subobject::Vs(vs)), Golden::default().u32s(&[1, 0]).ptr(VS.as_ptr()).u32s(&[VS.len() as u32, 0]));
+        let ps = PsShaderBytecode::from_static(PS).unwrap();
+        check(&single(Subobject::Ps(ps)), Golden::default().u32s(&[2, 0]).ptr(PS.as_ptr()).u32s(&[PS.len() as u32, 0]));
+        let cs = CsShaderBytecode::from_static(CS).unwrap();
+        check(&single(Subobject::Cs(cs)), Golden::default().u32s(&[6, 0]).ptr(CS.as_ptr()).u32s(&[CS.len() as u32, 0]));
+
+        let blob = [1u8, 2, 3];
+        check(&single(Subobject::CachedPso(&blob)), Golden::default().u32s(&[19, 0]).ptr(blob.as_ptr()).u32s(&[3, 0]));
+    }
+
+    #[test]
+    fn root_signature() {
+        let rootsig = RootSig::from_desc(&[], 0);
+        check(&single(Subobject::RootSignature(&rootsig)), Golden::default().u32s(&[0, 0]).ptr(rootsig_ptr(&rootsig)));
+    }
+
+    #[test]
+    fn arrays() {
+        let mut so = so::DescBuilder::new(1);
+        so.strides.push(16);
+        so.strides.push(32);
+        let stream = single(Subobject::StreamOutput(so));
+        let (entries, strides) = match stream.subobjects[0] {
+            Subobject::StreamOutput(ref so) => (so.entries.as_ptr(), so.strides.as_ptr()),
+            _ => unreachable!(),
+        };
+        check(&stream, Golden::default()
+            .u32s(&[7, 0]).ptr(entries).u32s(&[0, 0]).ptr(strides).u32s(&[2, 1]));
+
+        let stream = single(Subobject::InputLayout(ia::InputLayoutBuilder::new()));
+        let elements = match stream.subobjects[0] {
+            Subobject::InputLayout(ref layout) => layout.elements.as_ptr(),
+            _ => unreachable!(),
+        };
+        check(&stream, Golden::default().u32s(&[12, 0]).ptr(elements).u32s(&[0, 0]));
+
+        let locations = [
+            ViewInstanceLocation{viewport_array_index: 0, render_target_array_index: 0},
+            ViewInstanceLocation{viewport_array_index: 1, render_target_array_index: 1},
+        ];
+        let desc = ViewInstancingDesc::new(&locations, ViewInstancingFlags::ENABLE_VIEW_INSTANCE_MASKING).unwrap();
+        let stream = single(Subobject::ViewInstancing(desc));
+        let locations = match stream.subobjects[0] {
+            Subobject::ViewInstancing(ref desc) => desc.locations().as_ptr(),
+            _ => unreachable!(),
+        };
+        check(&stream, Golden::default().u32s(&[22, 0, 2, 0]).ptr(locations).u32s(&[1, 0]));
+    }
+
+    #[test]
+    fn subobjects_start_at_pointers() {
+        let mut stream = StreamBuilder::new();
+        stream.set(Subobject::SampleMask(1))
+            .set(Subobject::SampleDesc(SampleDesc{count: 1, quality: 0}))
+            .set(Subobject::NodeMask(1))
+            .set(Subobject::SampleMask(3));
+        assert_eq!(stream.subobjects.len(), 3);
+        check(&stream, Golden::default().u32s(&[9, 3, 17, 1, 0, 0, 18, 1]));
+
+        assert!(stream.remove(SubobjectType::SAMPLE_DESC).is_some());
+        assert!(stream.get(SubobjectType::SAMPLE_DESC).is_none());
+        check(&stream, Golden::default().u32s(&[9, 3, 18, 1]));
+    }
+}
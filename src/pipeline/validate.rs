@@ -0,0 +1,611 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! validation of graphics pipeline descriptions.
+//!
+//! the runtime reports most invalid descriptions with a bare `E_INVALIDARG`,
+//! so common mistakes are checked here before calling into the driver.
+
+use super::*;
+use format::{format_name, is_depth_format, is_uint_format};
+use dxbc::{DxbcContainer, DxbcError, Signature, SignatureElement, ShaderStage};
+use dxbc::{SystemValue, RegisterComponentType};
+use std::fmt;
+
+/// a problem found in a graphics pipeline description
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PipelineDiagnostic {
+    /// no vertex shader is set
+    MissingVertexShader,
+    /// more than 8 render targets
+    TooManyRenderTargets(u32),
+    /// a render target in use has an `UNKNOWN` format
+    MissingRtvFormat{index: u32},
+    /// a render target beyond `num_render_targets` has a format
+    UnusedRtvFormat{index: u32, format: DxgiFormat},
+    /// the depth stencil format isn't a depth format
+    NotADepthFormat(DxgiFormat),
+    /// `PATCH` topology without both a hull and a domain shader
+    PatchWithoutTessellation,
+    /// a hull or domain shader without `PATCH` topology
+    TessellationWithoutPatch(ia::PrimitiveTopologyType),
+    /// a strip cut value with a topology type that can't be a strip
+    StripCutWithoutStrip(ia::PrimitiveTopologyType),
+    /// a render target enables both blending and logic operations
+    BlendWithLogicOp{index: u32},
+    /// a render target enables logic operations, but isn't of a `UINT` format
+    LogicOpOnNonUintTarget{index: u32, format: DxgiFormat},
+    /// `sample_desc.count` is zero
+    ZeroSampleCount,
+    /// a forced sample count is set while rendering to multisampled targets
+    ForcedSampleCountWithMsaa{forced_sample_count: u32, sample_count: u32},
+    /// a forced sample count is set while depth or stencil testing is enabled
+    ForcedSampleCountWithDepthStencil{forced_sample_count: u32},
+    /// the signatures of a shader can't be read
+    UnreadableSignature{stage: ShaderStage, error: DxbcError},
+    /// a shader reads an element the previous stage doesn't output
+    MissingStageOutput{producer: ShaderStage, consumer: ShaderStage, semantic: Semantic},
+    /// a linked element is in different registers in the two stages
+    RegisterMismatch{
+        producer: ShaderStage, consumer: ShaderStage, semantic: Semantic,
+        output_register: u32, input_register: u32,
+    },
+    /// a shader declares components of an element the previous stage doesn't output
+    ComponentMismatch{
+        producer: ShaderStage, consumer: ShaderStage, semantic: Semantic,
+        output_mask: u8, input_mask: u8,
+    },
+    /// a linked element has different component types in the two stages
+    ComponentTypeMismatch{
+        producer: ShaderStage, consumer: ShaderStage, semantic: Semantic,
+        output_type: RegisterComponentType, input_type: RegisterComponentType,
+    },
+    /// a linked element has different system values in the two stages
+    SystemValueMismatch{
+        producer: ShaderStage, consumer: ShaderStage, semantic: Semantic,
+        output_value: SystemValue, input_value: SystemValue,
+    },
+    /// the last stage before the rasterizer doesn't output `SV_Position`
+    MissingPosition{stage: ShaderStage},
+    /// a stream output entry names an element the stage doesn't output to the entry's stream
+    MissingStreamOutput{entry: usize, stage: ShaderStage, semantic: Semantic},
+    /// a stream output entry writes components the element doesn't have
+    StreamOutputComponentMismatch{entry: usize, semantic: Semantic, output_mask: u8, entry_mask: u8},
+}
+
+/// semantic name and index of a signature element
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Semantic {
+    pub name: String,
+    pub index: u32,
+}
+
+impl Semantic {
+    #[inline]
+    fn of(element: &SignatureElement) -> Semantic {
+        Semantic{name: element.semantic_name.clone(), index: element.semantic_index}
+    }
+}
+
+impl fmt::Display for Semantic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if self.index == 0 {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{}{}", self.name, self.index)
+        }
+    }
+}
+
+impl fmt::Display for PipelineDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            PipelineDiagnostic::MissingVertexShader =>
+                write!(f, "no vertex shader is set"),
+            PipelineDiagnostic::TooManyRenderTargets(num) =>
+                write!(f, "{} render targets, at most 8 are supported", num),
+            PipelineDiagnostic::MissingRtvFormat{index} =>
+                write!(f, "render target {} is in use, but its format is UNKNOWN", index),
+            PipelineDiagnostic::UnusedRtvFormat{index, format} =>
+                write!(f, "render target {} is not in use, but its format is {}", index, FormatName(format)),
+            PipelineDiagnostic::NotADepthFormat(format) =>
+                write!(f, "depth stencil format {} is not a depth format", FormatName(format)),
+            PipelineDiagnostic::PatchWithoutTessellation =>
+                write!(f, "PATCH topology requires both a hull and a domain shader"),
+            PipelineDiagnostic::TessellationWithoutPatch(topology) =>
+                write!(f, "hull and domain shaders require PATCH topology, found {:?}", topology),
+            PipelineDiagnostic::StripCutWithoutStrip(topology) =>
+                write!(f, "strip cut value is set, but {:?} topology can't be a strip", topology),
+            PipelineDiagnostic::BlendWithLogicOp{index} =>
+                write!(f, "render target {} enables both blending and logic operations", index),
+            PipelineDiagnostic::LogicOpOnNonUintTarget{index, format} =>
+                write!(f, "render target {} enables logic operations, but its format {} is not UINT", index, FormatName(format)),
+            PipelineDiagnostic::ZeroSampleCount =>
+                write!(f, "sample count is zero"),
+            PipelineDiagnostic::ForcedSampleCountWithMsaa{forced_sample_count, sample_count} =>
+                write!(f, "forced sample count {} requires a sample count of 1, found {}", forced_sample_count, sample_count),
+            PipelineDiagnostic::ForcedSampleCountWithDepthStencil{forced_sample_count} =>
+                write!(f, "forced sample count {} requires depth and stencil testing to be disabled", forced_sample_count),
+            PipelineDiagnostic::UnreadableSignature{stage, ref error} =>
+                write!(f, "the signatures of the {} can't be read: {}", StageName(stage), error),
+            PipelineDiagnostic::MissingStageOutput{producer, consumer, ref semantic} =>
+                write!(f, "the {} reads {}, which the {} doesn't output",
+                    StageName(consumer), semantic, StageName(producer)),
+            PipelineDiagnostic::RegisterMismatch{producer, consumer, ref semantic, output_register, input_register} =>
+                write!(f, "the {} outputs {} in register {}, but the {} reads it from register {}",
+                    StageName(producer), semantic, output_register, StageName(consumer), input_register),
+            PipelineDiagnostic::ComponentMismatch{producer, consumer, ref semantic, output_mask, input_mask} =>
+                write!(f, "the {} reads {}.{}, but the {} outputs {}.{}",
+                    StageName(consumer), semantic, Mask(input_mask), StageName(producer), semantic, Mask(output_mask)),
+            PipelineDiagnostic::ComponentTypeMismatch{producer, consumer, ref semantic, output_type, input_type} =>
+                write!(f, "the {} outputs {} as {}, but the {} reads it as {}",
+                    StageName(producer), semantic, output_type.name(), StageName(consumer), input_type.name()),
+            PipelineDiagnostic::SystemValueMismatch{producer, consumer, ref semantic, output_value, input_value} =>
+                write!(f, "the {} outputs {} as system value {}, but the {} reads it as {}",
+                    StageName(producer), semantic, output_value.name(), StageName(consumer), input_value.name()),
+            PipelineDiagnostic::MissingPosition{stage} =>
+                write!(f, "the {}, the last stage before the rasterizer, doesn't output SV_Position", StageName(stage)),
+            PipelineDiagnostic::MissingStreamOutput{entry, stage, ref semantic} =>
+                write!(f, "stream output entry {} writes {}, which the {} doesn't output to its stream",
+                    entry, semantic, StageName(stage)),
+            PipelineDiagnostic::StreamOutputComponentMismatch{entry, ref semantic, output_mask, entry_mask} =>
+                write!(f, "stream output entry {} writes {}.{}, but only {}.{} is output",
+                    entry, semantic, Mask(entry_mask), semantic, Mask(output_mask)),
+        }
+    }
+}
+
+struct FormatName(DxgiFormat);
+
+impl fmt::Display for FormatName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match format_name(self.0) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", (self.0).0),
+        }
+    }
+}
+
+struct StageName(ShaderStage);
+
+impl fmt::Display for StageName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let name = match self.0 {
+            ShaderStage::VERTEX => "vertex shader",
+            ShaderStage::HULL => "hull shader",
+            ShaderStage::DOMAIN => "domain shader",
+            ShaderStage::GEOMETRY => "geometry shader",
+            ShaderStage::PIXEL => "pixel shader",
+            ShaderStage::COMPUTE => "compute shader",
+            ShaderStage::LIBRARY => "library",
+            ShaderStage::MESH => "mesh shader",
+            ShaderStage::AMPLIFICATION => "amplification shader",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// components of a register mask, e.g. `xyz`
+struct Mask(u8);
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for (i, component) in "xyzw".chars().enumerate() {
+            if self.0 & (1 << i) != 0 { write!(f, "{}", component)?; }
+        }
+        Ok(())
+    }
+}
+
+/// signatures of a shader in the pipeline
+struct StageSignatures {
+    stage: ShaderStage,
+    input: Signature,
+    output: Signature,
+    /// output of hull shaders, input of domain shaders
+    patch_constant: Signature,
+}
+
+impl StageSignatures {
+    fn read(stage: ShaderStage, bytecode: &[u8]) -> Result<StageSignatures, DxbcError> {
+        let container = DxbcContainer::from_bytes(bytecode)?;
+        Ok(StageSignatures{
+            stage,
+            input: or_empty(container.input_signature())?,
+            output: or_empty(container.output_signature())?,
+            patch_constant: or_empty(container.patch_constant_signature())?,
+        })
+    }
+}
+
+/// a missing signature part declares no elements
+#[inline]
+fn or_empty(signature: Result<Signature, DxbcError>) -> Result<Signature, DxbcError> {
+    match signature {
+        Err(DxbcError::MissingPart(_)) => Ok(Signature::default()),
+        signature => signature,
+    }
+}
+
+/// whether an input is generated by the pipeline rather than output by
+/// the previous stage
+#[inline]
+fn is_system_generated(input: &SignatureElement) -> bool {
+    matches!(input.system_value,
+        SystemValue::VERTEX_ID | SystemValue::INSTANCE_ID | SystemValue::PRIMITIVE_ID |
+        SystemValue::IS_FRONT_FACE | SystemValue::SAMPLE_INDEX | SystemValue::COVERAGE |
+        SystemValue::INNER_COVERAGE | SystemValue::BARYCENTRICS | SystemValue::SHADINGRATE)
+}
+
+/// check that every element `inputs` reads is output by `outputs`, in
+/// the same register, with the same type and system value
+fn link(
+    producer: ShaderStage, outputs: &[&SignatureElement],
+    consumer: ShaderStage, inputs: &Signature,
+    diagnostics: &mut Vec<PipelineDiagnostic>
+) {
+    for input in inputs.elements.iter().filter(|input| !is_system_generated(input)) {
+        let semantic = Semantic::of(input);
+        let output = outputs.iter().find(|output| {
+            output.semantic_index == input.semantic_index &&
+            output.semantic_name.eq_ignore_ascii_case(&input.semantic_name)
+        });
+        let output = match output {
+            Some(output) => output,
+            None => {
+                diagnostics.push(PipelineDiagnostic::MissingStageOutput{producer, consumer, semantic});
+                continue;
+            },
+        };
+        if output.register != input.register {
+            diagnostics.push(PipelineDiagnostic::RegisterMismatch{
+                producer, consumer, semantic,
+                output_register: output.register, input_register: input.register,
+            });
+        } else if input.mask & !output.mask != 0 {
+            diagnostics.push(PipelineDiagnostic::ComponentMismatch{
+                producer, consumer, semantic,
+                output_mask: output.mask, input_mask: input.mask,
+            });
+        } else if output.component_type != input.component_type {
+            diagnostics.push(PipelineDiagnostic::ComponentTypeMismatch{
+                producer, consumer, semantic,
+                output_type: output.component_type, input_type: input.component_type,
+            });
+        } else if output.system_value != input.system_value {
+            diagnostics.push(PipelineDiagnostic::SystemValueMismatch{
+                producer, consumer, semantic,
+                output_value: output.system_value, input_value: input.system_value,
+            });
+        }
+    }
+}
+
+/// `RasterizedStream` disabling rasterization
+const NO_RASTERIZED_STREAM: u32 = 0xffff_ffff;
+
+impl<'a> GraphicsPipelineStateBuilder<'a> {
+    /// check the description for common mistakes, returning all problems found.
+    ///
+    /// this includes the linkage between the signatures of consecutive
+    /// shader stages, and of the stream output entries.
+    pub fn validate(&self) -> Result<(), Vec<PipelineDiagnostic>> {
+        let mut diagnostics = Vec::new();
+
+        if self.vs.is_none() {
+            diagnostics.push(PipelineDiagnostic::MissingVertexShader);
+        }
+
+        // render target formats
+        if self.num_render_targets > 8 {
+            diagnostics.push(PipelineDiagnostic::TooManyRenderTargets(self.num_render_targets));
+        }
+        for (index, &format) in self.rtv_formats.iter().enumerate() {
+            let index = index as u32;
+            let unknown = format == ::format::DXGI_FORMAT_UNKNOWN;
+            if index < self.num_render_targets && unknown {
+                diagnostics.push(PipelineDiagnostic::MissingRtvFormat{index});
+            } else if index >= self.num_render_targets && !unknown {
+                diagnostics.push(PipelineDiagnostic::UnusedRtvFormat{index, format});
+            }
+        }
+        if self.dsv_format != ::format::DXGI_FORMAT_UNKNOWN && !is_depth_format(self.dsv_format) {
+            diagnostics.push(PipelineDiagnostic::NotADepthFormat(self.dsv_format));
+        }
+
+        // topology
+        let topology = self.primitive_topology_type;
+        let tessellation = self.hs.is_some() || self.ds.is_some();
+        if topology == ia::PrimitiveTopologyType::PATCH {
+            if self.hs.is_none() || self.ds.is_none() {
+                diagnostics.push(PipelineDiagnostic::PatchWithoutTessellation);
+            }
+        } else if tessellation {
+            diagnostics.push(PipelineDiagnostic::TessellationWithoutPatch(topology));
+        }
+        // only line and triangle topologies have strip variants
+        if self.strip_cut_value != ia::StripCutValue::DISABLED &&
+            topology != ia::PrimitiveTopologyType::LINE &&
+            topology != ia::PrimitiveTopologyType::TRIANGLE {
+            diagnostics.push(PipelineDiagnostic::StripCutWithoutStrip(topology));
+        }
+
+        // blending. without independent blending, the first render target
+        // blend description applies to all render targets.
+        let num_render_targets = ::std::cmp::min(self.num_render_targets, 8);
+        for index in 0..num_render_targets {
+            let blend = if self.blend_state.independent_blend.is_true() {
+                &self.blend_state.render_targets[index as usize]
+            } else {
+                &self.blend_state.render_targets[0]
+            };
+            if !blend.logic_op_enabled.is_true() { continue; }
+            if blend.blend_enabled.is_true() {
+                diagnostics.push(PipelineDiagnostic::BlendWithLogicOp{index});
+            }
+            let format = self.rtv_formats[index as usize];
+            if format != ::format::DXGI_FORMAT_UNKNOWN && !is_uint_format(format) {
+                diagnostics.push(PipelineDiagnostic::LogicOpOnNonUintTarget{index, format});
+            }
+        }
+
+        // multisampling
+        if self.sample_desc.count == 0 {
+            diagnostics.push(PipelineDiagnostic::ZeroSampleCount);
+        }
+        let forced_sample_count = self.rasterizer_state.forced_sample_count;
+        if forced_sample_count > 0 {
+            if self.sample_desc.count > 1 {
+                diagnostics.push(PipelineDiagnostic::ForcedSampleCountWithMsaa{
+                    forced_sample_count, sample_count: self.sample_desc.count,
+                });
+            }
+            let ds = &self.depth_stencil_state;
+            if self.dsv_format != ::format::DXGI_FORMAT_UNKNOWN &&
+                (ds.depth.is_true() || ds.stencil.is_true()) {
+                diagnostics.push(PipelineDiagnostic::ForcedSampleCountWithDepthStencil{
+                    forced_sample_count,
+                });
+            }
+        }
+
+        self.validate_linkage(&mut diagnostics);
+
+        if diagnostics.is_empty() { Ok(()) } else { Err(diagnostics) }
+    }
+
+    /// check the signatures of the shaders against each other and the
+    /// stream output description
+    fn validate_linkage(&self, diagnostics: &mut Vec<PipelineDiagnostic>) {
+        let shaders = [
+            (ShaderStage::VERTEX, self.vs.as_ref().map(|vs| vs.as_bytes())),
+            (ShaderStage::HULL, self.hs.as_ref().map(|hs| hs.as_bytes())),
+            (ShaderStage::DOMAIN, self.ds.as_ref().map(|ds| ds.as_bytes())),
+            (ShaderStage::GEOMETRY, self.gs.as_ref().map(|gs| gs.as_bytes())),
+            (ShaderStage::PIXEL, self.ps.as_ref().map(|ps| ps.as_bytes())),
+        ];
+        let mut stages = Vec::with_capacity(shaders.len());
+        for &(stage, bytecode) in shaders.iter() {
+            let bytecode = match bytecode { Some(bytecode) => bytecode, None => continue, };
+            match StageSignatures::read(stage, bytecode) {
+                Ok(signatures) => stages.push(signatures),
+                // without all signatures, linkage can't be checked reliably
+                Err(error) => {
+                    diagnostics.push(PipelineDiagnostic::UnreadableSignature{stage, error});
+                    return;
+                },
+            }
+        }
+
+        // the stages before the pixel shader
+        let pre_raster = match stages.iter().position(|stage| stage.stage == ShaderStage::PIXEL) {
+            Some(ps) => &stages[..ps],
+            None => &stages[..],
+        };
+        for pair in pre_raster.windows(2) {
+            let (producer, consumer) = (&pair[0], &pair[1]);
+            let outputs: Vec<_> = producer.output.elements.iter().collect();
+            link(producer.stage, &outputs, consumer.stage, &consumer.input, diagnostics);
+            if producer.stage == ShaderStage::HULL {
+                let outputs: Vec<_> = producer.patch_constant.elements.iter().collect();
+                link(producer.stage, &outputs, consumer.stage, &consumer.patch_constant, diagnostics);
+            }
+        }
+
+        let last = match pre_raster.last() { Some(last) => last, None => return, };
+        let rasterized_stream = if last.stage == ShaderStage::GEOMETRY {
+            self.stream_output.rasterized_stream
+        } else {
+            0
+        };
+        if let Some(ps) = stages.last().filter(|stage| stage.stage == ShaderStage::PIXEL) {
+            if rasterized_stream != NO_RASTERIZED_STREAM {
+                let outputs: Vec<_> = last.output.elements.iter()
+                    .filter(|output| output.stream == rasterized_stream)
+                    .collect();
+                if !outputs.iter().any(|output| output.system_value == SystemValue::POSITION) {
+                    diagnostics.push(PipelineDiagnostic::MissingPosition{stage: last.stage});
+                }
+                link(last.stage, &outputs, ps.stage, &ps.input, diagnostics);
+            }
+        }
+
+        for (entry, decl) in self.stream_output.entries.iter().enumerate() {
+            let name = match decl.get_semantic_name() {
+                Some(name) => name.to_string_lossy(),
+                None => continue,
+            };
+            let semantic = Semantic{name: name.into_owned(), index: decl.semantic_index};
+            let output = last.output.elements.iter().find(|output| {
+                output.stream == decl.stream &&
+                output.semantic_index == semantic.index &&
+                output.semantic_name.eq_ignore_ascii_case(&semantic.name)
+            });
+            let output = match output {
+                Some(output) => output,
+                None => {
+                    diagnostics.push(PipelineDiagnostic::MissingStreamOutput{entry, stage: last.stage, semantic});
+                    continue;
+                },
+            };
+            // components beyond `w` end up outside of the element mask
+            let count = ::std::cmp::min(decl.component_count as u32, 4);
+            let start = ::std::cmp::min(decl.start_component as u32, 4);
+            let entry_mask = (((1u32 << count) - 1) << start) as u8;
+            if entry_mask & !output.mask != 0 {
+                diagnostics.push(PipelineDiagnostic::StreamOutputComponentMismatch{
+                    entry, semantic, output_mask: output.mask, entry_mask,
+                });
+            }
+        }
+    }
+}
+
+impl GraphicsPipelineDesc {
+    /// check the description for common mistakes, returning all problems found
+    #[inline]
+    pub fn validate(&self) -> Result<(), Vec<PipelineDiagnostic>> {
+        self.to_builder().validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use format::*;
+    use pipeline::rootsig::{RootSig, RootSigBuilder, RootSigVersion};
+    use shader::VsShaderBytecode;
+
+    const VS: &[u8] = include_bytes!("../../tests/fixtures/passthrough_vs.cso");
+
+    /// a valid description rendering to one `R8G8B8A8_UNORM` target
+    fn desc() -> GraphicsPipelineDesc {
+        let rootsig = RootSig::from_desc(&RootSigBuilder::new().to_bytes(RootSigVersion::V1_1), 0);
+        let mut desc = GraphicsPipelineDesc::new(rootsig);
+        desc.vs = Some(VsShaderBytecode::from_bytes(VS).unwrap());
+        desc.rtv_formats[0] = DXGI_FORMAT_R8G8B8A8_UNORM;
+        desc
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(desc().validate(), Ok(()));
+    }
+
+    #[test]
+    fn missing_vertex_shader() {
+        let mut desc = desc();
+        desc.vs = None;
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::MissingVertexShader]));
+    }
+
+    #[test]
+    fn render_target_formats() {
+        let mut desc = desc();
+        desc.num_render_targets = 2;
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::MissingRtvFormat{index: 1}]));
+
+        let mut desc = self::desc();
+        desc.rtv_formats[2] = DXGI_FORMAT_R32_FLOAT;
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::UnusedRtvFormat{
+            index: 2, format: DXGI_FORMAT_R32_FLOAT,
+        }]));
+
+        let mut desc = self::desc();
+        desc.num_render_targets = 9;
+        desc.rtv_formats = [DXGI_FORMAT_R8G8B8A8_UNORM; 8];
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::TooManyRenderTargets(9)]));
+        desc.num_render_targets = 8;
+        assert_eq!(desc.validate(), Ok(()));
+    }
+
+    #[test]
+    fn depth_stencil_format() {
+        let mut desc = desc();
+        desc.dsv_format = DXGI_FORMAT_R32_FLOAT;
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::NotADepthFormat(DXGI_FORMAT_R32_FLOAT)]));
+        desc.dsv_format = DXGI_FORMAT_D32_FLOAT;
+        assert_eq!(desc.validate(), Ok(()));
+        desc.dsv_format = DXGI_FORMAT_UNKNOWN;
+        assert_eq!(desc.validate(), Ok(()));
+    }
+
+    #[test]
+    fn patch_without_tessellation() {
+        let mut desc = desc();
+        desc.primitive_topology_type = ia::PrimitiveTopologyType::PATCH;
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::PatchWithoutTessellation]));
+    }
+
+    #[test]
+    fn strip_cut_without_strip() {
+        let mut desc = desc();
+        desc.strip_cut_value = ia::StripCutValue::FFFFFFFF;
+        assert_eq!(desc.validate(), Ok(()));
+        desc.primitive_topology_type = ia::PrimitiveTopologyType::LINE;
+        assert_eq!(desc.validate(), Ok(()));
+        desc.primitive_topology_type = ia::PrimitiveTopologyType::POINT;
+        assert_eq!(desc.validate(), Err(vec![
+            PipelineDiagnostic::StripCutWithoutStrip(ia::PrimitiveTopologyType::POINT),
+        ]));
+    }
+
+    #[test]
+    fn logic_ops() {
+        let mut desc = desc();
+        desc.blend_state.render_targets[0].logic_op_enabled = true.into();
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::LogicOpOnNonUintTarget{
+            index: 0, format: DXGI_FORMAT_R8G8B8A8_UNORM,
+        }]));
+        desc.rtv_formats[0] = DXGI_FORMAT_R8G8B8A8_UINT;
+        assert_eq!(desc.validate(), Ok(()));
+
+        // without independent blending, the first description applies to every target
+        desc.num_render_targets = 2;
+        desc.rtv_formats[1] = DXGI_FORMAT_R16G16B16A16_FLOAT;
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::LogicOpOnNonUintTarget{
+            index: 1, format: DXGI_FORMAT_R16G16B16A16_FLOAT,
+        }]));
+        desc.blend_state.independent_blend = true.into();
+        assert_eq!(desc.validate(), Ok(()));
+
+        desc.blend_state.render_targets[0].blend_enabled = true.into();
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::BlendWithLogicOp{index: 0}]));
+    }
+
+    #[test]
+    fn sample_counts() {
+        let mut desc = desc();
+        desc.sample_desc.count = 0;
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::ZeroSampleCount]));
+
+        let mut desc = self::desc();
+        desc.rasterizer_state.forced_sample_count = 4;
+        assert_eq!(desc.validate(), Err(vec![
+            PipelineDiagnostic::ForcedSampleCountWithDepthStencil{forced_sample_count: 4},
+        ]));
+        desc.depth_stencil_state.depth = false.into();
+        assert_eq!(desc.validate(), Ok(()));
+        desc.sample_desc.count = 4;
+        assert_eq!(desc.validate(), Err(vec![PipelineDiagnostic::ForcedSampleCountWithMsaa{
+            forced_sample_count: 4, sample_count: 4,
+        }]));
+    }
+
+    #[test]
+    fn messages() {
+        assert_eq!(
+            PipelineDiagnostic::LogicOpOnNonUintTarget{index: 1, format: DXGI_FORMAT_R8G8B8A8_UNORM}.to_string(),
+            "render target 1 enables logic operations, but its format DXGI_FORMAT_R8G8B8A8_UNORM is not UINT"
+        );
+        assert_eq!(
+            PipelineDiagnostic::NotADepthFormat(DXGI_FORMAT(0xffff)).to_string(),
+            "depth stencil format 65535 is not a depth format"
+        );
+    }
+}
@@ -145,8 +145,8 @@ where H: super::heap::traits::AcceptBuffer + super::heap::traits::GpuOnly {
     pub fn gpu_only(
         device: &mut Device, mut heap: H, offset: u64, size: u64
     ) -> Result<Self, WinError> {
-        debug_assert_eq!(size % super::ResourceAlignment::SIXTY_FOUR_KB.bits(), 0);
-        debug_assert!(offset % super::ResourceAlignment::SIXTY_FOUR_KB.bits() == 0);
+        debug_assert_eq!(size % super::ResourceAlignment::SIXTY_FOUR_KB as u64, 0);
+        debug_assert!(offset % super::ResourceAlignment::SIXTY_FOUR_KB as u64 == 0);
         debug_assert!(offset + size <= heap.size());
         // TODO: check heap alignment?
         let raw = unsafe {
@@ -167,8 +167,8 @@ where H: super::heap::traits::AcceptBuffer + super::heap::traits::Upload {
     pub fn upload(
         device: &mut Device, mut heap: H, offset: u64, size: u64
     ) -> Result<Self, WinError> {
-        debug_assert_eq!(size % super::ResourceAlignment::SIXTY_FOUR_KB.bits(), 0);
-        debug_assert!(offset % super::description::ResourceAlignment::SIXTY_FOUR_KB.bits() == 0);
+        debug_assert_eq!(size % super::ResourceAlignment::SIXTY_FOUR_KB as u64, 0);
+        debug_assert!(offset % super::description::ResourceAlignment::SIXTY_FOUR_KB as u64 == 0);
         debug_assert!(offset + size <= heap.size());
         // TODO: check heap alignment?
         let raw = unsafe {
@@ -189,8 +189,8 @@ where H: super::heap::traits::AcceptBuffer + super::heap::traits::Readback {
     pub fn readback(
         device: &mut Device, mut heap: H, offset: u64, size: u64
     ) -> Result<Self, WinError> {
-        debug_assert_eq!(size % super::ResourceAlignment::SIXTY_FOUR_KB.bits(), 0);
-        debug_assert!(offset % super::description::ResourceAlignment::SIXTY_FOUR_KB.bits() == 0);
+        debug_assert_eq!(size % super::ResourceAlignment::SIXTY_FOUR_KB as u64, 0);
+        debug_assert!(offset % super::description::ResourceAlignment::SIXTY_FOUR_KB as u64 == 0);
         debug_assert!(offset + size <= heap.size());
         // TODO: check heap alignment?
         let raw = unsafe {
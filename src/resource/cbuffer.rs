@@ -0,0 +1,134 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! HLSL constant buffer packing.
+//!
+//! constant buffers are made of 16 byte registers. structs and arrays
+//! start at a register boundary, array elements each take a whole register,
+//! structs are padded to a whole number of registers, and other values are
+//! packed tightly, as long as they don't straddle a register boundary. [more info](https://msdn.microsoft.com/en-us/library/windows/desktop/bb509632(v=vs.85).aspx)
+//!
+//! `#[derive(ConstantBuffer)]`, available with the `derive` feature, checks
+//! at compile time that a `#[repr(C)]` struct follows these rules, while
+//! `#[constant_buffer]` inserts the padding they require.
+
+use format::Bool;
+
+#[cfg(feature = "derive")]
+pub use redirect_derive::{ConstantBuffer, constant_buffer};
+
+/// size of a constant buffer register in bytes
+pub const CB_REGISTER_SIZE: usize = 16;
+
+/// required alignment of constant buffer view sizes and locations
+pub const CBV_ALIGNMENT: usize = 256;
+
+/// a type with a known layout in HLSL constant buffers
+///
+/// # Safety
+///
+/// implementations must report the HLSL layout truthfully, as derived
+/// `ConstantBuffer` implementations rely on it.
+pub unsafe trait HlslPacked {
+    /// size of the value in a constant buffer. arrays exclude the padding
+    /// after their last element, which the next value can be packed into.
+    const HLSL_SIZE: usize;
+    /// whether the value starts at a register boundary
+    const REGISTER_ALIGNED: bool;
+    /// whether the rust layout of the value matches its HLSL layout
+    const LAYOUT_MATCHES: bool;
+}
+
+/// a struct that can be written into a buffer as HLSL constant buffer data
+///
+/// usually derived with `#[derive(ConstantBuffer)]`.
+///
+/// # Safety
+///
+/// `Self` must be laid out as HLSL lays out the constant buffer, which
+/// the derive checks field by field.
+pub unsafe trait ConstantBuffer: HlslPacked + Copy + Sized {
+    /// size of a constant buffer view holding `Self`, rounded up to 256 bytes
+    const CBV_SIZE: u32 = align_up(::std::mem::size_of::<Self>(), CBV_ALIGNMENT) as u32;
+}
+
+/// round `value` up to a multiple of `alignment`, which must be a power of 2
+#[inline]
+pub const fn align_up(value: usize, alignment: usize) -> usize {
+    (value + alignment - 1) & !(alignment - 1)
+}
+
+/// the HLSL offset of a value of `size` bytes placed right after `offset`
+#[inline]
+pub const fn hlsl_offset(offset: usize, size: usize, register_aligned: bool) -> usize {
+    let in_register = offset % CB_REGISTER_SIZE;
+    if register_aligned || (in_register != 0 && in_register + size > CB_REGISTER_SIZE) {
+        align_up(offset, CB_REGISTER_SIZE)
+    } else {
+        offset
+    }
+}
+
+macro_rules! impl_hlsl_packed_scalar {
+    ($($Scalar: ty),*) => {$(
+        unsafe impl HlslPacked for $Scalar {
+            const HLSL_SIZE: usize = 4;
+            const REGISTER_ALIGNED: bool = false;
+            const LAYOUT_MATCHES: bool = true;
+        }
+
+        unsafe impl HlslPacked for [$Scalar; 1] {
+            const HLSL_SIZE: usize = 4;
+            const REGISTER_ALIGNED: bool = false;
+            const LAYOUT_MATCHES: bool = true;
+        }
+
+        unsafe impl HlslPacked for [$Scalar; 2] {
+            const HLSL_SIZE: usize = 8;
+            const REGISTER_ALIGNED: bool = false;
+            const LAYOUT_MATCHES: bool = true;
+        }
+
+        unsafe impl HlslPacked for [$Scalar; 3] {
+            const HLSL_SIZE: usize = 12;
+            const REGISTER_ALIGNED: bool = false;
+            const LAYOUT_MATCHES: bool = true;
+        }
+
+        unsafe impl HlslPacked for [$Scalar; 4] {
+            const HLSL_SIZE: usize = 16;
+            const REGISTER_ALIGNED: bool = false;
+            const LAYOUT_MATCHES: bool = true;
+        }
+
+        /// arrays of vectors, including matrices. every element takes a
+        /// whole register, so only arrays of 4 component vectors match
+        /// the rust layout.
+        unsafe impl<const N: usize, const C: usize> HlslPacked for [[$Scalar; C]; N]
+        where [$Scalar; C]: HlslPacked {
+            const HLSL_SIZE: usize = if N == 0 { 0 } else {
+                CB_REGISTER_SIZE * (N - 1) + <[$Scalar; C] as HlslPacked>::HLSL_SIZE
+            };
+            const REGISTER_ALIGNED: bool = true;
+            const LAYOUT_MATCHES: bool = N <= 1 || C == 4;
+        }
+    )*}
+}
+
+impl_hlsl_packed_scalar!(f32, u32, i32, Bool);
+
+/// arrays of structs. every element starts at a register boundary, so
+/// elements must be padded to a multiple of 16 bytes to match the rust layout.
+unsafe impl<T: ConstantBuffer, const N: usize> HlslPacked for [T; N] {
+    const HLSL_SIZE: usize = if N == 0 { 0 } else {
+        align_up(T::HLSL_SIZE, CB_REGISTER_SIZE) * (N - 1) + T::HLSL_SIZE
+    };
+    const REGISTER_ALIGNED: bool = true;
+    const LAYOUT_MATCHES: bool = T::LAYOUT_MATCHES && (N <= 1 ||
+        ::std::mem::size_of::<T>() == align_up(T::HLSL_SIZE, CB_REGISTER_SIZE));
+}
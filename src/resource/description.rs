@@ -10,10 +10,15 @@
 
 use format::*;
 use swapchain::SampleDesc;
+#[cfg(windows)]
+use error::InvalidEnumValue;
+#[cfg(windows)]
+use std::convert::TryFrom;
 
 /// resource description
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ResourceDesc {
     pub dimension: ResourceDimension,
     pub alignment: ResourceAlignment,
@@ -21,6 +26,7 @@ pub struct ResourceDesc {
     pub height: u32,
     pub depth: u16,
     pub mip_levels: u16,
+    #[cfg_attr(feature = "serde", serde(with = "::format::serde_format"))]
     pub format: DxgiFormat,
     pub sample_desc: SampleDesc,
     pub layout: TextureLayout,
@@ -41,7 +47,7 @@ impl ResourceDesc{
             format: DXGI_FORMAT_UNKNOWN,
             sample_desc: Default::default(),
             layout: TextureLayout::ROW_MAJOR,
-            flags: flags,
+            flags,
         }
     }
 
@@ -57,11 +63,11 @@ impl ResourceDesc{
             width: length,
             height: 1,
             depth: array_size,
-            mip_levels: mip_levels,
-            format: format,
+            mip_levels,
+            format,
             sample_desc: Default::default(),
             layout: TextureLayout::UNKNOWN,
-            flags: flags,
+            flags,
         }
     }
 
@@ -74,14 +80,14 @@ impl ResourceDesc{
         ResourceDesc{
             dimension: ResourceDimension::TEXTURE2D,
             alignment,
-            width: width,
-            height: height,
+            width,
+            height,
             depth: array_size,
-            mip_levels: mip_levels,
-            format: format,
+            mip_levels,
+            format,
             sample_desc: Default::default(),
             layout: TextureLayout::UNKNOWN,
-            flags: flags,
+            flags,
         }
     }
 
@@ -94,34 +100,72 @@ impl ResourceDesc{
         ResourceDesc{
             dimension: ResourceDimension::TEXTURE3D,
             alignment, width, height, depth,
-            mip_levels: mip_levels,
-            format: format,
+            mip_levels,
+            format,
             sample_desc: Default::default(),
             layout: TextureLayout::UNKNOWN,
-            flags: flags,
+            flags,
         }
     }
 }
 
+#[cfg(windows)]
 impl From<ResourceDesc> for ::winapi::D3D12_RESOURCE_DESC {
     #[inline]
     fn from(desc: ResourceDesc) -> Self {
-        unsafe{ ::std::mem::transmute(desc)}
+        ::winapi::D3D12_RESOURCE_DESC{
+            Dimension: ::winapi::D3D12_RESOURCE_DIMENSION(desc.dimension as u32),
+            Alignment: desc.alignment as u64,
+            Width: desc.width,
+            Height: desc.height,
+            DepthOrArraySize: desc.depth,
+            MipLevels: desc.mip_levels,
+            Format: desc.format,
+            SampleDesc: ::winapi::DXGI_SAMPLE_DESC{
+                Count: desc.sample_desc.count,
+                Quality: desc.sample_desc.quality,
+            },
+            Layout: ::winapi::D3D12_TEXTURE_LAYOUT(desc.layout as u32),
+            Flags: ::winapi::D3D12_RESOURCE_FLAGS(desc.flags.bits()),
+        }
     }
 }
 
-bitflags!{
+#[cfg(windows)]
+impl TryFrom<::winapi::D3D12_RESOURCE_DESC> for ResourceDesc {
+    type Error = InvalidEnumValue;
+
+    #[inline]
+    fn try_from(desc: ::winapi::D3D12_RESOURCE_DESC) -> Result<Self, InvalidEnumValue> {
+        Ok(ResourceDesc{
+            dimension: ResourceDimension::try_from(desc.Dimension.0)?,
+            alignment: ResourceAlignment::try_from(desc.Alignment)?,
+            width: desc.Width,
+            height: desc.Height,
+            depth: desc.DepthOrArraySize,
+            mip_levels: desc.MipLevels,
+            format: desc.Format,
+            sample_desc: SampleDesc{
+                count: desc.SampleDesc.Count,
+                quality: desc.SampleDesc.Quality,
+            },
+            layout: TextureLayout::try_from(desc.Layout.0)?,
+            flags: ResourceFlags::from_bits_truncate(desc.Flags.0),
+        })
+    }
+}
+
+d3d_enum!{
     /// alignment of the resource
-    #[repr(C)]
-    pub struct ResourceAlignment: u64 {
+    pub enum ResourceAlignment: u64 {
         /// 4mb for msaa textures, 64kb for everything else. This is the deefault.
-        const DEFAULT = 0;
+        DEFAULT = 0,
         /// 4kb aligned
-        const FOUR_KB = 0x1_000;
+        FOUR_KB = 0x1_000,
         /// 64kb aligned
-        const SIXTY_FOUR_KB = 0x10_000;
+        SIXTY_FOUR_KB = 0x10_000,
         /// 4mb aligned
-        const FOUR_MB = 0x1_000_000;
+        FOUR_MB = 0x1_000_000,
     }
 }
 
@@ -132,25 +176,23 @@ impl Default for ResourceAlignment {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// dimension i.e. type of the resource
-    #[repr(C)]
-    pub struct ResourceDimension: u32 {
-        const UNKNOWN    = 0;
-        const BUFFER     = 1;
-        const TEXTURE1D  = 2;
-        const TEXTURE2D  = 3;
-        const TEXTURE3D  = 4;
+    pub enum ResourceDimension {
+        UNKNOWN    = 0,
+        BUFFER     = 1,
+        TEXTURE1D  = 2,
+        TEXTURE2D  = 3,
+        TEXTURE3D  = 4,
     }
 }
 
-bitflags! {
+d3d_enum!{
     /// texture layout
-    #[repr(C)]
-    pub struct TextureLayout: u32 {
+    pub enum TextureLayout {
         /// adapter-dependent layout. driver choose optimal layout
         /// during resource creation
-        const UNKNOWN                 = 0;
+        UNKNOWN                 = 0,
         /// data for the texture is stored in row-major order.
         /// only the following texture properties are supported:
         ///
@@ -163,9 +205,9 @@ bitflags! {
         /// cannot be a YUV format
         ///
         /// Note that buffers should be row major
-        const ROW_MAJOR               = 1;
-        const UNDEFINED_SWIZZLE  = 2;
-        const STANDARD_SWIZZLE   = 3;
+        ROW_MAJOR               = 1,
+        UNDEFINED_SWIZZLE  = 2,
+        STANDARD_SWIZZLE   = 3,
     }
 }
 
@@ -219,6 +261,8 @@ bitflags!{
     }
 }
 
+impl_bitflags_serde!(ResourceFlags, u32);
+
 impl Default for ResourceFlags {
     #[inline]
     fn default() -> Self {
@@ -8,9 +8,15 @@
 
 //! Types to describe a heap
 
+#[cfg(windows)]
+use error::InvalidEnumValue;
+#[cfg(windows)]
+use std::convert::TryFrom;
+
 /// description of a heap
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HeapDesc {
     /// heap size in bytes
     pub size: u64,
@@ -35,6 +41,7 @@ impl HeapDesc{
 /// describes heap properties
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HeapProperties {
     /// heap type
     pub heap_type: HeapType,
@@ -66,24 +73,54 @@ impl Default for HeapProperties {
     }
 }
 
-bitflags!{
+#[cfg(windows)]
+impl TryFrom<::winapi::D3D12_HEAP_PROPERTIES> for HeapProperties {
+    type Error = InvalidEnumValue;
+
+    #[inline]
+    fn try_from(properties: ::winapi::D3D12_HEAP_PROPERTIES) -> Result<Self, InvalidEnumValue> {
+        Ok(HeapProperties{
+            heap_type: HeapType::try_from(properties.Type.0)?,
+            page: PageProperty::try_from(properties.CPUPageProperty.0)?,
+            pool_preference: MemoryPoolPreference::try_from(properties.MemoryPoolPreference.0)?,
+            creation_node_mask: properties.CreationNodeMask,
+            visible_node_mask: properties.VisibleNodeMask,
+        })
+    }
+}
+
+#[cfg(windows)]
+impl TryFrom<::winapi::D3D12_HEAP_DESC> for HeapDesc {
+    type Error = InvalidEnumValue;
+
+    #[inline]
+    fn try_from(desc: ::winapi::D3D12_HEAP_DESC) -> Result<Self, InvalidEnumValue> {
+        Ok(HeapDesc{
+            size: desc.SizeInBytes,
+            properties: HeapProperties::try_from(desc.Properties)?,
+            alignment: HeapAlignment::try_from(desc.Alignment)?,
+            flags: HeapFlags::from_bits_truncate(desc.Flags.0),
+        })
+    }
+}
+
+d3d_enum!{
     /// [heap type](https://msdn.microsoft.com/library/windows/desktop/dn770374(v=vs.85).aspx).
-    #[repr(C)]
-    pub struct HeapType: u32 {
+    pub enum HeapType {
         /// GPU RW, no CPU access. This is the default heap type.
-        const DEFAULT   = 1;
+        DEFAULT   = 1,
         /// Optimal for CPU write.
         /// Best for CPU write-once, GPU read-once data.
         /// Resources in this heap must be created with `GENERATE_READ` state, and
         /// cannot be changed away.
-        const UPLOAD    = 2;
+        UPLOAD    = 2,
         /// Optimal for CPU write.
         /// Best for GPU write-once, CPU readable data.
         /// Resources in this heap must be created with `COPY_DEST` state, and
         /// cannot be changed away from this.
-        const READBACK  = 3;
+        READBACK  = 3,
         /// Custom heap for advanced usage.
-        const CUSTOM    = 4;
+        CUSTOM    = 4,
     }
 }
 
@@ -94,16 +131,15 @@ impl Default for HeapType {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// cpu page properties.
-    #[repr(C)]
-    pub struct PageProperty: u32 {
+    pub enum PageProperty {
         /// The default cpu page property.
-        const UNKNOWN        = 0;
+        UNKNOWN        = 0,
         /// The CPU cannot access the heap, thus no property available.
-        const NOT_AVAILABLE  = 1;
-        const WRITE_COMBINE  = 2;
-        const WRITE_BACK     = 3;
+        NOT_AVAILABLE  = 1,
+        WRITE_COMBINE  = 2,
+        WRITE_BACK     = 3,
     }
 }
 
@@ -114,14 +150,13 @@ impl Default for PageProperty {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// memory pool preference. [more info](https://msdn.microsoft.com/library/windows/desktop/dn770381(v=vs.85).aspx)
-    #[repr(C)]
-    pub struct MemoryPoolPreference: u32 {
+    pub enum MemoryPoolPreference {
         /// The default pool preference.
-        const UNKNOWN  = 0;
-        const L0       = 1;
-        const L1       = 2;
+        UNKNOWN  = 0,
+        L0       = 1,
+        L1       = 2,
     }
 }
 
@@ -132,16 +167,15 @@ impl Default for MemoryPoolPreference {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// heap alignment
-    #[repr(C)]
-    pub struct HeapAlignment: u64 {
+    pub enum HeapAlignment: u64 {
         /// alias for 64kb, the default.
-        const DEFAULT = 0;
+        DEFAULT = 0,
         /// 64kb aligned.
-        const DEFAULT_RESOURCE_PLACEMENT = ::winapi::D3D12_DEFAULT_RESOURCE_PLACEMENT_ALIGNMENT as u64;
+        DEFAULT_RESOURCE_PLACEMENT = 0x1_0000,
         /// 4mb aligned. MSAA resource heap must use this alignment.
-        const DEFAULT_MSAA_RESOURCE_PLACEMENT = ::winapi::D3D12_DEFAULT_MSAA_RESOURCE_PLACEMENT_ALIGNMENT as u64;
+        DEFAULT_MSAA_RESOURCE_PLACEMENT = 0x40_0000,
     }
 }
 
@@ -181,6 +215,8 @@ bitflags!{
     }
 }
 
+impl_bitflags_serde!(HeapFlags, u32);
+
 impl Default for HeapFlags {
     #[inline]
     fn default() -> Self {
@@ -11,21 +11,29 @@
 pub mod description;
 pub use self::description::*;
 
+#[cfg(windows)]
 pub mod raw;
+#[cfg(windows)]
 pub use self::raw::*;
 
+#[cfg(windows)]
 pub mod traits;
+#[cfg(windows)]
 pub use self::traits::*;
 
+#[cfg(windows)]
 use device::Device;
+#[cfg(windows)]
 use error::WinError;
 
 /// a safe heap with all properties set to default
+#[cfg(windows)]
 #[derive(Debug, Clone)]
 pub struct DefaultHeap {
     pub(crate) raw: RawHeap
 }
 
+#[cfg(windows)]
 impl DefaultHeap {
     #[inline]
     pub fn new(device: &mut Device, size: u64) -> Result<Self, WinError> {
@@ -41,11 +49,13 @@ impl DefaultHeap {
 }
 
 /// an upload heap with all properties set to default
+#[cfg(windows)]
 #[derive(Debug, Clone)]
 pub struct UploadHeap {
     pub(crate) raw: RawHeap
 }
 
+#[cfg(windows)]
 impl UploadHeap {
     #[inline]
     pub fn new(device: &mut Device, size: u64) -> Result<Self, WinError> {
@@ -61,11 +71,13 @@ impl UploadHeap {
 }
 
 /// an readback heap with all properties set to default
+#[cfg(windows)]
 #[derive(Debug, Clone)]
 pub struct ReadbackHeap {
     pub(crate) raw: RawHeap
 }
 
+#[cfg(windows)]
 impl ReadbackHeap {
     #[inline]
     pub fn new(device: &mut Device, size: u64) -> Result<Self, WinError> {
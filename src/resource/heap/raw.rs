@@ -11,6 +11,7 @@
 use winapi::ID3D12Heap;
 use comptr::ComPtr;
 use super::{HeapDesc, HeapAlignment};
+use std::convert::TryFrom;
 
 /// a continous memory region
 #[derive(Clone, Debug)]
@@ -31,14 +32,17 @@ impl RawHeap {
         ret
     }
 
-    /// get heap descriptions
+    /// get heap descriptions.
+    ///
+    /// panics if the runtime reports a value unknown to this crate.
     #[inline]
     pub fn get_desc(&mut self) -> HeapDesc {
-        unsafe {
+        let desc = unsafe {
             let mut ret = ::std::mem::uninitialized();
             self.ptr.GetDesc(&mut ret);
-            ::std::mem::transmute(ret)
-        }
+            ret
+        };
+        HeapDesc::try_from(desc).expect("unknown heap description")
     }
 
     /// get heap size
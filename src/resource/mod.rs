@@ -8,6 +8,7 @@
 
 //! resource
 
+#[cfg(windows)]
 macro_rules! impl_as_raw {
     ($Trait: ident, $Type: ident, $Raw: ident) => {
         impl $Trait for $Type {
@@ -36,21 +37,34 @@ pub use self::heap::*;
 pub mod raw;
 pub use self::raw::*;
 
+#[cfg(windows)]
 pub mod barrier;
+#[cfg(windows)]
 pub use self::barrier::*;
 
 pub mod state;
 pub use self::state::*;
 
+#[cfg(windows)]
 pub mod traits;
+#[cfg(windows)]
 pub use self::traits::*;
 
+#[cfg(windows)]
 pub mod buffer;
+#[cfg(windows)]
 pub use self::buffer::*;
 
+#[cfg(windows)]
 pub mod texture;
+#[cfg(windows)]
 pub use self::texture::*;
 
+pub mod cbuffer;
+pub use self::cbuffer::{HlslPacked, ConstantBuffer};
+#[cfg(feature = "derive")]
+pub use self::cbuffer::constant_buffer;
+
 use format::*;
 
 // TODO: find out a sound way to work with different types of resources
@@ -64,12 +78,14 @@ pub struct ResourceAllocInfo {
 }
 
 /// describes a resource used for GPU texture copying
+#[cfg(windows)]
 #[derive(Copy, Clone, Debug)]
 pub struct TextureCopyLocation {
     ptr: *mut ::winapi::ID3D12Resource,
     pub copy_type: TextureCopyType,
 }
 
+#[cfg(windows)]
 impl From<TextureCopyLocation> for ::winapi::D3D12_TEXTURE_COPY_LOCATION {
     #[inline]
     fn from(loc: TextureCopyLocation) -> Self {
@@ -8,27 +8,39 @@
 
 //! Raw resource
 
+#[cfg(windows)]
 use winapi::ID3D12Resource;
+#[cfg(windows)]
 use comptr::ComPtr;
+#[cfg(windows)]
 use error::WinError;
+#[cfg(windows)]
 use super::*;
+#[cfg(windows)]
 use format::Box3u;
+#[cfg(windows)]
+use std::convert::TryFrom;
 
 /// a raw resource
+#[cfg(windows)]
 #[derive(Clone, Debug)]
 pub struct RawResource {
     pub(crate) ptr: ComPtr<ID3D12Resource>,
 }
 
+#[cfg(windows)]
 impl RawResource {
-    /// get resource description
+    /// get resource description.
+    ///
+    /// panics if the runtime reports a value unknown to this crate.
     #[inline]
     pub fn get_desc(&mut self) -> ResourceDesc {
-        unsafe {
+        let desc = unsafe {
             let mut ret = ::std::mem::uninitialized();
             self.ptr.GetDesc(&mut ret);
-            ::std::mem::transmute(ret)
-        }
+            ret
+        };
+        ResourceDesc::try_from(desc).expect("unknown resource description")
     }
 
     /// get the GPU virtual address for a buffer resource, `0` for texture resources
@@ -39,14 +51,17 @@ impl RawResource {
 
     /// attempt to get the attached heap's info. This method would only work
     /// on committed or placed resources, not on reserved ones.
+    ///
+    /// panics if the runtime reports a value unknown to this crate.
     #[inline]
     pub fn get_heap_info(&mut self) -> Result<(HeapProperties, HeapFlags), WinError> {
         unsafe {
             let mut hp = ::std::mem::uninitialized();
-            let mut hf = ::std::mem::uninitialized();
+            let mut hf: ::winapi::D3D12_HEAP_FLAGS = ::std::mem::uninitialized();
             let hr = self.ptr.GetHeapProperties(&mut hp, &mut hf);
             WinError::from_hresult_or_ok(hr, || (
-                ::std::mem::transmute(hp), ::std::mem::transmute(hf)
+                HeapProperties::try_from(hp).expect("unknown heap properties"),
+                HeapFlags::from_bits_truncate(hf.0)
             ))
         }
     }
@@ -138,10 +153,12 @@ pub struct ResourceChunkDesc {
 /// GPU virtual device
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GpuVAddress {
     pub(crate) ptr: u64,
 }
 
+#[cfg(windows)]
 impl From<GpuVAddress> for ::winapi::D3D12_GPU_VIRTUAL_ADDRESS {
     #[inline]
     fn from(addr: GpuVAddress) -> Self {
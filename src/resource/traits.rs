@@ -8,11 +8,12 @@
 
 //! Resource Traits
 
-use super::raw::RawResource;
+use super::raw::{RawResource, GpuVAddress};
 use error::WinError;
 use descriptor::*;
 use device::Device;
 use super::buffer::BufferSlice;
+use super::cbuffer::{ConstantBuffer, CBV_ALIGNMENT};
 use pipeline::ia::{IndexBufferView, VertexBufferView};
 
 pub trait Resource {
@@ -92,6 +93,20 @@ pub unsafe trait Buffer: Resource {
         }, index);
     }
 
+    /// create a cbv viewing a `T` at `offset` bytes into this buffer on
+    /// `csu_heap` at `index`. `offset` must be a multiple of 256.
+    #[inline]
+    fn create_cbv_of<T: ConstantBuffer, DH: CsuHeap>(
+        &mut self, device: &mut Device, csu_heap: &mut DH, index: u32, offset: u64
+    ) {
+        debug_assert!(offset % CBV_ALIGNMENT as u64 == 0);
+        debug_assert!(offset + T::CBV_SIZE as u64 <= self.get_size());
+        csu_heap.create_cbv(device, &CbvDesc{
+            buffer_location: GpuVAddress{ptr: self.as_raw_mut().get_gpu_vaddress().ptr + offset},
+            size: T::CBV_SIZE,
+        }, index);
+    }
+
     // TODO: investigate rtv on buffer, https://msdn.microsoft.com/en-us/library/windows/desktop/dn770342(v=vs.85).aspx
 
     /// Create a srv for this buffer on `csu_heap` at `index` with the given `slice`
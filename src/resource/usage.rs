@@ -21,7 +21,7 @@ bitflags! {
         /// FIXME: wth?
         const CPU_SCRATCH = 3;
         /// resource can be used as shader input
-        const SHADER_INPUT = 1<<(0+4);
+        const SHADER_INPUT = 1<<4;
         /// resource can be used as render target output
         const RENDER_TARGET_OUTPUT = 1<<(1+4);
         /// resource is used as a back buffer. This flag don't need to be passed when creating a swapchain.
@@ -37,6 +37,7 @@ bitflags! {
     }
 }
 
+#[cfg(windows)]
 impl From<Usage> for ::winapi::DXGI_USAGE {
     fn from(usage: Usage) -> Self {
         ::winapi::DXGI_USAGE(usage.bits())
@@ -0,0 +1,839 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! compiler backends of `ShaderBuilder`: FXC through `D3DCompile`, DXC
+//! loaded at runtime, and lookups of shaders compiled ahead of time.
+
+#[cfg(windows)]
+use comptr::ComPtr;
+#[cfg(windows)]
+use winapi::{GUID, HMODULE, HRESULT, ID3DBlob, IUnknown, IUnknownVtbl, LPCWSTR, LPVOID, UINT32};
+#[cfg(windows)]
+use error::WinError;
+use dxbc::{ShaderStage, ShaderVersion};
+use super::{Diagnostic, Severity, ShaderBuilder, ShaderBytecodeError};
+#[cfg(windows)]
+use super::{ShaderCompileFlags, ShaderMacro};
+#[cfg(windows)]
+use smallvec::SmallVec;
+use std::collections::HashMap;
+#[cfg(windows)]
+use std::ffi::{CStr, CString};
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::{error, fmt, fs, io};
+#[cfg(windows)]
+use std::{mem, ptr, slice};
+
+/// a shader model, which together with the stage selects the compile target
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ShaderModel {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl ShaderModel {
+    pub const SM_5_0: ShaderModel = ShaderModel{major: 5, minor: 0};
+    pub const SM_5_1: ShaderModel = ShaderModel{major: 5, minor: 1};
+    pub const SM_6_0: ShaderModel = ShaderModel{major: 6, minor: 0};
+    pub const SM_6_1: ShaderModel = ShaderModel{major: 6, minor: 1};
+    pub const SM_6_2: ShaderModel = ShaderModel{major: 6, minor: 2};
+    pub const SM_6_3: ShaderModel = ShaderModel{major: 6, minor: 3};
+    pub const SM_6_4: ShaderModel = ShaderModel{major: 6, minor: 4};
+    pub const SM_6_5: ShaderModel = ShaderModel{major: 6, minor: 5};
+    pub const SM_6_6: ShaderModel = ShaderModel{major: 6, minor: 6};
+    pub const SM_6_7: ShaderModel = ShaderModel{major: 6, minor: 7};
+
+    #[inline]
+    pub fn new(major: u8, minor: u8) -> ShaderModel {
+        ShaderModel{major, minor}
+    }
+
+    /// the compile target of a `stage` program, e.g. `vs_5_1`
+    #[inline]
+    pub fn target(self, stage: ShaderStage) -> ShaderVersion {
+        ShaderVersion{stage, major: self.major, minor: self.minor}
+    }
+
+    /// whether programs of this model are DXIL, which only DXC compiles
+    #[inline]
+    pub fn is_dxil(self) -> bool {
+        self.major >= 6
+    }
+}
+
+impl Default for ShaderModel {
+    #[inline]
+    fn default() -> ShaderModel {
+        ShaderModel::SM_5_1
+    }
+}
+
+impl fmt::Display for ShaderModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// an error compiling a shader
+#[derive(Debug)]
+pub enum ShaderCompileError {
+    /// the compiler failed without reporting why
+    #[cfg(windows)]
+    Win(WinError),
+    /// the compiler rejected the source, reporting at least one error.
+    /// output with no recognizable error is kept whole as the message of one.
+    Compile(Vec<Diagnostic>),
+    /// the compiler reported warnings with `ShaderBuilder::fail_on_warnings` set
+    Warnings(Vec<Diagnostic>),
+    /// the compiler can't produce programs for the target, e.g. FXC and
+    /// shader model 6
+    UnsupportedTarget(ShaderVersion),
+    /// the compiler library couldn't be loaded
+    CompilerNotFound(PathBuf),
+    /// no precompiled byte code has this permutation key
+    NotPrecompiled(u64),
+    /// the compiler produced an invalid byte code
+    Bytecode(ShaderBytecodeError),
+}
+
+impl fmt::Display for ShaderCompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            #[cfg(windows)]
+            ShaderCompileError::Win(ref err) => write!(f, "{:?}", err),
+            ShaderCompileError::Compile(ref diagnostics) |
+            ShaderCompileError::Warnings(ref diagnostics) => {
+                for (idx, diagnostic) in diagnostics.iter().enumerate() {
+                    if idx > 0 { f.write_str("\n")?; }
+                    write!(f, "{}", diagnostic)?;
+                }
+                Ok(())
+            },
+            ShaderCompileError::UnsupportedTarget(target) =>
+                write!(f, "the compiler doesn't support {}", target),
+            ShaderCompileError::CompilerNotFound(ref path) =>
+                write!(f, "can't load the shader compiler {}", path.display()),
+            ShaderCompileError::NotPrecompiled(key) =>
+                write!(f, "no precompiled shader for permutation {:016x}", key),
+            ShaderCompileError::Bytecode(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for ShaderCompileError {}
+
+#[cfg(windows)]
+impl From<WinError> for ShaderCompileError {
+    #[inline]
+    fn from(err: WinError) -> Self {
+        ShaderCompileError::Win(err)
+    }
+}
+
+impl From<ShaderBytecodeError> for ShaderCompileError {
+    #[inline]
+    fn from(err: ShaderBytecodeError) -> Self {
+        ShaderCompileError::Bytecode(err)
+    }
+}
+
+/// the result of a successful compilation
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CompileOutput {
+    /// the DXBC or DXIL container
+    pub bytes: Vec<u8>,
+    /// warnings and notes reported by the compiler
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CompileOutput {
+    /// whether the compiler reported any warning
+    #[inline]
+    pub fn has_warnings(&self) -> bool {
+        self.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Warning)
+    }
+}
+
+impl From<Vec<u8>> for CompileOutput {
+    #[inline]
+    fn from(bytes: Vec<u8>) -> CompileOutput {
+        CompileOutput{bytes, diagnostics: Vec::new()}
+    }
+}
+
+/// a backend compiling the HLSL source of a `ShaderBuilder`.
+///
+/// closures taking a builder and a target implement it too, e.g. to fake
+/// a compiler in tests.
+pub trait ShaderCompiler {
+    /// compile the permutation of `builder` for `target`
+    fn compile(&self, builder: &ShaderBuilder, target: ShaderVersion) -> Result<CompileOutput, ShaderCompileError>;
+}
+
+impl<F> ShaderCompiler for F
+    where F: Fn(&ShaderBuilder, ShaderVersion) -> Result<CompileOutput, ShaderCompileError>
+{
+    #[inline]
+    fn compile(&self, builder: &ShaderBuilder, target: ShaderVersion) -> Result<CompileOutput, ShaderCompileError> {
+        self(builder, target)
+    }
+}
+
+/// check the result of a compilation, reporting its diagnostics on
+/// failure, or `hr` if the compiler gave no reason
+#[cfg(windows)]
+fn check_compiled(hr: HRESULT, text: &str) -> Result<Vec<Diagnostic>, ShaderCompileError> {
+    match WinError::from_hresult(hr) {
+        Ok(()) => Ok(Diagnostic::parse_all(text)),
+        Err(err) => {
+            let diagnostics = failure_diagnostics(text);
+            if diagnostics.is_empty() {
+                Err(ShaderCompileError::Win(err))
+            } else {
+                Err(ShaderCompileError::Compile(diagnostics))
+            }
+        },
+    }
+}
+
+/// the diagnostics of a failed compilation. output without any parsable
+/// error, e.g. a crash report, is kept whole as the message of an error.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn failure_diagnostics(text: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Diagnostic::parse_all(text);
+    let text = text.trim();
+    if !text.is_empty() && !diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        diagnostics.push(Diagnostic{
+            file: None, line: None, column: None,
+            severity: Severity::Error,
+            code: None,
+            message: text.to_owned(),
+            included_from: Vec::new(),
+        });
+    }
+    diagnostics
+}
+
+/// the error and warning text in `blob`, which may be null, taking its
+/// reference
+#[cfg(windows)]
+unsafe fn blob_text(blob: *mut ID3DBlob) -> String {
+    if blob.is_null() { return String::new(); }
+    let text = blob_bytes(&ComPtr::new(blob));
+    String::from_utf8_lossy(&text).trim_end_matches('\0').to_owned()
+}
+
+/// copy the content of `blob`
+#[cfg(windows)]
+fn blob_bytes(blob: &ComPtr<ID3DBlob>) -> Vec<u8> {
+    unsafe {
+        let blob = blob.as_mut_ptr();
+        slice::from_raw_parts((*blob).GetBufferPointer() as *const u8, (*blob).GetBufferSize() as usize)
+            .to_vec()
+    }
+}
+
+/// FXC, through `D3DCompile` of `d3dcompiler_47.dll`, compiling shader
+/// models up to 5.1
+#[cfg(windows)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Fxc;
+
+#[cfg(windows)]
+impl ShaderCompiler for Fxc {
+    fn compile(&self, builder: &ShaderBuilder, target: ShaderVersion) -> Result<CompileOutput, ShaderCompileError> {
+        if target.major >= 6 { return Err(ShaderCompileError::UnsupportedTarget(target)); }
+        let profile = CString::new(target.to_string()).unwrap();
+        // the macro list ends with a null macro
+        let mut macros = builder.shader_macros.iter().cloned().collect::<SmallVec<[ShaderMacro; 9]>>();
+        macros.push(Default::default());
+        unsafe {
+            let mut code = ptr::null_mut();
+            let mut errors = ptr::null_mut();
+            let hr = ::d3dcompiler::D3DCompile(
+                builder.src_data.as_ptr() as *const _,
+                builder.src_data.len() as _,
+                builder.source_name.map_or(ptr::null(), |name| name.as_ptr()),
+                macros.as_ptr() as *const _,
+                ::winapi::D3D_COMPILE_STANDARD_FILE_INCLUDE,
+                builder.entry_point.as_ptr(),
+                profile.as_ptr(),
+                builder.flags.bits(),
+                0,
+                &mut code,
+                &mut errors
+            );
+            let diagnostics = check_compiled(hr, &blob_text(errors))?;
+            Ok(CompileOutput{bytes: blob_bytes(&ComPtr::new(code)), diagnostics})
+        }
+    }
+}
+
+/// DXC, loaded from `dxcompiler.dll` at runtime, compiling shader model
+/// 6.0 and later. programs are signed if `dxil.dll` is found along with
+/// it, and are otherwise only accepted with experimental shader models.
+#[cfg(windows)]
+#[derive(Debug)]
+pub struct Dxc {
+    library: HMODULE,
+    create_instance: DxcCreateInstanceProc,
+}
+
+// `DxcCreateInstance` is thread safe, and the library is only freed on drop
+#[cfg(windows)]
+unsafe impl Send for Dxc {}
+#[cfg(windows)]
+unsafe impl Sync for Dxc {}
+
+#[cfg(windows)]
+impl Dxc {
+    /// load `dxcompiler.dll` from the library search path
+    #[inline]
+    pub fn load() -> Result<Dxc, ShaderCompileError> {
+        Dxc::load_from("dxcompiler.dll")
+    }
+
+    /// load the DXC library at `path`
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<Dxc, ShaderCompileError> {
+        let path = path.as_ref();
+        let wide_path = path.as_os_str().encode_wide().chain(Some(0)).collect::<Vec<_>>();
+        unsafe {
+            let library = ::kernel32::LoadLibraryW(wide_path.as_ptr());
+            if library.is_null() {
+                return Err(ShaderCompileError::CompilerNotFound(path.to_owned()));
+            }
+            let create_instance = ::kernel32::GetProcAddress(library, b"DxcCreateInstance\0".as_ptr() as *const _);
+            if create_instance.is_null() {
+                ::kernel32::FreeLibrary(library);
+                return Err(ShaderCompileError::CompilerNotFound(path.to_owned()));
+            }
+            Ok(Dxc{library, create_instance: mem::transmute::<_, DxcCreateInstanceProc>(create_instance)})
+        }
+    }
+
+    unsafe fn create_instance<T>(&self, clsid: &GUID, iid: &GUID) -> Result<ComPtr<T>, WinError> {
+        let mut ret = ptr::null_mut();
+        let hr = (self.create_instance)(clsid, iid, &mut ret);
+        WinError::from_hresult_or_ok(hr, || ComPtr::new(ret as *mut T))
+    }
+}
+
+#[cfg(windows)]
+impl Drop for Dxc {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { ::kernel32::FreeLibrary(self.library); }
+    }
+}
+
+#[cfg(windows)]
+impl ShaderCompiler for Dxc {
+    fn compile(&self, builder: &ShaderBuilder, target: ShaderVersion) -> Result<CompileOutput, ShaderCompileError> {
+        if target.major < 6 { return Err(ShaderCompileError::UnsupportedTarget(target)); }
+        let source_name = builder.source_name.map(|name| wide(&name.to_string_lossy()));
+        let entry_point = wide(&builder.entry_point.to_string_lossy());
+        let profile = wide(&target.to_string());
+        let arguments = dxc_arguments(builder.flags).iter()
+            .map(|argument| wide(argument))
+            .collect::<Vec<_>>();
+        let argument_ptrs = arguments.iter().map(|argument| argument.as_ptr()).collect::<Vec<_>>();
+        let macros = builder.shader_macros.iter()
+            .filter(|shader_macro| !shader_macro.name.is_null())
+            .map(|shader_macro| unsafe {
+                let definition = if shader_macro.definition.is_null() {
+                    None
+                } else {
+                    Some(wide(&CStr::from_ptr(shader_macro.definition).to_string_lossy()))
+                };
+                (wide(&CStr::from_ptr(shader_macro.name).to_string_lossy()), definition)
+            })
+            .collect::<Vec<_>>();
+        let defines = macros.iter()
+            .map(|(name, definition)| DxcDefine{
+                name: name.as_ptr(),
+                value: definition.as_ref().map_or(ptr::null(), |definition| definition.as_ptr()),
+            })
+            .collect::<Vec<_>>();
+
+        unsafe {
+            let library: ComPtr<IDxcLibrary> = self.create_instance(&CLSID_DXC_LIBRARY, &IID_IDXC_LIBRARY)?;
+            let compiler: ComPtr<IDxcCompiler> = self.create_instance(&CLSID_DXC_COMPILER, &IID_IDXC_COMPILER)?;
+            let library = library.as_mut_ptr();
+
+            let mut source = ptr::null_mut();
+            WinError::from_hresult(((*(*library).vtbl).create_blob_with_encoding_from_pinned)(
+                library, builder.src_data.as_ptr() as *const _, builder.src_data.len() as _,
+                ::winapi::CP_UTF8, &mut source
+            ))?;
+            let source = ComPtr::new(source);
+            // resolves `#include`s relative to the including file
+            let mut include_handler = ptr::null_mut();
+            WinError::from_hresult(((*(*library).vtbl).create_include_handler)(library, &mut include_handler))?;
+            let include_handler = ComPtr::new(include_handler);
+
+            let mut result = ptr::null_mut();
+            let compiler = compiler.as_mut_ptr();
+            WinError::from_hresult(((*(*compiler).vtbl).compile)(
+                compiler,
+                source.as_mut_ptr(),
+                source_name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+                entry_point.as_ptr(),
+                profile.as_ptr(),
+                argument_ptrs.as_ptr(),
+                argument_ptrs.len() as _,
+                defines.as_ptr(),
+                defines.len() as _,
+                include_handler.as_mut_ptr(),
+                &mut result
+            ))?;
+            let result: ComPtr<IDxcOperationResult> = ComPtr::new(result);
+            let result = result.as_mut_ptr();
+
+            let mut status = 0;
+            WinError::from_hresult(((*(*result).vtbl).get_status)(result, &mut status))?;
+            let mut errors = ptr::null_mut();
+            WinError::from_hresult(((*(*result).vtbl).get_error_buffer)(result, &mut errors))?;
+            let diagnostics = check_compiled(status, &blob_text(errors))?;
+            let mut code = ptr::null_mut();
+            WinError::from_hresult(((*(*result).vtbl).get_result)(result, &mut code))?;
+            Ok(CompileOutput{bytes: blob_bytes(&ComPtr::new(code)), diagnostics})
+        }
+    }
+}
+
+/// `s` as a nul terminated UTF-16 string
+#[cfg(windows)]
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+/// the DXC command line arguments equivalent to `flags`
+#[cfg(windows)]
+fn dxc_arguments(flags: ShaderCompileFlags) -> Vec<&'static str> {
+    let switches = [
+        (ShaderCompileFlags::SKIP_VALIDATION, "-Vd"),
+        (ShaderCompileFlags::SKIP_OPTIMIZATION, "-Od"),
+        (ShaderCompileFlags::PACK_MATRIX_ROW_MAJOR, "-Zpr"),
+        (ShaderCompileFlags::PACK_MATRIX_COLUMN_MAJOR, "-Zpc"),
+        (ShaderCompileFlags::AVOID_FLOW_CONTROL, "-Gfa"),
+        (ShaderCompileFlags::PREFER_FLOW_CONTROL, "-Gfp"),
+        (ShaderCompileFlags::ENABLE_STRICTNESS, "-Ges"),
+        (ShaderCompileFlags::ENABLE_BACKWARDS_COMPATIBILITY, "-Gec"),
+        (ShaderCompileFlags::IEEE_STRICTNESS, "-Gis"),
+        (ShaderCompileFlags::WARNINGS_ARE_ERRORS, "-WX"),
+        (ShaderCompileFlags::RESOURCES_MAY_ALIAS, "-res_may_alias"),
+        (ShaderCompileFlags::ALL_RESOURCES_BOUND, "-all_resources_bound"),
+    ];
+    let mut ret = switches.iter()
+        .filter(|&&(flag, _)| flags.contains(flag))
+        .map(|&(_, argument)| argument)
+        .collect::<Vec<_>>();
+    // debug information is embedded, as there's no separate PDB output
+    if flags.contains(ShaderCompileFlags::DEBUG) {
+        ret.push("-Zi");
+        ret.push("-Qembed_debug");
+    }
+    // the optimization level is a two bit field, where level 1 is zero and
+    // leaves DXC at its default
+    let level = flags & ShaderCompileFlags::OPTIMIZATION_LEVEL2;
+    if level == ShaderCompileFlags::OPTIMIZATION_LEVEL0 {
+        ret.push("-O0");
+    } else if level == ShaderCompileFlags::OPTIMIZATION_LEVEL2 {
+        ret.push("-O2");
+    } else if level == ShaderCompileFlags::OPTIMIZATION_LEVEL3 {
+        ret.push("-O3");
+    }
+    ret
+}
+
+/// a compiler looking up byte codes compiled ahead of time, e.g. for
+/// shipping builds without a runtime compiler. byte codes are keyed by
+/// `ShaderBuilder::permutation_key`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrecompiledShaders {
+    shaders: HashMap<u64, Vec<u8>>,
+}
+
+impl PrecompiledShaders {
+    #[inline]
+    pub fn new() -> PrecompiledShaders {
+        Default::default()
+    }
+
+    /// add the byte code of the permutation `key`, returning the replaced one
+    #[inline]
+    pub fn insert(&mut self, key: u64, bytes: Vec<u8>) -> Option<Vec<u8>> {
+        self.shaders.insert(key, bytes)
+    }
+
+    /// the byte code of the permutation `key`
+    #[inline]
+    pub fn get(&self, key: u64) -> Option<&[u8]> {
+        self.shaders.get(&key).map(|bytes| &bytes[..])
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.shaders.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.shaders.is_empty()
+    }
+
+    /// compile the permutation of `builder` for `target` with `compiler`
+    /// and keep the byte code, returning its key. e.g. in a build script,
+    /// followed by `save_dir`
+    pub fn record<C: ShaderCompiler + ?Sized>(
+        &mut self, compiler: &C, builder: &ShaderBuilder, target: ShaderVersion
+    ) -> Result<u64, ShaderCompileError> {
+        let key = builder.permutation_key(target);
+        let output = compiler.compile(builder, target)?;
+        self.shaders.insert(key, output.bytes);
+        Ok(key)
+    }
+
+    /// write each byte code into `dir`, named by its key in hex, e.g.
+    /// `0123456789abcdef.cso`
+    pub fn save_dir<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        for (key, bytes) in &self.shaders {
+            fs::write(dir.join(format!("{:016x}.cso", key)), bytes)?;
+        }
+        Ok(())
+    }
+
+    /// load the byte codes written by `save_dir` into `dir`, ignoring other files
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> io::Result<PrecompiledShaders> {
+        let mut ret = PrecompiledShaders::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension() != Some("cso".as_ref()) { continue; }
+            let key = path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .filter(|stem| stem.len() == 16)
+                .and_then(|stem| u64::from_str_radix(stem, 16).ok());
+            if let Some(key) = key {
+                ret.shaders.insert(key, fs::read(&path)?);
+            }
+        }
+        Ok(ret)
+    }
+}
+
+impl ShaderCompiler for PrecompiledShaders {
+    fn compile(&self, builder: &ShaderBuilder, target: ShaderVersion) -> Result<CompileOutput, ShaderCompileError> {
+        let key = builder.permutation_key(target);
+        self.get(key)
+            .map(|bytes| bytes.to_vec().into())
+            .ok_or(ShaderCompileError::NotPrecompiled(key))
+    }
+}
+
+// the parts of `dxcapi.h` used above. `IDxcBlob` shares the layout of
+// `ID3DBlob`, and vtables end at the last method called.
+
+#[cfg(windows)]
+type DxcCreateInstanceProc = unsafe extern "system" fn(*const GUID, *const GUID, *mut LPVOID) -> HRESULT;
+
+#[cfg(windows)]
+const CLSID_DXC_COMPILER: GUID = GUID{
+    Data1: 0x73e22d93, Data2: 0xe6ce, Data3: 0x47f3,
+    Data4: [0xb5, 0xbf, 0xf0, 0x66, 0x4f, 0x39, 0xc1, 0xb0],
+};
+#[cfg(windows)]
+const CLSID_DXC_LIBRARY: GUID = GUID{
+    Data1: 0x6245d6af, Data2: 0x66e0, Data3: 0x48fd,
+    Data4: [0x80, 0xb4, 0x4d, 0x27, 0x17, 0x96, 0x74, 0x8c],
+};
+#[cfg(windows)]
+const IID_IDXC_COMPILER: GUID = GUID{
+    Data1: 0x8c210bf3, Data2: 0x011f, Data3: 0x4422,
+    Data4: [0x8d, 0x70, 0x6f, 0x9a, 0xcb, 0x8d, 0xb6, 0x17],
+};
+#[cfg(windows)]
+const IID_IDXC_LIBRARY: GUID = GUID{
+    Data1: 0xe5204dc7, Data2: 0xd18c, Data3: 0x4c3c,
+    Data4: [0xbd, 0xfb, 0x85, 0x16, 0x73, 0x98, 0x0f, 0xe7],
+};
+
+#[cfg(windows)]
+#[repr(C)]
+struct DxcDefine {
+    name: LPCWSTR,
+    value: LPCWSTR,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+struct IDxcLibrary {
+    vtbl: *const IDxcLibraryVtbl,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+struct IDxcLibraryVtbl {
+    unknown: IUnknownVtbl,
+    set_malloc: usize,
+    create_blob_from_blob: usize,
+    create_blob_from_file: usize,
+    create_blob_with_encoding_from_pinned: unsafe extern "system" fn(
+        *mut IDxcLibrary, *const ::std::os::raw::c_void, UINT32, UINT32, *mut *mut ID3DBlob
+    ) -> HRESULT,
+    create_blob_with_encoding_on_heap_copy: usize,
+    create_blob_with_encoding_on_malloc: usize,
+    create_include_handler: unsafe extern "system" fn(*mut IDxcLibrary, *mut *mut IUnknown) -> HRESULT,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+struct IDxcCompiler {
+    vtbl: *const IDxcCompilerVtbl,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+struct IDxcCompilerVtbl {
+    unknown: IUnknownVtbl,
+    compile: unsafe extern "system" fn(
+        *mut IDxcCompiler,
+        *mut ID3DBlob,
+        LPCWSTR,
+        LPCWSTR,
+        LPCWSTR,
+        *const LPCWSTR,
+        UINT32,
+        *const DxcDefine,
+        UINT32,
+        *mut IUnknown,
+        *mut *mut IDxcOperationResult
+    ) -> HRESULT,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+struct IDxcOperationResult {
+    vtbl: *const IDxcOperationResultVtbl,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+struct IDxcOperationResultVtbl {
+    unknown: IUnknownVtbl,
+    get_status: unsafe extern "system" fn(*mut IDxcOperationResult, *mut HRESULT) -> HRESULT,
+    get_result: unsafe extern "system" fn(*mut IDxcOperationResult, *mut *mut ID3DBlob) -> HRESULT,
+    get_error_buffer: unsafe extern "system" fn(*mut IDxcOperationResult, *mut *mut ID3DBlob) -> HRESULT,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{ShaderMacro, ShaderCompileFlags};
+    use std::cell::Cell;
+    use std::ffi::CString;
+
+    const VS: &[u8] = include_bytes!("../../tests/fixtures/passthrough_vs.cso");
+
+    fn cstr(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    /// a fake compiler returning the passthrough vertex shader, counting its calls
+    fn fake_compiler(calls: &Cell<u32>) -> impl Fn(&ShaderBuilder, ShaderVersion) -> Result<CompileOutput, ShaderCompileError> + '_ {
+        move |_: &ShaderBuilder, _: ShaderVersion| {
+            calls.set(calls.get() + 1);
+            Ok(VS.to_vec().into())
+        }
+    }
+
+    #[test]
+    fn permutation_keys() {
+        let (main, other_main) = (cstr("main"), cstr("vs_main"));
+        let (name, other_name) = (cstr("a.hlsl"), cstr("b.hlsl"));
+        let (define, value, other_value) = (cstr("LIGHTS"), cstr("4"), cstr("8"));
+        let (split_a, split_b) = (cstr("LIGHTS4"), cstr(""));
+        let base = ShaderBuilder::new(b"float4 main() : SV_Position { return 0; }", &main);
+        let target = ShaderModel::SM_5_1.target(ShaderStage::VERTEX);
+        let key = base.permutation_key(target);
+
+        // stable across runs and hosts, which `PrecompiledShaders` relies on
+        assert_eq!(key, 0x1913_e6fc_1270_9a85);
+        // the source isn't hashed, nor the null macro ending a macro list
+        let mut builder = ShaderBuilder::new(b"", &main);
+        builder.shader_macros.push(ShaderMacro::default());
+        assert_eq!(builder.permutation_key(target), key);
+
+        assert_ne!(base.permutation_key(ShaderModel::SM_5_0.target(ShaderStage::VERTEX)), key);
+        assert_ne!(base.permutation_key(ShaderModel::SM_5_1.target(ShaderStage::PIXEL)), key);
+        assert_ne!(ShaderBuilder::new(base.src_data, &other_main).permutation_key(target), key);
+
+        let mut builder = ShaderBuilder::new(base.src_data, &main);
+        builder.source_name = Some(&name);
+        let named = builder.permutation_key(target);
+        assert_ne!(named, key);
+        builder.source_name = Some(&other_name);
+        assert_ne!(builder.permutation_key(target), named);
+
+        let mut builder = ShaderBuilder::new(base.src_data, &main);
+        builder.flags = ShaderCompileFlags::DEBUG;
+        assert_ne!(builder.permutation_key(target), key);
+
+        let mut four = ShaderBuilder::new(base.src_data, &main);
+        four.shader_macros.push(ShaderMacro::new(&define, &value));
+        let mut eight = ShaderBuilder::new(base.src_data, &main);
+        eight.shader_macros.push(ShaderMacro::new(&define, &other_value));
+        let mut split = ShaderBuilder::new(base.src_data, &main);
+        split.shader_macros.push(ShaderMacro::new(&split_a, &split_b));
+        let keys = [key, four.permutation_key(target), eight.permutation_key(target), split.permutation_key(target)];
+        for (i, a) in keys.iter().enumerate() {
+            for b in &keys[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn closures_compile() {
+        let main = cstr("main");
+        let mut builder = ShaderBuilder::new(b"", &main);
+        builder.shader_model = ShaderModel::SM_5_0;
+        let target = Cell::new(None);
+        let compiler = |_: &ShaderBuilder, version: ShaderVersion| {
+            target.set(Some(version));
+            Ok(VS.to_vec().into())
+        };
+        let vs = builder.build_vs_with(&compiler).unwrap();
+        assert_eq!(vs.as_bytes(), VS);
+        assert_eq!(target.get(), Some(ShaderModel::SM_5_0.target(ShaderStage::VERTEX)));
+
+        // the container is checked against the stage built
+        match builder.build_ps_with(&compiler) {
+            Err(ShaderCompileError::Bytecode(_)) => {},
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(target.get(), Some(ShaderModel::SM_5_0.target(ShaderStage::PIXEL)));
+    }
+
+    #[test]
+    fn warnings() {
+        let main = cstr("main");
+        let mut builder = ShaderBuilder::new(b"", &main);
+        let compiler = |_: &ShaderBuilder, _: ShaderVersion| Ok(CompileOutput{
+            bytes: VS.to_vec(),
+            diagnostics: Diagnostic::parse_all("a.hlsl(3,4): warning X3206: implicit truncation of vector type"),
+        });
+        let output = builder.compile_with(&compiler, ShaderStage::VERTEX).unwrap();
+        assert!(output.has_warnings());
+        assert!(builder.build_vs_with(&compiler).is_ok());
+
+        builder.fail_on_warnings = true;
+        match builder.build_vs_with(&compiler) {
+            Err(ShaderCompileError::Warnings(ref diagnostics)) => assert_eq!(diagnostics, &output.diagnostics),
+            other => panic!("unexpected result {:?}", other),
+        }
+        // fail_on_warnings isn't part of the permutation
+        let target = builder.shader_model.target(ShaderStage::VERTEX);
+        let key = builder.permutation_key(target);
+        builder.fail_on_warnings = false;
+        assert_eq!(builder.permutation_key(target), key);
+    }
+
+    #[test]
+    fn precompiled_permutations() {
+        let calls = Cell::new(0);
+        let compiler = fake_compiler(&calls);
+        let (main, define, one, two) = (cstr("main"), cstr("VARIANT"), cstr("1"), cstr("2"));
+        let mut first = ShaderBuilder::new(b"", &main);
+        first.shader_macros.push(ShaderMacro::new(&define, &one));
+        let mut second = ShaderBuilder::new(b"", &main);
+        second.shader_macros.push(ShaderMacro::new(&define, &two));
+        let target = ShaderModel::SM_5_1.target(ShaderStage::VERTEX);
+
+        let mut precompiled = PrecompiledShaders::new();
+        let key = precompiled.record(&compiler, &first, target).unwrap();
+        assert_eq!(key, first.permutation_key(target));
+        assert_eq!(calls.get(), 1);
+        assert_eq!(precompiled.len(), 1);
+
+        // looking up the recorded permutation doesn't compile
+        let vs = first.build_vs_with(&precompiled).unwrap();
+        assert_eq!(vs.as_bytes(), VS);
+        assert_eq!(calls.get(), 1);
+
+        // other permutations, stages and models aren't found
+        let second_key = second.permutation_key(target);
+        match second.build_vs_with(&precompiled) {
+            Err(ShaderCompileError::NotPrecompiled(key)) => assert_eq!(key, second_key),
+            other => panic!("unexpected result {:?}", other),
+        }
+        match first.build_ps_with(&precompiled) {
+            Err(ShaderCompileError::NotPrecompiled(_)) => {},
+            other => panic!("unexpected result {:?}", other),
+        }
+        first.shader_model = ShaderModel::SM_5_0;
+        assert!(first.build_vs_with(&precompiled).is_err());
+
+        // a failed compilation records nothing
+        let failing = |_: &ShaderBuilder, _: ShaderVersion| {
+            Err(ShaderCompileError::Compile(failure_diagnostics("internal compiler error")))
+        };
+        assert!(precompiled.record(&failing, &second, target).is_err());
+        assert_eq!(precompiled.len(), 1);
+        assert_eq!(precompiled.record(&compiler, &second, target).unwrap(), second_key);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(precompiled.len(), 2);
+    }
+
+    #[test]
+    fn precompiled_dirs() {
+        let dir = ::std::env::temp_dir().join(format!("redirect-precompiled-{}", ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut precompiled = PrecompiledShaders::new();
+        precompiled.insert(0x0123_4567_89ab_cdef, VS.to_vec());
+        precompiled.insert(0xfedc_ba98_7654_3210, vec![1, 2, 3]);
+        precompiled.save_dir(&dir).unwrap();
+        assert!(dir.join("0123456789abcdef.cso").is_file());
+
+        // files not named by a key are ignored
+        fs::write(dir.join("notes.txt"), b"").unwrap();
+        fs::write(dir.join("0123.cso"), b"").unwrap();
+        fs::write(dir.join("0123456789abcdeg.cso"), b"").unwrap();
+        let loaded = PrecompiledShaders::load_dir(&dir).unwrap();
+        assert_eq!(loaded, precompiled);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failures_keep_unparsed_output() {
+        // nothing parses, e.g. a crash of the compiler
+        let diagnostics = failure_diagnostics("internal compiler error: access violation\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].message, "internal compiler error: access violation");
+
+        // only warnings parse, the whole output is kept as the error
+        let output = "a.hlsl(3,4): warning X3206: implicit truncation of vector type\nvalidation failed\n";
+        let diagnostics = failure_diagnostics(output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+        assert_eq!(diagnostics[1].message, output.trim());
+    }
+
+    #[test]
+    fn failures_with_errors_are_parsed() {
+        let diagnostics = failure_diagnostics("a.hlsl(3,4): error X3004: undeclared identifier 'foo'\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("X3004"));
+        assert!(failure_diagnostics(" \n").is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! errors and warnings reported by shader compilers.
+
+use std::fmt;
+
+/// severity of a compiler diagnostic
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    /// additional information attached to the previous diagnostic
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str(match *self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        })
+    }
+}
+
+/// an error or warning reported by a shader compiler
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// the source file, as named by the compiler
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub severity: Severity,
+    /// e.g. `X3004` for FXC, or the warning option `-Wconversion` for DXC
+    pub code: Option<String>,
+    pub message: String,
+    /// the `#include` directives leading to `file`, as `(file, line)`,
+    /// outermost first, from the `In file included from` lines of DXC
+    pub included_from: Vec<(String, u32)>,
+}
+
+impl Diagnostic {
+    /// parse the error and warning text of FXC or DXC, skipping lines
+    /// that don't open a diagnostic, e.g. the source excerpts of DXC.
+    ///
+    /// FXC reports `file(line,column-column): error X3004: message`, and
+    /// DXC reports `file:line:column: warning: message [-Wcode]`, preceded
+    /// by `In file included from file:line:` lines for included files.
+    pub fn parse_all(text: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut included_from = Vec::new();
+        for line in text.lines() {
+            if let Some(include) = parse_include(line) {
+                included_from.push(include);
+            } else if let Some(mut diagnostic) = Diagnostic::parse(line) {
+                diagnostic.included_from = ::std::mem::take(&mut included_from);
+                diagnostics.push(diagnostic);
+            }
+        }
+        diagnostics
+    }
+
+    /// parse a single line of compiler output, without its include stack
+    pub fn parse(line: &str) -> Option<Diagnostic> {
+        let line = line.trim_end();
+        // the severity opens the line, or follows the location and ": "
+        let candidates = Some(0).into_iter()
+            .chain(line.match_indices(": ").map(|(idx, _)| idx + 2));
+        for start in candidates {
+            let rest = &line[start..];
+            for &(keyword, severity) in SEVERITY_KEYWORDS {
+                if !rest.starts_with(keyword) { continue; }
+                let rest = &rest[keyword.len()..];
+                let (code, message) = if let Some(message) = rest.strip_prefix(':') {
+                    split_option(message.trim())
+                } else if let Some(rest) = rest.strip_prefix(' ') {
+                    match rest.find(": ") {
+                        Some(idx) if is_code(&rest[..idx]) =>
+                            (Some(rest[..idx].to_owned()), rest[idx + 2..].trim().to_owned()),
+                        _ => continue,
+                    }
+                } else {
+                    continue;
+                };
+                let location = if start == 0 { "" } else { &line[..start - 2] };
+                let (file, line, column) = parse_location(location.trim());
+                return Some(Diagnostic{
+                    file, line, column, severity, code, message,
+                    included_from: Vec::new(),
+                });
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if let Some(ref file) = self.file {
+            write!(f, "{}:", file)?;
+            if let Some(line) = self.line { write!(f, "{}:", line)?; }
+            if let Some(column) = self.column { write!(f, "{}:", column)?; }
+            f.write_str(" ")?;
+        }
+        write!(f, "{}", self.severity)?;
+        if let Some(ref code) = self.code { write!(f, " {}", code)?; }
+        write!(f, ": {}", self.message)
+    }
+}
+
+// "fatal error" goes before "error", which would match "fatal" as a location
+const SEVERITY_KEYWORDS: &[(&str, Severity)] = &[
+    ("fatal error", Severity::Error),
+    ("error", Severity::Error),
+    ("warning", Severity::Warning),
+    ("note", Severity::Note),
+];
+
+/// whether `s` is a FXC code, e.g. `X3004`
+fn is_code(s: &str) -> bool {
+    s.len() > 1 && s.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// split the trailing warning option of a DXC message, e.g. `[-Wconversion]`
+fn split_option(message: &str) -> (Option<String>, String) {
+    if message.ends_with(']') {
+        if let Some(idx) = message.rfind(" [-W") {
+            return (
+                Some(message[idx + 2..message.len() - 1].to_owned()),
+                message[..idx].to_owned()
+            );
+        }
+    }
+    (None, message.to_owned())
+}
+
+/// parse an `In file included from file:line:` line of DXC
+fn parse_include(line: &str) -> Option<(String, u32)> {
+    let location = line.trim_end().strip_prefix("In file included from ")?.strip_suffix(':')?;
+    match parse_location(location) {
+        (Some(file), Some(line), None) => Some((file, line)),
+        _ => None,
+    }
+}
+
+/// parse `file(line,column)` or `file(line,column-column)` of FXC, or
+/// `file:line:column` of DXC
+fn parse_location(location: &str) -> (Option<String>, Option<u32>, Option<u32>) {
+    if location.is_empty() { return (None, None, None); }
+    if location.ends_with(')') {
+        if let Some(open) = location.rfind('(') {
+            let mut numbers = location[open + 1..location.len() - 1].split(',');
+            let line = numbers.next().and_then(|line| line.trim().parse().ok());
+            let column = numbers.next()
+                .and_then(|column| column.split('-').next())
+                .and_then(|column| column.trim().parse().ok());
+            if line.is_some() {
+                return (Some(location[..open].to_owned()), line, column);
+            }
+        }
+    }
+    let mut fields = location.rsplitn(3, ':');
+    let last = fields.next().and_then(|field| field.parse::<u32>().ok());
+    let second = fields.next().map(|field| (field, field.parse::<u32>().ok()));
+    match (last, second, fields.next()) {
+        (Some(column), Some((_, Some(line))), Some(file)) =>
+            (Some(file.to_owned()), Some(line), Some(column)),
+        (Some(line), Some((file, None)), rest) => {
+            let file = match rest {
+                Some(rest) => format!("{}:{}", rest, file),
+                None => file.to_owned(),
+            };
+            (Some(file), Some(line), None)
+        },
+        _ => (Some(location.to_owned()), None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(
+        file: Option<&str>, line: Option<u32>, column: Option<u32>,
+        severity: Severity, code: Option<&str>, message: &str
+    ) -> Diagnostic {
+        Diagnostic{
+            file: file.map(ToOwned::to_owned), line, column, severity,
+            code: code.map(ToOwned::to_owned),
+            message: message.to_owned(),
+            included_from: Vec::new(),
+        }
+    }
+
+    fn included_from(mut diagnostic: Diagnostic, includes: &[(&str, u32)]) -> Diagnostic {
+        diagnostic.included_from = includes.iter()
+            .map(|&(file, line)| (file.to_owned(), line))
+            .collect();
+        diagnostic
+    }
+
+    #[test]
+    fn parse_fxc() {
+        assert_eq!(
+            Diagnostic::parse("shader.hlsl(12,5-18): error X3004: undeclared identifier 'foo'"),
+            Some(diagnostic(Some("shader.hlsl"), Some(12), Some(5), Severity::Error, Some("X3004"), "undeclared identifier 'foo'"))
+        );
+        assert_eq!(
+            Diagnostic::parse("shader.hlsl(3,10): warning X3206: implicit truncation of vector type"),
+            Some(diagnostic(Some("shader.hlsl"), Some(3), Some(10), Severity::Warning, Some("X3206"), "implicit truncation of vector type"))
+        );
+        // errors about the whole program have no location
+        assert_eq!(
+            Diagnostic::parse("error X3501: 'main': entrypoint not found"),
+            Some(diagnostic(None, None, None, Severity::Error, Some("X3501"), "'main': entrypoint not found"))
+        );
+    }
+
+    #[test]
+    fn parse_dxc() {
+        assert_eq!(
+            Diagnostic::parse("shader.hlsl:4:12: warning: implicit truncation of vector type [-Wconversion]"),
+            Some(diagnostic(Some("shader.hlsl"), Some(4), Some(12), Severity::Warning, Some("-Wconversion"), "implicit truncation of vector type"))
+        );
+        assert_eq!(
+            Diagnostic::parse("shader.hlsl:7:5: error: use of undeclared identifier 'foo'"),
+            Some(diagnostic(Some("shader.hlsl"), Some(7), Some(5), Severity::Error, None, "use of undeclared identifier 'foo'"))
+        );
+        assert_eq!(
+            Diagnostic::parse("shader.hlsl:2:8: note: previous definition is here"),
+            Some(diagnostic(Some("shader.hlsl"), Some(2), Some(8), Severity::Note, None, "previous definition is here"))
+        );
+    }
+
+    #[test]
+    fn parse_fatal_error() {
+        assert_eq!(
+            Diagnostic::parse("shader.hlsl:1:10: fatal error: 'common.hlsli' file not found"),
+            Some(diagnostic(Some("shader.hlsl"), Some(1), Some(10), Severity::Error, None, "'common.hlsli' file not found"))
+        );
+    }
+
+    #[test]
+    fn parse_drive_letter_paths() {
+        assert_eq!(
+            Diagnostic::parse(r"C:\src\shader.hlsl(12,5-18): error X3004: undeclared identifier 'foo'"),
+            Some(diagnostic(Some(r"C:\src\shader.hlsl"), Some(12), Some(5), Severity::Error, Some("X3004"), "undeclared identifier 'foo'"))
+        );
+        assert_eq!(
+            Diagnostic::parse(r"C:\src\shader.hlsl:4:12: error: use of undeclared identifier 'foo'"),
+            Some(diagnostic(Some(r"C:\src\shader.hlsl"), Some(4), Some(12), Severity::Error, None, "use of undeclared identifier 'foo'"))
+        );
+    }
+
+    #[test]
+    fn parse_skips_source_excerpts() {
+        assert_eq!(Diagnostic::parse("    float4 color = foo;"), None);
+        assert_eq!(Diagnostic::parse("                   ^"), None);
+        assert_eq!(Diagnostic::parse("    // error: not a diagnostic"), None);
+        assert_eq!(Diagnostic::parse(""), None);
+
+        let output = "\
+shader.hlsl:7:20: error: use of undeclared identifier 'foo'
+    float4 color = foo;
+                   ^
+shader.hlsl:9:12: warning: implicit truncation of vector type [-Wconversion]
+    return color;
+           ^
+1 error generated.
+";
+        assert_eq!(Diagnostic::parse_all(output), vec![
+            diagnostic(Some("shader.hlsl"), Some(7), Some(20), Severity::Error, None, "use of undeclared identifier 'foo'"),
+            diagnostic(Some("shader.hlsl"), Some(9), Some(12), Severity::Warning, Some("-Wconversion"), "implicit truncation of vector type"),
+        ]);
+    }
+
+    // the samples below are written out in the layout of FXC 10.1 and DXC
+    // 1.7 output, down to the blank line and summary FXC ends with, the
+    // caret lines of DXC and its notes and include stacks
+
+    #[test]
+    fn parse_all_fxc() {
+        let output = r"C:\shaders\lighting.hlsl(14,19-21): error X3004: undeclared identifier 'nrm'
+C:\shaders\lighting.hlsl(21,12-23): error X3013: 'shade': no matching 1 parameter function
+C:\shaders\lighting.hlsl(21,12-23): error X3013: Possible functions are:
+C:\shaders\lighting.hlsl(21,12-23): error X3013:     float4 shade(Light, float3)
+C:\shaders\common.hlsli(8,12-40): warning X3571: pow(f, e) will not work for negative f, use abs(f) or conditionally handle negative values if you expect them
+C:\shaders\lighting.hlsl(26,12-16): warning X3206: implicit truncation of vector type
+
+compilation failed; no code produced
+";
+        let file = Some(r"C:\shaders\lighting.hlsl");
+        assert_eq!(Diagnostic::parse_all(output), vec![
+            diagnostic(file, Some(14), Some(19), Severity::Error, Some("X3004"), "undeclared identifier 'nrm'"),
+            diagnostic(file, Some(21), Some(12), Severity::Error, Some("X3013"), "'shade': no matching 1 parameter function"),
+            diagnostic(file, Some(21), Some(12), Severity::Error, Some("X3013"), "Possible functions are:"),
+            diagnostic(file, Some(21), Some(12), Severity::Error, Some("X3013"), "float4 shade(Light, float3)"),
+            diagnostic(
+                Some(r"C:\shaders\common.hlsli"), Some(8), Some(12), Severity::Warning, Some("X3571"),
+                "pow(f, e) will not work for negative f, use abs(f) or conditionally handle negative values if you expect them"
+            ),
+            diagnostic(file, Some(26), Some(12), Severity::Warning, Some("X3206"), "implicit truncation of vector type"),
+        ]);
+
+        let output = "error X3501: 'main': entrypoint not found\n\ncompilation failed; no code produced\n";
+        assert_eq!(Diagnostic::parse_all(output), vec![
+            diagnostic(None, None, None, Severity::Error, Some("X3501"), "'main': entrypoint not found"),
+        ]);
+    }
+
+    #[test]
+    fn parse_all_dxc() {
+        let output = "\
+In file included from lighting.hlsl:2:
+./common.hlsli:6:1: error: unknown type name 'flaot3'
+flaot3 ambient;
+^
+lighting.hlsl:14:19: error: use of undeclared identifier 'nrm'
+    float d = dot(nrm, light.dir);
+                  ^
+lighting.hlsl:21:12: error: no matching function for call to 'shade'
+    return shade(light);
+           ^~~~~
+lighting.hlsl:9:8: note: candidate function not viable: requires 2 arguments, but 1 was provided
+float4 shade(Light light, float3 normal)
+       ^
+lighting.hlsl:26:12: warning: implicit truncation of vector type [-Wconversion]
+    return color;
+           ^
+1 warning and 3 errors generated.
+";
+        let file = Some("lighting.hlsl");
+        assert_eq!(Diagnostic::parse_all(output), vec![
+            included_from(
+                diagnostic(Some("./common.hlsli"), Some(6), Some(1), Severity::Error, None, "unknown type name 'flaot3'"),
+                &[("lighting.hlsl", 2)]
+            ),
+            diagnostic(file, Some(14), Some(19), Severity::Error, None, "use of undeclared identifier 'nrm'"),
+            diagnostic(file, Some(21), Some(12), Severity::Error, None, "no matching function for call to 'shade'"),
+            diagnostic(file, Some(9), Some(8), Severity::Note, None, "candidate function not viable: requires 2 arguments, but 1 was provided"),
+            diagnostic(file, Some(26), Some(12), Severity::Warning, Some("-Wconversion"), "implicit truncation of vector type"),
+        ]);
+    }
+
+    #[test]
+    fn parse_all_nested_includes() {
+        let output = "\
+In file included from main.hlsl:1:
+In file included from ./lighting.hlsli:3:
+./brdf.hlsli:12:10: error: use of undeclared identifier 'PI'
+  return PI * roughness;
+         ^
+main.hlsl:8:28: error: use of undeclared identifier 'uv'
+    return tex.Sample(smp, uv);
+                           ^
+2 errors generated.
+";
+        assert_eq!(Diagnostic::parse_all(output), vec![
+            included_from(
+                diagnostic(Some("./brdf.hlsli"), Some(12), Some(10), Severity::Error, None, "use of undeclared identifier 'PI'"),
+                &[("main.hlsl", 1), ("./lighting.hlsli", 3)]
+            ),
+            diagnostic(Some("main.hlsl"), Some(8), Some(28), Severity::Error, None, "use of undeclared identifier 'uv'"),
+        ]);
+    }
+
+    #[test]
+    fn includes() {
+        assert_eq!(parse_include("In file included from main.hlsl:1:"), Some(("main.hlsl".to_owned(), 1)));
+        assert_eq!(parse_include(r"In file included from C:\src\main.hlsl:12:"), Some((r"C:\src\main.hlsl".to_owned(), 12)));
+        assert_eq!(parse_include("In file included from main.hlsl"), None);
+        assert_eq!(parse_include("main.hlsl:1:1: error: x"), None);
+    }
+
+    #[test]
+    fn locations() {
+        assert_eq!(parse_location(""), (None, None, None));
+        assert_eq!(parse_location("a.hlsl(3,4)"), (Some("a.hlsl".to_owned()), Some(3), Some(4)));
+        assert_eq!(parse_location("a.hlsl(3,4-9)"), (Some("a.hlsl".to_owned()), Some(3), Some(4)));
+        assert_eq!(parse_location("a.hlsl(3)"), (Some("a.hlsl".to_owned()), Some(3), None));
+        assert_eq!(parse_location("a.hlsl:3:4"), (Some("a.hlsl".to_owned()), Some(3), Some(4)));
+        assert_eq!(parse_location("a.hlsl:3"), (Some("a.hlsl".to_owned()), Some(3), None));
+        assert_eq!(parse_location(r"D:\a.hlsl:3"), (Some(r"D:\a.hlsl".to_owned()), Some(3), None));
+        assert_eq!(parse_location(r"D:\a.hlsl(3,4)"), (Some(r"D:\a.hlsl".to_owned()), Some(3), Some(4)));
+        assert_eq!(parse_location("<stdin>"), (Some("<stdin>".to_owned()), None, None));
+    }
+
+    #[test]
+    fn display() {
+        let error = diagnostic(Some("a.hlsl"), Some(3), Some(4), Severity::Error, Some("X3004"), "undeclared identifier 'foo'");
+        assert_eq!(error.to_string(), "a.hlsl:3:4: error X3004: undeclared identifier 'foo'");
+        let warning = diagnostic(None, None, None, Severity::Warning, None, "unused variable");
+        assert_eq!(warning.to_string(), "warning: unused variable");
+    }
+}
@@ -0,0 +1,459 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! HLSL declarations generated from rust types.
+//!
+//! `HlslStruct` is implemented by `#[derive(HlslStruct)]`, and by
+//! `#[derive(Vertex)]` with `#[vertex(hlsl)]`, available with the `derive`
+//! feature. an `HlslModule`
+//! collects structs, constant buffers and structured buffers into HLSL
+//! source, e.g. for a build script to write into a shared `.hlsli`.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::Write as FmtWrite;
+use std::path::Path;
+use std::{fs, io};
+use format::Bool;
+
+#[cfg(feature = "derive")]
+pub use redirect_derive::HlslStruct;
+
+/// a type with an HLSL counterpart
+pub trait HlslType {
+    /// the HLSL type name, e.g. `float3`
+    fn hlsl_type() -> Cow<'static, str>;
+
+    /// the array length, if the type is an array
+    #[inline]
+    fn hlsl_array_len() -> Option<usize> { None }
+
+    /// declare the structs this type uses in `module`
+    #[inline]
+    fn declare_dependencies(_module: &mut HlslModule) { }
+}
+
+/// a struct with an HLSL counterpart
+pub trait HlslStruct {
+    /// name of the HLSL struct
+    fn hlsl_name() -> Cow<'static, str>;
+
+    /// fields of the HLSL struct, in declaration order
+    fn hlsl_fields() -> Vec<HlslField>;
+
+    /// declare the structs the fields use in `module`
+    #[inline]
+    fn declare_dependencies(_module: &mut HlslModule) { }
+}
+
+impl<T: HlslStruct> HlslType for T {
+    #[inline]
+    fn hlsl_type() -> Cow<'static, str> { T::hlsl_name() }
+
+    #[inline]
+    fn declare_dependencies(module: &mut HlslModule) {
+        module.add_struct::<T>();
+    }
+}
+
+impl<T: HlslStruct, const N: usize> HlslType for [T; N] {
+    #[inline]
+    fn hlsl_type() -> Cow<'static, str> { T::hlsl_name() }
+
+    #[inline]
+    fn hlsl_array_len() -> Option<usize> { Some(N) }
+
+    #[inline]
+    fn declare_dependencies(module: &mut HlslModule) {
+        module.add_struct::<T>();
+    }
+}
+
+macro_rules! impl_hlsl_type_scalar {
+    ($($Scalar: ty, $name: expr;)*) => {$(
+        impl HlslType for $Scalar {
+            #[inline]
+            fn hlsl_type() -> Cow<'static, str> { Cow::Borrowed($name) }
+        }
+
+        impl HlslType for [$Scalar; 1] {
+            #[inline]
+            fn hlsl_type() -> Cow<'static, str> { Cow::Borrowed(concat!($name, "1")) }
+        }
+
+        impl HlslType for [$Scalar; 2] {
+            #[inline]
+            fn hlsl_type() -> Cow<'static, str> { Cow::Borrowed(concat!($name, "2")) }
+        }
+
+        impl HlslType for [$Scalar; 3] {
+            #[inline]
+            fn hlsl_type() -> Cow<'static, str> { Cow::Borrowed(concat!($name, "3")) }
+        }
+
+        impl HlslType for [$Scalar; 4] {
+            #[inline]
+            fn hlsl_type() -> Cow<'static, str> { Cow::Borrowed(concat!($name, "4")) }
+        }
+
+        /// arrays of vectors
+        impl<const N: usize, const C: usize> HlslType for [[$Scalar; C]; N]
+        where [$Scalar; C]: HlslType {
+            #[inline]
+            fn hlsl_type() -> Cow<'static, str> { <[$Scalar; C]>::hlsl_type() }
+
+            #[inline]
+            fn hlsl_array_len() -> Option<usize> { Some(N) }
+        }
+    )*}
+}
+
+impl_hlsl_type_scalar!{
+    f32, "float";
+    u32, "uint";
+    i32, "int";
+    Bool, "bool";
+}
+
+/// a field of an HLSL struct or constant buffer
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HlslField {
+    /// the field name
+    pub name: Cow<'static, str>,
+    /// the type name, including modifiers such as `row_major`
+    pub ty: Cow<'static, str>,
+    /// the array length, if the field is an array
+    pub array_len: Option<usize>,
+    /// the semantic, including its index
+    pub semantic: Option<Cow<'static, str>>,
+}
+
+impl HlslField {
+    /// a field of type `ty`
+    #[inline]
+    pub fn new<N, T>(name: N, ty: T) -> Self
+        where N: Into<Cow<'static, str>>, T: Into<Cow<'static, str>>,
+    {
+        HlslField{
+            name: name.into(), ty: ty.into(), array_len: None, semantic: None,
+        }
+    }
+
+    /// a field of the HLSL counterpart of `T`
+    #[inline]
+    pub fn of<T: HlslType, N: Into<Cow<'static, str>>>(name: N) -> Self {
+        HlslField{
+            name: name.into(), ty: T::hlsl_type(),
+            array_len: T::hlsl_array_len(), semantic: None,
+        }
+    }
+
+    /// set the semantic of the field
+    #[inline]
+    pub fn with_semantic<S: Into<Cow<'static, str>>>(mut self, semantic: S) -> Self {
+        self.semantic = Some(semantic.into());
+        self
+    }
+
+    /// turn an array of vectors into a matrix. with `row_major`, each vector
+    /// is a row, otherwise a column. `None` if the field isn't an array of 1
+    /// to 4 vectors of 1 to 4 components.
+    pub fn into_matrix(mut self, row_major: bool) -> Option<Self> {
+        let len = self.array_len?;
+        let (scalar, width) = match self.ty.as_bytes().split_last() {
+            Some((&width, scalar)) if width.is_ascii_digit() && !scalar.is_empty() &&
+                scalar.iter().all(u8::is_ascii_alphabetic) =>
+                (self.ty[..scalar.len()].to_owned(), (width - b'0') as usize),
+            _ => return None,
+        };
+        if !(1..=4).contains(&len) || !(1..=4).contains(&width) {
+            return None;
+        }
+        self.ty = if row_major {
+            format!("row_major {}{}x{}", scalar, len, width)
+        } else {
+            format!("column_major {}{}x{}", scalar, width, len)
+        }.into();
+        self.array_len = None;
+        Some(self)
+    }
+}
+
+/// HLSL source built from rust types, declaring each struct once, after
+/// the structs it depends on
+#[derive(Clone, Debug, Default)]
+pub struct HlslModule {
+    source: String,
+    declared: HashSet<String>,
+}
+
+impl HlslModule {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// declare `struct T`, unless already declared
+    pub fn add_struct<T: HlslStruct>(&mut self) -> &mut Self {
+        let name = T::hlsl_name();
+        if !self.declared.insert(name.to_string()) { return self; }
+        T::declare_dependencies(self);
+
+        let _ = write!(self.source, "struct {}\n{{\n", name);
+        self.write_fields(&T::hlsl_fields());
+        self.source.push_str("};\n\n");
+        self
+    }
+
+    /// declare a `cbuffer` with the fields of `T` at register `b<register>`
+    pub fn add_cbuffer<T: HlslStruct>(&mut self, name: &str, register: u32, space: u32) -> &mut Self {
+        T::declare_dependencies(self);
+
+        let _ = write!(
+            self.source, "cbuffer {} : register(b{}, space{})\n{{\n",
+            name, register, space
+        );
+        self.write_fields(&T::hlsl_fields());
+        self.source.push_str("}\n\n");
+        self
+    }
+
+    /// declare a `StructuredBuffer<T>` at register `t<register>`
+    #[inline]
+    pub fn add_structured_buffer<T: HlslStruct>(&mut self, name: &str, register: u32, space: u32) -> &mut Self {
+        self.add_buffer::<T>("StructuredBuffer", name, 't', register, space)
+    }
+
+    /// declare a `RWStructuredBuffer<T>` at register `u<register>`
+    #[inline]
+    pub fn add_rw_structured_buffer<T: HlslStruct>(&mut self, name: &str, register: u32, space: u32) -> &mut Self {
+        self.add_buffer::<T>("RWStructuredBuffer", name, 'u', register, space)
+    }
+
+    /// append `source` verbatim
+    #[inline]
+    pub fn add_source(&mut self, source: &str) -> &mut Self {
+        self.source.push_str(source);
+        self
+    }
+
+    /// the generated HLSL source
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// write the source to `path` if its content differs, returning whether
+    /// it was written. keeps the modification time intact otherwise, so
+    /// build scripts don't trigger needless rebuilds.
+    pub fn write_if_changed<P: AsRef<Path>>(&self, path: P) -> io::Result<bool> {
+        let path = path.as_ref();
+        match fs::read(path) {
+            Ok(ref old) if old.as_slice() == self.source.as_bytes() => return Ok(false),
+            Ok(_) => (),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err),
+        }
+        fs::write(path, &self.source)?;
+        Ok(true)
+    }
+
+    fn add_buffer<T: HlslStruct>(
+        &mut self, buffer: &str, name: &str, class: char, register: u32, space: u32
+    ) -> &mut Self {
+        self.add_struct::<T>();
+        let _ = write!(
+            self.source, "{}<{}> {} : register({}{}, space{});\n\n",
+            buffer, T::hlsl_name(), name, class, register, space
+        );
+        self
+    }
+
+    fn write_fields(&mut self, fields: &[HlslField]) {
+        for field in fields {
+            let _ = write!(self.source, "    {} {}", field.ty, field.name);
+            if let Some(len) = field.array_len {
+                let _ = write!(self.source, "[{}]", len);
+            }
+            if let Some(ref semantic) = field.semantic {
+                let _ = write!(self.source, " : {}", semantic);
+            }
+            self.source.push_str(";\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Light;
+
+    impl HlslStruct for Light {
+        fn hlsl_name() -> Cow<'static, str> { "Light".into() }
+
+        fn hlsl_fields() -> Vec<HlslField> {
+            vec![
+                HlslField::of::<[f32; 3], _>("position"),
+                HlslField::of::<f32, _>("range"),
+                HlslField::of::<[[f32; 4]; 2], _>("colors"),
+            ]
+        }
+    }
+
+    struct Scene;
+
+    impl HlslStruct for Scene {
+        fn hlsl_name() -> Cow<'static, str> { "Scene".into() }
+
+        fn hlsl_fields() -> Vec<HlslField> {
+            vec![
+                HlslField::of::<[[f32; 4]; 4], _>("view").into_matrix(true).unwrap(),
+                HlslField::of::<[[f32; 4]; 3], _>("normal").into_matrix(false).unwrap(),
+                HlslField::of::<[Light; 4], _>("lights"),
+                HlslField::of::<Light, _>("sun"),
+                HlslField::of::<u32, _>("light_count"),
+                HlslField::of::<Bool, _>("shadows"),
+            ]
+        }
+
+        fn declare_dependencies(module: &mut HlslModule) {
+            <[Light; 4] as HlslType>::declare_dependencies(module);
+            <Light as HlslType>::declare_dependencies(module);
+        }
+    }
+
+    struct Input;
+
+    impl HlslStruct for Input {
+        fn hlsl_name() -> Cow<'static, str> { "Input".into() }
+
+        fn hlsl_fields() -> Vec<HlslField> {
+            vec![
+                HlslField::of::<[f32; 3], _>("position").with_semantic("POSITION"),
+                HlslField::new("uv", "float2").with_semantic("TEXCOORD1"),
+                HlslField::of::<[i32; 1], _>("id"),
+            ]
+        }
+    }
+
+    #[test]
+    fn structs() {
+        let mut module = HlslModule::new();
+        module.add_struct::<Input>();
+        assert_eq!(module.as_str(), "\
+struct Input
+{
+    float3 position : POSITION;
+    float2 uv : TEXCOORD1;
+    int1 id;
+};
+
+");
+    }
+
+    #[test]
+    fn dependencies_come_first_and_once() {
+        let mut module = HlslModule::new();
+        module.add_struct::<Scene>().add_struct::<Light>().add_struct::<Scene>();
+        assert_eq!(module.as_str(), "\
+struct Light
+{
+    float3 position;
+    float range;
+    float4 colors[2];
+};
+
+struct Scene
+{
+    row_major float4x4 view;
+    column_major float4x3 normal;
+    Light lights[4];
+    Light sun;
+    uint light_count;
+    bool shadows;
+};
+
+");
+    }
+
+    #[test]
+    fn buffers() {
+        let mut module = HlslModule::new();
+        module.add_cbuffer::<Scene>("scene", 0, 1)
+            .add_structured_buffer::<Light>("lights", 2, 0)
+            .add_rw_structured_buffer::<Input>("inputs", 1, 3)
+            .add_source("// end\n");
+        assert_eq!(module.as_str(), "\
+struct Light
+{
+    float3 position;
+    float range;
+    float4 colors[2];
+};
+
+cbuffer scene : register(b0, space1)
+{
+    row_major float4x4 view;
+    column_major float4x3 normal;
+    Light lights[4];
+    Light sun;
+    uint light_count;
+    bool shadows;
+}
+
+StructuredBuffer<Light> lights : register(t2, space0);
+
+struct Input
+{
+    float3 position : POSITION;
+    float2 uv : TEXCOORD1;
+    int1 id;
+};
+
+RWStructuredBuffer<Input> inputs : register(u1, space3);
+
+// end
+");
+    }
+
+    #[test]
+    fn matrices() {
+        let matrix = |field: HlslField, row_major| field.into_matrix(row_major).map(|field| field.ty);
+        assert_eq!(matrix(HlslField::of::<[[f32; 3]; 2], _>("m"), true), Some("row_major float2x3".into()));
+        assert_eq!(matrix(HlslField::of::<[[f32; 3]; 2], _>("m"), false), Some("column_major float3x2".into()));
+        assert_eq!(matrix(HlslField::of::<[[u32; 1]; 4], _>("m"), true), Some("row_major uint4x1".into()));
+        // not an array, or not of vectors
+        assert_eq!(matrix(HlslField::of::<[f32; 4], _>("m"), true), None);
+        assert_eq!(matrix(HlslField::of::<[Light; 4], _>("m"), true), None);
+        assert_eq!(matrix(HlslField::new("m", ""), true), None);
+        let mut field = HlslField::new("m", "float4");
+        field.array_len = Some(5);
+        assert_eq!(matrix(field, true), None);
+        let mut field = HlslField::new("m", "float44");
+        field.array_len = Some(4);
+        assert_eq!(matrix(field, true), None);
+    }
+
+    #[test]
+    fn write_if_changed() {
+        let dir = ::std::env::temp_dir().join(format!("redirect-hlsl-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("shared.hlsli");
+        let mut module = HlslModule::new();
+        module.add_struct::<Input>();
+
+        assert!(module.write_if_changed(&path).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), module.as_str());
+        assert!(!module.write_if_changed(&path).unwrap());
+        module.add_struct::<Light>();
+        assert!(module.write_if_changed(&path).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), module.as_str());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
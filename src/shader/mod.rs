@@ -8,118 +8,283 @@
 
 //! executable code snippet for GPU
 
-use comptr::ComPtr;
-use winapi::ID3DBlob;
 use std::os::raw::c_char;
 use std::ffi::CStr;
+use std::hash::Hasher;
 use std::marker::PhantomData;
-use error::WinError;
+use std::path::Path;
+use std::sync::Arc;
 use smallvec::SmallVec;
+use dxbc::{DxbcContainer, DxbcError, ShaderStage, ShaderVersion};
+use std::{error, fmt, fs, io};
+
+pub mod hlsl;
+pub use self::hlsl::*;
+
+mod compiler;
+pub use self::compiler::*;
+
+mod diagnostic;
+pub use self::diagnostic::*;
+
+/// storage of a shader byte code
+#[derive(Debug, Clone)]
+enum Code {
+    /// bytes returned by a compiler or loaded from a file
+    Shared(Arc<[u8]>),
+    /// bytes embedded into the executable, e.g. with `include_bytes!`
+    Static(&'static [u8]),
+}
+
+impl Code {
+    #[inline]
+    fn as_bytes(&self) -> &[u8] {
+        match *self {
+            Code::Shared(ref bytes) => bytes,
+            Code::Static(bytes) => bytes,
+        }
+    }
+}
 
 /// a compiled piece of VS shader byte code
 #[derive(Debug, Clone)]
 pub struct VsShaderBytecode {
-    pub(crate) ptr: ComPtr<ID3DBlob>,
+    code: Code,
 }
 
 /// a compiled piece of PS shader byte code
 #[derive(Debug, Clone)]
 pub struct PsShaderBytecode {
-    pub(crate) ptr: ComPtr<ID3DBlob>,
+    code: Code,
 }
 
 /// a compiled piece of CS shader byte code
 #[derive(Debug, Clone)]
 pub struct CsShaderBytecode {
-    pub(crate) ptr: ComPtr<ID3DBlob>,
+    code: Code,
 }
 
 /// a compiled piece of HS shader byte code
 #[derive(Debug, Clone)]
 pub struct HsShaderBytecode {
-    pub(crate) ptr: ComPtr<ID3DBlob>,
+    code: Code,
 }
 
 /// a compiled piece of GS shader byte code
 #[derive(Debug, Clone)]
 pub struct GsShaderBytecode {
-    pub(crate) ptr: ComPtr<ID3DBlob>,
+    code: Code,
 }
 
 /// a compiled piece of DS shader byte code
 #[derive(Debug, Clone)]
 pub struct DsShaderBytecode {
-    pub(crate) ptr: ComPtr<ID3DBlob>,
+    code: Code,
+}
+
+/// a compiled piece of AS shader byte code. amplification shaders require
+/// shader model 6.5, and are compiled with `Dxc`
+#[derive(Debug, Clone)]
+pub struct AsShaderBytecode {
+    code: Code,
+}
+
+/// a compiled piece of MS shader byte code. mesh shaders require
+/// shader model 6.5, and are compiled with `Dxc`
+#[derive(Debug, Clone)]
+pub struct MsShaderBytecode {
+    code: Code,
+}
+
+/// an error loading a precompiled shader byte code
+#[derive(Debug)]
+pub enum ShaderBytecodeError {
+    /// failed to read the file
+    Io(io::Error),
+    /// the bytes are not a valid DXBC or DXIL container
+    Container(DxbcError),
+    /// the checksum doesn't match the content, which the runtime rejects
+    InvalidHash,
+    /// the program is of another stage than the byte code type
+    StageMismatch{expected: ShaderStage, found: ShaderStage},
+}
+
+impl fmt::Display for ShaderBytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            ShaderBytecodeError::Io(ref err) => write!(f, "io error: {}", err),
+            ShaderBytecodeError::Container(ref err) => write!(f, "{}", err),
+            ShaderBytecodeError::InvalidHash =>
+                write!(f, "the container checksum doesn't match its content"),
+            ShaderBytecodeError::StageMismatch{expected, found} =>
+                write!(f, "expected a {} program, found a {} program", expected.name(), found.name()),
+        }
+    }
+}
+
+impl error::Error for ShaderBytecodeError {}
+
+impl From<DxbcError> for ShaderBytecodeError {
+    #[inline]
+    fn from(err: DxbcError) -> Self {
+        ShaderBytecodeError::Container(err)
+    }
+}
+
+impl From<io::Error> for ShaderBytecodeError {
+    #[inline]
+    fn from(err: io::Error) -> Self {
+        ShaderBytecodeError::Io(err)
+    }
+}
+
+/// check that `bytes` is a container the runtime accepts, holding a
+/// program of `expected` stage
+fn check_container(bytes: &[u8], expected: ShaderStage) -> Result<(), ShaderBytecodeError> {
+    let container = DxbcContainer::from_bytes(bytes)?;
+    let found = container.shader_version()?.stage;
+    if found != expected {
+        return Err(ShaderBytecodeError::StageMismatch{expected, found});
+    }
+    // DXIL programs not signed by the validator carry a zero checksum,
+    // which the runtime accepts with experimental shader models enabled
+    let unsigned = container.is_dxil() && container.hash() == [0; 16];
+    if !unsigned && !container.has_valid_hash() {
+        return Err(ShaderBytecodeError::InvalidHash);
+    }
+    Ok(())
 }
 
 macro_rules! impl_shader_bytecode {
-    ($Shader: ty) => {
+    ($Shader: ident, $Stage: ident) => {
         impl $Shader {
+            /// copy `bytes`, a precompiled DXBC or DXIL container, e.g. the
+            /// content of a `.cso` file, into a new byte code, checking the
+            /// container and that the program is of the right stage.
+            /// doesn't need the runtime compiler.
+            pub fn from_bytes(bytes: &[u8]) -> Result<$Shader, ShaderBytecodeError> {
+                check_container(bytes, ShaderStage::$Stage)?;
+                Ok($Shader{code: Code::Shared(bytes.into())})
+            }
+
+            /// wrap `bytes` embedded into the executable without copying,
+            /// e.g. `include_bytes!("shader.cso")`, checked as `from_bytes` does
+            pub fn from_static(bytes: &'static [u8]) -> Result<$Shader, ShaderBytecodeError> {
+                check_container(bytes, ShaderStage::$Stage)?;
+                Ok($Shader{code: Code::Static(bytes)})
+            }
+
+            /// load a precompiled byte code from a file, e.g. a `.cso`
+            /// file, checked as `from_bytes` does
+            pub fn from_file<P: AsRef<Path>>(path: P) -> Result<$Shader, ShaderBytecodeError> {
+                let bytes = fs::read(path)?;
+                check_container(&bytes, ShaderStage::$Stage)?;
+                Ok($Shader{code: Code::Shared(bytes.into())})
+            }
+
+            #[cfg(windows)]
             #[inline]
             pub fn to_shader_bytecode(&mut self) -> ::winapi::D3D12_SHADER_BYTECODE {
-                let mut ret: ::winapi::D3D12_SHADER_BYTECODE = unsafe {
-                    ::std::mem::uninitialized()
-                };
-                ret.pShaderBytecode = unsafe {
-                    self.ptr.GetBufferPointer() as *const _
-                };
-                ret.BytecodeLength = unsafe {
-                    self.ptr.GetBufferSize()
-                };
-                ret
+                let bytes = self.as_bytes();
+                ::winapi::D3D12_SHADER_BYTECODE{
+                    pShaderBytecode: bytes.as_ptr() as *const _,
+                    BytecodeLength: bytes.len() as _,
+                }
+            }
+
+            /// get the compiled byte code
+            #[inline]
+            pub fn as_bytes(&self) -> &[u8] {
+                self.code.as_bytes()
+            }
+
+            /// parse the DXBC container of the byte code for reflection
+            #[inline]
+            pub fn container(&self) -> Result<DxbcContainer<'_>, DxbcError> {
+                DxbcContainer::from_bytes(self.as_bytes())
+            }
+
+            /// stage and shader model of the program
+            #[inline]
+            pub fn shader_version(&self) -> Result<ShaderVersion, DxbcError> {
+                self.container()?.shader_version()
+            }
+        }
+
+        // byte codes are compared by content
+        impl PartialEq for $Shader {
+            #[inline]
+            fn eq(&self, other: &$Shader) -> bool {
+                self.as_bytes() == other.as_bytes()
+            }
+        }
+
+        impl Eq for $Shader {}
+
+        impl ::std::hash::Hash for $Shader {
+            #[inline]
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                self.as_bytes().hash(state)
+            }
+        }
+
+        impl ::fnv::StableHash for $Shader {
+            #[inline]
+            fn stable_hash(&self, hasher: &mut ::fnv::FnvHasher) {
+                hasher.write_bytes(self.as_bytes())
             }
         }
     }
 }
 
-impl_shader_bytecode!(VsShaderBytecode);
-impl_shader_bytecode!(PsShaderBytecode);
-impl_shader_bytecode!(DsShaderBytecode);
-impl_shader_bytecode!(CsShaderBytecode);
-impl_shader_bytecode!(HsShaderBytecode);
-impl_shader_bytecode!(GsShaderBytecode);
+impl_shader_bytecode!(VsShaderBytecode, VERTEX);
+impl_shader_bytecode!(PsShaderBytecode, PIXEL);
+impl_shader_bytecode!(DsShaderBytecode, DOMAIN);
+impl_shader_bytecode!(CsShaderBytecode, COMPUTE);
+impl_shader_bytecode!(HsShaderBytecode, HULL);
+impl_shader_bytecode!(GsShaderBytecode, GEOMETRY);
+impl_shader_bytecode!(AsShaderBytecode, AMPLIFICATION);
+impl_shader_bytecode!(MsShaderBytecode, MESH);
 
 /// shader builder
 #[derive(Debug)]
 pub struct ShaderBuilder<'a> {
     pub src_data: &'a [u8],
-    // TODO: source names for error info?
+    /// name of the source, used in error messages and to resolve relative
+    /// `#include`s, which are otherwise resolved against the working directory
+    pub source_name: Option<&'a CStr>,
     pub shader_macros: SmallVec<[ShaderMacro<'a>; 8]>,
     // TODO: custom include handlers?
     pub entry_point: &'a CStr,
-    // TODO: custom shader levels?
+    /// shader model of the compile target, 5.1 by default
+    pub shader_model: ShaderModel,
     pub flags: ShaderCompileFlags,
+    /// fail with `ShaderCompileError::Warnings` if the compiler reports
+    /// warnings. unlike `WARNINGS_ARE_ERRORS`, the compiler isn't told, so
+    /// the byte code and the permutation key stay the same
+    pub fail_on_warnings: bool,
     // TODO: effect flags?
     // TODO: secondary data?
 }
 
 macro_rules! impl_build {
-    ($func: ident, $Ret: tt, $Target: tt) => {
+    ($build_with: ident, $Ret: ident, $Stage: ident) => {
+    /// compile with `compiler` for `self.shader_model`
+    pub fn $build_with<C: ShaderCompiler + ?Sized>(&self, compiler: &C) -> Result<$Ret, ShaderCompileError> {
+        let output = self.compile_with(compiler, ShaderStage::$Stage)?;
+        check_container(&output.bytes, ShaderStage::$Stage)?;
+        Ok($Ret{code: Code::Shared(output.bytes.into())})
+    }};
+    ($build: ident, $build_with: ident, $Ret: ident, $Stage: ident) => {
+    /// compile with `Fxc` for `self.shader_model`
+    #[cfg(windows)]
     #[inline]
-    pub fn $func(&mut self) -> Result<$Ret, WinError> {
-        self.shader_macros.push(Default::default());
-        unsafe {
-            let mut ret = ::std::mem::uninitialized();
-            let hr = ::d3dcompiler::D3DCompile(
-                self.src_data.as_ptr() as *const _ as *const _,
-                self.src_data.len() as _,
-                ::std::ptr::null(),
-                self.shader_macros.as_ptr() as *const _,
-                ::winapi::D3D_COMPILE_STANDARD_FILE_INCLUDE,
-                self.entry_point.as_ptr() as _,
-                $Target.as_ptr() as *const _,
-                ::std::mem::transmute(self.flags),
-                0,
-                &mut ret,
-                ::std::ptr::null_mut()
-            );
-
-            self.shader_macros.pop();
-            WinError::from_hresult_or_ok(hr, || $Ret{
-                ptr: ComPtr::new(ret)
-            })
-        }
-    }}
+    pub fn $build(&self) -> Result<$Ret, ShaderCompileError> {
+        self.$build_with(&Fxc)
+    }
+
+    impl_build!($build_with, $Ret, $Stage);
+    };
 }
 
 impl<'a> ShaderBuilder<'a> {
@@ -129,17 +294,56 @@ impl<'a> ShaderBuilder<'a> {
     ) -> Self {
         ShaderBuilder{
             src_data, entry_point,
+            source_name: None,
             shader_macros: Default::default(),
+            shader_model: Default::default(),
             flags: Default::default(),
+            fail_on_warnings: false,
+        }
+    }
+
+    /// compile a `stage` program with `compiler` for `self.shader_model`,
+    /// keeping the warnings reported along with the container
+    pub fn compile_with<C: ShaderCompiler + ?Sized>(
+        &self, compiler: &C, stage: ShaderStage
+    ) -> Result<CompileOutput, ShaderCompileError> {
+        let output = compiler.compile(self, self.shader_model.target(stage))?;
+        if self.fail_on_warnings && output.has_warnings() {
+            return Err(ShaderCompileError::Warnings(output.diagnostics));
+        }
+        Ok(output)
+    }
+
+    /// a stable key of the permutation compiled for `target`, hashing the
+    /// source name, entry point, macros and flags but not the source, so
+    /// that `PrecompiledShaders` finds byte codes without compiling
+    pub fn permutation_key(&self, target: ShaderVersion) -> u64 {
+        let mut hasher = ::fnv::FnvHasher::default();
+        hasher.write(self.source_name.map_or(&[0][..], |name| name.to_bytes_with_nul()));
+        hasher.write(self.entry_point.to_bytes_with_nul());
+        hasher.write(target.to_string().as_bytes());
+        for shader_macro in &self.shader_macros {
+            if shader_macro.name.is_null() { continue; }
+            unsafe {
+                hasher.write(CStr::from_ptr(shader_macro.name).to_bytes_with_nul());
+                if !shader_macro.definition.is_null() {
+                    hasher.write(CStr::from_ptr(shader_macro.definition).to_bytes());
+                }
+                hasher.write(&[0]);
+            }
         }
+        hasher.write(&self.flags.bits().to_le_bytes());
+        hasher.finish()
     }
 
-    impl_build!(build_vs, VsShaderBytecode, "vs_5_1\0");
-    impl_build!(build_ps, PsShaderBytecode, "ps_5_1\0");
-    impl_build!(build_hs, HsShaderBytecode, "hs_5_1\0");
-    impl_build!(build_cs, CsShaderBytecode, "cs_5_1\0");
-    impl_build!(build_gs, GsShaderBytecode, "gs_5_1\0");
-    impl_build!(build_ds, DsShaderBytecode, "ds_5_1\0");
+    impl_build!(build_vs, build_vs_with, VsShaderBytecode, VERTEX);
+    impl_build!(build_ps, build_ps_with, PsShaderBytecode, PIXEL);
+    impl_build!(build_hs, build_hs_with, HsShaderBytecode, HULL);
+    impl_build!(build_cs, build_cs_with, CsShaderBytecode, COMPUTE);
+    impl_build!(build_gs, build_gs_with, GsShaderBytecode, GEOMETRY);
+    impl_build!(build_ds, build_ds_with, DsShaderBytecode, DOMAIN);
+    impl_build!(build_as_with, AsShaderBytecode, AMPLIFICATION);
+    impl_build!(build_ms_with, MsShaderBytecode, MESH);
 }
 
 /// shader macros
@@ -176,30 +380,30 @@ impl<'a> Default for ShaderMacro<'a> {
 }
 
 bitflags!{
-    /// shader compile flags. [more](https://msdn.microsoft.com/en-us/library/windows/desktop/gg615083(v=vs.85).aspx)
+    /// shader compile flags, same values as `D3DCOMPILE_*`. [more](https://msdn.microsoft.com/en-us/library/windows/desktop/gg615083(v=vs.85).aspx)
     pub struct ShaderCompileFlags: u32 {
-        const DEBUG = ::winapi::D3DCOMPILE_DEBUG;
-        const SKIP_VALIDATION = ::winapi::D3DCOMPILE_SKIP_VALIDATION;
-        const SKIP_OPTIMIZATION = ::winapi::D3DCOMPILE_SKIP_OPTIMIZATION;
-        const PACK_MATRIX_ROW_MAJOR = ::winapi::D3DCOMPILE_PACK_MATRIX_ROW_MAJOR;
-        const PACK_MATRIX_COLUMN_MAJOR = ::winapi::D3DCOMPILE_PACK_MATRIX_COLUMN_MAJOR;
-        const PARTIAL_PRECISION = ::winapi::D3DCOMPILE_PARTIAL_PRECISION;
-        const FORCE_VS_SOFTWARE_NO_OPT = ::winapi::D3DCOMPILE_FORCE_VS_SOFTWARE_NO_OPT;
-        const FORCE_PS_SOFTWARE_NO_OPT = ::winapi::D3DCOMPILE_FORCE_PS_SOFTWARE_NO_OPT;
-        const NO_PRESHADER = ::winapi::D3DCOMPILE_NO_PRESHADER;
-        const AVOID_FLOW_CONTROL = ::winapi::D3DCOMPILE_AVOID_FLOW_CONTROL;
-        const PREFER_FLOW_CONTROL = ::winapi::D3DCOMPILE_PREFER_FLOW_CONTROL;
-        const ENABLE_STRICTNESS = ::winapi::D3DCOMPILE_ENABLE_STRICTNESS;
-        const ENABLE_BACKWARDS_COMPATIBILITY = ::winapi::D3DCOMPILE_ENABLE_BACKWARDS_COMPATIBILITY;
-        const IEEE_STRICTNESS = ::winapi::D3DCOMPILE_IEEE_STRICTNESS;
-        const OPTIMIZATION_LEVEL0 = ::winapi::D3DCOMPILE_OPTIMIZATION_LEVEL0;
-        const OPTIMIZATION_LEVEL1 = ::winapi::D3DCOMPILE_OPTIMIZATION_LEVEL1;
-        const OPTIMIZATION_LEVEL2 = ::winapi::D3DCOMPILE_OPTIMIZATION_LEVEL2;
-        const OPTIMIZATION_LEVEL3 = ::winapi::D3DCOMPILE_OPTIMIZATION_LEVEL3;
-        const WARNINGS_ARE_ERRORS = ::winapi::D3DCOMPILE_WARNINGS_ARE_ERRORS;
-        const RESOURCES_MAY_ALIAS = ::winapi::D3DCOMPILE_RESOURCES_MAY_ALIAS;
-        const ENABLE_UNBOUNDED_DESCRIPTOR_TABLES = ::winapi::D3DCOMPILE_ENABLE_UNBOUNDED_DESCRIPTOR_TABLES;
-        const ALL_RESOURCES_BOUND = ::winapi::D3DCOMPILE_ALL_RESOURCES_BOUND;
+        const DEBUG = 1 << 0;
+        const SKIP_VALIDATION = 1 << 1;
+        const SKIP_OPTIMIZATION = 1 << 2;
+        const PACK_MATRIX_ROW_MAJOR = 1 << 3;
+        const PACK_MATRIX_COLUMN_MAJOR = 1 << 4;
+        const PARTIAL_PRECISION = 1 << 5;
+        const FORCE_VS_SOFTWARE_NO_OPT = 1 << 6;
+        const FORCE_PS_SOFTWARE_NO_OPT = 1 << 7;
+        const NO_PRESHADER = 1 << 8;
+        const AVOID_FLOW_CONTROL = 1 << 9;
+        const PREFER_FLOW_CONTROL = 1 << 10;
+        const ENABLE_STRICTNESS = 1 << 11;
+        const ENABLE_BACKWARDS_COMPATIBILITY = 1 << 12;
+        const IEEE_STRICTNESS = 1 << 13;
+        const OPTIMIZATION_LEVEL0 = 1 << 14;
+        const OPTIMIZATION_LEVEL1 = 0;
+        const OPTIMIZATION_LEVEL2 = (1 << 14) | (1 << 15);
+        const OPTIMIZATION_LEVEL3 = 1 << 15;
+        const WARNINGS_ARE_ERRORS = 1 << 18;
+        const RESOURCES_MAY_ALIAS = 1 << 19;
+        const ENABLE_UNBOUNDED_DESCRIPTOR_TABLES = 1 << 20;
+        const ALL_RESOURCES_BOUND = 1 << 21;
     }
 }
 
@@ -8,18 +8,25 @@
 
 //! link between the graphics API and the target surface
 
+#[cfg(windows)]
 use comptr::ComPtr;
+#[cfg(windows)]
 use winapi::IDXGISwapChain3;
 use format::*;
 use resource::*;
-use error::WinError;
+#[cfg(windows)]
+use error::{WinError, InvalidEnumValue};
+#[cfg(windows)]
+use std::convert::TryFrom;
 
 /// link between the graphics API and the target surface
+#[cfg(windows)]
 #[derive(Debug)]
 pub struct SwapChain {
     pub(crate) ptr: ComPtr<IDXGISwapChain3>,
 }
 
+#[cfg(windows)]
 impl SwapChain {
     /// gets the index of this swapchain's current back buffer
     #[inline]
@@ -109,24 +116,24 @@ impl SwapChain {
 
     // TODO: add `get_core_window`?
 
-    /// get description
+    /// get description.
+    ///
+    /// fails with `E_UNEXPECTED` if the runtime reports a value unknown to this crate.
     #[inline]
     pub fn get_desc(&mut self) -> Result<SwapChainDesc, WinError> {
-        unsafe {
-            let mut ret = ::std::mem::uninitialized();
-            let hr = self.ptr.GetDesc1(&mut ret);
-            WinError::from_hresult_or_ok(hr, || ::std::mem::transmute(ret))
-        }
+        let mut ret = unsafe { ::std::mem::zeroed() };
+        WinError::from_hresult(unsafe { self.ptr.GetDesc1(&mut ret) })?;
+        Ok(SwapChainDesc::try_from(ret)?)
     }
 
-    /// get fullscreen description
+    /// get fullscreen description.
+    ///
+    /// fails with `E_UNEXPECTED` if the runtime reports a value unknown to this crate.
     #[inline]
     pub fn get_fullscreen_desc(&mut self) -> Result<FullScreenDesc, WinError> {
-        unsafe {
-            let mut ret = ::std::mem::uninitialized();
-            let hr = self.ptr.GetFullscreenDesc(&mut ret);
-            WinError::from_hresult_or_ok(hr, || ::std::mem::transmute(ret))
-        }
+        let mut ret = unsafe { ::std::mem::zeroed() };
+        WinError::from_hresult(unsafe { self.ptr.GetFullscreenDesc(&mut ret) })?;
+        Ok(FullScreenDesc::try_from(ret)?)
     }
 
     /// get the underlying `HWMD` handle for the swapchain object
@@ -171,6 +178,7 @@ impl SwapChain {
     }
 }
 
+#[cfg(windows)]
 impl From<ComPtr<IDXGISwapChain3>> for SwapChain {
     #[inline]
     fn from(ptr: ComPtr<IDXGISwapChain3>) -> SwapChain {
@@ -229,15 +237,54 @@ impl SwapChainDesc {
     }
 }
 
+#[cfg(windows)]
 impl From<SwapChainDesc> for ::winapi::DXGI_SWAP_CHAIN_DESC1 {
     #[inline]
     fn from(desc: SwapChainDesc) -> Self {
-        unsafe {
-            ::std::mem::transmute(desc)
+        ::winapi::DXGI_SWAP_CHAIN_DESC1{
+            Width: desc.width,
+            Height: desc.height,
+            Format: desc.format,
+            Stereo: desc.stereo.into(),
+            SampleDesc: ::winapi::DXGI_SAMPLE_DESC{
+                Count: desc.sample_desc.count,
+                Quality: desc.sample_desc.quality,
+            },
+            BufferUsage: ::winapi::DXGI_USAGE(desc.buffer_usage.bits()),
+            BufferCount: desc.buffer_count,
+            Scaling: desc.scaling.into(),
+            SwapEffect: ::winapi::DXGI_SWAP_EFFECT(desc.swap_effect as u32),
+            AlphaMode: ::winapi::DXGI_ALPHA_MODE(desc.alpha_mode as u32),
+            Flags: desc.flags.bits(),
         }
     }
 }
 
+#[cfg(windows)]
+impl TryFrom<::winapi::DXGI_SWAP_CHAIN_DESC1> for SwapChainDesc {
+    type Error = InvalidEnumValue;
+
+    #[inline]
+    fn try_from(desc: ::winapi::DXGI_SWAP_CHAIN_DESC1) -> Result<Self, InvalidEnumValue> {
+        Ok(SwapChainDesc{
+            width: desc.Width,
+            height: desc.Height,
+            format: desc.Format,
+            stereo: (desc.Stereo != 0).into(),
+            sample_desc: SampleDesc{
+                count: desc.SampleDesc.Count,
+                quality: desc.SampleDesc.Quality,
+            },
+            buffer_usage: Usage::from_bits_truncate(desc.BufferUsage.0),
+            buffer_count: desc.BufferCount,
+            scaling: Scaling::try_from(desc.Scaling.0)?,
+            swap_effect: SwapEffect::try_from(desc.SwapEffect.0)?,
+            alpha_mode: AlphaMode::try_from(desc.AlphaMode.0)?,
+            flags: SwapChainFlags::from_bits_truncate(desc.Flags),
+        })
+    }
+}
+
 /// optional description of a fullsceen swapchain
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -268,11 +315,35 @@ impl Default for FullScreenDesc {
     }
 }
 
+#[cfg(windows)]
 impl From<FullScreenDesc> for ::winapi::DXGI_SWAP_CHAIN_FULLSCREEN_DESC {
     #[inline]
     fn from(desc: FullScreenDesc) -> Self {
-        // TODO: double check
-        unsafe { ::std::mem::transmute(desc)}
+        ::winapi::DXGI_SWAP_CHAIN_FULLSCREEN_DESC{
+            RefreshRate: ::winapi::DXGI_RATIONAL{
+                Numerator: desc.refresh_numerator,
+                Denominator: desc.refresh_denominator,
+            },
+            ScanlineOrdering: ::winapi::DXGI_MODE_SCANLINE_ORDER(desc.scanline_order as u32),
+            Scaling: ::winapi::DXGI_MODE_SCALING(desc.scaling as u32),
+            Windowed: desc.windowed.into(),
+        }
+    }
+}
+
+#[cfg(windows)]
+impl TryFrom<::winapi::DXGI_SWAP_CHAIN_FULLSCREEN_DESC> for FullScreenDesc {
+    type Error = InvalidEnumValue;
+
+    #[inline]
+    fn try_from(desc: ::winapi::DXGI_SWAP_CHAIN_FULLSCREEN_DESC) -> Result<Self, InvalidEnumValue> {
+        Ok(FullScreenDesc{
+            refresh_numerator: desc.RefreshRate.Numerator,
+            refresh_denominator: desc.RefreshRate.Denominator,
+            scanline_order: ScanlineOrder::try_from(desc.ScanlineOrdering.0)?,
+            scaling: ModeScaling::try_from(desc.Scaling.0)?,
+            windowed: (desc.Windowed != 0).into(),
+        })
     }
 }
 
@@ -307,7 +378,8 @@ impl SwapChainResizeDesc {
 /// multi-sampling scheme description. Default to count 1 and quality 0,
 /// representing no aa
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SampleDesc {
     /// the number of multisamples per pixel
     pub count: u32,
@@ -321,24 +393,24 @@ impl Default for SampleDesc {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// scaling behavor when the back buffer got presented. 
-    #[repr(C)]
-    pub struct Scaling: u32 {
+    pub enum Scaling {
         /// back buffer content would be scaled to fill the presentation target
-        const STRETCH = 0;
+        STRETCH = 0,
         /// back buffer content would appear without scaling, with top edge
         /// aligned with the presentation target.
-        const NONE = 1;
+        NONE = 1,
         /// back buffer content would be scaled to fit the presentation target,
         /// while preserving the aspect ratio, centered with black borders
-        const ASPECT_RATIO_STRETCH = 2;
+        ASPECT_RATIO_STRETCH = 2,
     }
 }
 
+#[cfg(windows)]
 impl From<Scaling> for ::winapi::DXGI_SCALING {
     fn from(scaling: Scaling) -> Self {
-        ::winapi::DXGI_SCALING(scaling.bits())
+        ::winapi::DXGI_SCALING(scaling as u32)
     }
 }
 
@@ -349,50 +421,40 @@ impl Default for Scaling {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// presentation model, as well as how the back buffer would be
     /// handled after calling `swapchain.present()`.
     /// [more info](https://msdn.microsoft.com/en-us/library/windows/desktop/bb173077%28v=vs.85%29.aspx?f=255&MSPPError=-2147217396)
-    #[repr(C)]
-    pub struct SwapEffect: u32 {
+    #[derive(Default)]
+    pub enum SwapEffect {
         /// bitblt, back buffer content would be discarded after presented
-        const DISCARD = 0;
+        DISCARD = 0,
         /// bitblt, back buffer content would persist after presented,
         /// cannot be used with multisampling
-        const SEQUENTIAL = 1;
+        SEQUENTIAL = 1,
         /// flip, back buffer content would persist after presented,
         /// cannot be used with multisampling
-        const FLIP_SEQUENTIAL = 3;
+        FLIP_SEQUENTIAL = 3,
         /// flip, back buffer content would be discared after presented,
         /// cannot be used with multisampling and partial presentation
-        const FLIP_DISCARD = 4;
-    }
-}
-
-impl Default for SwapEffect {
-    fn default() -> SwapEffect {
-        SwapEffect::FLIP_DISCARD
+        #[default]
+        FLIP_DISCARD = 4,
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// transparency behavior of a surface
-    #[repr(C)]
-    pub struct AlphaMode: u32 {
+    #[derive(Default)]
+    pub enum AlphaMode {
         /// transparency behavior is not specified
-        const UNSPECIFIED = 0;
+        #[default]
+        UNSPECIFIED = 0,
         /// each color channel is premultiplied by the alpha value
-        const PREMULTIPLIED = 1;
+        PREMULTIPLIED = 1,
         /// each color channel is not premultiplied by the alpha value
-        const STRAIGHT = 2;
+        STRAIGHT = 2,
         /// alpha channel would be ignored
-        const IGNORE = 3;
-    }
-}
-
-impl Default for AlphaMode {
-    fn default() -> AlphaMode {
-        AlphaMode::UNSPECIFIED
+        IGNORE = 3,
     }
 }
 
@@ -473,17 +535,16 @@ impl Default for PresentFlags {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// method the raster uses to create an image on the surface
-    #[repr(C)]
-    pub struct ScanlineOrder: u32 {
-        const UNSPECIFIED = 0;
+    pub enum ScanlineOrder {
+        UNSPECIFIED = 0,
         /// image is created from the first scanline to the last without skipping any
-        const PROGRESSIVE = 1;
+        PROGRESSIVE = 1,
         /// image is created beginning with the upper field
-        const UPPER_FIELD_FIRST = 2;
+        UPPER_FIELD_FIRST = 2,
         /// image is created beginning with the lower field
-        const LOWER_FIELD_FIRST = 3;
+        LOWER_FIELD_FIRST = 3,
     }
 }
 
@@ -494,13 +555,12 @@ impl Default for ScanlineOrder {
     }
 }
 
-bitflags!{
+d3d_enum!{
     /// scaling behavior for an image on a monitor
-    #[repr(C)]
-    pub struct ModeScaling: u32 {
-        const UNSPECIFIED = 0;
-        const CENTERED = 1;
-        const STRETCHED = 2;
+    pub enum ModeScaling {
+        UNSPECIFIED = 0,
+        CENTERED = 1,
+        STRETCHED = 2,
     }
 }
 
@@ -0,0 +1,324 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! the derive macros, expanded against the public API.
+
+#![cfg(feature = "derive")]
+
+extern crate redirect;
+
+use redirect::format::*;
+use redirect::pipeline::ia::{InputClassification, InputLayoutBuilder, SlotLayout, Vertex};
+use redirect::resource::{ConstantBuffer, HlslPacked, constant_buffer};
+use redirect::shader::hlsl::{HlslField, HlslModule, HlslStruct};
+use std::borrow::Cow;
+use std::mem::{offset_of, size_of, size_of_val};
+
+#[derive(HlslStruct)]
+#[allow(dead_code)]
+struct Material {
+    albedo: [f32; 4],
+    #[hlsl(name = "roughness_metalness")]
+    params: [f32; 2],
+    #[hlsl(skip)]
+    cpu_only: u64,
+    #[hlsl(ty = "Texture2D")]
+    texture: u32,
+}
+
+#[derive(HlslStruct)]
+#[hlsl(name = "Scene")]
+#[allow(dead_code)]
+struct SceneConstants {
+    #[hlsl(row_major)]
+    view: [[f32; 4]; 4],
+    #[hlsl(column_major)]
+    normal: [[f32; 4]; 3],
+    #[hlsl(row_major)]
+    flags: [[Bool; 2]; 1],
+    materials: [Material; 2],
+    #[cbuffer(padding)]
+    _pad: [u32; 3],
+    light_count: u32,
+}
+
+#[derive(HlslStruct)]
+#[allow(dead_code)]
+struct VsOutput {
+    #[hlsl(semantic = "SV_Position")]
+    position: [f32; 4],
+    #[hlsl(semantic = "TEXCOORD0")]
+    r#uv: [f32; 2],
+}
+
+#[test]
+fn hlsl_struct() {
+    let mut module = HlslModule::new();
+    module.add_cbuffer::<SceneConstants>("scene", 0, 0)
+        .add_struct::<VsOutput>();
+    assert_eq!(module.as_str(), "\
+struct Material
+{
+    float4 albedo;
+    float2 roughness_metalness;
+    Texture2D texture;
+};
+
+cbuffer scene : register(b0, space0)
+{
+    row_major float4x4 view;
+    column_major float4x3 normal;
+    row_major bool1x2 flags;
+    Material materials[2];
+    uint light_count;
+}
+
+struct VsOutput
+{
+    float4 position : SV_Position;
+    float2 uv : TEXCOORD0;
+};
+
+");
+}
+
+#[derive(Vertex)]
+#[vertex(hlsl)]
+#[repr(C)]
+#[allow(dead_code)]
+struct MeshVertex {
+    position: [f32; 3],
+    #[vertex(semantic = "TEXCOORD", index = 1)]
+    uv: [u16; 2],
+    #[vertex(normalized)]
+    color: [u8; 4],
+    #[vertex(format = "R10G10B10A2_UNORM")]
+    normal: u32,
+    #[vertex(format = "R32_UINT", hlsl_type = "uint")]
+    material: u32,
+    #[vertex(skip)]
+    _pad: u32,
+}
+
+#[test]
+fn vertex_hlsl_struct() {
+    let mut module = HlslModule::new();
+    module.add_struct::<MeshVertex>();
+    assert_eq!(module.as_str(), "\
+struct MeshVertex
+{
+    float3 position : POSITION;
+    uint2 uv : TEXCOORD1;
+    float4 color : COLOR;
+    float4 normal : NORMAL;
+    uint material : MATERIAL;
+};
+
+");
+}
+
+/// semantic, index, format, offset and slot of each element
+fn elements(layout: &InputLayoutBuilder) -> Vec<(String, u32, DxgiFormat, u32, u32)> {
+    layout.elements.iter().map(|element| (
+        element.get_semantic_name().to_str().unwrap().to_owned(),
+        element.semantic_index, element.format,
+        element.aligned_byte_offset, element.input_slot,
+    )).collect()
+}
+
+#[test]
+fn vertex_layout() {
+    let vertex = MeshVertex{position: [0.0; 3], uv: [0; 2], color: [0; 4], normal: 0, material: 0, _pad: 0};
+    let layout = vertex.get_input_layout();
+    assert_eq!(elements(&layout), vec![
+        ("POSITION".to_owned(), 0, DXGI_FORMAT_R32G32B32_FLOAT, 0, 0),
+        ("TEXCOORD".to_owned(), 1, DXGI_FORMAT_R16G16_UINT, 12, 0),
+        ("COLOR".to_owned(), 0, DXGI_FORMAT_R8G8B8A8_UNORM, 16, 0),
+        ("NORMAL".to_owned(), 0, DXGI_FORMAT_R10G10B10A2_UNORM, 20, 0),
+        ("MATERIAL".to_owned(), 0, DXGI_FORMAT_R32_UINT, 24, 0),
+    ]);
+    for element in &layout.elements {
+        assert_eq!(element.input_slot_class, InputClassification::PER_VERTEX);
+        assert_eq!(element.instance_data_step_rate, 0);
+    }
+
+    // the skipped padding is part of the stride, but not of the elements
+    assert_eq!(vertex.get_stride(), 32);
+    assert_eq!(layout.validate().unwrap().into_vec(), vec![SlotLayout{
+        slot: 0, stride: 28, input_slot_class: InputClassification::PER_VERTEX,
+    }]);
+}
+
+#[derive(Vertex)]
+#[vertex(slot = 1, per_instance, step_rate = 2)]
+#[repr(C)]
+#[allow(dead_code)]
+struct InstanceData {
+    id: u8,
+    #[vertex(semantic = "TRANSFORM")]
+    row0: [f32; 4],
+    #[vertex(semantic = "TRANSFORM", index = 1)]
+    row1: [f32; 4],
+    tint: [i16; 2],
+}
+
+#[test]
+fn instance_layout() {
+    let instance = InstanceData{id: 0, row0: [0.0; 4], row1: [0.0; 4], tint: [0; 2]};
+    let layout = instance.get_input_layout();
+    // `repr(C)` aligns the rows after the single byte id
+    assert_eq!(elements(&layout), vec![
+        ("ID".to_owned(), 0, DXGI_FORMAT_R8_UINT, 0, 1),
+        ("TRANSFORM".to_owned(), 0, DXGI_FORMAT_R32G32B32A32_FLOAT, 4, 1),
+        ("TRANSFORM".to_owned(), 1, DXGI_FORMAT_R32G32B32A32_FLOAT, 20, 1),
+        ("TINT".to_owned(), 0, DXGI_FORMAT_R16G16_SINT, 36, 1),
+    ]);
+    for element in &layout.elements {
+        assert_eq!(element.input_slot_class, InputClassification::PER_INSTANCE);
+        assert_eq!(element.instance_data_step_rate, 2);
+    }
+
+    assert_eq!(instance.get_stride(), 40);
+    assert_eq!(layout.validate().unwrap().into_vec(), vec![SlotLayout{
+        slot: 1, stride: 40, input_slot_class: InputClassification::PER_INSTANCE,
+    }]);
+}
+
+/// without `#[vertex(hlsl)]`, `HlslStruct` is left to the user
+#[derive(Vertex)]
+#[repr(C)]
+#[allow(dead_code)]
+struct PlainVertex {
+    position: [f32; 2],
+}
+
+impl HlslStruct for PlainVertex {
+    fn hlsl_name() -> Cow<'static, str> { "Plain".into() }
+
+    fn hlsl_fields() -> Vec<HlslField> {
+        vec![HlslField::of::<[f32; 2], _>("pos").with_semantic("POSITION")]
+    }
+}
+
+#[test]
+fn vertex_without_hlsl() {
+    let layout = PlainVertex{position: [0.0; 2]}.get_input_layout();
+    assert_eq!(layout.elements.len(), 1);
+    assert_eq!(PlainVertex::hlsl_name(), "Plain");
+}
+
+#[constant_buffer]
+#[derive(Clone, Copy, Default)]
+struct Light {
+    position: [f32; 3],
+    range: f32,
+    direction: [f32; 3],
+    // would straddle the third register
+    spot: [f32; 2],
+    intensity: f32,
+}
+
+#[test]
+fn cbuffer_vectors() {
+    let light = Light::default();
+    assert_eq!(
+        [offset_of!(Light, position), offset_of!(Light, range), offset_of!(Light, direction),
+         offset_of!(Light, spot), offset_of!(Light, intensity)],
+        [0, 12, 16, 32, 40]
+    );
+    assert_eq!(
+        [size_of_val(&light._pad_range), size_of_val(&light._pad_direction),
+         size_of_val(&light._pad_spot), size_of_val(&light._pad_intensity), size_of_val(&light._pad)],
+        [0, 0, 4, 0, 4]
+    );
+    assert_eq!(Light::HLSL_SIZE, 48);
+    assert_eq!(size_of::<Light>(), 48);
+    assert_eq!(Light::CBV_SIZE, 256);
+}
+
+#[constant_buffer]
+#[derive(Clone, Copy, Default)]
+struct Camera {
+    view: [[f32; 4]; 4],
+    near: f32,
+    // matrices start at a register
+    normal: [[f32; 4]; 3],
+    jitter: [f32; 2],
+}
+
+#[test]
+fn cbuffer_matrices() {
+    let camera = Camera::default();
+    assert_eq!(
+        [offset_of!(Camera, view), offset_of!(Camera, near), offset_of!(Camera, normal), offset_of!(Camera, jitter)],
+        [0, 64, 80, 128]
+    );
+    assert_eq!(
+        [size_of_val(&camera._pad_near), size_of_val(&camera._pad_normal),
+         size_of_val(&camera._pad_jitter), size_of_val(&camera._pad)],
+        [0, 12, 0, 8]
+    );
+    assert_eq!(Camera::HLSL_SIZE, 144);
+    assert_eq!(size_of::<Camera>(), 144);
+    assert_eq!(Camera::CBV_SIZE, 256);
+}
+
+#[constant_buffer]
+#[derive(Clone, Copy, Default)]
+struct Scene {
+    ambient: [f32; 3],
+    light_count: u32,
+    sun: Light,
+    // starts the register after the struct
+    exposure: f32,
+    lights: [Light; 4],
+    weights: [[f32; 4]; 2],
+    shadow_count: u32,
+}
+
+#[test]
+fn cbuffer_structs_and_arrays() {
+    let scene = Scene::default();
+    assert_eq!(
+        [offset_of!(Scene, ambient), offset_of!(Scene, light_count), offset_of!(Scene, sun),
+         offset_of!(Scene, exposure), offset_of!(Scene, lights), offset_of!(Scene, weights),
+         offset_of!(Scene, shadow_count)],
+        [0, 12, 16, 64, 80, 272, 304]
+    );
+    assert_eq!(
+        [size_of_val(&scene._pad_light_count), size_of_val(&scene._pad_sun),
+         size_of_val(&scene._pad_exposure), size_of_val(&scene._pad_lights),
+         size_of_val(&scene._pad_weights), size_of_val(&scene._pad_shadow_count), size_of_val(&scene._pad)],
+        [0, 0, 0, 12, 0, 0, 12]
+    );
+    assert_eq!(<[Light; 4] as HlslPacked>::HLSL_SIZE, 192);
+    const { assert!(<[Light; 4] as HlslPacked>::LAYOUT_MATCHES) };
+    assert_eq!(Scene::HLSL_SIZE, 320);
+    assert_eq!(size_of::<Scene>(), 320);
+    assert_eq!(Scene::CBV_SIZE, 512);
+}
+
+/// padded by hand, except for the tail
+#[derive(Clone, Copy, ConstantBuffer)]
+#[repr(C)]
+struct Params {
+    scale: [f32; 2],
+    bias: f32,
+}
+
+#[test]
+fn cbuffer_derive() {
+    assert_eq!(size_of::<Params>(), 12);
+    assert_eq!(Params::HLSL_SIZE, 16);
+    assert_eq!(Params::CBV_SIZE, 256);
+    // a single element is fine, but the elements of longer arrays are 16 bytes apart
+    const { assert!(<[Params; 1] as HlslPacked>::LAYOUT_MATCHES) };
+    const { assert!(!<[Params; 2] as HlslPacked>::LAYOUT_MATCHES) };
+    // the last element of an array of scalars isn't padded
+    assert_eq!(<[[f32; 1]; 3] as HlslPacked>::HLSL_SIZE, 36);
+}
@@ -0,0 +1,78 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! disassembles the fixtures in `tests/fixtures`, comparing them against
+//! hand written listings in the `fxc /dumpbin` format.
+
+extern crate redirect;
+
+use redirect::dxbc::DxbcContainer;
+use std::process::Command;
+
+const FIXTURES: &[(&str, &[u8], &str)] = &[
+    ("passthrough_vs", include_bytes!("fixtures/passthrough_vs.cso"), include_str!("fixtures/passthrough_vs.txt")),
+    ("textured_ps", include_bytes!("fixtures/textured_ps.cso"), include_str!("fixtures/textured_ps.txt")),
+    ("ranges_cs", include_bytes!("fixtures/ranges_cs.cso"), include_str!("fixtures/ranges_cs.txt")),
+];
+
+fn fixture_path(name: &str) -> String {
+    format!("{}/tests/fixtures/{}.cso", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn listings_match_expected() {
+    for &(name, bytes, expected) in FIXTURES {
+        let container = DxbcContainer::from_bytes(bytes).unwrap();
+        assert!(container.has_valid_hash(), "{}", name);
+        let listing = container.disassemble().unwrap();
+        for (line, (actual, expected)) in listing.lines().zip(expected.lines()).enumerate() {
+            assert_eq!(actual, expected, "{}, line {}", name, line + 1);
+        }
+        assert_eq!(listing, expected, "{}", name);
+    }
+}
+
+#[test]
+fn binary_prints_listing() {
+    let output = Command::new(env!("CARGO_BIN_EXE_disassemble"))
+        .arg(fixture_path("textured_ps"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), FIXTURES[1].2);
+}
+
+#[test]
+fn binary_names_each_of_several_shaders() {
+    let output = Command::new(env!("CARGO_BIN_EXE_disassemble"))
+        .arg(fixture_path("passthrough_vs"))
+        .arg(fixture_path("ranges_cs"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let expected = format!(
+        "// {}\n{}// {}\n{}",
+        fixture_path("passthrough_vs"), FIXTURES[0].2, fixture_path("ranges_cs"), FIXTURES[2].2
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
+
+#[test]
+fn binary_reports_failures() {
+    let output = Command::new(env!("CARGO_BIN_EXE_disassemble")).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_disassemble"))
+        .arg(fixture_path("missing"))
+        .arg(fixture_path("passthrough_vs"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("missing.cso"));
+    assert!(String::from_utf8(output.stdout).unwrap().ends_with(FIXTURES[0].2));
+}
@@ -0,0 +1,321 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! json round trips of the serializable descriptions.
+
+#![cfg(feature = "serde")]
+
+extern crate redirect;
+extern crate serde_json;
+
+use redirect::descriptor::*;
+use redirect::format::*;
+use redirect::pipeline::ComparisonFunc;
+use redirect::pipeline::blend::*;
+use redirect::pipeline::ds::*;
+use redirect::pipeline::rasterizer::*;
+use redirect::pipeline::rootsig::*;
+use redirect::pipeline::sampler::*;
+use redirect::resource::*;
+use redirect::swapchain::SampleDesc;
+use serde_json::{Value, json};
+
+/// serializes `$value`, reads it back as `$ty` and checks that it
+/// serializes the same again. evaluates to the read back value and its json.
+macro_rules! round_trip {
+    ($ty:ty, $value:expr) => {{
+        let json = serde_json::to_value(&$value).unwrap();
+        let back: $ty = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), json);
+        (back, json)
+    }};
+}
+
+#[test]
+fn resource_desc() {
+    let desc = ResourceDesc{
+        sample_desc: SampleDesc{count: 4, quality: 1},
+        ..ResourceDesc::tex2d(
+            640, 480, 2, 1, DXGI_FORMAT_R8G8B8A8_UNORM,
+            ResourceFlags::ALLOW_RENDER_TARGET | ResourceFlags::ALLOW_UNORDERED_ACCESS,
+            ResourceAlignment::FOUR_MB
+        )
+    };
+    let (back, json) = round_trip!(ResourceDesc, desc);
+    assert_eq!(json["format"], "DXGI_FORMAT_R8G8B8A8_UNORM");
+    assert_eq!(back.dimension, ResourceDimension::TEXTURE2D);
+    assert_eq!(back.alignment, ResourceAlignment::FOUR_MB);
+    assert_eq!((back.width, back.height, back.depth, back.mip_levels), (640, 480, 2, 1));
+    assert_eq!(back.format, DXGI_FORMAT_R8G8B8A8_UNORM);
+    assert_eq!(back.sample_desc, desc.sample_desc);
+    assert_eq!(back.layout, TextureLayout::UNKNOWN);
+    assert_eq!(back.flags, desc.flags);
+
+    let (back, json) = round_trip!(ResourceDesc, ResourceDesc::buffer(256, ResourceFlags::NONE));
+    assert_eq!(json["format"], "DXGI_FORMAT_UNKNOWN");
+    assert_eq!(back.dimension, ResourceDimension::BUFFER);
+    assert_eq!(back.width, 256);
+}
+
+#[test]
+fn heap_desc() {
+    let desc = HeapDesc{
+        properties: HeapProperties{
+            page: PageProperty::WRITE_BACK,
+            pool_preference: MemoryPoolPreference::L0,
+            creation_node_mask: 1,
+            visible_node_mask: 3,
+            ..HeapProperties::new(HeapType::CUSTOM)
+        },
+        alignment: HeapAlignment::DEFAULT_MSAA_RESOURCE_PLACEMENT,
+        ..HeapDesc::new(1 << 20, Default::default(), HeapFlags::ALLOW_ONLY_BUFFERS)
+    };
+    let (back, _) = round_trip!(HeapDesc, desc);
+    assert_eq!(back.size, 1 << 20);
+    assert_eq!(back.properties.heap_type, HeapType::CUSTOM);
+    assert_eq!(back.properties.page, PageProperty::WRITE_BACK);
+    assert_eq!(back.properties.pool_preference, MemoryPoolPreference::L0);
+    assert_eq!((back.properties.creation_node_mask, back.properties.visible_node_mask), (1, 3));
+    assert_eq!(back.alignment, HeapAlignment::DEFAULT_MSAA_RESOURCE_PLACEMENT);
+    assert_eq!(back.flags, HeapFlags::ALLOW_ONLY_BUFFERS);
+}
+
+#[test]
+fn samplers() {
+    let desc = SamplerDesc{
+        filter: Filter::ANISOTROPIC,
+        address_u: TextureAddressMode::MIRROR,
+        address_v: TextureAddressMode::BORDER,
+        address_w: TextureAddressMode::CLAMP,
+        mip_bias: -0.5,
+        max_anisotropy: 16,
+        comparison_func: ComparisonFunc::LESS_EQUAL,
+        border_color: BorderColor::OPAQUE_BLACK,
+        min_lod: 0.25,
+        max_lod: 8.0,
+    };
+    let (back, _) = round_trip!(SamplerDesc, desc);
+    assert_eq!(back.filter, Filter::ANISOTROPIC);
+    assert_eq!(
+        (back.address_u, back.address_v, back.address_w),
+        (TextureAddressMode::MIRROR, TextureAddressMode::BORDER, TextureAddressMode::CLAMP)
+    );
+    assert_eq!((back.mip_bias, back.min_lod, back.max_lod), (-0.5, 0.25, 8.0));
+    assert_eq!(back.max_anisotropy, 16);
+    assert_eq!(back.comparison_func, ComparisonFunc::LESS_EQUAL);
+    assert_eq!(back.border_color, BorderColor::OPAQUE_BLACK);
+
+    let desc = StaticSamplerDesc{
+        address_u: TextureAddressMode::CLAMP,
+        max_lod: 4.0,
+        visibility: ShaderVisibility::PIXEL,
+        ..StaticSamplerDesc::new(Filter::MIN_MAG_MIP_LINEAR, 3, 1)
+    };
+    let (back, mut json) = round_trip!(StaticSamplerDesc, desc);
+    assert_eq!(back.filter, Filter::MIN_MAG_MIP_LINEAR);
+    assert_eq!(back.address_u, TextureAddressMode::CLAMP);
+    assert_eq!(back.max_lod, 4.0);
+    assert_eq!((back.shader_register, back.register_space), (3, 1));
+    assert_eq!(back.visibility, ShaderVisibility::PIXEL);
+
+    // descriptions saved before samplers had a visibility are visible to all stages
+    json.as_object_mut().unwrap().remove("visibility");
+    let back: StaticSamplerDesc = serde_json::from_value(json).unwrap();
+    assert_eq!(back.visibility, ShaderVisibility::ALL);
+}
+
+#[test]
+fn pipeline_states() {
+    let mut blend = BlendDesc{
+        alpha_to_coverage: true.into(),
+        independent_blend: true.into(),
+        ..Default::default()
+    };
+    blend.render_targets[0].set_blend(Blend::SRC_ALPHA, Blend::INV_SRC_ALPHA, BlendOp::ADD);
+    blend.render_targets[3].logic_op_enabled = true.into();
+    blend.render_targets[3].logic_op = LogicOp::XOR;
+    let (back, json) = round_trip!(BlendDesc, blend);
+    assert_eq!(back, blend);
+    assert_eq!(json["alpha_to_coverage"], true);
+
+    let rasterizer = RasterizerDesc{
+        fill: FillMode::WIREFRAME,
+        cull: CullMode::NONE,
+        depth_bias: -2,
+        depth_bias_clamp: 0.5,
+        slope_scaled_depth_bias: 1.5,
+        forced_sample_count: 4,
+        conservative_raster: ConservativeMode::ON,
+        ..Default::default()
+    };
+    let (back, _) = round_trip!(RasterizerDesc, rasterizer);
+    assert_eq!((back.fill, back.cull), (FillMode::WIREFRAME, CullMode::NONE));
+    assert_eq!(back.depth_bias, -2);
+    assert_eq!((back.depth_bias_clamp, back.slope_scaled_depth_bias), (0.5, 1.5));
+    assert_eq!(back.front_ccw, rasterizer.front_ccw);
+    assert_eq!(back.forced_sample_count, 4);
+    assert_eq!(back.conservative_raster, ConservativeMode::ON);
+
+    let ds = DepthStencilDesc{
+        depth_func: ComparisonFunc::LESS_EQUAL,
+        stencil: true.into(),
+        stencil_write_mask: 0x0f,
+        front_op: StencilOpDesc{pass: StencilOp::REPLACE, ..Default::default()},
+        ..Default::default()
+    };
+    let (back, _) = round_trip!(DepthStencilDesc, ds);
+    assert_eq!(back, ds);
+}
+
+#[test]
+fn root_sig_builder() {
+    let mut builder = RootSigBuilder::new();
+    builder.flags = RootSigFlags::ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT
+        | RootSigFlags::DENY_HULL_SHADER_ROOT_ACCESS;
+    builder.root_params.push(RootParam{
+        visibility: ShaderVisibility::ALL,
+        param_type: RootParamType::Cbv{
+            shader_register: 0, register_space: 0, flags: RootDescriptorFlags::DATA_STATIC,
+        },
+    });
+    builder.root_params.push(RootParam{
+        visibility: ShaderVisibility::VERTEX,
+        param_type: RootParamType::Constant{
+            shader_register: 1, register_space: 0, num_32bit_values: 4,
+        },
+    });
+    builder.root_params.push(RootParam{
+        visibility: ShaderVisibility::PIXEL,
+        param_type: RootParamType::DescriptorTable{
+            descriptor_ranges: vec![
+                DescriptorRange{
+                    range_type: DescriptorRangeType::SRV,
+                    num_descriptors: 2,
+                    base_shader_register: 0,
+                    register_space: 0,
+                    offset_from_table_start: 0,
+                    flags: DescriptorRangeFlags::DESCRIPTORS_VOLATILE,
+                },
+                DescriptorRange{
+                    range_type: DescriptorRangeType::UAV,
+                    num_descriptors: 1,
+                    base_shader_register: 0,
+                    register_space: 1,
+                    offset_from_table_start: 0xffff_ffff,
+                    flags: DescriptorRangeFlags::NONE,
+                },
+            ].into_iter().collect(),
+        },
+    });
+    builder.static_samplers.push(StaticSamplerDesc{
+        visibility: ShaderVisibility::PIXEL,
+        ..StaticSamplerDesc::new(Filter::MIN_MAG_MIP_POINT, 0, 0)
+    });
+
+    let (back, _) = round_trip!(RootSigBuilder, builder);
+    assert_eq!(back.to_bytes(RootSigVersion::V1_1), builder.to_bytes(RootSigVersion::V1_1));
+    assert_eq!(back.to_hlsl(), builder.to_hlsl());
+}
+
+#[test]
+fn views() {
+    let (back, json) = round_trip!(CbvDesc, serde_json::from_value::<CbvDesc>(json!({
+        "buffer_location": {"ptr": 0x1_0000_0100u64},
+        "size": 256,
+    })).unwrap());
+    assert_eq!(json["buffer_location"]["ptr"], 0x1_0000_0100u64);
+    assert_eq!(back.size, 256);
+
+    let dsv = DsvDesc{
+        format: DXGI_FORMAT_D32_FLOAT,
+        flags: DsvFlags::READ_ONLY_DEPTH,
+        dimension: DsvDimension::Tex2DArray(DsvTex2DArrayDesc{
+            mip_slice: 1, first_slice: 2, array_size: 3,
+        }),
+    };
+    let (back, json) = round_trip!(DsvDesc, dsv);
+    assert_eq!(json["format"], "DXGI_FORMAT_D32_FLOAT");
+    assert_eq!(back.format, DXGI_FORMAT_D32_FLOAT);
+    assert_eq!(back.flags, DsvFlags::READ_ONLY_DEPTH);
+    match back.dimension {
+        DsvDimension::Tex2DArray(DsvTex2DArrayDesc{mip_slice: 1, first_slice: 2, array_size: 3}) => {},
+        other => panic!("unexpected dimension {:?}", other),
+    }
+    let (back, _) = round_trip!(DsvDesc, DsvDesc{dimension: DsvDimension::Tex2DMs, ..dsv});
+    match back.dimension {
+        DsvDimension::Tex2DMs => {},
+        other => panic!("unexpected dimension {:?}", other),
+    }
+
+    let rtv = RtvDesc{
+        format: DXGI_FORMAT_R16G16B16A16_FLOAT,
+        dimension: RtvDimension::Buffer(RtvBufferDesc{offset: 16, num_elements: 64}),
+    };
+    let (back, json) = round_trip!(RtvDesc, rtv);
+    assert_eq!(json["format"], "DXGI_FORMAT_R16G16B16A16_FLOAT");
+    match back.dimension {
+        RtvDimension::Buffer(RtvBufferDesc{offset: 16, num_elements: 64}) => {},
+        other => panic!("unexpected dimension {:?}", other),
+    }
+
+    let srv = SrvDesc{
+        format: DXGI_FORMAT_R32_TYPELESS,
+        dimension: SrvDimension::Buffer(SrvBufferDesc{
+            offset: 8, num_elements: 32, byte_stride: 0, raw: 1,
+        }),
+        component_mapping: Default::default(),
+    };
+    let (back, json) = round_trip!(SrvDesc, srv);
+    assert_eq!(json["format"], "DXGI_FORMAT_R32_TYPELESS");
+    assert_eq!(json["component_mapping"], serde_json::to_value(Shader4ComponentMapping::default()).unwrap());
+    match back.dimension {
+        SrvDimension::Buffer(SrvBufferDesc{offset: 8, num_elements: 32, byte_stride: 0, raw: 1}) => {},
+        other => panic!("unexpected dimension {:?}", other),
+    }
+
+    let uav = UavDesc{
+        format: DXGI_FORMAT_UNKNOWN,
+        dimension: UavDimension::Buffer(UavBufferDesc{
+            offset: 0, num_elements: 128, byte_stride: 16, counter_offset: 4096, raw: 0,
+        }),
+    };
+    let (back, json) = round_trip!(UavDesc, uav);
+    assert_eq!(json["format"], "DXGI_FORMAT_UNKNOWN");
+    match back.dimension {
+        UavDimension::Buffer(UavBufferDesc{
+            offset: 0, num_elements: 128, byte_stride: 16, counter_offset: 4096, raw: 0,
+        }) => {},
+        other => panic!("unexpected dimension {:?}", other),
+    }
+}
+
+#[test]
+fn formats_by_name() {
+    let json = serde_json::to_value(RtvDesc{
+        format: DXGI_FORMAT_B8G8R8A8_UNORM_SRGB,
+        dimension: RtvDimension::Tex2D(RtvTex2DDesc{mip_slice: 0, plane_slice: 0}),
+    }).unwrap();
+    assert_eq!(json["format"], Value::from("DXGI_FORMAT_B8G8R8A8_UNORM_SRGB"));
+
+    // neither numbers nor unknown names are accepted
+    let mut numeric = json.clone();
+    numeric["format"] = Value::from(DXGI_FORMAT_B8G8R8A8_UNORM_SRGB.0);
+    assert!(serde_json::from_value::<RtvDesc>(numeric).is_err());
+    let mut unknown = json;
+    unknown["format"] = Value::from("DXGI_FORMAT_R8G8B8A8_UNROM");
+    let err = serde_json::from_value::<RtvDesc>(unknown).unwrap_err();
+    assert!(err.to_string().contains("unknown format `DXGI_FORMAT_R8G8B8A8_UNROM`"), "{}", err);
+
+    // formats the crate doesn't name can't be written out
+    let err = serde_json::to_value(SrvDesc{
+        format: DXGI_FORMAT(0xffff),
+        dimension: SrvDimension::Unknown,
+        component_mapping: Default::default(),
+    }).unwrap_err();
+    assert!(err.to_string().contains("unknown format 65535"), "{}", err);
+}
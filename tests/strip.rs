@@ -0,0 +1,105 @@
+// Copyright 2017 Dasein Phaos aka. Luxko
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! rewrites the containers in `tests/fixtures`, checking that stripping
+//! keeps the program byte for byte with a checksum the runtime accepts.
+
+extern crate redirect;
+
+use redirect::dxbc::{DxbcBuilder, DxbcContainer, StripFlags};
+use redirect::pipeline::rootsig::RootSigVersion;
+
+/// containers compiled by dxc, the program in `DXIL`
+const DXIL_FIXTURES: &[(&str, &[u8])] = &[
+    ("sdl_colors_ps", include_bytes!("fixtures/sdl_colors_ps.cso")),
+    ("imgui_vs", include_bytes!("fixtures/imgui_vs.cso")),
+];
+
+/// containers in the fxc format, the program in `SHEX`
+const SHEX_FIXTURES: &[(&str, &[u8])] = &[
+    ("passthrough_vs", include_bytes!("fixtures/passthrough_vs.cso")),
+    ("textured_ps", include_bytes!("fixtures/textured_ps.cso")),
+    ("ranges_cs", include_bytes!("fixtures/ranges_cs.cso")),
+];
+
+fn fourccs(container: &DxbcContainer) -> Vec<[u8; 4]> {
+    container.parts().iter().map(|part| part.fourcc).collect()
+}
+
+#[test]
+fn fixtures_have_valid_hashes() {
+    for &(name, bytes) in DXIL_FIXTURES.iter().chain(SHEX_FIXTURES) {
+        let container = DxbcContainer::from_bytes(bytes).unwrap();
+        assert!(container.has_valid_hash(), "{}", name);
+        assert_eq!(container.compute_hash(), container.hash(), "{}", name);
+    }
+}
+
+#[test]
+fn rewrite_is_byte_exact() {
+    for &(name, bytes) in DXIL_FIXTURES.iter().chain(SHEX_FIXTURES) {
+        let container = DxbcContainer::from_bytes(bytes).unwrap();
+        assert!(DxbcBuilder::from_container(&container).to_bytes() == bytes, "{}", name);
+    }
+}
+
+fn check_strip(name: &str, bytes: &[u8], program: &[u8; 4]) {
+    let original = DxbcContainer::from_bytes(bytes).unwrap();
+    let stripped = DxbcBuilder::from_container(&original)
+        .strip(StripFlags::all())
+        .to_bytes();
+    let container = DxbcContainer::from_bytes(&stripped).unwrap();
+    assert!(container.has_valid_hash(), "{}", name);
+    assert!(container.part(program) == original.part(program), "{}", name);
+    assert!(stripped.len() < bytes.len(), "{}", name);
+
+    let expected = fourccs(&original).into_iter()
+        .filter(|fourcc| StripFlags::of_part(fourcc).is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(fourccs(&container), expected, "{}", name);
+    for fourcc in &expected {
+        assert!(container.part(fourcc) == original.part(fourcc), "{}", name);
+    }
+}
+
+#[test]
+fn strip_dxil() {
+    for &(name, bytes) in DXIL_FIXTURES {
+        check_strip(name, bytes, b"DXIL");
+    }
+}
+
+#[test]
+fn strip_shex() {
+    for &(name, bytes) in SHEX_FIXTURES {
+        check_strip(name, bytes, b"SHEX");
+    }
+}
+
+#[test]
+fn strip_and_restore_root_signature() {
+    let bytes = DXIL_FIXTURES[0].1;
+    let original = DxbcContainer::from_bytes(bytes).unwrap();
+    let (rootsig, version) = original.root_signature().unwrap();
+    assert_eq!(version, RootSigVersion::V1_1);
+
+    let mut builder = DxbcBuilder::from_container(&original);
+    builder.strip(StripFlags::ROOT_SIGNATURE);
+    let stripped = builder.to_bytes();
+    let container = DxbcContainer::from_bytes(&stripped).unwrap();
+    assert!(container.has_valid_hash());
+    assert!(container.part(b"RTS0").is_none());
+    assert!(container.part(b"STAT") == original.part(b"STAT"));
+
+    builder.set_root_signature(&rootsig, version);
+    let restored = builder.to_bytes();
+    let container = DxbcContainer::from_bytes(&restored).unwrap();
+    assert!(container.has_valid_hash());
+    assert!(container.part(b"RTS0") == original.part(b"RTS0"));
+    assert!(container.part(b"DXIL") == original.part(b"DXIL"));
+}
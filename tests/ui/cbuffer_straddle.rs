@@ -0,0 +1,15 @@
+extern crate redirect;
+
+use redirect::resource::ConstantBuffer;
+
+// `direction` would straddle the first register at offset 8, so HLSL
+// packs it at 16
+#[derive(Clone, Copy, ConstantBuffer)]
+#[repr(C)]
+struct Light {
+    uv_scale: [f32; 2],
+    direction: [f32; 3],
+    intensity: f32,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+extern crate redirect;
+
+use redirect::shader::hlsl::HlslStruct;
+
+#[derive(HlslStruct)]
+struct Transform {
+    #[hlsl(column_major)]
+    scale: f32,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+extern crate redirect;
+
+use redirect::shader::hlsl::HlslStruct;
+
+#[derive(HlslStruct)]
+struct Transform {
+    #[hlsl(row_major)]
+    world: [[f32; 5]; 4],
+}
+
+fn main() {}